@@ -73,6 +73,7 @@ pub use vtansi_derive::{AnsiInput, AnsiOutput, FromAnsi, ToAnsi};
 /// - `muxwith = field` - Multiplex with another field
 /// - `locate = "params" | "data" | "final"` - Field location in sequence
 /// - `flatten` - Flatten nested structure
+/// - `default` - Decode an omitted or empty parameter as `Default::default()`
 ///
 /// # Variant-Level Attributes
 ///
@@ -984,11 +985,28 @@ pub const flatten: () = ();
 // VARIANT ATTRIBUTES
 // =============================================================================
 
-/// Default variant for unrecognized values.
+/// Default variant for unrecognized values, or default field value.
 ///
 /// Marks an enum variant as the fallback for values that don't match
 /// any other variant. Only one variant can be marked as default.
 ///
+/// On a struct field, an omitted or empty parameter decodes to the
+/// field type's `Default` value, as ECMA-48 prescribes for numeric
+/// parameters.  Like `Option` fields, defaulted fields must come after
+/// all required fields, and a CSI sequence whose fields can all be
+/// omitted also matches without any parameters.
+///
+/// ```ignore
+/// #[derive(AnsiOutput)]
+/// #[vtansi(csi, finalbyte = 'H')]
+/// struct SetCursorPosition {
+///     #[vtansi(default)]
+///     row: u16,  // `CSI ; 5 H` decodes with row 0
+///     #[vtansi(default)]
+///     col: u16,  // `CSI H` decodes with row 0 and col 0
+/// }
+/// ```
+///
 /// # Unit Default
 ///
 /// Returns a constant value for unrecognized inputs:
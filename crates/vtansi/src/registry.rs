@@ -157,7 +157,7 @@ pub fn build_trie_from_static_items<I, V, KF, VF>(
     mut value_fn: VF,
 ) -> ByteTrie<V>
 where
-    KF: FnMut(&'static I) -> &[u8],
+    KF: FnMut(&'static I) -> &'static [u8],
     VF: FnMut(&'static I) -> V,
 {
     let mut builder: ByteTrieBuilder<V> = ByteTrieBuilder::new();
@@ -389,6 +389,19 @@ pub fn ansi_input_dcs_trie_cursor() -> AnsiControlFunctionTrieCursor {
     *ANSI_INPUT_DCS_TRIE_CURSOR
 }
 
+static ANSI_INPUT_APC_TRIE_CURSOR: LazyLock<AnsiControlFunctionTrieCursor> =
+    LazyLock::new(|| {
+        let mut cursor = ansi_control_input_function_trie_cursor();
+        cursor.advance_slice(b"\x1B_");
+        cursor
+    });
+
+#[must_use]
+#[inline]
+pub fn ansi_input_apc_trie_cursor() -> AnsiControlFunctionTrieCursor {
+    *ANSI_INPUT_APC_TRIE_CURSOR
+}
+
 #[must_use]
 #[inline]
 pub fn ansi_control_output_function_trie_cursor()
@@ -462,3 +475,16 @@ static ANSI_OUTPUT_DCS_TRIE_CURSOR: LazyLock<AnsiControlFunctionTrieCursor> =
 pub fn ansi_output_dcs_trie_cursor() -> AnsiControlFunctionTrieCursor {
     *ANSI_OUTPUT_DCS_TRIE_CURSOR
 }
+
+static ANSI_OUTPUT_APC_TRIE_CURSOR: LazyLock<AnsiControlFunctionTrieCursor> =
+    LazyLock::new(|| {
+        let mut cursor = ansi_control_output_function_trie_cursor();
+        cursor.advance_slice(b"\x1B_");
+        cursor
+    });
+
+#[must_use]
+#[inline]
+pub fn ansi_output_apc_trie_cursor() -> AnsiControlFunctionTrieCursor {
+    *ANSI_OUTPUT_APC_TRIE_CURSOR
+}
@@ -306,6 +306,9 @@ impl DocSpans {
             FieldMeta::Flatten { kw } => {
                 self.add_const(kw.span(), "flatten");
             }
+            FieldMeta::Default { kw } => {
+                self.add_const(kw.span(), "default");
+            }
         }
     }
 
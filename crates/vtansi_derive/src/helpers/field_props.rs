@@ -69,6 +69,12 @@ pub struct FieldProperties {
     /// When this is `Some`, the field will delegate parameter iterator parsing
     /// to its `try_from_ansi_iter` method instead of consuming a single parameter.
     pub flatten: Option<kw::flatten>,
+
+    /// The `#[vtansi(default)]` keyword, if present.
+    ///
+    /// When this is `Some`, an omitted or empty parameter decodes to the
+    /// field type's `Default` value instead of failing.
+    pub default: Option<kw::default>,
 }
 
 impl HasFieldProperties for Field {
@@ -89,6 +95,7 @@ impl HasFieldProperties for Field {
         let mut mux_kw: Option<kw::muxwith> = None;
         let mut mux_field: Option<syn::Member> = None;
         let mut flatten_kw: Option<kw::flatten> = None;
+        let mut default_kw: Option<kw::default> = None;
 
         for meta in self.get_metadata()? {
             match meta {
@@ -122,6 +129,19 @@ impl HasFieldProperties for Field {
 
                     flatten_kw = Some(kw);
                 }
+                FieldMeta::Default { kw } => {
+                    if let Some(fst_kw) = default_kw {
+                        return Err(occurrence_error(fst_kw, kw, "default"));
+                    }
+                    if is_optional {
+                        return Err(syn::Error::new(
+                            kw.span,
+                            "`default` cannot be used on `Option` fields",
+                        ));
+                    }
+
+                    default_kw = Some(kw);
+                }
             }
         }
 
@@ -133,6 +153,7 @@ impl HasFieldProperties for Field {
             location,
             mux_field,
             flatten: flatten_kw,
+            default: default_kw,
         })
     }
 }
@@ -740,6 +740,9 @@ pub enum FieldMeta {
     /// `#[vtansi(flatten)]` - flatten this field, delegating parameter
     /// iterator parsing to the field's `try_from_ansi_iter` method.
     Flatten { kw: kw::flatten },
+    /// `#[vtansi(default)]` - decode an omitted or empty parameter as
+    /// the field type's `Default` value.
+    Default { kw: kw::default },
 }
 
 impl Parse for FieldMeta {
@@ -775,6 +778,9 @@ impl Parse for FieldMeta {
         } else if lookahead.peek(kw::flatten) {
             let kw = input.parse::<kw::flatten>()?;
             Ok(FieldMeta::Flatten { kw })
+        } else if lookahead.peek(kw::default) {
+            let kw = input.parse::<kw::default>()?;
+            Ok(FieldMeta::Default { kw })
         } else {
             Err(lookahead.error())
         }
@@ -52,6 +52,13 @@ pub struct FieldInfo {
     /// When true, the field delegates parameter iterator parsing to its
     /// `try_from_ansi_iter` method instead of consuming a single parameter.
     pub is_flatten: bool,
+
+    /// Whether the field has `#[vtansi(default)]`.
+    ///
+    /// Defaulted fields are not `Option`, but like optional fields they
+    /// may be omitted; an omitted or empty parameter decodes to the
+    /// type's `Default` value.
+    pub has_default: bool,
 }
 
 impl FieldInfo {
@@ -94,7 +101,7 @@ pub struct Params {
     /// this list.
     pub fields: Vec<FieldInfo>,
 
-    /// Number of required (non-optional) fields.
+    /// Number of required (non-optional, non-defaulted) fields.
     pub required_count: usize,
 
     /// Total number of encoded parameters (excludes muxed fields since they
@@ -278,12 +285,15 @@ fn extract_fields_info<'a>(
             index,
             mux_index: None, // For now
             is_flatten: field_props.flatten.is_some(),
+            has_default: field_props.default.is_some(),
         };
 
         field_map.insert(member.clone(), (index, field_has_mux));
         field_infos.push(field_info);
 
-        if field_props.is_optional {
+        let may_be_omitted =
+            field_props.is_optional || field_props.default.is_some();
+        if may_be_omitted {
             first_optional_idx = Some(index)
         } else if let Some(first_optional_idx) = first_optional_idx
             && !format_is_kv
@@ -291,9 +301,8 @@ fn extract_fields_info<'a>(
             return Err(syn::Error::new_spanned(
                 field,
                 format!(
-                    "non-optional field '{:?}' at position {} cannot \
-                     appear after optional field at position {}",
-                    member, idx, first_optional_idx
+                    "non-optional field '{member:?}' at position {idx} cannot \
+                     appear after optional field at position {first_optional_idx}"
                 ),
             ));
         }
@@ -305,7 +314,7 @@ fn extract_fields_info<'a>(
         } else if !field_has_mux {
             index += 1;
 
-            if !field_props.is_optional {
+            if !may_be_omitted {
                 required_count += 1
             }
         } else {
@@ -277,15 +277,43 @@ impl ControlProperties {
         }
     }
 
+    /// Return the encoding of data parameters.
+    ///
+    /// Without a data delimiter, the first data parameter directly follows
+    /// the static data and the parameters are separated by the regular
+    /// delimiter.
     #[must_use]
     pub fn get_data_param_encoding(&self) -> ParamEncoding {
+        let Some(data_delimiter) = &self.data_delimiter else {
+            return ParamEncoding {
+                format: self.format,
+                delimiter: Some(self.delimiter.clone()),
+                offset: 0,
+            };
+        };
         ParamEncoding {
             format: self.format,
-            delimiter: self.data_delimiter.clone(),
-            offset: if !self.data.is_empty() { 1 } else { 0 },
+            delimiter: Some(data_delimiter.clone()),
+            offset: if !self.data.is_empty() || self.is_bare_osc() {
+                1
+            } else {
+                0
+            },
         }
     }
 
+    /// Return `true` for OSC sequences without static data.
+    ///
+    /// The number of such sequences is followed by the data delimiter only
+    /// when there are data fields to encode, so e.g. `OSC 104 ST` and
+    /// `OSC 104 ; 1 ST` are both representable.
+    #[must_use]
+    pub fn is_bare_osc(&self) -> bool {
+        self.kind == ControlFunctionKind::Osc
+            && self.number.is_some()
+            && self.data.is_empty()
+    }
+
     #[must_use]
     pub fn get_static_prefix(&self) -> Vec<u8> {
         let mut buf: Vec<u8> = Vec::new();
@@ -298,7 +326,9 @@ impl ControlProperties {
             && let Some(number) = &self.number
         {
             buf.extend(number);
-            buf.push(*self.delimiter);
+            if !self.is_bare_osc() {
+                buf.push(*self.delimiter);
+            }
             i += 1;
         }
         if matches!(
@@ -379,11 +409,18 @@ impl ControlProperties {
             && let Some(number) = &self.number
         {
             buf.extend(number);
-            buf.push(*self.delimiter);
+            if !self.is_bare_osc() || has_data_params {
+                buf.push(*self.delimiter);
+            }
             i += 1;
         }
-        // Include OSC data field for disambiguation (e.g., "SetMark", "Copy=")
-        if self.kind == ControlFunctionKind::Osc && !self.data.is_empty() {
+        // Include OSC and ESC...ST data field for disambiguation (e.g.,
+        // "SetMark", "Copy=", or "G" for kitty graphics APC)
+        if matches!(
+            self.kind,
+            ControlFunctionKind::Osc | ControlFunctionKind::EscSt
+        ) && !self.data.is_empty()
+        {
             buf.extend(self.data.iter());
             // Include data_delimiter only if there are actual data params to follow
             // (e.g., '=' in "Copy=" when there's a field after it, but not for
@@ -968,7 +1005,7 @@ impl HasTypeProperties for DeriveInput {
                 Err(e) => {
                     return Err(syn::Error::new_spanned(
                         value_lit,
-                        format!("invalid code value: {}", e),
+                        format!("invalid code value: {e}"),
                     ));
                 }
             }
@@ -979,15 +1016,20 @@ impl HasTypeProperties for DeriveInput {
             None => StructFormat::Vector,
         };
 
-        if seen_data_delimiter.is_none() {
+        // ESC...ST data (e.g. APC `ESC _ G ...`) immediately follows the
+        // static data unless a delimiter is requested explicitly.
+        if seen_data_delimiter.is_none() && kind != ControlFunctionKind::EscSt {
             data_delimiter = Some(syn::parse_quote!(b';'));
         }
 
-        // For SS3 and OSC sequences, default field location to Data if not explicitly set
+        // For SS3, OSC and ESC...ST sequences, default field location to Data
+        // if not explicitly set
         let field_location = if seen_location.is_none()
             && matches!(
                 kind,
-                ControlFunctionKind::Ss3 | ControlFunctionKind::Osc
+                ControlFunctionKind::Ss3
+                    | ControlFunctionKind::Osc
+                    | ControlFunctionKind::EscSt
             ) {
             FieldLocation::Data
         } else {
@@ -1023,8 +1065,7 @@ fn duplicate_intro_error(
     syn::Error::new(
         proc_macro2::Span::call_site(),
         format!(
-            "found multiple occurrences of control function kind ({:?} and {:?})",
-            fst, snd
+            "found multiple occurrences of control function kind ({fst:?} and {snd:?})"
         ),
     )
 }
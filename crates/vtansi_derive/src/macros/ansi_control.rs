@@ -568,7 +568,7 @@ fn generate_esc_encode_dynamic_impl(
             .push(quote! { ::core::option::Option::Some(#terminator_len) });
     }
 
-    if data_prefix_len > 0
+    if (data_prefix_len > 0 || props.is_bare_osc())
         && !params.data_params.is_empty()
         && props.data_delimiter.is_some()
     {
@@ -587,7 +587,7 @@ fn generate_esc_encode_dynamic_impl(
     let len_sum_vars: Vec<_> = (0..len_sum_terms.len())
         .map(|i| {
             syn::Ident::new(
-                &format!("__len_{}", i),
+                &format!("__len_{i}"),
                 proc_macro2::Span::mixed_site(),
             )
         })
@@ -685,7 +685,7 @@ pub fn generate_registry_entries(
         &ParamSource::new(&param_source, ParamSourceFormat::Split),
         Some(&ParamSource::new(
             &data_param_source,
-            ParamSourceFormat::Flat,
+            ParamSourceFormat::Data,
         )),
         Some(&ParamSource::new(
             &final_byte_source,
@@ -693,6 +693,31 @@ pub fn generate_registry_entries(
         )),
         props.into.as_ref(),
     )?;
+    // OSC and ESC...ST entries whose key does not end with the data
+    // delimiter (unit structs, or the no-data entry of all-optional data
+    // fields) must match their key exactly, so that a shorter key can still
+    // claim sequences carrying extra data (e.g. `OSC 8 ; ; ST` vs
+    // `OSC 8 ; ; URI ST`) and `OSC 104 5 ST` isn't mistaken for
+    // `OSC 104 ; 5 ST`.
+    let trailing_data_check = |has_data_params: bool| {
+        if matches!(
+            props.kind,
+            ControlFunctionKind::Osc | ControlFunctionKind::EscSt
+        ) && !has_data_params
+        {
+            quote! {
+                if !#data_param_source.is_empty() {
+                    return ::core::result::Result::Err(
+                        ::vtansi::parse::ParseError::InvalidValue(
+                            "unexpected trailing data".to_string(),
+                        )
+                    );
+                }
+            }
+        } else {
+            quote! {}
+        }
+    };
     let kind = props.kind.as_lib_enum();
     let prefix = props.get_static_prefix();
     let intro_bytes = props.kind.introducer();
@@ -756,7 +781,7 @@ pub fn generate_registry_entries(
     let has_all_optional_data_params = params.data_params.required_count == 0
         && params.data_params.total_count > 0
         && props.kind == ControlFunctionKind::Osc
-        && !props.data.is_empty();
+        && (!props.data.is_empty() || props.number.is_some());
 
     let emit_entry = |suffix: Option<usize>,
                       final_byte: Option<u8>,
@@ -776,7 +801,7 @@ pub fn generate_registry_entries(
                     struct_name_str.to_uppercase(),
                     suffix,
                 ),
-                format!("{}_{}", handler_name, suffix),
+                format!("{handler_name}_{suffix}"),
             )
         } else {
             (
@@ -786,6 +811,7 @@ pub fn generate_registry_entries(
         };
 
         let handler_name = syn::Ident::new(&handler_name, struct_name.span());
+        let trailing_data_check = trailing_data_check(has_data_params);
         let registry_name = syn::Ident::new(&registry_name, struct_name.span());
         let registry_list = syn::Ident::new(&registry, struct_name.span());
         let final_byte = if let Some(final_byte) = final_byte {
@@ -804,6 +830,7 @@ pub fn generate_registry_entries(
                 let mut #param_source = #event_data.iter_params().unwrap_or_default();
                 let #data_param_source = #event_data.get_data().unwrap_or_default();
                 let #final_byte_source = #event_data.get_finalbyte().unwrap_or_default();
+                #trailing_data_check
                 #param_decoding
                 let #event = #constructor;
                 #cb(&#event);
@@ -23,6 +23,10 @@ use crate::helpers::{
 pub enum ParamSourceFormat {
     /// Parameter data is a flat `&[u8]` slice that needs splitting
     Flat,
+    /// Like `Flat`, but the last field receives the remaining data verbatim,
+    /// including any delimiters (used for OSC/DCS string data, such as
+    /// titles or URIs, that may legitimately contain the delimiter).
+    Data,
     /// Parameter data is already split, i.e a `&[&[u8]]`.
     Split,
     /// Parameter data comes from an external iterator (for TryFromAnsiIter)
@@ -51,6 +55,8 @@ impl ParamSource {
 /// - Key-value format: fields can appear in any order, matched by name
 /// - Value format: fields must appear in declaration order
 /// - Optional fields (Option<T> types) - set to None if not present
+/// - Defaulted fields (`#[vtansi(default)]`) - set to `Default::default()`
+///   if not present or empty
 /// - Required fields - error if not present
 ///
 /// # Returns
@@ -178,7 +184,7 @@ fn generate_map_decoding(
         let name = &field.ident();
         let source = field_source_map
             .get(&field.member)
-            .unwrap_or_else(|| panic!("source field for {}", name));
+            .unwrap_or_else(|| panic!("source field for {name}"));
         let source_str = match source {
             syn::Member::Named(ident) => ident.to_string(),
             syn::Member::Unnamed(_) => panic!("unexpected unnamed field source"),
@@ -202,7 +208,7 @@ fn generate_map_decoding(
 
     let source_ident = &source.ident;
     let pair_iter = match source.format {
-        ParamSourceFormat::Flat => quote! {
+        ParamSourceFormat::Flat | ParamSourceFormat::Data => quote! {
             ::vtansi::parse::parse_keyvalue_pairs(#source_ident, #delimiter_lit)
         },
         ParamSourceFormat::Split => quote! {
@@ -235,6 +241,10 @@ fn generate_map_decoding(
         if field.is_optional {
             // Option fields don't need unwrapping
             None
+        } else if field.has_default {
+            Some(quote! {
+                let #name = #name.unwrap_or_default();
+            })
         } else {
             // Required fields must be present
             let name_str = name.to_string();
@@ -287,7 +297,16 @@ fn generate_vector_decoding(
 
     let source_ident = &source.ident;
     let (_iter, setup) = match source.format {
-        ParamSourceFormat::Flat => {
+        ParamSourceFormat::Data if total_count > 0 && !params.has_flatten => {
+            let iter = quote! { #source_ident.splitn(#total_count, |&b| b == #delimiter_lit) };
+            let setup = quote! {
+                let mut #param_iterator = #iter;
+                let mut #params_exhausted = false;
+                #mux_setup
+            };
+            (iter, setup)
+        }
+        ParamSourceFormat::Flat | ParamSourceFormat::Data => {
             let iter = quote! { #source_ident.split(|&b| b == #delimiter_lit) };
             let setup = quote! {
                 let mut #param_iterator = #iter;
@@ -387,6 +406,49 @@ fn generate_vector_decoding(
                         }
                     };
                 }
+            } else if field.has_default {
+                quote! {
+                    let #field_name: #field_type = if #params_exhausted {
+                        ::core::default::Default::default()
+                    } else {
+                        match #param_iterator.next() {
+                            ::core::option::Option::Some(val) => {
+                                #save
+                                if val.is_empty() {
+                                    ::core::default::Default::default()
+                                } else {
+                                    <#field_type as ::vtansi::parse::TryFromAnsi>::try_from_ansi(val)?
+                                }
+                            }
+                            _ => {
+                                #params_exhausted = true;
+                                ::core::default::Default::default()
+                            }
+                        }
+                    };
+                }
+            } else if matches!(source.format, ParamSourceFormat::Data) {
+                // Empty values are meaningful in string data (e.g. an
+                // empty title), so let the field type decide.
+                quote! {
+                    let #field_name = match #param_iterator.next() {
+                        ::core::option::Option::Some(val) => {
+                            #save
+                            <#field_type as ::vtansi::parse::TryFromAnsi>::try_from_ansi(val)?
+                        }
+                        ::core::option::Option::None => {
+                            return ::core::result::Result::Err(
+                                ::vtansi::parse::ParseError::InvalidValue(
+                                    ::std::format!(
+                                        "expected at least {} fields, got {}",
+                                        #required_count,
+                                        #field_idx,
+                                    )
+                                )
+                            );
+                        }
+                    };
+                }
             } else {
                 quote! {
                     let #field_name = match #param_iterator.next() {
@@ -94,130 +94,40 @@ use vtio::{AnyEvent, TerminalInputParser};
 #[cfg(unix)]
 mod raw_mode {
     use std::io;
-    use std::os::unix::io::AsRawFd;
     use std::time::Duration;
 
+    use vtio::tty;
+
     pub struct RawModeGuard {
-        original_termios: Option<libc::termios>,
+        _guard: Option<tty::RawModeGuard>,
     }
 
     impl RawModeGuard {
         pub fn new() -> io::Result<Self> {
-            let fd = io::stdin().as_raw_fd();
-
-            // Check if stdin is a TTY
-            let is_tty = unsafe { libc::isatty(fd) == 1 };
-            if !is_tty {
+            let stdin = io::stdin();
+            if !tty::is_tty(&stdin) {
                 // Not a TTY, don't enable raw mode
                 tracing::info!("stdin is not a TTY, raw mode not enabled");
-                return Ok(Self {
-                    original_termios: None,
-                });
+                return Ok(Self { _guard: None });
             }
 
             tracing::info!("stdin is a TTY, enabling raw mode");
-
-            // Get current terminal settings
-            let original_termios = unsafe {
-                let mut termios = std::mem::zeroed();
-                if libc::tcgetattr(fd, &mut termios) != 0 {
-                    return Err(io::Error::last_os_error());
-                }
-                termios
-            };
-
-            // Create modified settings for raw mode
-            let mut raw_termios = original_termios;
-
-            // Disable canonical mode (line buffering) and echo
-            raw_termios.c_lflag &= !(libc::ICANON
-                | libc::ECHO
-                | libc::ECHONL
-                | libc::IEXTEN
-                | libc::ISIG);
-
-            // Disable input processing
-            raw_termios.c_iflag &= !(libc::IXON
-                | libc::ICRNL
-                | libc::INLCR
-                | libc::IGNCR
-                | libc::BRKINT
-                | libc::INPCK
-                | libc::ISTRIP);
-
-            // Disable output processing
-            raw_termios.c_oflag &= !(libc::OPOST);
-
-            // Set character size to 8 bits
-            raw_termios.c_cflag &= !(libc::CSIZE | libc::PARENB);
-            raw_termios.c_cflag |= libc::CS8;
-
-            // Set read to return immediately with at least 1 byte
-            raw_termios.c_cc[libc::VMIN] = 1;
-            raw_termios.c_cc[libc::VTIME] = 0;
-
-            // Apply the raw mode settings
-            unsafe {
-                if libc::tcsetattr(fd, libc::TCSANOW, &raw_termios) != 0 {
-                    return Err(io::Error::last_os_error());
-                }
-            }
-
             Ok(Self {
-                original_termios: Some(original_termios),
+                _guard: Some(tty::RawModeGuard::raw(&stdin)?),
             })
         }
     }
 
-    impl Drop for RawModeGuard {
-        fn drop(&mut self) {
-            if let Some(original_termios) = &self.original_termios {
-                let fd = io::stdin().as_raw_fd();
-                unsafe {
-                    let _ =
-                        libc::tcsetattr(fd, libc::TCSANOW, original_termios);
-                }
-            }
-        }
-    }
-
     /// Poll stdin for data with a timeout
     /// Returns true if data is available, false if timeout elapsed
     pub fn poll_stdin(timeout: Duration) -> io::Result<bool> {
-        let fd = io::stdin().as_raw_fd();
-        let timeout_ms = timeout.as_millis() as libc::c_int;
-
-        tracing::debug!("poll_stdin: fd={}, timeout_ms={}", fd, timeout_ms);
-
-        unsafe {
-            let mut pollfd = libc::pollfd {
-                fd,
-                events: libc::POLLIN,
-                revents: 0,
-            };
-
-            let result = libc::poll(&mut pollfd, 1, timeout_ms);
-
-            tracing::debug!(
-                "poll_stdin: result={}, revents={:x}",
-                result,
-                pollfd.revents
-            );
-
-            if result < 0 {
-                let err = io::Error::last_os_error();
-                tracing::debug!("poll_stdin: error={}", err);
-                Err(err)
-            } else if result == 0 {
-                // Timeout
-                tracing::debug!("poll_stdin: timeout, no data available");
-                Ok(false)
-            } else {
-                // Data available
-                tracing::debug!("poll_stdin: data available (POLLIN set)");
-                Ok(true)
-            }
-        }
+        let result = tty::poll_readable(io::stdin(), timeout);
+        tracing::debug!(
+            "poll_stdin: timeout={:?}, result={:?}",
+            timeout,
+            result
+        );
+        result
     }
 }
 
@@ -384,8 +294,7 @@ impl EventLog {
         if let Some(ref mut file) = self.log_file {
             let _ = writeln!(
                 file,
-                "[{}] @ {}ms\n  Decoded: {}\n  Raw:     {}\n",
-                source, timestamp_ms, decoded, raw_bytes
+                "[{source}] @ {timestamp_ms}ms\n  Decoded: {decoded}\n  Raw:     {raw_bytes}\n"
             );
             let _ = file.flush();
         }
@@ -994,8 +903,7 @@ fn draw_startup_summary(
     if missing_count > 0 {
         writeln!(
             stdout,
-            "\r  ⚠ {} mode(s) did not respond (terminal may not support them)",
-            missing_count
+            "\r  ⚠ {missing_count} mode(s) did not respond (terminal may not support them)"
         )?;
     }
     writeln!(stdout, "\r")?;
@@ -1207,7 +1115,7 @@ fn draw_key_events_tab(
         stdout,
         "\r  ───────────────────────────────────────────────────────────────"
     )?;
-    writeln!(stdout, "\r    {}", last_event)?;
+    writeln!(stdout, "\r    {last_event}")?;
     writeln!(stdout, "\r")?;
 
     // Raw Bytes Display
@@ -1216,7 +1124,7 @@ fn draw_key_events_tab(
         stdout,
         "\r  ───────────────────────────────────────────────────────────────"
     )?;
-    writeln!(stdout, "\r    {}", last_bytes)?;
+    writeln!(stdout, "\r    {last_bytes}")?;
     writeln!(stdout, "\r")?;
 
     // Help
@@ -1647,12 +1555,12 @@ fn format_key_event(key_event: &KeyEvent) -> String {
 
     // Base layout key
     if let Some(base_key) = &key_event.base_layout_key {
-        details.push(format!("base_layout_key: {:?}", base_key));
+        details.push(format!("base_layout_key: {base_key:?}"));
     }
 
     // Associated text
     if let Some(text) = &key_event.text {
-        details.push(format!("text: {:?}", text));
+        details.push(format!("text: {text:?}"));
     }
 
     parts.push(format!("    Details: {}", details.join(", ")));
@@ -1661,7 +1569,7 @@ fn format_key_event(key_event: &KeyEvent) -> String {
 }
 
 fn format_bytes(bytes: &[u8]) -> String {
-    let hex: Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    let hex: Vec<String> = bytes.iter().map(|b| format!("{b:02x}")).collect();
     let ascii: String = bytes
         .iter()
         .map(|&b| {
@@ -1720,7 +1628,7 @@ fn run_non_interactive(quiet: bool) -> io::Result<()> {
 
         let input = &buffer[..n];
         if !quiet {
-            eprintln!("Read {} bytes: {:02x?}", n, input);
+            eprintln!("Read {n} bytes: {input:02x?}");
         }
 
         parser.feed_with(input, &mut |event| {
@@ -1744,9 +1652,9 @@ fn run_non_interactive(quiet: bool) -> io::Result<()> {
                 // Handle terminal mode events and other events
                 let type_name = std::any::type_name_of_val(event);
                 if type_name.contains("Mode") {
-                    println!("TerminalMode #{}: {}", event_count, type_name);
+                    println!("TerminalMode #{event_count}: {type_name}");
                 } else {
-                    println!("Event #{}: {}", event_count, type_name);
+                    println!("Event #{event_count}: {type_name}");
                 }
             }
         });
@@ -1774,17 +1682,16 @@ fn run_non_interactive(quiet: bool) -> io::Result<()> {
             let type_name = std::any::type_name_of_val(event);
             if type_name.contains("Mode") {
                 println!(
-                    "TerminalMode #{} (from idle): {}",
-                    event_count, type_name
+                    "TerminalMode #{event_count} (from idle): {type_name}"
                 );
             } else {
-                println!("Event #{} (from idle): {}", event_count, type_name);
+                println!("Event #{event_count} (from idle): {type_name}");
             }
         }
     });
 
     if !quiet {
-        eprintln!("Processed {} total events", event_count);
+        eprintln!("Processed {event_count} total events");
     }
     Ok(())
 }
@@ -2155,15 +2062,12 @@ fn main() -> io::Result<()> {
                 match key_event.code {
                     KeyCode::F(1) => {
                         current_tab = Tab::KeyEvents;
-                        should_redraw = true;
                     }
                     KeyCode::F(2) => {
                         current_tab = Tab::TerminalState;
-                        should_redraw = true;
                     }
                     KeyCode::F(3) => {
                         current_tab = Tab::EventLog;
-                        should_redraw = true;
                     }
                     // Handle keyboard enhancement flag toggling (only on Key Events tab)
                     // Handle keyboard/mouse mode toggles
@@ -2175,7 +2079,6 @@ fn main() -> io::Result<()> {
                         if should_toggle {
                             keyboard_state.toggle_all();
                             flags_changed = true;
-                            should_redraw = true;
                         }
                     }
                     KeyCode::Char('1') if current_tab == Tab::KeyEvents => {
@@ -2184,7 +2087,6 @@ fn main() -> io::Result<()> {
                         if should_toggle {
                             keyboard_state.toggle_flag(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES);
                             flags_changed = true;
-                            should_redraw = true;
                         }
                     }
                     KeyCode::Char('2') if current_tab == Tab::KeyEvents => {
@@ -2193,7 +2095,6 @@ fn main() -> io::Result<()> {
                         if should_toggle {
                             keyboard_state.toggle_flag(KeyboardEnhancementFlags::REPORT_EVENT_TYPES);
                             flags_changed = true;
-                            should_redraw = true;
                         }
                     }
                     KeyCode::Char('3') if current_tab == Tab::KeyEvents => {
@@ -2202,7 +2103,6 @@ fn main() -> io::Result<()> {
                         if should_toggle {
                             keyboard_state.toggle_flag(KeyboardEnhancementFlags::REPORT_ALTERNATE_KEYS);
                             flags_changed = true;
-                            should_redraw = true;
                         }
                     }
                     KeyCode::Char('4') if current_tab == Tab::KeyEvents => {
@@ -2211,7 +2111,6 @@ fn main() -> io::Result<()> {
                         if should_toggle {
                             keyboard_state.toggle_flag(KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES);
                             flags_changed = true;
-                            should_redraw = true;
                         }
                     }
                     KeyCode::Char('5') if current_tab == Tab::KeyEvents => {
@@ -2220,7 +2119,6 @@ fn main() -> io::Result<()> {
                         if should_toggle {
                             keyboard_state.toggle_flag(KeyboardEnhancementFlags::REPORT_ASSOCIATED_TEXT);
                             flags_changed = true;
-                            should_redraw = true;
                         }
                     }
                     KeyCode::Char('6') if current_tab == Tab::KeyEvents => {
@@ -2228,7 +2126,6 @@ fn main() -> io::Result<()> {
                             || key_event.is_release();
                         if should_toggle {
                             mouse_state.toggle_flag(MouseModeFlags::DOWN_UP_TRACKING);
-                            should_redraw = true;
                         }
                     }
                     KeyCode::Char('7') if current_tab == Tab::KeyEvents => {
@@ -2236,7 +2133,6 @@ fn main() -> io::Result<()> {
                             || key_event.is_release();
                         if should_toggle {
                             mouse_state.toggle_flag(MouseModeFlags::CLICK_DRAG_TRACKING);
-                            should_redraw = true;
                         }
                     }
                     KeyCode::Char('8') if current_tab == Tab::KeyEvents => {
@@ -2244,7 +2140,6 @@ fn main() -> io::Result<()> {
                             || key_event.is_release();
                         if should_toggle {
                             mouse_state.toggle_flag(MouseModeFlags::ANY_EVENT_TRACKING);
-                            should_redraw = true;
                         }
                     }
                     KeyCode::Char('9') if current_tab == Tab::KeyEvents => {
@@ -2252,7 +2147,6 @@ fn main() -> io::Result<()> {
                             || key_event.is_release();
                         if should_toggle {
                             mouse_state.toggle_flag(MouseModeFlags::SGR_FORMAT);
-                            should_redraw = true;
                         }
                     }
                     KeyCode::Char('m') if current_tab == Tab::KeyEvents => {
@@ -2261,7 +2155,6 @@ fn main() -> io::Result<()> {
                         if should_toggle {
                             mouse_state.toggle_all();
                             tracing::info!("Toggling all mouse modes");
-                            should_redraw = true;
                         }
                     }
                     _ => {}
@@ -2439,15 +2332,15 @@ fn format_mouse_event(mouse_event: &vtio::event::MouseEvent) -> String {
     // Event kind with button info
     match &mouse_event.kind {
         MouseEventKind::Down(button) => {
-            details.push(format!("button: {:?}", button));
+            details.push(format!("button: {button:?}"));
             details.push("action: Down".to_string());
         }
         MouseEventKind::Up(button) => {
-            details.push(format!("button: {:?}", button));
+            details.push(format!("button: {button:?}"));
             details.push("action: Up".to_string());
         }
         MouseEventKind::Drag(button) => {
-            details.push(format!("button: {:?}", button));
+            details.push(format!("button: {button:?}"));
             details.push("action: Drag".to_string());
         }
         MouseEventKind::Moved => {
@@ -0,0 +1,377 @@
+//! Clipboard access (OSC 52).
+//!
+//! The xterm clipboard protocol allows applications to set and query the
+//! contents of the terminal's selections:
+//!
+//! ```text
+//! ESC ] 52 ; Pc ; Pd ST
+//! ```
+//!
+//! `Pc` is a list of zero or more selection names (see
+//! [`ClipboardSelection`]) and `Pd` is either `?` (query) or the
+//! base64-encoded selection contents.  The types in this module handle
+//! the base64 encoding and decoding, so callers only deal with raw bytes.
+//!
+//! See <https://invisible-island.net/xterm/ctlseqs/ctlseqs.html#h3-Operating-System-Commands>
+//! for details.
+
+use base64::Engine as _;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use vtansi::{AnsiEncode, EncodeError, ParseError, TryFromAnsi};
+
+/// Maximum size of decoded clipboard data accepted by the parser.
+///
+/// Larger payloads are rejected with [`ParseError::InvalidValue`].
+pub const MAX_CLIPBOARD_DATA_LEN: usize = 1024 * 1024;
+
+/// Base64 engine accepting both padded and unpadded input, as emitted by
+/// different terminals.
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &base64::alphabet::STANDARD,
+    GeneralPurposeConfig::new()
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+bitflags::bitflags! {
+    /// Set of selections targeted by an OSC 52 sequence.
+    ///
+    /// An empty set is interpreted by terminals as `s0`.
+    #[cfg_attr(
+        feature = "serde",
+        derive(serde::Serialize, serde::Deserialize),
+        serde(transparent)
+    )]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub struct ClipboardSelection: u16 {
+        /// The clipboard (`c`).
+        const CLIPBOARD = 1 << 0;
+        /// The primary selection (`p`).
+        const PRIMARY = 1 << 1;
+        /// The secondary selection (`q`).
+        const SECONDARY = 1 << 2;
+        /// The configurable "select" selection (`s`).
+        const SELECT = 1 << 3;
+        /// Cut buffer 0 (`0`).
+        const CUT_BUFFER_0 = 1 << 4;
+        /// Cut buffer 1 (`1`).
+        const CUT_BUFFER_1 = 1 << 5;
+        /// Cut buffer 2 (`2`).
+        const CUT_BUFFER_2 = 1 << 6;
+        /// Cut buffer 3 (`3`).
+        const CUT_BUFFER_3 = 1 << 7;
+        /// Cut buffer 4 (`4`).
+        const CUT_BUFFER_4 = 1 << 8;
+        /// Cut buffer 5 (`5`).
+        const CUT_BUFFER_5 = 1 << 9;
+        /// Cut buffer 6 (`6`).
+        const CUT_BUFFER_6 = 1 << 10;
+        /// Cut buffer 7 (`7`).
+        const CUT_BUFFER_7 = 1 << 11;
+    }
+}
+
+/// Selection names in encoding order.  Bit `n` corresponds to `SELECTION_NAMES[n]`.
+const SELECTION_NAMES: &[u8; 12] = b"cpqs01234567";
+
+impl ClipboardSelection {
+    /// Return the selection flag for a selection name character.
+    #[must_use]
+    pub fn from_selection_name(name: u8) -> Option<Self> {
+        SELECTION_NAMES
+            .iter()
+            .position(|&c| c == name)
+            .and_then(|bit| Self::from_bits(1 << bit))
+    }
+}
+
+impl<'a> TryFromAnsi<'a> for ClipboardSelection {
+    fn try_from_ansi(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        bytes.iter().try_fold(Self::empty(), |acc, &c| {
+            Self::from_selection_name(c)
+                .map(|sel| acc | sel)
+                .ok_or_else(|| {
+                    ParseError::InvalidValue(format!(
+                        "invalid clipboard selection: {:?}",
+                        char::from(c)
+                    ))
+                })
+        })
+    }
+}
+
+impl AnsiEncode for ClipboardSelection {
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        let mut written = 0;
+        for (bit, &name) in SELECTION_NAMES.iter().enumerate() {
+            if self.bits() & (1 << bit) != 0 {
+                written += vtansi::write_byte_into(sink, name)?;
+            }
+        }
+        Ok(written)
+    }
+}
+
+/// Clipboard contents, base64-encoded on the wire.
+///
+/// Empty data clears the selection when sent to the terminal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ClipboardData(pub Vec<u8>);
+
+impl ClipboardData {
+    /// Return the clipboard contents as a string, if they are valid UTF-8.
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.0).ok()
+    }
+}
+
+impl std::ops::Deref for ClipboardData {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for ClipboardData {
+    fn from(value: Vec<u8>) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&[u8]> for ClipboardData {
+    fn from(value: &[u8]) -> Self {
+        Self(value.to_vec())
+    }
+}
+
+impl From<&str> for ClipboardData {
+    fn from(value: &str) -> Self {
+        Self(value.as_bytes().to_vec())
+    }
+}
+
+impl<'a> TryFromAnsi<'a> for ClipboardData {
+    fn try_from_ansi(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        if base64::decoded_len_estimate(bytes.len()) > MAX_CLIPBOARD_DATA_LEN {
+            return Err(ParseError::InvalidValue(format!(
+                "clipboard data exceeds {MAX_CLIPBOARD_DATA_LEN} bytes"
+            )));
+        }
+        BASE64.decode(bytes).map(Self).map_err(|err| {
+            ParseError::InvalidValue(format!("invalid clipboard data: {err}"))
+        })
+    }
+}
+
+impl AnsiEncode for ClipboardData {
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        vtansi::write_str_into(sink, &BASE64.encode(&self.0))
+    }
+}
+
+/// Clipboard operation: query the selection or set its contents.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ClipboardAction {
+    /// Query the selection contents (`?`).
+    Query,
+    /// Set the selection contents.
+    Set(ClipboardData),
+}
+
+impl ClipboardAction {
+    /// Check if this is a query.
+    #[must_use]
+    pub const fn is_query(&self) -> bool {
+        matches!(self, Self::Query)
+    }
+
+    /// Get the data if this is a Set action.
+    #[must_use]
+    pub const fn as_set(&self) -> Option<&ClipboardData> {
+        match self {
+            Self::Set(data) => Some(data),
+            Self::Query => None,
+        }
+    }
+}
+
+impl<'a> TryFromAnsi<'a> for ClipboardAction {
+    fn try_from_ansi(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        if bytes == b"?" {
+            Ok(Self::Query)
+        } else {
+            ClipboardData::try_from_ansi(bytes).map(Self::Set)
+        }
+    }
+}
+
+impl AnsiEncode for ClipboardAction {
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        match self {
+            Self::Query => '?'.encode_ansi_into(sink),
+            Self::Set(data) => data.encode_ansi_into(sink),
+        }
+    }
+}
+
+/// Set or query the contents of terminal selections (`OSC 52`).
+///
+/// When queried, the terminal replies with a [`ClipboardResponse`].
+///
+/// See <https://invisible-island.net/xterm/ctlseqs/ctlseqs.html#h3-Operating-System-Commands>
+/// for terminal support specifics.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput)]
+#[vtansi(osc, number = "52")]
+pub struct RequestOrSetClipboard {
+    /// The selections to operate on.
+    pub selection: ClipboardSelection,
+    /// The operation to perform.
+    pub action: ClipboardAction,
+}
+
+impl RequestOrSetClipboard {
+    /// Create a command setting `selection` to `data`.
+    #[must_use]
+    pub fn set(
+        selection: ClipboardSelection,
+        data: impl Into<ClipboardData>,
+    ) -> Self {
+        Self {
+            selection,
+            action: ClipboardAction::Set(data.into()),
+        }
+    }
+
+    /// Create a query for the contents of `selection`.
+    #[must_use]
+    pub const fn query(selection: ClipboardSelection) -> Self {
+        Self {
+            selection,
+            action: ClipboardAction::Query,
+        }
+    }
+}
+
+/// Clipboard query response (`OSC 52 ; Pc ; Pd ST`).
+///
+/// Sent by the terminal in reply to [`RequestOrSetClipboard::query`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, vtansi::derive::AnsiInput)]
+#[vtansi(osc, number = "52")]
+pub struct ClipboardResponse {
+    /// The selection the data was read from.
+    pub selection: ClipboardSelection,
+    /// The selection contents.
+    pub data: ClipboardData,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{TerminalInputParser, TerminalOutputParser};
+    use better_any::TidExt;
+
+    #[test]
+    fn test_selection_encoding() {
+        let sel = ClipboardSelection::CLIPBOARD
+            | ClipboardSelection::PRIMARY
+            | ClipboardSelection::CUT_BUFFER_7;
+        assert_eq!(sel.encode_ansi().unwrap(), b"cp7");
+        assert_eq!(ClipboardSelection::try_from_ansi(b"7pc").unwrap(), sel);
+        assert_eq!(
+            ClipboardSelection::try_from_ansi(b"").unwrap(),
+            ClipboardSelection::empty()
+        );
+        assert!(ClipboardSelection::try_from_ansi(b"x").is_err());
+    }
+
+    #[test]
+    fn test_set_encoding() {
+        let cmd =
+            RequestOrSetClipboard::set(ClipboardSelection::CLIPBOARD, "hello");
+        assert_eq!(cmd.encode_ansi().unwrap(), b"\x1b]52;c;aGVsbG8=\x1b\\");
+    }
+
+    #[test]
+    fn test_query_encoding() {
+        let cmd = RequestOrSetClipboard::query(ClipboardSelection::PRIMARY);
+        assert_eq!(cmd.encode_ansi().unwrap(), b"\x1b]52;p;?\x1b\\");
+    }
+
+    #[test]
+    fn test_parse_output() {
+        let mut cmds = Vec::new();
+        TerminalOutputParser::new().feed_with(
+            b"\x1b]52;c;aGVsbG8=\x07\x1b]52;;?\x07\x1b]52;s0;aGk\x07\x1b]52;c;\x07",
+            &mut |event| {
+                if let Some(cmd) = event.downcast_ref::<RequestOrSetClipboard>()
+                {
+                    cmds.push(cmd.clone());
+                }
+            },
+        );
+        assert_eq!(
+            cmds,
+            vec![
+                RequestOrSetClipboard::set(
+                    ClipboardSelection::CLIPBOARD,
+                    "hello"
+                ),
+                RequestOrSetClipboard::query(ClipboardSelection::empty()),
+                RequestOrSetClipboard::set(
+                    ClipboardSelection::SELECT
+                        | ClipboardSelection::CUT_BUFFER_0,
+                    "hi"
+                ),
+                RequestOrSetClipboard::set(
+                    ClipboardSelection::CLIPBOARD,
+                    ClipboardData::default()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let mut responses = Vec::new();
+        TerminalInputParser::new().feed_with(
+            b"\x1b]52;c;aGVsbG8gd29ybGQ=\x1b\\",
+            &mut |event| {
+                if let Some(resp) = event.downcast_ref::<ClipboardResponse>() {
+                    responses.push(resp.clone());
+                }
+            },
+        );
+        assert_eq!(
+            responses,
+            vec![ClipboardResponse {
+                selection: ClipboardSelection::CLIPBOARD,
+                data: "hello world".into(),
+            }]
+        );
+        assert_eq!(responses[0].data.as_str(), Some("hello world"));
+    }
+
+    #[test]
+    fn test_invalid_data() {
+        assert!(matches!(
+            ClipboardData::try_from_ansi(b"not base64!"),
+            Err(ParseError::InvalidValue(_))
+        ));
+        let huge = vec![b'A'; MAX_CLIPBOARD_DATA_LEN * 2];
+        assert!(matches!(
+            ClipboardData::try_from_ansi(&huge),
+            Err(ParseError::InvalidValue(_))
+        ));
+    }
+}
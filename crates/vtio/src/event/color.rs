@@ -290,57 +290,225 @@ impl TerminalPaletteColorResponse {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, vtansi::derive::AnsiOutput)]
+#[allow(deprecated)]
+pub use palette_color::RequestOrSetTerminalPaletteColor;
+
+/// Deprecated single palette color type, kept in its own module so the code
+/// derived for it can use it without warnings.
+mod palette_color {
+    #![allow(deprecated)]
+
+    use std::ops::Deref;
+
+    use xparsecolor::XColor;
+
+    use super::{TerminalColorAction, TerminalPaletteAction};
+
+    /// Change/read a single palette color.
+    ///
+    /// This is an encode-only alias: the parser reports all `OSC 4`
+    /// sequences as [`RequestOrSetTerminalPaletteColors`], which can carry
+    /// several `index;spec` pairs.
+    ///
+    /// # Compatibility
+    ///
+    /// Earlier versions of the output parser produced this type for `OSC 4`.
+    /// It no longer does, so code that downcasts parsed events to
+    /// `RequestOrSetTerminalPaletteColor` will not match anything.  Downcast
+    /// to [`RequestOrSetTerminalPaletteColors`] instead and iterate over its
+    /// actions, or use [`RequestOrSetTerminalPaletteColors::single`] for
+    /// single-color sequences.
+    ///
+    /// [`RequestOrSetTerminalPaletteColors`]: super::RequestOrSetTerminalPaletteColors
+    /// [`RequestOrSetTerminalPaletteColors::single`]: super::RequestOrSetTerminalPaletteColors::single
+    #[deprecated(note = "the parser never produces this type; use \
+                `RequestOrSetTerminalPaletteColors` instead")]
+    #[derive(Debug, Clone, Copy, PartialEq, vtansi::derive::AnsiOutput)]
+    #[vtansi(osc, number = "4", alias_of = super::RequestOrSetTerminalPaletteColors)]
+    pub struct RequestOrSetTerminalPaletteColor(
+        /// The palette index.
+        #[vtansi(flatten)]
+        TerminalPaletteAction,
+    );
+
+    impl RequestOrSetTerminalPaletteColor {
+        #[must_use]
+        pub const fn query(index: i16) -> Self {
+            Self(TerminalPaletteAction::query(index))
+        }
+
+        /// Create a query for the default foreground color (index -1).
+        ///
+        /// This is an iTerm2 extension.
+        #[must_use]
+        pub const fn query_foreground() -> Self {
+            Self::query(-1)
+        }
+
+        /// Create a query for the default background color (index -2).
+        ///
+        /// This is an iTerm2 extension.
+        #[must_use]
+        pub const fn query_background() -> Self {
+            Self::query(-2)
+        }
+
+        #[must_use]
+        pub const fn set(index: i16, color: &XColor) -> Self {
+            Self(TerminalPaletteAction::new(
+                index,
+                TerminalColorAction::Set(*color),
+            ))
+        }
+
+        #[must_use]
+        pub const fn set_foreground(color: &XColor) -> Self {
+            Self::set(-1, color)
+        }
+
+        #[must_use]
+        pub const fn set_background(color: &XColor) -> Self {
+            Self::set(-2, color)
+        }
+    }
+
+    impl Deref for RequestOrSetTerminalPaletteColor {
+        type Target = TerminalPaletteAction;
+
+        #[inline]
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+}
+
+/// A list of palette color actions, encoded as `index;spec;index;spec...`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TerminalPaletteActions(pub Vec<TerminalPaletteAction>);
+
+impl Deref for TerminalPaletteActions {
+    type Target = [TerminalPaletteAction];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromIterator<TerminalPaletteAction> for TerminalPaletteActions {
+    fn from_iter<T: IntoIterator<Item = TerminalPaletteAction>>(
+        iter: T,
+    ) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl vtansi::AnsiEncode for TerminalPaletteActions {
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, vtansi::EncodeError> {
+        let mut written = 0;
+        for (i, action) in self.0.iter().enumerate() {
+            if i > 0 {
+                written += vtansi::write_byte_into(sink, b';')?;
+            }
+            written += action.encode_ansi_into(sink)?;
+        }
+        Ok(written)
+    }
+}
+
+impl<'a> vtansi::TryFromAnsi<'a> for TerminalPaletteActions {
+    fn try_from_ansi(bytes: &'a [u8]) -> Result<Self, vtansi::ParseError> {
+        let mut parts = bytes.split(|&b| b == b';');
+        let mut actions = Vec::new();
+        while let Some(index) = parts.next() {
+            let spec = parts.next().ok_or_else(|| {
+                vtansi::ParseError::InvalidValue(
+                    "palette color index without color spec".to_string(),
+                )
+            })?;
+            actions.push(TerminalPaletteAction::new(
+                i16::try_from_ansi(index)?,
+                TerminalColorAction::try_from_ansi(spec)?,
+            ));
+        }
+        Ok(Self(actions))
+    }
+}
+
+/// Change/read one or more palette colors.
+///
+/// `OSC 4 ; index ; spec [; index ; spec ...] ST`. Each `spec` is either
+/// `?` to query the color (the terminal answers with one
+/// [`TerminalPaletteColorResponse`] per query) or a color to set.
+///
+/// See <https://terminalguide.namepad.de/seq/osc-4/> for
+/// terminal support specifics.
+#[derive(Debug, Clone, PartialEq, vtansi::derive::AnsiOutput)]
 #[vtansi(osc, number = "4")]
-pub struct RequestOrSetTerminalPaletteColor(
-    /// The palette index.
-    #[vtansi(flatten)]
-    TerminalPaletteAction,
-);
+pub struct RequestOrSetTerminalPaletteColors(TerminalPaletteActions);
 
-impl RequestOrSetTerminalPaletteColor {
+impl RequestOrSetTerminalPaletteColors {
+    /// Create a batch from a list of palette actions.
     #[must_use]
-    pub const fn query(index: i16) -> Self {
-        Self(TerminalPaletteAction::query(index))
+    pub fn new(
+        actions: impl IntoIterator<Item = TerminalPaletteAction>,
+    ) -> Self {
+        Self(actions.into_iter().collect())
     }
 
-    /// Create a query for the default foreground color (index -1).
-    ///
-    /// This is an iTerm2 extension.
+    /// Create a batch setting each `(index, color)` pair.
     #[must_use]
-    pub const fn query_foreground() -> Self {
-        Self::query(-1)
+    pub fn set(colors: impl IntoIterator<Item = (i16, XColor)>) -> Self {
+        Self::new(
+            colors
+                .into_iter()
+                .map(|(index, color)| TerminalPaletteAction::set(index, color)),
+        )
     }
 
-    /// Create a query for the default background color (index -2).
-    ///
-    /// This is an iTerm2 extension.
+    /// Create a batch querying each of `indices`.
     #[must_use]
-    pub const fn query_background() -> Self {
-        Self::query(-2)
+    pub fn query(indices: impl IntoIterator<Item = i16>) -> Self {
+        Self::new(indices.into_iter().map(TerminalPaletteAction::query))
     }
 
+    /// Return the action if this batch contains exactly one action.
     #[must_use]
-    pub const fn set(index: i16, color: &XColor) -> Self {
-        Self(TerminalPaletteAction::new(
-            index,
-            TerminalColorAction::Set(*color),
-        ))
+    pub fn single(&self) -> Option<&TerminalPaletteAction> {
+        match &**self {
+            [action] => Some(action),
+            _ => None,
+        }
     }
+}
 
-    #[must_use]
-    pub const fn set_foreground(color: &XColor) -> Self {
-        Self::set(-1, color)
+#[allow(deprecated)]
+impl From<RequestOrSetTerminalPaletteColor>
+    for RequestOrSetTerminalPaletteColors
+{
+    fn from(value: RequestOrSetTerminalPaletteColor) -> Self {
+        Self::new([*value])
     }
+}
 
-    #[must_use]
-    pub const fn set_background(color: &XColor) -> Self {
-        Self::set(-2, color)
+impl Deref for RequestOrSetTerminalPaletteColors {
+    type Target = [TerminalPaletteAction];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
     }
 }
 
-impl Deref for RequestOrSetTerminalPaletteColor {
-    type Target = TerminalPaletteAction;
+/// A list of palette indices, encoded as `index;index...`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct TerminalPaletteIndices(pub Vec<i16>);
+
+impl Deref for TerminalPaletteIndices {
+    type Target = [i16];
 
     #[inline]
     fn deref(&self) -> &Self::Target {
@@ -348,6 +516,68 @@ impl Deref for RequestOrSetTerminalPaletteColor {
     }
 }
 
+impl FromIterator<i16> for TerminalPaletteIndices {
+    fn from_iter<T: IntoIterator<Item = i16>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl vtansi::AnsiEncode for TerminalPaletteIndices {
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, vtansi::EncodeError> {
+        let mut written = 0;
+        for (i, index) in self.0.iter().enumerate() {
+            if i > 0 {
+                written += vtansi::write_byte_into(sink, b';')?;
+            }
+            written += index.encode_ansi_into(sink)?;
+        }
+        Ok(written)
+    }
+}
+
+impl<'a> vtansi::TryFromAnsi<'a> for TerminalPaletteIndices {
+    fn try_from_ansi(bytes: &'a [u8]) -> Result<Self, vtansi::ParseError> {
+        bytes
+            .split(|&b| b == b';')
+            .map(i16::try_from_ansi)
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// Reset palette colors to their defaults.
+///
+/// `OSC 104 ST` resets the whole palette, `OSC 104 ; index [; index ...] ST`
+/// resets only the given entries.
+///
+/// See <https://invisible-island.net/xterm/ctlseqs/ctlseqs.html#h3-Operating-System-Commands>
+/// for reference.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput)]
+#[vtansi(osc, number = "104")]
+pub struct ResetTerminalPaletteColors {
+    /// The entries to reset, or `None` to reset all of them.
+    pub indices: Option<TerminalPaletteIndices>,
+}
+
+impl ResetTerminalPaletteColors {
+    /// Create a command resetting the whole palette.
+    #[must_use]
+    pub const fn all() -> Self {
+        Self { indices: None }
+    }
+
+    /// Create a command resetting the given palette entries.
+    #[must_use]
+    pub fn indices(indices: impl IntoIterator<Item = i16>) -> Self {
+        Self {
+            indices: Some(indices.into_iter().collect()),
+        }
+    }
+}
+
 /// Response to a query for the special text default foreground color.
 ///
 /// See <https://invisible-island.net/xterm/ctlseqs/ctlseqs.html#h3-Operating-System-Commands>
@@ -809,6 +1039,61 @@ impl RequestOrSetHighlightForegroundColor {
     }
 }
 
+macro_rules! special_color_reset {
+    ($(#[$meta:meta])* $name:ident, $number:literal) => {
+        $(#[$meta])*
+        ///
+        /// See <https://invisible-island.net/xterm/ctlseqs/ctlseqs.html#h3-Operating-System-Commands>
+        /// for reference.
+        #[derive(
+            Debug, Clone, Copy, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput,
+        )]
+        #[vtansi(osc, number = $number)]
+        pub struct $name;
+    };
+}
+
+special_color_reset!(
+    /// Reset the special text default foreground color (`OSC 110`).
+    ResetSpecialTextForegroundColor, "110"
+);
+special_color_reset!(
+    /// Reset the special text default background color (`OSC 111`).
+    ResetSpecialTextBackgroundColor, "111"
+);
+special_color_reset!(
+    /// Reset the text cursor color (`OSC 112`).
+    ResetCursorColor, "112"
+);
+special_color_reset!(
+    /// Reset the mouse pointer foreground color (`OSC 113`).
+    ResetPointerForegroundColor, "113"
+);
+special_color_reset!(
+    /// Reset the mouse pointer background color (`OSC 114`).
+    ResetPointerBackgroundColor, "114"
+);
+special_color_reset!(
+    /// Reset the Tektronix foreground color (`OSC 115`).
+    ResetTektronixForegroundColor, "115"
+);
+special_color_reset!(
+    /// Reset the Tektronix background color (`OSC 116`).
+    ResetTektronixBackgroundColor, "116"
+);
+special_color_reset!(
+    /// Reset the highlight (selection) background color (`OSC 117`).
+    ResetHighlightBackgroundColor, "117"
+);
+special_color_reset!(
+    /// Reset the Tektronix cursor color (`OSC 118`).
+    ResetTektronixCursorColor, "118"
+);
+special_color_reset!(
+    /// Reset the highlight (selection) foreground color (`OSC 119`).
+    ResetHighlightForegroundColor, "119"
+);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1013,6 +1298,7 @@ mod tests {
     // RequestOrSetTerminalPaletteColor tests (AnsiOutput)
 
     #[test]
+    #[allow(deprecated)]
     fn test_request_palette_color_query() {
         let request = RequestOrSetTerminalPaletteColor::query(5);
         let encoded = request.encode_ansi().unwrap();
@@ -1020,6 +1306,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn test_request_palette_color_query_foreground() {
         let request = RequestOrSetTerminalPaletteColor::query_foreground();
         let encoded = request.encode_ansi().unwrap();
@@ -1027,6 +1314,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn test_request_palette_color_query_background() {
         let request = RequestOrSetTerminalPaletteColor::query_background();
         let encoded = request.encode_ansi().unwrap();
@@ -1034,6 +1322,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn test_request_palette_color_set_rgb() {
         let color = XColor::rgb(0xffff, 0x8080, 0x0000);
         let request = RequestOrSetTerminalPaletteColor::set(10, &color);
@@ -1042,6 +1331,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn test_request_palette_color_set_rgb_intensity() {
         let color = XColor::rgb_intensity(1.0, 0.5, 0.25);
         let request = RequestOrSetTerminalPaletteColor::set(20, &color);
@@ -1050,6 +1340,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn test_request_palette_color_set_foreground() {
         let color = XColor::from_rgb8(255, 255, 255);
         let request = RequestOrSetTerminalPaletteColor::set_foreground(&color);
@@ -1058,6 +1349,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn test_request_palette_color_set_background() {
         let color = XColor::from_rgb8(0, 0, 0);
         let request = RequestOrSetTerminalPaletteColor::set_background(&color);
@@ -1066,6 +1358,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn test_request_palette_color_set_named_color() {
         let color: XColor = "cornflower blue".parse().unwrap();
         let request = RequestOrSetTerminalPaletteColor::set(100, &color);
@@ -1259,4 +1552,124 @@ mod tests {
         let encoded = request.encode_ansi().unwrap();
         assert_eq!(encoded, b"\x1b]19;rgb:dddd/eeee/ffff\x1b\\");
     }
+
+    // Batch palette and reset tests
+
+    fn parse_output(input: &[u8]) -> Vec<Vec<u8>> {
+        let mut events = Vec::new();
+        crate::parser::TerminalOutputParser::new().feed_with(
+            input,
+            &mut |event| {
+                events.push(event.encode_ansi().unwrap());
+            },
+        );
+        events
+    }
+
+    #[test]
+    fn test_palette_batch_encode() {
+        let request = RequestOrSetTerminalPaletteColors::set([
+            (1, XColor::rgb(0xffff, 0x0000, 0x0000)),
+            (2, XColor::rgb(0x0000, 0xffff, 0x0000)),
+        ]);
+        assert_eq!(
+            request.encode_ansi().unwrap(),
+            b"\x1b]4;1;rgb:ffff/0000/0000;2;rgb:0000/ffff/0000\x1b\\"
+        );
+        assert_eq!(
+            RequestOrSetTerminalPaletteColors::query([3, 4])
+                .encode_ansi()
+                .unwrap(),
+            b"\x1b]4;3;?;4;?\x1b\\"
+        );
+    }
+
+    #[test]
+    fn test_palette_batch_parse() {
+        use better_any::TidExt;
+
+        let mut batches = Vec::new();
+        crate::parser::TerminalOutputParser::new().feed_with(
+            b"\x1b]4;1;?;2;rgb:ffff/0000/0000\x07\x1b]4;5;?\x07\x1b]4;1\x07",
+            &mut |event| {
+                if let Some(batch) =
+                    event.downcast_ref::<RequestOrSetTerminalPaletteColors>()
+                {
+                    batches.push(batch.clone());
+                }
+            },
+        );
+        assert_eq!(
+            batches,
+            vec![
+                RequestOrSetTerminalPaletteColors::new([
+                    TerminalPaletteAction::query(1),
+                    TerminalPaletteAction::set(
+                        2,
+                        XColor::rgb(0xffff, 0x0000, 0x0000)
+                    ),
+                ]),
+                RequestOrSetTerminalPaletteColors::query([5]),
+            ]
+        );
+        assert_eq!(batches[0].single(), None);
+        assert_eq!(batches[1].single(), Some(&TerminalPaletteAction::query(5)));
+    }
+
+    #[test]
+    fn test_reset_palette_colors() {
+        assert_eq!(
+            ResetTerminalPaletteColors::all().encode_ansi().unwrap(),
+            b"\x1b]104\x1b\\"
+        );
+        assert_eq!(
+            ResetTerminalPaletteColors::indices([1, 15])
+                .encode_ansi()
+                .unwrap(),
+            b"\x1b]104;1;15\x1b\\"
+        );
+        let mut resets = Vec::new();
+        crate::parser::TerminalOutputParser::new().feed_with(
+            b"\x1b]104\x07\x1b]104;3;4;5\x07\x1b]104;x\x07\x1b]1045\x07",
+            &mut |event| {
+                use better_any::TidExt;
+                if let Some(reset) =
+                    event.downcast_ref::<ResetTerminalPaletteColors>()
+                {
+                    resets.push(reset.clone());
+                }
+            },
+        );
+        assert_eq!(
+            resets,
+            vec![
+                ResetTerminalPaletteColors::all(),
+                ResetTerminalPaletteColors::indices([3, 4, 5]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reset_special_colors() {
+        use vtansi::StaticAnsiEncode;
+
+        let all: [&[u8]; 10] = [
+            ResetSpecialTextForegroundColor::BYTES,
+            ResetSpecialTextBackgroundColor::BYTES,
+            ResetCursorColor::BYTES,
+            ResetPointerForegroundColor::BYTES,
+            ResetPointerBackgroundColor::BYTES,
+            ResetTektronixForegroundColor::BYTES,
+            ResetTektronixBackgroundColor::BYTES,
+            ResetHighlightBackgroundColor::BYTES,
+            ResetTektronixCursorColor::BYTES,
+            ResetHighlightForegroundColor::BYTES,
+        ];
+        let mut input = Vec::new();
+        for (i, bytes) in all.iter().enumerate() {
+            assert_eq!(*bytes, format!("\x1b]11{i}\x1b\\").as_bytes());
+            input.extend_from_slice(bytes);
+        }
+        assert_eq!(parse_output(&input), all.map(<[u8]>::to_vec));
+    }
 }
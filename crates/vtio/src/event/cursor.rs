@@ -329,7 +329,9 @@ pub struct CarriageReturn;
 )]
 #[vtansi(csi, finalbyte = 'H')]
 pub struct SetCursorPosition {
+    #[vtansi(default)]
     pub row: u16,
+    #[vtansi(default)]
     pub col: u16,
 }
 
@@ -481,6 +483,48 @@ pub struct NextLine;
 #[vtansi(esc, finalbyte = 'H')]
 pub struct HorizontalTabSet;
 
+/// Tab Clear (`TBC`) for the current column.
+///
+/// Remove the tab stop at the current cursor column, if any.
+///
+/// This is the same as `CSI g` or `CSI 0 g`.
+///
+/// See <https://terminalguide.namepad.de/seq/csi_sg/> for
+/// terminal support specifics.
+#[derive(
+    Debug,
+    PartialOrd,
+    PartialEq,
+    Eq,
+    Clone,
+    Copy,
+    Hash,
+    vtansi::derive::AnsiOutput,
+)]
+#[vtansi(csi, finalbyte = 'g')]
+pub struct TabClearCurrentColumn;
+
+/// Tab Clear (`TBC`) for all columns.
+///
+/// Remove all tab stops.
+///
+/// This is the same as `CSI 3 g`.
+///
+/// See <https://terminalguide.namepad.de/seq/csi_sg/> for
+/// terminal support specifics.
+#[derive(
+    Debug,
+    PartialOrd,
+    PartialEq,
+    Eq,
+    Clone,
+    Copy,
+    Hash,
+    vtansi::derive::AnsiOutput,
+)]
+#[vtansi(csi, params = ["3"], finalbyte = 'g')]
+pub struct TabClearAll;
+
 /// Reverse Index (`RI`).
 ///
 /// Move the cursor to the previous line in the scrolling region,
@@ -543,7 +587,7 @@ pub struct ReverseIndex;
     vtansi::derive::AnsiOutput,
 )]
 #[vtansi(csi, finalbyte = 'A')]
-pub struct CursorUp(pub u16);
+pub struct CursorUp(#[vtansi(default)] pub u16);
 
 /// Cursor Down (`CUD`).
 ///
@@ -576,7 +620,7 @@ pub struct CursorUp(pub u16);
     vtansi::derive::AnsiOutput,
 )]
 #[vtansi(csi, finalbyte = 'B')]
-pub struct CursorDown(pub u16);
+pub struct CursorDown(#[vtansi(default)] pub u16);
 
 /// Cursor Left (`CUB`).
 ///
@@ -623,7 +667,7 @@ pub struct CursorDown(pub u16);
     vtansi::derive::AnsiOutput,
 )]
 #[vtansi(csi, finalbyte = 'D')]
-pub struct CursorLeft(pub u16);
+pub struct CursorLeft(#[vtansi(default)] pub u16);
 
 /// Cursor Right (`CUF`).
 ///
@@ -657,7 +701,7 @@ pub struct CursorLeft(pub u16);
     vtansi::derive::AnsiOutput,
 )]
 #[vtansi(csi, finalbyte = 'C')]
-pub struct CursorRight(pub u16);
+pub struct CursorRight(#[vtansi(default)] pub u16);
 
 /// Cursor Next Line (`CNL`).
 ///
@@ -681,7 +725,7 @@ pub struct CursorRight(pub u16);
     vtansi::derive::AnsiOutput,
 )]
 #[vtansi(csi, finalbyte = 'E')]
-pub struct CursorNextLine(pub u16);
+pub struct CursorNextLine(#[vtansi(default)] pub u16);
 
 /// Cursor Previous Line (`CPL`).
 ///
@@ -705,7 +749,7 @@ pub struct CursorNextLine(pub u16);
     vtansi::derive::AnsiOutput,
 )]
 #[vtansi(csi, finalbyte = 'F')]
-pub struct CursorPreviousLine(pub u16);
+pub struct CursorPreviousLine(#[vtansi(default)] pub u16);
 
 /// Cursor Horizontal Absolute (`CHA`).
 ///
@@ -731,7 +775,7 @@ pub struct CursorPreviousLine(pub u16);
     vtansi::derive::AnsiOutput,
 )]
 #[vtansi(csi, finalbyte = 'G')]
-pub struct CursorHorizontalAbsolute(pub u16);
+pub struct CursorHorizontalAbsolute(#[vtansi(default)] pub u16);
 
 /// Cursor Horizontal Forward Tabulation (`CHT`).
 ///
@@ -763,6 +807,7 @@ pub struct CursorHorizontalAbsolute(pub u16);
 )]
 #[vtansi(csi, finalbyte = 'I')]
 pub struct CursorHorizontalForwardTab {
+    #[vtansi(default)]
     pub amount: u16,
 }
 
@@ -793,7 +838,7 @@ pub struct CursorHorizontalForwardTab {
     vtansi::derive::AnsiOutput,
 )]
 #[vtansi(csi, finalbyte = 'Z')]
-pub struct CursorHorizontalBackwardTab(pub u16);
+pub struct CursorHorizontalBackwardTab(#[vtansi(default)] pub u16);
 
 /// Cursor Horizontal Position Relative (`HPR`).
 ///
@@ -827,7 +872,7 @@ pub struct CursorHorizontalBackwardTab(pub u16);
     vtansi::derive::AnsiOutput,
 )]
 #[vtansi(csi, finalbyte = 'a')]
-pub struct CursorHorizontalRelative(pub u16);
+pub struct CursorHorizontalRelative(#[vtansi(default)] pub u16);
 
 /// Cursor Vertical Position Absolute (`VPA`).
 ///
@@ -857,7 +902,7 @@ pub struct CursorHorizontalRelative(pub u16);
     vtansi::derive::AnsiOutput,
 )]
 #[vtansi(csi, finalbyte = 'd')]
-pub struct CursorVerticalAbsolute(pub u16);
+pub struct CursorVerticalAbsolute(#[vtansi(default)] pub u16);
 
 /// Vertical Position Relative (`VPR`).
 ///
@@ -890,7 +935,7 @@ pub struct CursorVerticalAbsolute(pub u16);
     vtansi::derive::AnsiOutput,
 )]
 #[vtansi(csi, finalbyte = 'e')]
-pub struct CursorVerticalRelative(pub u16);
+pub struct CursorVerticalRelative(#[vtansi(default)] pub u16);
 
 /// Cursor style variants for `DECSCUSR`.
 ///
@@ -965,8 +1010,12 @@ pub struct SetCursorStyle {
 ///
 /// Request the current cursor style.
 ///
-/// The terminal will respond with a DCS sequence containing the current
-/// cursor style setting.
+/// The terminal replies with a
+/// [`StatusStringReport`](crate::event::status::StatusStringReport)
+/// containing [`SetCursorStyle`].
+///
+/// This is an encode-only alias of
+/// [`RequestStatusString`](crate::event::status::RequestStatusString).
 ///
 /// See <https://terminalguide.namepad.de/seq/dcs-dollar-q-space-q/> for
 /// terminal support specifics.
@@ -980,97 +1029,150 @@ pub struct SetCursorStyle {
     Hash,
     vtansi::derive::AnsiOutput,
 )]
-#[vtansi(dcs, intermediate = "$", finalbyte = 'q', data = " q")]
+#[vtansi(
+    dcs,
+    intermediate = "$",
+    finalbyte = 'q',
+    data = " q",
+    alias_of = crate::event::status::RequestStatusString
+)]
 pub struct RequestCursorStyle;
 
-/// Cursor Style Report (`DECRQSS` response).
-///
-/// Response from the terminal to [`RequestCursorStyle`].
-///
-/// Contains the current cursor style setting as reported via DECRQSS.
-///
-/// The response format is:
-/// `DCS 1 $ r Ps SP q ST`
-///
-/// Where `Ps` is the cursor style value (0-6).
-///
-/// A response with parameter `0` instead of `1` indicates the request
-/// was not recognized.
-///
-/// See <https://terminalguide.namepad.de/seq/dcs-dollar-q-space-q/> for
-/// terminal support specifics.
-#[derive(
-    Debug, Clone, Copy, PartialEq, Eq, Hash, vtansi::derive::AnsiInput,
-)]
-#[vtansi(dcs, locate_all = "data", params = ["1"], intermediate = "$", finalbyte = 'r')]
-pub struct CursorStyleReport {
-    /// The cursor style value.
-    pub style: CursorStyleReportData,
-}
+#[allow(deprecated)]
+pub use cursor_style_report::{CursorStyleReport, CursorStyleReportData};
 
-/// Wrapper for parsing cursor style from DECRQSS response data.
-///
-/// The response data format is `Ps SP q` where Ps is the cursor style value.
-/// This wrapper parses that format and extracts the [`CursorStyle`] value.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
-pub struct CursorStyleReportData(pub CursorStyle);
-
-impl vtansi::parse::TryFromAnsi<'_> for CursorStyleReportData {
-    fn try_from_ansi(bytes: &[u8]) -> Result<Self, ParseError> {
-        // Expected format: "Ps q" where Ps is a number (0-6)
-        // The space is 0x20 and q is 0x71
-        // So we need at least 3 bytes: digit, space, 'q'
-        if bytes.len() < 3 {
-            return Err(ParseError::WrongLen {
-                expected: 3,
-                got: bytes.len(),
-            });
-        }
+/// Deprecated cursor style report types, kept in their own module so the
+/// code derived for them can use them without warnings.
+mod cursor_style_report {
+    #![allow(deprecated)]
+
+    use super::{CursorStyle, EncodeError, ParseError};
+
+    /// Cursor Style Report (`DECRQSS` response).
+    ///
+    /// Response from the terminal to [`RequestCursorStyle`](super::RequestCursorStyle).
+    ///
+    /// Contains the current cursor style setting as reported via DECRQSS.
+    ///
+    /// The response format is:
+    /// `DCS 1 $ r Ps SP q ST`
+    ///
+    /// Where `Ps` is the cursor style value (0-6).
+    ///
+    /// A response with parameter `0` instead of `1` indicates the request
+    /// was not recognized.
+    ///
+    /// The input parser reports all `DECRPSS` responses as
+    /// [`StatusStringReport`](crate::event::status::StatusStringReport);
+    /// convert one with [`TryFrom`].
+    ///
+    /// See <https://terminalguide.namepad.de/seq/dcs-dollar-q-space-q/> for
+    /// terminal support specifics.
+    #[deprecated(
+        note = "match `StatusStringReport` with `StatusString::CursorStyle` \
+                instead"
+    )]
+    #[derive(
+        Debug, Clone, Copy, PartialEq, Eq, Hash, vtansi::derive::AnsiInput,
+    )]
+    #[vtansi(
+        dcs,
+        locate_all = "data",
+        params = ["1"],
+        intermediate = "$",
+        finalbyte = 'r',
+        alias_of = crate::event::status::StatusStringReport
+    )]
+    pub struct CursorStyleReport {
+        /// The cursor style value.
+        pub style: CursorStyleReportData,
+    }
 
-        // Check that it ends with " q" (space + 'q')
-        if bytes[bytes.len() - 2] != b' ' || bytes[bytes.len() - 1] != b'q' {
-            return Err(ParseError::InvalidValue(
-                "cursor style response must end with ' q'".to_string(),
-            ));
+    impl TryFrom<&crate::event::status::StatusStringReport> for CursorStyleReport {
+        type Error = ParseError;
+
+        fn try_from(
+            report: &crate::event::status::StatusStringReport,
+        ) -> Result<Self, Self::Error> {
+            match report.setting() {
+                Some(crate::event::status::StatusString::CursorStyle(set)) => {
+                    Ok(Self {
+                        style: CursorStyleReportData(set.style),
+                    })
+                }
+                _ => Err(ParseError::InvalidValue(
+                    "not a cursor style report".to_string(),
+                )),
+            }
         }
+    }
 
-        // Parse the numeric value (everything before " q")
-        let num_bytes = &bytes[..bytes.len() - 2];
-        let num: u8 = vtansi::parse::TryFromAnsi::try_from_ansi(num_bytes)?;
+    /// Wrapper for parsing cursor style from DECRQSS response data.
+    ///
+    /// The response data format is `Ps SP q` where Ps is the cursor style value.
+    /// This wrapper parses that format and extracts the [`CursorStyle`] value.
+    #[deprecated(note = "use `SetCursorStyle` instead")]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub struct CursorStyleReportData(pub CursorStyle);
+
+    impl vtansi::parse::TryFromAnsi<'_> for CursorStyleReportData {
+        fn try_from_ansi(bytes: &[u8]) -> Result<Self, ParseError> {
+            // Expected format: "Ps q" where Ps is a number (0-6)
+            // The space is 0x20 and q is 0x71
+            // So we need at least 3 bytes: digit, space, 'q'
+            if bytes.len() < 3 {
+                return Err(ParseError::WrongLen {
+                    expected: 3,
+                    got: bytes.len(),
+                });
+            }
 
-        // Convert to CursorStyle
-        let style = CursorStyle::try_from(num).map_err(|_| {
-            ParseError::InvalidValue(format!(
-                "invalid cursor style value: {num}"
-            ))
-        })?;
+            // Check that it ends with " q" (space + 'q')
+            if bytes[bytes.len() - 2] != b' ' || bytes[bytes.len() - 1] != b'q'
+            {
+                return Err(ParseError::InvalidValue(
+                    "cursor style response must end with ' q'".to_string(),
+                ));
+            }
+
+            // Parse the numeric value (everything before " q")
+            let num_bytes = &bytes[..bytes.len() - 2];
+            let num: u8 = vtansi::parse::TryFromAnsi::try_from_ansi(num_bytes)?;
+
+            // Convert to CursorStyle
+            let style = CursorStyle::try_from(num).map_err(|_| {
+                ParseError::InvalidValue(format!(
+                    "invalid cursor style value: {num}"
+                ))
+            })?;
 
-        Ok(CursorStyleReportData(style))
+            Ok(CursorStyleReportData(style))
+        }
     }
-}
 
-impl vtansi::encode::AnsiEncode for CursorStyleReportData {
-    fn encode_ansi_into<W: std::io::Write + ?Sized>(
-        &self,
-        writer: &mut W,
-    ) -> Result<usize, EncodeError> {
-        let style_byte: u8 = self.0.into();
-        let mut len = 0;
-        len += vtansi::encode::write_int(writer, style_byte)?;
-        len += vtansi::encode::write_bytes_into(writer, b" q")?;
-        Ok(len)
+    impl vtansi::encode::AnsiEncode for CursorStyleReportData {
+        fn encode_ansi_into<W: std::io::Write + ?Sized>(
+            &self,
+            writer: &mut W,
+        ) -> Result<usize, EncodeError> {
+            let style_byte: u8 = self.0.into();
+            let mut len = 0;
+            len += vtansi::encode::write_int(writer, style_byte)?;
+            len += vtansi::encode::write_bytes_into(writer, b" q")?;
+            Ok(len)
+        }
     }
-}
 
-impl From<CursorStyle> for CursorStyleReportData {
-    fn from(style: CursorStyle) -> Self {
-        Self(style)
+    impl From<CursorStyle> for CursorStyleReportData {
+        fn from(style: CursorStyle) -> Self {
+            Self(style)
+        }
     }
-}
 
-impl From<CursorStyleReportData> for CursorStyle {
-    fn from(data: CursorStyleReportData) -> Self {
-        data.0
+    impl From<CursorStyleReportData> for CursorStyle {
+        fn from(data: CursorStyleReportData) -> Self {
+            data.0
+        }
     }
 }
 
@@ -1687,6 +1789,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn test_cursor_style_report_encoding() {
         // Test encoding of DECRQSS cursor style response
         // Format: DCS 1 $ r Ps SP q ST
@@ -1722,6 +1825,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn test_cursor_style_report_data_parsing() {
         use vtansi::parse::TryFromAnsi;
 
@@ -1764,4 +1868,33 @@ mod tests {
             "Should fail with too short input"
         );
     }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_cursor_style_report_from_status_string() {
+        use crate::event::status::{StatusString, StatusStringReport};
+
+        let report = StatusStringReport::new(StatusString::CursorStyle(
+            SetCursorStyle {
+                style: CursorStyle::BlinkingBar,
+            },
+        ));
+        assert_eq!(
+            CursorStyleReport::try_from(&report).unwrap(),
+            CursorStyleReport {
+                style: CursorStyleReportData(CursorStyle::BlinkingBar),
+            }
+        );
+        assert_eq!(
+            CursorStyleReport::try_from(&report)
+                .unwrap()
+                .encode_ansi()
+                .unwrap(),
+            report.encode_ansi().unwrap()
+        );
+        assert!(
+            CursorStyleReport::try_from(&StatusStringReport::invalid())
+                .is_err()
+        );
+    }
 }
@@ -0,0 +1,973 @@
+//! Kitty graphics protocol (APC G).
+//!
+//! Graphics commands are sent as APC strings:
+//!
+//! ```text
+//! ESC _ G key=value,key=value,... ; payload ESC \
+//! ```
+//!
+//! The control data is a `,`-separated list of `key=value` pairs with
+//! single-character keys (see [`KittyGraphicsControl`]), and the payload is
+//! base64-encoded image data (or, for the file and shared memory
+//! transmission media, a base64-encoded path).  Large payloads are split
+//! over several commands, all but the last of which carry `m=1`; use
+//! [`KittyGraphicsAssembler`] to combine them.
+//!
+//! The terminal answers most commands with a [`KittyGraphicsResponse`] of
+//! the form `ESC _ G i=id ; OK ESC \` or `ESC _ G i=id ; ECODE:message
+//! ESC \`.
+//!
+//! See <https://sw.kovidgoyal.net/kitty/graphics-protocol/> for details.
+
+use std::borrow::Cow;
+
+use base64::Engine as _;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use vtansi::{
+    AnsiEncode, EncodeError, ParseError, TryFromAnsi, write_byte_into,
+    write_str_into,
+};
+
+/// Base64 engine accepting both padded and unpadded chunks.
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &base64::alphabet::STANDARD,
+    GeneralPurposeConfig::new()
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Maximum size of the encoded payload of a single chunk, as mandated by
+/// the protocol.
+pub const MAX_KITTY_GRAPHICS_CHUNK_LEN: usize = 4096;
+
+/// Maximum size of decoded image data accepted by
+/// [`KittyGraphicsAssembler`], matching the limit used by kitty itself.
+pub const MAX_KITTY_GRAPHICS_DATA_LEN: usize = 400 * 1024 * 1024;
+
+/// The action of a graphics command (`a=`).
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum::IntoStaticStr,
+    strum::Display,
+    strum::EnumString,
+    vtansi::derive::ToAnsi,
+    vtansi::derive::FromAnsi,
+)]
+pub enum KittyGraphicsAction {
+    /// Transmit image data (`t`).
+    #[default]
+    #[strum(serialize = "t")]
+    Transmit,
+    /// Transmit image data and display it (`T`).
+    #[strum(serialize = "T")]
+    TransmitAndDisplay,
+    /// Query terminal support without storing the image (`q`).
+    #[strum(serialize = "q")]
+    Query,
+    /// Display a previously transmitted image (`p`).
+    #[strum(serialize = "p")]
+    Put,
+    /// Delete images or placements (`d`).
+    #[strum(serialize = "d")]
+    Delete,
+    /// Transmit data for an animation frame (`f`).
+    #[strum(serialize = "f")]
+    Frame,
+    /// Control an animation (`a`).
+    #[strum(serialize = "a")]
+    Animate,
+    /// Compose animation frames (`c`).
+    #[strum(serialize = "c")]
+    Compose,
+}
+
+/// Pixel format of the transmitted data (`f=`).
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    num_enum::TryFromPrimitive,
+    num_enum::IntoPrimitive,
+    vtansi::derive::FromAnsi,
+    vtansi::derive::ToAnsi,
+)]
+#[repr(u8)]
+pub enum KittyGraphicsFormat {
+    /// 24-bit RGB, 3 bytes per pixel.
+    Rgb = 24,
+    /// 32-bit RGBA, 4 bytes per pixel.
+    #[default]
+    Rgba = 32,
+    /// PNG image data.
+    Png = 100,
+}
+
+/// How the image data is transmitted (`t=`).
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum::IntoStaticStr,
+    strum::Display,
+    strum::EnumString,
+    vtansi::derive::ToAnsi,
+    vtansi::derive::FromAnsi,
+)]
+pub enum KittyGraphicsMedium {
+    /// The payload is the image data (`d`).
+    #[default]
+    #[strum(serialize = "d")]
+    Direct,
+    /// The payload is the path of a file to read (`f`).
+    #[strum(serialize = "f")]
+    File,
+    /// The payload is the path of a temporary file to read and delete
+    /// (`t`).
+    #[strum(serialize = "t")]
+    TempFile,
+    /// The payload is the name of a shared memory object (`s`).
+    #[strum(serialize = "s")]
+    SharedMemory,
+}
+
+/// Compression of the image data (`o=`).
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum::IntoStaticStr,
+    strum::Display,
+    strum::EnumString,
+    vtansi::derive::ToAnsi,
+    vtansi::derive::FromAnsi,
+)]
+pub enum KittyGraphicsCompression {
+    /// RFC 1950 zlib compression (`z`).
+    #[strum(serialize = "z")]
+    Zlib,
+}
+
+/// Which responses the terminal should suppress (`q=`).
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    num_enum::TryFromPrimitive,
+    num_enum::IntoPrimitive,
+    vtansi::derive::FromAnsi,
+    vtansi::derive::ToAnsi,
+)]
+#[repr(u8)]
+pub enum KittyGraphicsQuiet {
+    /// Send all responses.
+    #[default]
+    Verbose = 0,
+    /// Suppress `OK` responses.
+    SuppressOk = 1,
+    /// Suppress all responses, including errors.
+    SuppressAll = 2,
+}
+
+/// What a delete command (`a=d`) applies to (`d=`).
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum::IntoStaticStr,
+    strum::Display,
+    strum::EnumString,
+)]
+pub enum KittyGraphicsDeleteTarget {
+    /// All placements visible on screen (`a`).
+    #[default]
+    #[strum(serialize = "a")]
+    All,
+    /// Placements of the image with the given `i=` (and `p=`) (`i`).
+    #[strum(serialize = "i")]
+    ById,
+    /// Placements of the newest image with the given `I=` (`n`).
+    #[strum(serialize = "n")]
+    ByNumber,
+    /// Placements intersecting the cursor (`c`).
+    #[strum(serialize = "c")]
+    AtCursor,
+    /// Animation frames (`f`).
+    #[strum(serialize = "f")]
+    Frames,
+    /// Placements intersecting the cell at `x=`, `y=` (`p`).
+    #[strum(serialize = "p")]
+    AtCell,
+    /// Placements intersecting the cell at `x=`, `y=` with z-index `z=`
+    /// (`q`).
+    #[strum(serialize = "q")]
+    AtCellWithZIndex,
+    /// Images with ids in the range `x=` to `y=` (`r`).
+    #[strum(serialize = "r")]
+    IdRange,
+    /// Placements intersecting column `x=` (`x`).
+    #[strum(serialize = "x")]
+    Column,
+    /// Placements intersecting row `y=` (`y`).
+    #[strum(serialize = "y")]
+    Row,
+    /// Placements with z-index `z=` (`z`).
+    #[strum(serialize = "z")]
+    ZIndex,
+}
+
+/// The `d=` value of a delete command.
+///
+/// Upper-case values additionally free the image data when no placements
+/// reference it anymore.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KittyGraphicsDelete {
+    pub target: KittyGraphicsDeleteTarget,
+    pub free: bool,
+}
+
+impl TryFromAnsi<'_> for KittyGraphicsDelete {
+    fn try_from_ansi(bytes: &[u8]) -> Result<Self, ParseError> {
+        let [byte] = bytes else {
+            return Err(ParseError::WrongLen {
+                expected: 1,
+                got: bytes.len(),
+            });
+        };
+        let lower = [byte.to_ascii_lowercase()];
+        let target = <&str>::try_from_ansi(&lower)?
+            .parse::<KittyGraphicsDeleteTarget>()
+            .map_err(|_| {
+                ParseError::InvalidValue(format!(
+                    "kitty graphics: invalid delete target: {}",
+                    char::from(*byte)
+                ))
+            })?;
+        Ok(Self {
+            target,
+            free: byte.is_ascii_uppercase(),
+        })
+    }
+}
+
+impl AnsiEncode for KittyGraphicsDelete {
+    const ENCODED_LEN: Option<usize> = Some(1);
+
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        let target = <&'static str>::from(self.target).as_bytes()[0];
+        let byte = if self.free {
+            target.to_ascii_uppercase()
+        } else {
+            target
+        };
+        write_byte_into(sink, byte)
+    }
+}
+
+/// Placement geometry of a graphics command.
+///
+/// The same keys select positions and ranges for delete commands and
+/// describe frame geometry for animation commands; the fields are named
+/// after their placement meaning.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KittyPlacementGeometry {
+    /// Left edge of the displayed source rectangle, in pixels (`x=`).
+    pub source_x: Option<u32>,
+    /// Top edge of the displayed source rectangle, in pixels (`y=`).
+    pub source_y: Option<u32>,
+    /// Width of the displayed source rectangle, in pixels (`w=`).
+    pub source_width: Option<u32>,
+    /// Height of the displayed source rectangle, in pixels (`h=`).
+    pub source_height: Option<u32>,
+    /// Horizontal offset within the first cell, in pixels (`X=`).
+    pub cell_x_offset: Option<u32>,
+    /// Vertical offset within the first cell, in pixels (`Y=`).
+    pub cell_y_offset: Option<u32>,
+    /// Number of columns to display the image over (`c=`).
+    pub columns: Option<u32>,
+    /// Number of rows to display the image over (`r=`).
+    pub rows: Option<u32>,
+    /// Stacking order relative to text and other images (`z=`).
+    pub z_index: Option<i32>,
+    /// Whether to leave the cursor in place after displaying (`C=1`).
+    pub keep_cursor: Option<bool>,
+    /// Whether to create a virtual placement for Unicode placeholders
+    /// (`U=1`).
+    pub unicode_placeholder: Option<bool>,
+    /// Image id of the parent placement (`P=`).
+    pub parent_image_id: Option<u32>,
+    /// Placement id of the parent placement (`Q=`).
+    pub parent_placement_id: Option<u32>,
+    /// Horizontal offset from the parent placement, in cells (`H=`).
+    pub parent_x_offset: Option<i32>,
+    /// Vertical offset from the parent placement, in cells (`V=`).
+    pub parent_y_offset: Option<i32>,
+}
+
+/// Control data of a graphics command or response.
+///
+/// Keys without a dedicated field are preserved in `other` so that the
+/// command can be re-emitted unchanged.  They are borrowed from the input
+/// when parsing; use [`into_owned`](Self::into_owned) to keep the control
+/// data beyond the lifetime of the input.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct KittyGraphicsControl<'a> {
+    /// The action to perform (`a=`).  Defaults to transmit.
+    pub action: Option<KittyGraphicsAction>,
+    /// Which responses to suppress (`q=`).
+    pub quiet: Option<KittyGraphicsQuiet>,
+    /// Pixel format of the data (`f=`).  Defaults to RGBA.
+    pub format: Option<KittyGraphicsFormat>,
+    /// Transmission medium (`t=`).  Defaults to direct.
+    pub medium: Option<KittyGraphicsMedium>,
+    /// Compression of the data (`o=`).
+    pub compression: Option<KittyGraphicsCompression>,
+    /// Whether more chunks follow (`m=`).
+    pub more: Option<bool>,
+    /// Image id (`i=`).
+    pub image_id: Option<u32>,
+    /// Image number (`I=`), for which the terminal assigns an id.
+    pub image_number: Option<u32>,
+    /// Placement id (`p=`).
+    pub placement_id: Option<u32>,
+    /// Width of the image data, in pixels (`s=`).
+    pub data_width: Option<u32>,
+    /// Height of the image data, in pixels (`v=`).
+    pub data_height: Option<u32>,
+    /// Number of bytes to read from a file (`S=`).
+    pub data_size: Option<u32>,
+    /// Offset to read from in a file (`O=`).
+    pub data_offset: Option<u32>,
+    /// What to delete (`d=`).
+    pub delete: Option<KittyGraphicsDelete>,
+    /// Placement geometry.
+    pub placement: KittyPlacementGeometry,
+    /// Any other `key=value` pairs, in the order they appeared.
+    pub other: Vec<(Cow<'a, str>, Cow<'a, str>)>,
+}
+
+impl KittyGraphicsControl<'_> {
+    /// Return the action, applying the protocol default.
+    #[must_use]
+    pub fn action(&self) -> KittyGraphicsAction {
+        self.action.unwrap_or_default()
+    }
+
+    /// Return `true` if more chunks follow this command.
+    #[must_use]
+    pub fn has_more(&self) -> bool {
+        self.more.unwrap_or(false)
+    }
+
+    /// Convert to a version that does not borrow from the input.
+    #[must_use]
+    pub fn into_owned(self) -> KittyGraphicsControl<'static> {
+        KittyGraphicsControl {
+            other: self
+                .other
+                .into_iter()
+                .map(|(key, value)| {
+                    (
+                        Cow::Owned(key.into_owned()),
+                        Cow::Owned(value.into_owned()),
+                    )
+                })
+                .collect(),
+            ..self
+        }
+    }
+}
+
+impl<'a> TryFromAnsi<'a> for KittyGraphicsControl<'a> {
+    fn try_from_ansi(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        fn value<'a, T: TryFromAnsi<'a>>(
+            value: &'a [u8],
+        ) -> Result<Option<T>, ParseError> {
+            T::try_from_ansi(value).map(Some)
+        }
+
+        let mut control = Self::default();
+        if bytes.is_empty() {
+            return Ok(control);
+        }
+        let placement = &mut control.placement;
+        for pair in vtansi::parse_keyvalue_pairs(bytes, b',') {
+            let (key, v) = pair?;
+            match key {
+                b"a" => control.action = value(v)?,
+                b"q" => control.quiet = value(v)?,
+                b"f" => control.format = value(v)?,
+                b"t" => control.medium = value(v)?,
+                b"o" => control.compression = value(v)?,
+                b"m" => control.more = value(v)?,
+                b"i" => control.image_id = value(v)?,
+                b"I" => control.image_number = value(v)?,
+                b"p" => control.placement_id = value(v)?,
+                b"s" => control.data_width = value(v)?,
+                b"v" => control.data_height = value(v)?,
+                b"S" => control.data_size = value(v)?,
+                b"O" => control.data_offset = value(v)?,
+                b"d" => control.delete = value(v)?,
+                b"x" => placement.source_x = value(v)?,
+                b"y" => placement.source_y = value(v)?,
+                b"w" => placement.source_width = value(v)?,
+                b"h" => placement.source_height = value(v)?,
+                b"X" => placement.cell_x_offset = value(v)?,
+                b"Y" => placement.cell_y_offset = value(v)?,
+                b"c" => placement.columns = value(v)?,
+                b"r" => placement.rows = value(v)?,
+                b"z" => placement.z_index = value(v)?,
+                b"C" => placement.keep_cursor = value(v)?,
+                b"U" => placement.unicode_placeholder = value(v)?,
+                b"P" => placement.parent_image_id = value(v)?,
+                b"Q" => placement.parent_placement_id = value(v)?,
+                b"H" => placement.parent_x_offset = value(v)?,
+                b"V" => placement.parent_y_offset = value(v)?,
+                _ => control.other.push((
+                    Cow::Borrowed(<&str>::try_from_ansi(key)?),
+                    Cow::Borrowed(<&str>::try_from_ansi(v)?),
+                )),
+            }
+        }
+        Ok(control)
+    }
+}
+
+/// Writer for `,`-separated `key=value` pairs.
+struct PairWriter<'w, W: std::io::Write + ?Sized> {
+    sink: &'w mut W,
+    written: usize,
+}
+
+impl<W: std::io::Write + ?Sized> PairWriter<'_, W> {
+    fn pair(
+        &mut self,
+        key: &str,
+        value: Option<impl AnsiEncode>,
+    ) -> Result<(), EncodeError> {
+        if let Some(value) = value {
+            if self.written > 0 {
+                self.written += write_byte_into(self.sink, b',')?;
+            }
+            self.written += write_str_into(self.sink, key)?;
+            self.written += write_byte_into(self.sink, b'=')?;
+            self.written += value.encode_ansi_into(self.sink)?;
+        }
+        Ok(())
+    }
+}
+
+impl AnsiEncode for KittyGraphicsControl<'_> {
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        let placement = &self.placement;
+        let mut w = PairWriter { sink, written: 0 };
+        w.pair("a", self.action)?;
+        w.pair("q", self.quiet)?;
+        w.pair("f", self.format)?;
+        w.pair("t", self.medium)?;
+        w.pair("o", self.compression)?;
+        w.pair("i", self.image_id)?;
+        w.pair("I", self.image_number)?;
+        w.pair("p", self.placement_id)?;
+        w.pair("s", self.data_width)?;
+        w.pair("v", self.data_height)?;
+        w.pair("S", self.data_size)?;
+        w.pair("O", self.data_offset)?;
+        w.pair("d", self.delete)?;
+        w.pair("x", placement.source_x)?;
+        w.pair("y", placement.source_y)?;
+        w.pair("w", placement.source_width)?;
+        w.pair("h", placement.source_height)?;
+        w.pair("X", placement.cell_x_offset)?;
+        w.pair("Y", placement.cell_y_offset)?;
+        w.pair("c", placement.columns)?;
+        w.pair("r", placement.rows)?;
+        w.pair("z", placement.z_index)?;
+        w.pair("C", placement.keep_cursor)?;
+        w.pair("U", placement.unicode_placeholder)?;
+        w.pair("P", placement.parent_image_id)?;
+        w.pair("Q", placement.parent_placement_id)?;
+        w.pair("H", placement.parent_x_offset)?;
+        w.pair("V", placement.parent_y_offset)?;
+        for (key, value) in &self.other {
+            w.pair(key, Some(value.as_ref()))?;
+        }
+        w.pair("m", self.more)?;
+        Ok(w.written)
+    }
+}
+
+/// Kitty graphics command (`ESC _ G control ; payload ESC \`).
+///
+/// The payload is kept base64-encoded; use
+/// [`decoded_payload`](Self::decoded_payload) to decode a single command,
+/// or [`KittyGraphicsAssembler`] to combine chunked transmissions.
+///
+/// See <https://sw.kovidgoyal.net/kitty/graphics-protocol/> for details.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput)]
+#[vtansi(escst, finalbyte = '_', data = "G")]
+pub struct KittyGraphicsCommand<'a> {
+    pub control: KittyGraphicsControl<'a>,
+    pub payload: Option<&'a str>,
+}
+
+impl<'a> KittyGraphicsCommand<'a> {
+    /// Create a command with the given control data and encoded payload.
+    #[must_use]
+    pub fn new(
+        control: KittyGraphicsControl<'a>,
+        payload: Option<&'a str>,
+    ) -> Self {
+        Self { control, payload }
+    }
+
+    /// Return the base64-decoded payload.
+    ///
+    /// # Errors
+    ///
+    /// Return [`ParseError::InvalidValue`] if the payload is not valid
+    /// base64.
+    pub fn decoded_payload(&self) -> Result<Vec<u8>, ParseError> {
+        decode_payload(self.payload.unwrap_or_default())
+    }
+
+    /// Write `data` as a series of commands, splitting it into chunks of
+    /// at most [`MAX_KITTY_GRAPHICS_CHUNK_LEN`] encoded bytes.
+    ///
+    /// The first command carries `control`; the following ones only carry
+    /// the `m=` key and, if set, the `q=` key, as required by the protocol.
+    ///
+    /// # Errors
+    ///
+    /// Return an error if writing to `sink` fails.
+    pub fn encode_chunked_into<W: std::io::Write + ?Sized>(
+        control: &KittyGraphicsControl<'_>,
+        data: &[u8],
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        let encoded = BASE64.encode(data);
+        let mut chunks = encoded
+            .as_bytes()
+            .chunks(MAX_KITTY_GRAPHICS_CHUNK_LEN)
+            .peekable();
+        if chunks.peek().is_none() {
+            return KittyGraphicsCommand::new(control.clone(), None)
+                .encode_ansi_into(sink);
+        }
+
+        let mut written = 0;
+        let mut first = true;
+        while let Some(chunk) = chunks.next() {
+            let mut chunk_control = if first {
+                control.clone()
+            } else {
+                KittyGraphicsControl {
+                    quiet: control.quiet,
+                    ..Default::default()
+                }
+            };
+            chunk_control.more = Some(chunks.peek().is_some());
+            first = false;
+            // Base64 output is always ASCII.
+            let payload = std::str::from_utf8(chunk).unwrap_or_default();
+            written += KittyGraphicsCommand::new(chunk_control, Some(payload))
+                .encode_ansi_into(sink)?;
+        }
+        Ok(written)
+    }
+}
+
+fn decode_payload(payload: &str) -> Result<Vec<u8>, ParseError> {
+    BASE64.decode(payload).map_err(|err| {
+        ParseError::InvalidValue(format!(
+            "kitty graphics: invalid payload: {err}"
+        ))
+    })
+}
+
+/// Kitty graphics response from the terminal
+/// (`ESC _ G control ; message ESC \`).
+///
+/// The control data identifies the command being answered (`i=`, `I=`
+/// and `p=`).  The message is `OK` on success, or an error code followed
+/// by `:` and a description.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, vtansi::derive::AnsiInput)]
+#[vtansi(escst, finalbyte = '_', data = "G")]
+pub struct KittyGraphicsResponse<'a> {
+    pub control: KittyGraphicsControl<'a>,
+    pub message: &'a str,
+}
+
+impl<'a> KittyGraphicsResponse<'a> {
+    /// Return `true` if the command succeeded.
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.message == "OK"
+    }
+
+    /// Return the error code (e.g. `ENOENT`), or `None` on success.
+    #[must_use]
+    pub fn error_code(&self) -> Option<&'a str> {
+        if self.is_ok() {
+            return None;
+        }
+        self.message.split(':').next()
+    }
+
+    /// Return the error description, or `None` on success.
+    #[must_use]
+    pub fn error_message(&self) -> Option<&'a str> {
+        if self.is_ok() {
+            return None;
+        }
+        self.message.split_once(':').map(|(_, message)| message)
+    }
+}
+
+/// A graphics command combined from one or more chunks.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct KittyGraphicsTransmission {
+    /// Control data of the first chunk.
+    pub control: KittyGraphicsControl<'static>,
+    /// The decoded payload of all chunks.
+    pub data: Vec<u8>,
+}
+
+/// Reassemble chunked kitty graphics commands.
+///
+/// Feed every [`KittyGraphicsCommand`] to [`feed`](Self::feed); commands
+/// with `m=1` start or continue a chunked transmission, which is returned
+/// once the final chunk arrives.  Commands that are not chunked are
+/// returned immediately.  The protocol does not allow chunked
+/// transmissions to be interleaved, so at most one is pending at a time.
+#[derive(Debug, Default)]
+pub struct KittyGraphicsAssembler {
+    pending: Option<KittyGraphicsTransmission>,
+}
+
+impl KittyGraphicsAssembler {
+    /// Create a new, empty assembler.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return `true` if a chunked transmission is in progress.
+    #[must_use]
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Add a command chunk.
+    ///
+    /// Return the complete transmission once its last chunk is fed.
+    ///
+    /// # Errors
+    ///
+    /// Return [`ParseError::InvalidValue`] if the payload cannot be
+    /// decoded, or if the combined data exceeds
+    /// [`MAX_KITTY_GRAPHICS_DATA_LEN`].  The partial transmission is
+    /// discarded in that case.
+    pub fn feed(
+        &mut self,
+        chunk: &KittyGraphicsCommand<'_>,
+    ) -> Result<Option<KittyGraphicsTransmission>, ParseError> {
+        let payload = match chunk.decoded_payload() {
+            Ok(payload) => payload,
+            Err(err) => {
+                self.pending = None;
+                return Err(err);
+            }
+        };
+        let mut pending =
+            self.pending
+                .take()
+                .unwrap_or_else(|| KittyGraphicsTransmission {
+                    control: KittyGraphicsControl {
+                        more: None,
+                        ..chunk.control.clone().into_owned()
+                    },
+                    data: Vec::new(),
+                });
+        if pending.data.len() + payload.len() > MAX_KITTY_GRAPHICS_DATA_LEN {
+            return Err(ParseError::InvalidValue(format!(
+                "kitty graphics data exceeds \
+                 {MAX_KITTY_GRAPHICS_DATA_LEN} bytes"
+            )));
+        }
+        pending.data.extend_from_slice(&payload);
+
+        if chunk.control.has_more() {
+            self.pending = Some(pending);
+            Ok(None)
+        } else {
+            Ok(Some(pending))
+        }
+    }
+
+    /// Discard a partially received transmission.
+    pub fn clear(&mut self) {
+        self.pending = None;
+    }
+}
+
+impl AnsiEncode for KittyGraphicsTransmission {
+    /// Encode the transmission as a series of chunked commands.
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        KittyGraphicsCommand::encode_chunked_into(
+            &self.control,
+            &self.data,
+            sink,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{TerminalInputParser, TerminalOutputParser};
+    use better_any::TidExt;
+
+    fn parse_commands(
+        input: &[u8],
+    ) -> Vec<(KittyGraphicsControl<'static>, String)> {
+        let mut commands = Vec::new();
+        TerminalOutputParser::new().feed_with(input, &mut |event| {
+            if let Some(cmd) = event.downcast_ref::<KittyGraphicsCommand>() {
+                commands.push((
+                    cmd.control.clone().into_owned(),
+                    cmd.payload.unwrap_or_default().to_string(),
+                ));
+            }
+        });
+        commands
+    }
+
+    #[test]
+    fn test_parse_command() {
+        let commands = parse_commands(
+            b"\x1b_Ga=T,f=100,t=d,i=31,p=7,c=10,r=5,z=-1,C=1,m=1;iVBO\x1b\\",
+        );
+        assert_eq!(commands.len(), 1);
+        let (control, payload) = &commands[0];
+        assert_eq!(
+            control.action,
+            Some(KittyGraphicsAction::TransmitAndDisplay)
+        );
+        assert_eq!(control.format, Some(KittyGraphicsFormat::Png));
+        assert_eq!(control.medium, Some(KittyGraphicsMedium::Direct));
+        assert_eq!(control.image_id, Some(31));
+        assert_eq!(control.placement_id, Some(7));
+        assert_eq!(control.placement.columns, Some(10));
+        assert_eq!(control.placement.rows, Some(5));
+        assert_eq!(control.placement.z_index, Some(-1));
+        assert_eq!(control.placement.keep_cursor, Some(true));
+        assert!(control.has_more());
+        assert_eq!(payload, "iVBO");
+    }
+
+    #[test]
+    fn test_parse_delete_without_payload() {
+        let commands = parse_commands(b"\x1b_Ga=d,d=N,I=5\x1b\\");
+        assert_eq!(commands.len(), 1);
+        let (control, payload) = &commands[0];
+        assert_eq!(control.action(), KittyGraphicsAction::Delete);
+        assert_eq!(
+            control.delete,
+            Some(KittyGraphicsDelete {
+                target: KittyGraphicsDeleteTarget::ByNumber,
+                free: true,
+            })
+        );
+        assert!(payload.is_empty());
+    }
+
+    #[test]
+    fn test_events_keep_stream_order() {
+        let mut events = Vec::new();
+        TerminalOutputParser::new().feed_with(
+            b"a\x1b_Ga=q,i=1;AAAA\x1b\\b\x1b_Xunknown\x1b\\c",
+            &mut |event| {
+                events.push(
+                    String::from_utf8(event.encode_ansi().unwrap()).unwrap(),
+                );
+            },
+        );
+        assert_eq!(
+            events,
+            vec![
+                "a",
+                "\x1b_Ga=q,i=1;AAAA\x1b\\",
+                "b",
+                "\x1b_Xunknown\x1b\\",
+                "c"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let input: &[u8] =
+            b"\x1b_Ga=p,q=2,i=1,p=2,x=10,y=20,w=30,h=40,X=1,Y=2,U=1,P=3,Q=4,H=-2,V=5,K=z\x1b\\";
+        let mut encoded = None;
+        TerminalOutputParser::new().feed_with(input, &mut |event| {
+            encoded = event
+                .downcast_ref::<KittyGraphicsCommand>()
+                .map(|cmd| cmd.encode_ansi().unwrap());
+        });
+        assert_eq!(encoded.as_deref(), Some(input));
+    }
+
+    #[test]
+    fn test_invalid_control() {
+        assert!(KittyGraphicsControl::try_from_ansi(b"a=x").is_err());
+        assert!(KittyGraphicsControl::try_from_ansi(b"i=abc").is_err());
+        assert!(KittyGraphicsControl::try_from_ansi(b"d=k").is_err());
+        assert!(KittyGraphicsControl::try_from_ansi(b"f=16").is_err());
+    }
+
+    #[test]
+    fn test_response() {
+        let mut responses = Vec::new();
+        TerminalInputParser::new().feed_with(
+            b"\x1b_Gi=31;OK\x1b\\\x1b_Gi=2,p=1;ENOENT:Put command refers to non-existent image\x1b\\",
+            &mut |event| {
+                if let Some(response) =
+                    event.downcast_ref::<KittyGraphicsResponse>()
+                {
+                    responses.push((
+                        response.control.image_id,
+                        response.is_ok(),
+                        response.error_code().map(str::to_string),
+                        response.error_message().map(str::to_string),
+                    ));
+                }
+            },
+        );
+        assert_eq!(
+            responses,
+            vec![
+                (Some(31), true, None, None),
+                (
+                    Some(2),
+                    false,
+                    Some("ENOENT".to_string()),
+                    Some(
+                        "Put command refers to non-existent image".to_string()
+                    )
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_response_after_bracketed_paste() {
+        let input = b"\x1b[200~foo\x1b]bar\x1b[201~\x1b_Gi=1;OK\x1b\\";
+        // Both in one go and split right after the paste
+        for split in [input.len(), 21] {
+            let mut parser = TerminalInputParser::new();
+            let mut responses = Vec::new();
+            for chunk in [&input[..split], &input[split..]] {
+                parser.feed_with(chunk, &mut |event| {
+                    if let Some(response) =
+                        event.downcast_ref::<KittyGraphicsResponse>()
+                    {
+                        responses.push(response.control.image_id);
+                    }
+                });
+            }
+            assert_eq!(responses, vec![Some(1)]);
+        }
+    }
+
+    #[test]
+    fn test_chunked_round_trip() {
+        let data: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        let control = KittyGraphicsControl {
+            action: Some(KittyGraphicsAction::TransmitAndDisplay),
+            quiet: Some(KittyGraphicsQuiet::SuppressOk),
+            image_id: Some(9),
+            data_width: Some(50),
+            data_height: Some(50),
+            ..Default::default()
+        };
+        let mut encoded = Vec::new();
+        KittyGraphicsCommand::encode_chunked_into(
+            &control,
+            &data,
+            &mut encoded,
+        )
+        .unwrap();
+
+        let commands = parse_commands(&encoded);
+        assert_eq!(commands.len(), 4);
+        assert_eq!(commands[1].0.image_id, None);
+        assert_eq!(commands[1].0.quiet, Some(KittyGraphicsQuiet::SuppressOk));
+        assert_eq!(commands[3].0.more, Some(false));
+
+        let mut assembler = KittyGraphicsAssembler::new();
+        let mut result = None;
+        for (control, payload) in &commands {
+            let cmd = KittyGraphicsCommand::new(control.clone(), Some(payload));
+            result = assembler.feed(&cmd).unwrap();
+            if result.is_none() {
+                assert!(assembler.is_pending());
+            }
+        }
+        let result = result.unwrap();
+        assert_eq!(result.control, control);
+        assert_eq!(result.data, data);
+        assert!(!assembler.is_pending());
+    }
+
+    #[test]
+    fn test_assembler_invalid_chunk() {
+        let mut assembler = KittyGraphicsAssembler::new();
+        let first = KittyGraphicsCommand::new(
+            KittyGraphicsControl {
+                more: Some(true),
+                ..Default::default()
+            },
+            Some("AAAA"),
+        );
+        assert_eq!(assembler.feed(&first).unwrap(), None);
+        let bad = KittyGraphicsCommand::new(
+            KittyGraphicsControl::default(),
+            Some("!!"),
+        );
+        assert!(assembler.feed(&bad).is_err());
+        assert!(!assembler.is_pending());
+    }
+}
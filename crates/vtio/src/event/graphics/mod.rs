@@ -0,0 +1,7 @@
+//! Terminal graphics protocols.
+//!
+//! - [`kitty`]: the kitty graphics protocol (`APC G`).
+//! - [`sixel`]: DEC sixel graphics (`DCS q`).
+
+pub mod kitty;
+pub mod sixel;
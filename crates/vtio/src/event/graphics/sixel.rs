@@ -0,0 +1,749 @@
+//! Sixel graphics (`DCS q`).
+//!
+//! A sixel image is sent as a DCS string:
+//!
+//! ```text
+//! ESC P P1 ; P2 ; P3 q sixel-data ESC \
+//! ```
+//!
+//! `P1` selects the pixel aspect ratio, `P2` whether unset pixels are
+//! transparent and `P3` the horizontal grid size.  The sixel data is a
+//! stream of commands:
+//!
+//! - `" Pan ; Pad ; Ph ; Pv`: raster attributes (pixel aspect ratio and
+//!   image size), see [`SixelRasterAttributes`];
+//! - `# Pc ; Pu ; Px ; Py ; Pz`: define color register `Pc` in the RGB
+//!   (`Pu` = 2) or HLS (`Pu` = 1) color space, see [`SixelColor`];
+//! - `# Pc`: select color register `Pc`;
+//! - `! Pn c`: repeat sixel `c` `Pn` times;
+//! - `$`: return to the start of the current sixel row;
+//! - `-`: move to the start of the next sixel row;
+//! - `?` to `~`: a column of six vertical pixels, bit 0 at the top.
+//!
+//! Terminals announce sixel support with
+//! [`TerminalCapability::SixelGraphics`](crate::event::terminal::TerminalCapability::SixelGraphics)
+//! in their primary device attributes.  [`SixelDecoder`] turns sixel data
+//! into an RGBA pixel buffer.
+//!
+//! See <https://vt100.net/docs/vt3xx-gp/chapter14.html> for details.
+
+use vtansi::{AnsiEncode, EncodeError, ParseError, TryFromAnsi};
+
+/// Number of color registers supported by [`SixelDecoder`].
+///
+/// Register numbers beyond this limit wrap around.
+pub const SIXEL_COLOR_REGISTERS: usize = 1024;
+
+/// Default maximum width and height of an image produced by
+/// [`SixelDecoder`].
+///
+/// Pixels outside of this area are discarded, and raster attributes
+/// declaring a larger image are clamped to it, since the decoded image is
+/// allocated from the declared size.  Use
+/// [`SixelDecoder::with_max_dimensions`] to change the limit.
+pub const MAX_SIXEL_DIMENSION: usize = 2048;
+
+/// A color register definition in the sixel color space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SixelColor {
+    /// Red, green and blue intensities, in percent (0-100).
+    Rgb { r: u8, g: u8, b: u8 },
+    /// Hue angle in degrees (0-360, with blue at 0 and red at 120),
+    /// lightness and saturation in percent (0-100).
+    Hls { h: u16, l: u8, s: u8 },
+}
+
+impl SixelColor {
+    /// Convert the color to 8-bit RGB components.
+    #[must_use]
+    pub fn to_rgb8(self) -> [u8; 3] {
+        match self {
+            Self::Rgb { r, g, b } => {
+                [percent_to_u8(r), percent_to_u8(g), percent_to_u8(b)]
+            }
+            Self::Hls { h, l, s } => hls_to_rgb8(h, l, s),
+        }
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn percent_to_u8(value: u8) -> u8 {
+    ((u32::from(value.min(100)) * 255 + 50) / 100) as u8
+}
+
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::many_single_char_names
+)]
+fn hls_to_rgb8(h: u16, l: u8, s: u8) -> [u8; 3] {
+    // DEC hue angles start at blue; shift them so that 0 is red.
+    let h = f64::from((h % 360 + 240) % 360) / 360.0;
+    let l = f64::from(l.min(100)) / 100.0;
+    let s = f64::from(s.min(100)) / 100.0;
+    if s == 0.0 {
+        let v = (l * 255.0).round() as u8;
+        return [v, v, v];
+    }
+    let q = if l < 0.5 {
+        l * (1.0 + s)
+    } else {
+        l + s - l * s
+    };
+    let p = 2.0 * l - q;
+    let channel = |t: f64| {
+        let t = t.rem_euclid(1.0);
+        let v = if t < 1.0 / 6.0 {
+            p + (q - p) * 6.0 * t
+        } else if t < 0.5 {
+            q
+        } else if t < 2.0 / 3.0 {
+            p + (q - p) * (2.0 / 3.0 - t) * 6.0
+        } else {
+            p
+        };
+        (v * 255.0).round() as u8
+    };
+    [channel(h + 1.0 / 3.0), channel(h), channel(h - 1.0 / 3.0)]
+}
+
+/// A color register definition (`# Pc ; Pu ; Px ; Py ; Pz`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SixelColorRegister {
+    /// The register number, wrapped to [`SIXEL_COLOR_REGISTERS`].
+    pub index: u16,
+    /// The color assigned to the register.
+    pub color: SixelColor,
+}
+
+/// Raster attributes (`" Pan ; Pad ; Ph ; Pv`).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SixelRasterAttributes {
+    /// Pixel aspect ratio numerator (vertical).
+    pub pan: u32,
+    /// Pixel aspect ratio denominator (horizontal).
+    pub pad: u32,
+    /// Image width in pixels, if specified.
+    pub width: Option<u32>,
+    /// Image height in pixels, if specified.
+    pub height: Option<u32>,
+}
+
+impl SixelRasterAttributes {
+    fn from_params(params: &[u32]) -> Self {
+        let nonzero = |i: usize| params.get(i).copied().filter(|&v| v > 0);
+        Self {
+            pan: nonzero(0).unwrap_or(1),
+            pad: nonzero(1).unwrap_or(1),
+            width: nonzero(2),
+            height: nonzero(3),
+        }
+    }
+}
+
+/// Incremental parser for sixel data commands.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum CommandState {
+    #[default]
+    Ground,
+    Raster,
+    Color,
+    Repeat,
+}
+
+/// A parsed sixel data command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command<'p> {
+    Raster(&'p [u32]),
+    Color(&'p [u32]),
+    Sixel { bits: u8, count: u32 },
+    CarriageReturn,
+    NewLine,
+}
+
+const MAX_COMMAND_PARAMS: usize = 5;
+
+#[derive(Debug, Default, Clone)]
+struct CommandParser {
+    state: CommandState,
+    params: [u32; MAX_COMMAND_PARAMS],
+    param_count: usize,
+    in_param: bool,
+}
+
+impl CommandParser {
+    fn start(&mut self, state: CommandState) {
+        self.state = state;
+        self.params = [0; MAX_COMMAND_PARAMS];
+        self.param_count = 0;
+        self.in_param = false;
+    }
+
+    fn params(&self) -> &[u32] {
+        let count = self.param_count + usize::from(self.in_param);
+        &self.params[..count.min(MAX_COMMAND_PARAMS)]
+    }
+
+    /// Feed one byte, calling `f` for every complete command.
+    fn advance(&mut self, byte: u8, f: &mut impl FnMut(Command<'_>)) {
+        if self.state != CommandState::Ground {
+            match byte {
+                b'0'..=b'9' => {
+                    if self.param_count < MAX_COMMAND_PARAMS {
+                        let param = &mut self.params[self.param_count];
+                        *param = param
+                            .saturating_mul(10)
+                            .saturating_add(u32::from(byte - b'0'));
+                    }
+                    self.in_param = true;
+                    return;
+                }
+                b';' => {
+                    self.param_count += 1;
+                    self.in_param = false;
+                    return;
+                }
+                _ => {
+                    let state = std::mem::take(&mut self.state);
+                    match state {
+                        CommandState::Raster => {
+                            f(Command::Raster(self.params()));
+                        }
+                        CommandState::Color => {
+                            f(Command::Color(self.params()));
+                        }
+                        CommandState::Repeat => {
+                            if let b'?'..=b'~' = byte {
+                                f(Command::Sixel {
+                                    bits: byte - b'?',
+                                    count: self.params[0].max(1),
+                                });
+                                return;
+                            }
+                        }
+                        CommandState::Ground => {}
+                    }
+                }
+            }
+        }
+
+        match byte {
+            b'"' => self.start(CommandState::Raster),
+            b'#' => self.start(CommandState::Color),
+            b'!' => self.start(CommandState::Repeat),
+            b'$' => f(Command::CarriageReturn),
+            b'-' => f(Command::NewLine),
+            b'?'..=b'~' => f(Command::Sixel {
+                bits: byte - b'?',
+                count: 1,
+            }),
+            _ => {}
+        }
+    }
+
+    /// Complete a command pending at the end of the data.
+    fn finish(&mut self, f: &mut impl FnMut(Command<'_>)) {
+        // Any byte that is not a parameter terminates the command.
+        self.advance(0, f);
+    }
+}
+
+fn color_definition(params: &[u32]) -> Option<SixelColorRegister> {
+    let &[index, space, x, y, z, ..] = params else {
+        return None;
+    };
+    #[allow(clippy::cast_possible_truncation)]
+    let percent = |v: u32| v.min(100) as u8;
+    #[allow(clippy::cast_possible_truncation)]
+    let color = match space {
+        1 => SixelColor::Hls {
+            h: x.min(360) as u16,
+            l: percent(y),
+            s: percent(z),
+        },
+        2 => SixelColor::Rgb {
+            r: percent(x),
+            g: percent(y),
+            b: percent(z),
+        },
+        _ => return None,
+    };
+    #[allow(clippy::cast_possible_truncation)]
+    Some(SixelColorRegister {
+        index: color_register(index) as u16,
+        color,
+    })
+}
+
+/// Map a register number to one of the [`SIXEL_COLOR_REGISTERS`] registers.
+const fn color_register(index: u32) -> usize {
+    index as usize % SIXEL_COLOR_REGISTERS
+}
+
+/// Sixel data of a [`Sixel`] image.
+///
+/// The raster attributes and color register definitions are parsed when
+/// the data is decoded; the pixels themselves are only decoded on demand
+/// with [`SixelDecoder`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct SixelData<'a> {
+    bytes: &'a [u8],
+    raster_attributes: Option<SixelRasterAttributes>,
+    color_registers: Vec<SixelColorRegister>,
+}
+
+impl<'a> SixelData<'a> {
+    /// Parse sixel data.
+    #[must_use]
+    pub fn new(bytes: &'a [u8]) -> Self {
+        let mut raster_attributes = None;
+        let mut color_registers = Vec::new();
+        let mut seen_sixel = false;
+        let mut parser = CommandParser::default();
+        let mut handle = |command: Command<'_>| match command {
+            Command::Raster(params) if !seen_sixel => {
+                raster_attributes
+                    .get_or_insert(SixelRasterAttributes::from_params(params));
+            }
+            Command::Color(params) => {
+                color_registers.extend(color_definition(params));
+            }
+            Command::Sixel { .. } => seen_sixel = true,
+            _ => {}
+        };
+        for &byte in bytes {
+            parser.advance(byte, &mut handle);
+        }
+        parser.finish(&mut handle);
+        Self {
+            bytes,
+            raster_attributes,
+            color_registers,
+        }
+    }
+
+    /// Return the raw sixel data.
+    #[must_use]
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Return the raster attributes, if the image specifies them.
+    #[must_use]
+    pub fn raster_attributes(&self) -> Option<SixelRasterAttributes> {
+        self.raster_attributes
+    }
+
+    /// Return the color register definitions, in the order they appear.
+    #[must_use]
+    pub fn color_registers(&self) -> &[SixelColorRegister] {
+        &self.color_registers
+    }
+}
+
+impl<'a> TryFromAnsi<'a> for SixelData<'a> {
+    fn try_from_ansi(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        Ok(Self::new(bytes))
+    }
+}
+
+impl AnsiEncode for SixelData<'_> {
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        vtansi::write_bytes_into(sink, self.bytes)
+    }
+}
+
+/// Sixel image (`DCS P1 ; P2 ; P3 q data ST`).
+///
+/// See <https://vt100.net/docs/vt3xx-gp/chapter14.html> for details.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput)]
+#[vtansi(dcs, finalbyte = 'q')]
+pub struct Sixel<'a> {
+    /// Pixel aspect ratio selector (`P1`); superseded by raster
+    /// attributes.
+    pub aspect_ratio: Option<u8>,
+    /// Background select (`P2`): `1` leaves unset pixels transparent, `0`
+    /// and `2` fill them with the background color.
+    pub background: Option<u8>,
+    /// Horizontal grid size (`P3`); ignored by most terminals.
+    pub grid_size: Option<u16>,
+    /// The sixel data.
+    #[vtansi(locate = "data")]
+    pub data: SixelData<'a>,
+}
+
+impl<'a> Sixel<'a> {
+    /// Create a sixel image from raw sixel data, with transparent
+    /// background.
+    #[must_use]
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            aspect_ratio: Some(0),
+            background: Some(1),
+            grid_size: None,
+            data: SixelData::new(data),
+        }
+    }
+
+    /// Return `true` if unset pixels are transparent.
+    #[must_use]
+    pub fn is_transparent(&self) -> bool {
+        self.background == Some(1)
+    }
+
+    /// Decode the image into RGBA pixels.
+    #[must_use]
+    pub fn decode(&self) -> SixelImage {
+        let mut decoder = SixelDecoder::new();
+        decoder.feed(self.data.as_bytes());
+        decoder.finish()
+    }
+}
+
+/// A decoded sixel image.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct SixelImage {
+    /// Image width in pixels.
+    pub width: usize,
+    /// Image height in pixels.
+    pub height: usize,
+    /// Row-major RGBA pixels, 4 bytes per pixel.  Pixels that were never
+    /// set are fully transparent.
+    pub pixels: Vec<u8>,
+}
+
+impl SixelImage {
+    /// Return the RGBA value of the pixel at `(x, y)`.
+    #[must_use]
+    pub fn pixel(&self, x: usize, y: usize) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let offset = (y * self.width + x) * 4;
+        self.pixels[offset..offset + 4].try_into().ok()
+    }
+}
+
+/// The VT340 default color palette, in percent.
+const DEFAULT_PALETTE: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (20, 20, 80),
+    (80, 13, 13),
+    (20, 80, 20),
+    (80, 20, 80),
+    (20, 80, 80),
+    (80, 80, 20),
+    (53, 53, 53),
+    (26, 26, 26),
+    (33, 33, 60),
+    (60, 26, 26),
+    (33, 60, 33),
+    (60, 33, 60),
+    (33, 60, 60),
+    (60, 60, 33),
+    (80, 80, 80),
+];
+
+/// Streaming sixel decoder.
+///
+/// Sixel data can be fed in arbitrary pieces with [`feed`](Self::feed), so
+/// that images can be decoded while they are being received; the image is
+/// returned by [`finish`](Self::finish).  The image grows as pixels are
+/// drawn, and is at least as large as the raster attributes specify, up to
+/// the maximum dimensions ([`MAX_SIXEL_DIMENSION`] by default).  Pixel
+/// storage is only allocated for drawn rows until the image is finished.
+/// Pixels are decoded with a 1:1 aspect ratio.
+#[derive(Debug, Clone)]
+pub struct SixelDecoder {
+    parser: CommandParser,
+    palette: Vec<[u8; 4]>,
+    color: [u8; 4],
+    raster_attributes: Option<SixelRasterAttributes>,
+    seen_sixel: bool,
+    x: usize,
+    y: usize,
+    max_width: usize,
+    max_height: usize,
+    /// Pixel rows, each as long as its rightmost set pixel.
+    rows: Vec<Vec<[u8; 4]>>,
+}
+
+impl Default for SixelDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SixelDecoder {
+    /// Create a decoder with the VT340 default palette.
+    #[must_use]
+    pub fn new() -> Self {
+        let mut palette = vec![[0, 0, 0, 255]; SIXEL_COLOR_REGISTERS];
+        for (entry, &(r, g, b)) in palette.iter_mut().zip(&DEFAULT_PALETTE) {
+            let [r, g, b] = SixelColor::Rgb { r, g, b }.to_rgb8();
+            *entry = [r, g, b, 255];
+        }
+        Self {
+            parser: CommandParser::default(),
+            color: palette[0],
+            palette,
+            raster_attributes: None,
+            seen_sixel: false,
+            x: 0,
+            y: 0,
+            max_width: MAX_SIXEL_DIMENSION,
+            max_height: MAX_SIXEL_DIMENSION,
+            rows: Vec::new(),
+        }
+    }
+
+    /// Set the maximum width and height of the decoded image.
+    ///
+    /// Pixels outside of this area are discarded.
+    #[must_use]
+    pub fn with_max_dimensions(mut self, width: usize, height: usize) -> Self {
+        self.max_width = width;
+        self.max_height = height;
+        self
+    }
+
+    /// Return the raster attributes, once they have been decoded.
+    #[must_use]
+    pub fn raster_attributes(&self) -> Option<SixelRasterAttributes> {
+        self.raster_attributes
+    }
+
+    /// Decode a piece of sixel data.
+    pub fn feed(&mut self, data: &[u8]) {
+        let mut parser = std::mem::take(&mut self.parser);
+        for &byte in data {
+            parser.advance(byte, &mut |command| self.apply(command));
+        }
+        self.parser = parser;
+    }
+
+    /// Finish decoding and return the image.
+    #[must_use]
+    pub fn finish(mut self) -> SixelImage {
+        let mut parser = std::mem::take(&mut self.parser);
+        parser.finish(&mut |command| self.apply(command));
+
+        let raster = self.raster_attributes.unwrap_or_default();
+        let raster_width = raster.width.map_or(0, |w| w as usize);
+        let raster_height = raster.height.map_or(0, |h| h as usize);
+        let drawn_height = self
+            .rows
+            .iter()
+            .rposition(|row| !row.is_empty())
+            .map_or(0, |y| y + 1);
+        let drawn_width = self.rows.iter().map(Vec::len).max().unwrap_or(0);
+        let width = raster_width.max(drawn_width).min(self.max_width);
+        let height = raster_height.max(drawn_height).min(self.max_height);
+
+        let mut pixels = vec![0; width * height * 4];
+        for (row, line) in self
+            .rows
+            .iter()
+            .take(height)
+            .zip(pixels.chunks_exact_mut(width * 4))
+        {
+            for (pixel, out) in row.iter().zip(line.chunks_exact_mut(4)) {
+                out.copy_from_slice(pixel);
+            }
+        }
+        SixelImage {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    fn apply(&mut self, command: Command<'_>) {
+        match command {
+            Command::Raster(params) => {
+                if !self.seen_sixel && self.raster_attributes.is_none() {
+                    self.raster_attributes =
+                        Some(SixelRasterAttributes::from_params(params));
+                }
+            }
+            Command::Color(params) => {
+                if let Some(register) = color_definition(params) {
+                    let [r, g, b] = register.color.to_rgb8();
+                    self.palette[usize::from(register.index)] = [r, g, b, 255];
+                }
+                if let Some(&register) = params.first() {
+                    self.color = self.palette[color_register(register)];
+                }
+            }
+            Command::Sixel { bits, count } => {
+                self.seen_sixel = true;
+                self.draw(bits, count as usize);
+            }
+            Command::CarriageReturn => self.x = 0,
+            Command::NewLine => {
+                self.x = 0;
+                self.y += 6;
+            }
+        }
+    }
+
+    fn draw(&mut self, bits: u8, count: usize) {
+        let start = self.x.min(self.max_width);
+        let end = self.x.saturating_add(count).min(self.max_width);
+        self.x = self.x.saturating_add(count);
+        if bits == 0 || start == end {
+            return;
+        }
+        for bit in 0..6 {
+            if bits & (1 << bit) == 0 {
+                continue;
+            }
+            let y = self.y + bit;
+            if y >= self.max_height {
+                break;
+            }
+            if self.rows.len() <= y {
+                self.rows.resize_with(y + 1, Vec::new);
+            }
+            let row = &mut self.rows[y];
+            if row.len() < end {
+                row.resize(end, [0; 4]);
+            }
+            row[start..end].fill(self.color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::TerminalOutputParser;
+    use better_any::TidExt;
+
+    #[test]
+    fn test_parse_sixel() {
+        let input =
+            b"\x1bP0;1;0q\"1;1;4;6#0;2;100;0;0#1;1;120;50;100#0~~!2@-#1N\x1b\\";
+        let mut parsed = None;
+        TerminalOutputParser::new().feed_with(input, &mut |event| {
+            if let Some(sixel) = event.downcast_ref::<Sixel>() {
+                parsed = Some((
+                    sixel.is_transparent(),
+                    sixel.data.raster_attributes(),
+                    sixel.data.color_registers().to_vec(),
+                    sixel.encode_ansi().unwrap(),
+                ));
+            }
+        });
+        let (transparent, raster, colors, encoded) = parsed.unwrap();
+        assert!(transparent);
+        assert_eq!(
+            raster,
+            Some(SixelRasterAttributes {
+                pan: 1,
+                pad: 1,
+                width: Some(4),
+                height: Some(6),
+            })
+        );
+        assert_eq!(
+            colors,
+            vec![
+                SixelColorRegister {
+                    index: 0,
+                    color: SixelColor::Rgb { r: 100, g: 0, b: 0 },
+                },
+                SixelColorRegister {
+                    index: 1,
+                    color: SixelColor::Hls {
+                        h: 120,
+                        l: 50,
+                        s: 100
+                    },
+                },
+            ]
+        );
+        assert_eq!(encoded, input);
+    }
+
+    #[test]
+    fn test_decode() {
+        let sixel = Sixel::new(b"#0;2;100;0;0#1;1;120;50;100#0~~!2@-#1N");
+        let image = sixel.decode();
+        assert_eq!((image.width, image.height), (4, 10));
+        let red = Some([255, 0, 0, 255]);
+        let transparent = Some([0, 0, 0, 0]);
+        assert_eq!(image.pixel(0, 0), red);
+        assert_eq!(image.pixel(1, 5), red);
+        // `!2@` sets the top pixel of columns 2 and 3.
+        assert_eq!(image.pixel(3, 0), red);
+        assert_eq!(image.pixel(3, 1), transparent);
+        // HLS hue 120 is red in the DEC color wheel.
+        assert_eq!(image.pixel(0, 6), red);
+        assert_eq!(image.pixel(0, 9), red);
+        assert_eq!(image.pixel(1, 7), transparent);
+        assert_eq!(image.pixel(4, 0), None);
+    }
+
+    #[test]
+    fn test_streaming_decode() {
+        let data: &[u8] = b"\"1;1;8;12#3!8~$#2!4?!4~-#15!8B";
+        let mut whole = SixelDecoder::new();
+        whole.feed(data);
+        let whole = whole.finish();
+
+        let mut streaming = SixelDecoder::new();
+        for byte in data.chunks(1) {
+            streaming.feed(byte);
+        }
+        assert_eq!(
+            streaming.raster_attributes().and_then(|r| r.width),
+            Some(8)
+        );
+        let streaming = streaming.finish();
+        assert_eq!(streaming, whole);
+        assert_eq!((whole.width, whole.height), (8, 12));
+        // Default palette: register 2 is red, overdrawn on the right half.
+        assert_eq!(whole.pixel(0, 0), Some([51, 204, 51, 255]));
+        assert_eq!(whole.pixel(7, 0), Some([204, 33, 33, 255]));
+        assert_eq!(whole.pixel(0, 7), Some([204, 204, 204, 255]));
+        assert_eq!(whole.pixel(0, 8), Some([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_decode_limits() {
+        // Raster attributes alone must not allocate an oversized image.
+        let image = Sixel::new(b"\"1;1;8192;8192").decode();
+        assert_eq!(
+            (image.width, image.height),
+            (MAX_SIXEL_DIMENSION, MAX_SIXEL_DIMENSION)
+        );
+
+        let mut decoder = SixelDecoder::new().with_max_dimensions(4, 6);
+        decoder.feed(b"\"1;1;100;100#0!10~-~");
+        let image = decoder.finish();
+        assert_eq!((image.width, image.height), (4, 6));
+        assert_eq!(image.pixels.len(), 4 * 6 * 4);
+    }
+
+    #[test]
+    fn test_color_register_wraparound() {
+        // Register numbers wrap consistently when defining and selecting.
+        let image = Sixel::new(b"#70000;2;100;0;0#70000~").decode();
+        assert_eq!(image.pixel(0, 0), Some([255, 0, 0, 255]));
+        let data = SixelData::new(b"#70000;2;100;0;0");
+        assert_eq!(data.color_registers()[0].index, 368);
+    }
+
+    #[test]
+    fn test_hls_conversion() {
+        let hls = |h, l, s| SixelColor::Hls { h, l, s }.to_rgb8();
+        assert_eq!(hls(0, 50, 100), [0, 0, 255]);
+        assert_eq!(hls(240, 50, 100), [0, 255, 0]);
+        assert_eq!(hls(0, 100, 0), [255, 255, 255]);
+        assert_eq!(hls(0, 0, 100), [0, 0, 0]);
+    }
+}
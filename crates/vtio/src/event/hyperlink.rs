@@ -0,0 +1,418 @@
+//! Hyperlink sequences (OSC 8).
+//!
+//! Hyperlinks attach a URI to the text written after `OSC 8 ; params ; URI
+//! ST`, until the link is closed with `OSC 8 ; ; ST`:
+//!
+//! ```text
+//! ESC ] 8 ; id=42 ; https://example.com ESC \ link text ESC ] 8 ; ; ESC \
+//! ```
+//!
+//! The `params` field is a list of `key=value` pairs separated by `:`.
+//! The only key currently defined is `id`, which allows terminals to
+//! recognize separate runs of text (e.g. split by a redraw) as the same
+//! link.  Any `OSC 8` with an empty URI closes the link, including ones
+//! that carry parameters (`OSC 8 ; id=42 ; ST`).
+//!
+//! Hyperlinks are normally written by applications to the terminal
+//! ([`Hyperlink`], [`HyperlinkEnd`]), but they also show up on the input
+//! side, e.g. in text pasted from another terminal or echoed back by a
+//! nested multiplexer; those are parsed as [`HyperlinkInput`] and
+//! [`HyperlinkEndInput`].
+//!
+//! See <https://gist.github.com/egmontkob/eb114294efbcd5adb1944c9f3cb5feda>
+//! for the specification.
+
+use std::ops::Deref;
+
+use vtansi::{
+    AnsiEncode, EncodeError, ParseError, TryFromAnsi, write_byte_into,
+    write_str_into,
+};
+
+/// Parameters of an OSC 8 hyperlink.
+///
+/// Keys other than `id` are preserved in `other` so that the link can be
+/// re-emitted unchanged.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct HyperlinkParams<'a> {
+    /// The link identifier (`id=`).
+    pub id: Option<&'a str>,
+    /// Any other `key=value` pairs, in the order they appeared.
+    pub other: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> HyperlinkParams<'a> {
+    /// Create parameters with the given link identifier.
+    #[must_use]
+    pub fn with_id(id: &'a str) -> Self {
+        Self {
+            id: Some(id),
+            other: Vec::new(),
+        }
+    }
+
+    /// Return `true` if there are no parameters.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.id.is_none() && self.other.is_empty()
+    }
+}
+
+impl<'a> TryFromAnsi<'a> for HyperlinkParams<'a> {
+    fn try_from_ansi(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        let mut params = Self::default();
+        if bytes.is_empty() {
+            return Ok(params);
+        }
+        for pair in vtansi::parse_keyvalue_pairs(bytes, b':') {
+            let (key, value) = pair?;
+            let key = <&str>::try_from_ansi(key)?;
+            let value = <&str>::try_from_ansi(value)?;
+            if key == "id" {
+                params.id = Some(value);
+            } else {
+                params.other.push((key, value));
+            }
+        }
+        Ok(params)
+    }
+}
+
+impl AnsiEncode for HyperlinkParams<'_> {
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        let mut written = 0;
+        let pairs = self
+            .id
+            .iter()
+            .map(|id| ("id", *id))
+            .chain(self.other.iter().copied());
+        for (i, (key, value)) in pairs.enumerate() {
+            if i > 0 {
+                written += write_byte_into(sink, b':')?;
+            }
+            written += write_str_into(sink, key)?;
+            written += write_byte_into(sink, b'=')?;
+            written += write_str_into(sink, value)?;
+        }
+        Ok(written)
+    }
+}
+
+/// The parameters and URI of an OSC 8 hyperlink.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct HyperlinkTarget<'a> {
+    /// Link parameters.
+    pub params: HyperlinkParams<'a>,
+    /// The link URI.
+    pub uri: &'a str,
+}
+
+impl<'a> TryFromAnsi<'a> for HyperlinkTarget<'a> {
+    fn try_from_ansi(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        // The URI may itself contain `;`, so split on the first one only.
+        let sep = bytes.iter().position(|&b| b == b';').ok_or_else(|| {
+            ParseError::InvalidValue(
+                "hyperlink: missing URI separator".to_string(),
+            )
+        })?;
+        let uri = <&str>::try_from_ansi(&bytes[sep + 1..])?;
+        if uri.is_empty() {
+            return Err(ParseError::InvalidValue(
+                "hyperlink: empty URI".to_string(),
+            ));
+        }
+        Ok(Self {
+            params: HyperlinkParams::try_from_ansi(&bytes[..sep])?,
+            uri,
+        })
+    }
+}
+
+impl AnsiEncode for HyperlinkTarget<'_> {
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        let mut written = self.params.encode_ansi_into(sink)?;
+        written += write_byte_into(sink, b';')?;
+        written += write_str_into(sink, self.uri)?;
+        Ok(written)
+    }
+}
+
+/// Start a hyperlink (`OSC 8 ; params ; URI ST`).
+///
+/// Text written after this sequence is part of the link until a
+/// [`HyperlinkEnd`] or another `Hyperlink` is received.
+///
+/// See <https://gist.github.com/egmontkob/eb114294efbcd5adb1944c9f3cb5feda>
+/// for terminal support specifics.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput)]
+#[vtansi(osc, number = "8")]
+pub struct Hyperlink<'a>(pub HyperlinkTarget<'a>);
+
+impl<'a> Hyperlink<'a> {
+    /// Create a hyperlink to `uri` without parameters.
+    #[must_use]
+    pub fn new(uri: &'a str) -> Self {
+        Self(HyperlinkTarget {
+            params: HyperlinkParams::default(),
+            uri,
+        })
+    }
+
+    /// Create a hyperlink to `uri` with the given link identifier.
+    #[must_use]
+    pub fn with_id(id: &'a str, uri: &'a str) -> Self {
+        Self(HyperlinkTarget {
+            params: HyperlinkParams::with_id(id),
+            uri,
+        })
+    }
+}
+
+impl<'a> Deref for Hyperlink<'a> {
+    type Target = HyperlinkTarget<'a>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// End the current hyperlink (`OSC 8 ; ; ST`).
+///
+/// See <https://gist.github.com/egmontkob/eb114294efbcd5adb1944c9f3cb5feda>
+/// for terminal support specifics.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput,
+)]
+#[vtansi(osc, number = "8", data = ";")]
+pub struct HyperlinkEnd;
+
+/// A hyperlink start (`OSC 8 ; params ; URI ST`) received as input.
+///
+/// The input-side counterpart of [`Hyperlink`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, vtansi::derive::AnsiInput)]
+#[vtansi(osc, number = "8")]
+pub struct HyperlinkInput<'a>(pub HyperlinkTarget<'a>);
+
+impl<'a> Deref for HyperlinkInput<'a> {
+    type Target = HyperlinkTarget<'a>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// A hyperlink end (`OSC 8 ; ; ST`) received as input.
+///
+/// The input-side counterpart of [`HyperlinkEnd`].
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, vtansi::derive::AnsiInput,
+)]
+#[vtansi(osc, number = "8", data = ";")]
+pub struct HyperlinkEndInput;
+
+/// Handle `OSC 8 ; params ; ST`, i.e. a hyperlink end carrying parameters.
+///
+/// The derived [`HyperlinkEnd`] only matches the exact `OSC 8 ; ; ST`
+/// form, and [`Hyperlink`] rejects an empty URI, so this is registered
+/// under the bare `OSC 8` key and is tried after both of them fail.
+fn parse_hyperlink_end_with_params(data: &[u8]) -> Result<(), ParseError> {
+    let target = data.strip_prefix(b";").ok_or_else(|| {
+        ParseError::InvalidValue(
+            "hyperlink: missing params separator".to_string(),
+        )
+    })?;
+    let sep = target.iter().position(|&b| b == b';').ok_or_else(|| {
+        ParseError::InvalidValue("hyperlink: missing URI separator".to_string())
+    })?;
+    if sep + 1 != target.len() {
+        return Err(ParseError::InvalidValue(
+            "hyperlink: not a hyperlink end".to_string(),
+        ));
+    }
+    HyperlinkParams::try_from_ansi(&target[..sep])?;
+    Ok(())
+}
+
+fn hyperlink_end_with_params_output_handler(
+    data: &vtansi::registry::AnsiEventData<'_>,
+    cb: &mut vtansi::registry::AnsiEmitFn,
+) -> Result<(), ParseError> {
+    parse_hyperlink_end_with_params(data.get_data().unwrap_or_default())?;
+    cb(&HyperlinkEnd);
+    Ok(())
+}
+
+fn hyperlink_end_with_params_input_handler(
+    data: &vtansi::registry::AnsiEventData<'_>,
+    cb: &mut vtansi::registry::AnsiEmitFn,
+) -> Result<(), ParseError> {
+    parse_hyperlink_end_with_params(data.get_data().unwrap_or_default())?;
+    cb(&HyperlinkEndInput);
+    Ok(())
+}
+
+#[linkme::distributed_slice(
+    vtansi::registry::ANSI_CONTROL_OUTPUT_FUNCTION_REGISTRY
+)]
+static HYPERLINK_END_WITH_PARAMS_OUTPUT:
+    vtansi::registry::AnsiControlFunctionMatchEntry =
+    vtansi::registry::AnsiControlFunctionMatchEntry {
+        name: "HyperlinkEnd",
+        key: b"\x1b]\x008",
+        kind: vtansi::AnsiControlFunctionKind::Osc,
+        prefix: b"8",
+        final_byte: None,
+        handler: hyperlink_end_with_params_output_handler,
+    };
+
+#[linkme::distributed_slice(
+    vtansi::registry::ANSI_CONTROL_INPUT_FUNCTION_REGISTRY
+)]
+static HYPERLINK_END_WITH_PARAMS_INPUT:
+    vtansi::registry::AnsiControlFunctionMatchEntry =
+    vtansi::registry::AnsiControlFunctionMatchEntry {
+        name: "HyperlinkEndInput",
+        key: b"\x1b]\x008",
+        kind: vtansi::AnsiControlFunctionKind::Osc,
+        prefix: b"8",
+        final_byte: None,
+        handler: hyperlink_end_with_params_input_handler,
+    };
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{TerminalInputParser, TerminalOutputParser};
+    use better_any::TidExt;
+    use vtansi::StaticAnsiEncode;
+
+    #[test]
+    fn test_hyperlink_end_encoding() {
+        assert_eq!(HyperlinkEnd::BYTES, b"\x1b]8;;\x1b\\");
+    }
+
+    #[test]
+    fn test_hyperlink_encoding() {
+        assert_eq!(
+            Hyperlink::new("https://example.com").encode_ansi().unwrap(),
+            b"\x1b]8;;https://example.com\x1b\\"
+        );
+        assert_eq!(
+            Hyperlink::with_id("a1", "file:///tmp/x")
+                .encode_ansi()
+                .unwrap(),
+            b"\x1b]8;id=a1;file:///tmp/x\x1b\\"
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let input = b"\x1b]8;id=1:foo=bar;https://example.com/?a=1;b=2\x1b\\\
+                      link\
+                      \x1b]8;;\x1b\\\
+                      \x1b]8;;http://x\x07";
+        let mut links = Vec::new();
+        let mut ends = 0;
+        TerminalOutputParser::new().feed_with(input, &mut |event| {
+            if let Some(link) = event.downcast_ref::<Hyperlink>() {
+                links.push((
+                    link.params.id.map(str::to_string),
+                    link.params
+                        .other
+                        .iter()
+                        .map(|(k, v)| format!("{k}={v}"))
+                        .collect::<Vec<_>>(),
+                    link.uri.to_string(),
+                ));
+            } else if event.downcast_ref::<HyperlinkEnd>().is_some() {
+                ends += 1;
+            }
+        });
+        assert_eq!(
+            links,
+            vec![
+                (
+                    Some("1".to_string()),
+                    vec!["foo=bar".to_string()],
+                    "https://example.com/?a=1;b=2".to_string()
+                ),
+                (None, vec![], "http://x".to_string()),
+            ]
+        );
+        assert_eq!(ends, 1);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let link = Hyperlink(HyperlinkTarget {
+            params: HyperlinkParams {
+                id: Some("x"),
+                other: vec![("k", "v")],
+            },
+            uri: "https://example.com/a;b",
+        });
+        let encoded = link.encode_ansi().unwrap();
+        let mut parsed = None;
+        TerminalOutputParser::new().feed_with(&encoded, &mut |event| {
+            parsed = event
+                .downcast_ref::<Hyperlink>()
+                .map(|l| l.encode_ansi().unwrap());
+        });
+        assert_eq!(parsed, Some(encoded));
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        assert!(HyperlinkTarget::try_from_ansi(b"id;https://x").is_err());
+        assert!(HyperlinkTarget::try_from_ansi(b"id=1").is_err());
+    }
+
+    #[test]
+    fn test_end_with_params() {
+        let mut ends = 0;
+        let mut other = 0;
+        TerminalOutputParser::new().feed_with(
+            b"\x1b]8;id=x;\x1b\\\x1b]8;;\x1b\\",
+            &mut |event| {
+                if event.downcast_ref::<HyperlinkEnd>().is_some() {
+                    ends += 1;
+                } else {
+                    other += 1;
+                }
+            },
+        );
+        assert_eq!((ends, other), (2, 0));
+    }
+
+    #[test]
+    fn test_parse_input() {
+        let input = b"\x1b]8;id=1;https://example.com\x1b\\\
+                      \x1b]8;id=1;\x1b\\\
+                      \x1b]8;;\x07";
+        let mut links = Vec::new();
+        let mut ends = 0;
+        TerminalInputParser::new().feed_with(input, &mut |event| {
+            if let Some(link) = event.downcast_ref::<HyperlinkInput>() {
+                links.push((
+                    link.params.id.map(str::to_string),
+                    link.uri.to_string(),
+                ));
+            } else if event.downcast_ref::<HyperlinkEndInput>().is_some() {
+                ends += 1;
+            }
+        });
+        assert_eq!(
+            links,
+            vec![(Some("1".to_string()), "https://example.com".to_string())]
+        );
+        assert_eq!(ends, 2);
+    }
+}
@@ -0,0 +1,355 @@
+//! Owned terminal input events.
+//!
+//! [`TerminalInputParser::feed_with`](crate::TerminalInputParser::feed_with)
+//! hands out borrowed `&dyn AnsiEvent` values that only live for the
+//! duration of the callback.  [`InputEvent`] is an owned, matchable
+//! alternative: events can be queued, sent across threads and matched
+//! exhaustively.  Use
+//! [`TerminalInputParser::feed_owned`](crate::TerminalInputParser::feed_owned)
+//! to obtain them directly from the parser.
+
+use better_any::TidExt;
+use vtansi::{AnsiEncode, AnsiEvent, EncodeError};
+
+use crate::event::cursor::CursorPositionReport;
+use crate::event::dsr::{DsrReport, OperatingStatusReport};
+use crate::event::keyboard::{
+    KeyEvent, KeyboardEnhancementFlagsResponse, ModifyOtherKeys,
+    ModifyOtherKeysReport,
+};
+use crate::event::mode::{ModeControl, ModeFunction, TerminalModeState};
+use crate::event::mouse::MouseEvent;
+use crate::event::status::StatusStringReport;
+use crate::event::terminal::{
+    BracketedPaste, FocusGained, FocusLost, PrimaryDeviceAttributesResponse,
+    SecondaryDeviceAttributesResponse, TerminalNameAndVersionResponse,
+    TerminalNameAndVersionResponseOwned, TertiaryDeviceAttributesResponse,
+};
+use crate::event::text::PlainText;
+use crate::event::window::InBandResizeReport;
+
+/// Mode state report (`DECRPM`) for an arbitrary mode.
+///
+/// Every mode defined with [`terminal_mode!`](crate::terminal_mode) has
+/// its own report type; this is the type-erased form used by
+/// [`InputEvent::Mode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ModeReport {
+    /// Whether this is a DEC private mode (`CSI ? Ps ; Pm $ y`).
+    pub private: bool,
+    /// The mode number.
+    pub mode: u16,
+    /// The reported state.
+    pub state: TerminalModeState,
+}
+
+impl ModeReport {
+    /// Return `event` as a mode report, if it is the report type of a
+    /// [`terminal_mode!`](crate::terminal_mode) mode, or a report for a
+    /// mode the parser does not know.
+    #[must_use]
+    pub fn from_event(event: &dyn AnsiEvent<'_>) -> Option<Self> {
+        let control = ModeControl::from_event(event)?;
+        let ModeFunction::Report(state) = control.function else {
+            return None;
+        };
+        Some(Self {
+            private: control.private,
+            mode: control.modes[0],
+            state,
+        })
+    }
+}
+
+impl AnsiEncode for ModeReport {
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        let mut n = vtansi::write_bytes_into(sink, b"\x1b[")?;
+        if self.private {
+            n += vtansi::write_byte_into(sink, b'?')?;
+        }
+        n += vtansi::write_int(sink, self.mode)?;
+        n += vtansi::write_byte_into(sink, b';')?;
+        n += vtansi::write_int(sink, u8::from(self.state))?;
+        n += vtansi::write_bytes_into(sink, b"$y")?;
+        Ok(n)
+    }
+}
+
+/// An owned terminal input event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputEvent {
+    /// A key press, repeat or release.
+    Key(KeyEvent),
+    /// A mouse event.
+    Mouse(MouseEvent),
+    /// Bracketed paste contents.
+    Paste(Vec<u8>),
+    /// The terminal gained focus.
+    FocusGained,
+    /// The terminal lost focus.
+    FocusLost,
+    /// Plain text.
+    Text(String),
+    /// In-band window resize notification.
+    Resize(InBandResizeReport),
+    /// Mode state report (`DECRPM`).
+    Mode(ModeReport),
+    /// Keyboard enhancement flags report.
+    KeyboardEnhancementFlags(KeyboardEnhancementFlagsResponse),
+    /// xterm `modifyOtherKeys` report (`XTQMODKEYS` response).
+    ModifyOtherKeys(ModifyOtherKeys),
+    /// Cursor position report (`CPR`).
+    CursorPosition(CursorPositionReport),
+    /// Operating status report (`DSR 0`).
+    OperatingStatus,
+    /// Private device status report (`CSI ? Ps n`).
+    DeviceStatus(DsrReport),
+    /// Primary device attributes (`DA1`).
+    PrimaryDeviceAttributes(PrimaryDeviceAttributesResponse),
+    /// Secondary device attributes (`DA2`).
+    SecondaryDeviceAttributes(SecondaryDeviceAttributesResponse),
+    /// Tertiary device attributes (`DA3`).
+    TertiaryDeviceAttributes(TertiaryDeviceAttributesResponse),
+    /// Terminal name and version (`XTVERSION`).
+    TerminalNameAndVersion(TerminalNameAndVersionResponseOwned),
+    /// Status string report (`DECRPSS`).
+    StatusString(StatusStringReport),
+    /// Any other event, as its encoded bytes.
+    ///
+    /// Unrecognized sequences are passed through verbatim; recognized
+    /// events without a dedicated variant are re-encoded.
+    Raw(Vec<u8>),
+    /// An event without a dedicated variant that could not be re-encoded.
+    Unencodable {
+        /// The terse representation of the event.
+        event: String,
+        /// The encoding error.
+        error: String,
+    },
+}
+
+impl InputEvent {
+    /// Convert a borrowed parser event into an owned event.
+    #[must_use]
+    pub fn from_event(event: &dyn AnsiEvent<'_>) -> Self {
+        if let Some(key) = event.downcast_ref::<KeyEvent>() {
+            Self::Key(key.clone())
+        } else if let Some(mouse) = event.downcast_ref::<MouseEvent>() {
+            Self::Mouse(*mouse)
+        } else if let Some(paste) = event.downcast_ref::<BracketedPaste>() {
+            Self::Paste(paste.0.to_vec())
+        } else if event.is::<FocusGained>() {
+            Self::FocusGained
+        } else if event.is::<FocusLost>() {
+            Self::FocusLost
+        } else if let Some(text) = event.downcast_ref::<PlainText>() {
+            Self::Text(text.0.to_string())
+        } else if let Some(resize) = event.downcast_ref::<InBandResizeReport>()
+        {
+            Self::Resize(*resize)
+        } else if let Some(flags) =
+            event.downcast_ref::<KeyboardEnhancementFlagsResponse>()
+        {
+            Self::KeyboardEnhancementFlags(*flags)
+        } else if let Some(report) =
+            event.downcast_ref::<ModifyOtherKeysReport>()
+        {
+            Self::ModifyOtherKeys(report.0)
+        } else if let Some(cpr) = event.downcast_ref::<CursorPositionReport>() {
+            Self::CursorPosition(*cpr)
+        } else if event.is::<OperatingStatusReport>() {
+            Self::OperatingStatus
+        } else if let Some(dsr) = event.downcast_ref::<DsrReport>() {
+            Self::DeviceStatus(dsr.clone())
+        } else if let Some(da) =
+            event.downcast_ref::<PrimaryDeviceAttributesResponse>()
+        {
+            Self::PrimaryDeviceAttributes(da.clone())
+        } else if let Some(da) =
+            event.downcast_ref::<SecondaryDeviceAttributesResponse>()
+        {
+            Self::SecondaryDeviceAttributes(*da)
+        } else if let Some(da) =
+            event.downcast_ref::<TertiaryDeviceAttributesResponse>()
+        {
+            Self::TertiaryDeviceAttributes(da.clone())
+        } else if let Some(version) =
+            event.downcast_ref::<TerminalNameAndVersionResponse>()
+        {
+            Self::TerminalNameAndVersion(version.to_owned())
+        } else if let Some(report) = event.downcast_ref::<StatusStringReport>()
+        {
+            Self::StatusString(report.clone())
+        } else if let Some(report) = ModeReport::from_event(event) {
+            Self::Mode(report)
+        } else {
+            match event.encode_ansi() {
+                Ok(bytes) => Self::Raw(bytes),
+                Err(err) => Self::Unencodable {
+                    event: format!("{event:?}"),
+                    error: err.to_string(),
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TerminalInputParser;
+    use crate::event::{KeyCode, KeyModifiers};
+
+    #[test]
+    fn test_feed_owned() {
+        let mut parser = TerminalInputParser::new();
+        let events: Vec<_> = parser
+            .feed_owned(
+                b"a\x1b[I\x1b[200~hi\x1b[201~\x1b[?2004;1$y\x1b[4;2$y\
+                  \x1b[12;40R\x1b[48;24;80;384;640t\x1b[1t",
+            )
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                InputEvent::Key(KeyEvent::new(
+                    KeyCode::Char('a'),
+                    KeyModifiers::empty()
+                )),
+                InputEvent::FocusGained,
+                InputEvent::Paste(b"hi".to_vec()),
+                InputEvent::Mode(ModeReport {
+                    private: true,
+                    mode: 2004,
+                    state: TerminalModeState::Set,
+                }),
+                InputEvent::Mode(ModeReport {
+                    private: false,
+                    mode: 4,
+                    state: TerminalModeState::Reset,
+                }),
+                InputEvent::CursorPosition(CursorPositionReport {
+                    row: 12,
+                    col: 40,
+                }),
+                InputEvent::Resize(InBandResizeReport {
+                    rows: 24,
+                    cols: 80,
+                    height: 384,
+                    width: 640,
+                }),
+                InputEvent::Raw(b"\x1b[1t".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_mode_report() {
+        let mut parser = TerminalInputParser::new();
+        let events: Vec<_> = parser
+            .feed_owned(b"\x1b[?9999;1$y\x1b[12;0$y\x1b[?9999;7$y")
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                InputEvent::Mode(ModeReport {
+                    private: true,
+                    mode: 9999,
+                    state: TerminalModeState::Set,
+                }),
+                InputEvent::Mode(ModeReport {
+                    private: false,
+                    mode: 12,
+                    state: TerminalModeState::NotRecognized,
+                }),
+                InputEvent::Raw(b"\x1b[?9999;7$y".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mode_report_round_trip() {
+        let report = ModeReport {
+            private: true,
+            mode: 1049,
+            state: TerminalModeState::PermanentlyReset,
+        };
+        let bytes = report.encode_ansi().unwrap();
+        assert_eq!(bytes, b"\x1b[?1049;4$y");
+        let mut parser = TerminalInputParser::new();
+        assert_eq!(
+            parser.feed_owned(&bytes).collect::<Vec<_>>(),
+            vec![InputEvent::Mode(report)]
+        );
+    }
+
+    #[test]
+    fn test_idle_owned() {
+        let mut parser = TerminalInputParser::new();
+        assert_eq!(parser.feed_owned(b"\x1b").count(), 0);
+        assert_eq!(
+            parser.idle_owned().collect::<Vec<_>>(),
+            vec![InputEvent::Key(KeyEvent::new(
+                KeyCode::Esc,
+                KeyModifiers::empty()
+            ))]
+        );
+    }
+
+    #[test]
+    fn test_unencodable_event() {
+        struct Broken;
+
+        better_any::tid! {Broken}
+
+        impl vtansi::TerseDisplay for Broken {
+            fn terse_fmt(
+                &self,
+                f: &mut std::fmt::Formatter<'_>,
+            ) -> std::fmt::Result {
+                f.write_str("broken")
+            }
+        }
+
+        impl AnsiEncode for Broken {
+            fn encode_ansi_into<W: std::io::Write + ?Sized>(
+                &self,
+                _sink: &mut W,
+            ) -> Result<usize, EncodeError> {
+                Err(EncodeError::Unencodeable("nope".to_string()))
+            }
+        }
+
+        impl vtansi::AnsiEvent<'_> for Broken {
+            fn ansi_control_kind(
+                &self,
+            ) -> Option<vtansi::AnsiControlFunctionKind> {
+                None
+            }
+
+            fn ansi_direction(&self) -> vtansi::AnsiControlDirection {
+                vtansi::AnsiControlDirection::Input
+            }
+
+            vtansi::impl_ansi_event_encode!();
+            vtansi::impl_ansi_event_terse_fmt!();
+        }
+
+        assert_eq!(
+            InputEvent::from_event(&Broken),
+            InputEvent::Unencodable {
+                event: "broken".to_string(),
+                error: "Unencodeable value: nope".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_events_are_send() {
+        fn assert_send<T: Send + 'static>() {}
+        assert_send::<InputEvent>();
+    }
+}
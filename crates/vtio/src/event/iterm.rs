@@ -16,8 +16,29 @@
 //!
 //! This module provides type-safe wrappers for known sequences and a
 //! generic mechanism for encoding arbitrary key=value pairs.
+//!
+//! Files and inline images are transferred either in a single
+//! [`File`] sequence, or split into a [`MultipartFile`] header, any number
+//! of [`FilePart`] chunks and a final [`FileEnd`].  The base64 payload can
+//! be decoded incrementally with [`FilePayloadDecoder`].
 
+use base64::Engine as _;
+use base64::engine::DecodePaddingMode;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig};
 use vtansi::derive::{FromAnsi, ToAnsi};
+use vtansi::{
+    AnsiEncode, EncodeError, ParseError, TryFromAnsi, write_byte_into,
+    write_int, write_str_into,
+};
+
+/// Base64 engine for file names and payloads.
+///
+/// Encodes with padding and accepts input with or without it.
+const BASE64: GeneralPurpose = GeneralPurpose::new(
+    &base64::alphabet::STANDARD,
+    GeneralPurposeConfig::new()
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
 
 /// Set a mark at the current cursor position.
 ///
@@ -587,3 +608,600 @@ pub struct AddHiddenAnnotation<'a> {
     pub message: AnnotationMessage<'a>,
     pub coords: Option<AnnotationCoords>,
 }
+
+/// Width or height of a file displayed inline.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileDimension {
+    /// Use the image's inherent size (`auto`).
+    #[default]
+    Auto,
+    /// A number of character cells (`N`).
+    Cells(u32),
+    /// A number of pixels (`Npx`).
+    Pixels(u32),
+    /// A percentage of the session's width or height (`N%`).
+    Percent(u32),
+}
+
+impl<'a> TryFromAnsi<'a> for FileDimension {
+    fn try_from_ansi(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        if bytes == b"auto" {
+            return Ok(Self::Auto);
+        }
+        if let Some(pixels) = bytes.strip_suffix(b"px") {
+            Ok(Self::Pixels(u32::try_from_ansi(pixels)?))
+        } else if let Some(percent) = bytes.strip_suffix(b"%") {
+            Ok(Self::Percent(u32::try_from_ansi(percent)?))
+        } else {
+            Ok(Self::Cells(u32::try_from_ansi(bytes)?))
+        }
+    }
+}
+
+impl AnsiEncode for FileDimension {
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        match *self {
+            Self::Auto => write_str_into(sink, "auto"),
+            Self::Cells(n) => write_int(sink, n),
+            Self::Pixels(n) => {
+                Ok(write_int(sink, n)? + write_str_into(sink, "px")?)
+            }
+            Self::Percent(n) => {
+                Ok(write_int(sink, n)? + write_byte_into(sink, b'%')?)
+            }
+        }
+    }
+}
+
+/// Arguments of a [`File`] or [`MultipartFile`] transfer.
+///
+/// Keys without a dedicated field are preserved in `other`, and the order
+/// of the parsed keys in `order`, so that the sequence can be re-emitted
+/// unchanged.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct FileArguments<'a> {
+    /// Base64-encoded file name (`name=`).
+    pub name: Option<&'a str>,
+    /// File size in bytes (`size=`).
+    pub size: Option<u64>,
+    /// Display width (`width=`).
+    pub width: Option<FileDimension>,
+    /// Display height (`height=`).
+    pub height: Option<FileDimension>,
+    /// Whether to keep the aspect ratio when both width and height are
+    /// given (`preserveAspectRatio=`).  Defaults to `true`.
+    pub preserve_aspect_ratio: Option<bool>,
+    /// Whether to display the file inline instead of downloading it
+    /// (`inline=`).  Defaults to `false`.
+    pub inline: Option<bool>,
+    /// Any other `key=value` pairs, in the order they appeared.
+    pub other: Vec<(&'a str, &'a str)>,
+    /// The keys in the order they appeared.
+    ///
+    /// Keys are encoded in this order.  Keys that are set but not listed
+    /// are written after the listed ones, known keys first.
+    pub order: Vec<&'a str>,
+}
+
+/// Keys of [`FileArguments`] with a dedicated field, in default encoding
+/// order.
+const FILE_ARGUMENT_KEYS: [&str; 6] = [
+    "name",
+    "size",
+    "width",
+    "height",
+    "preserveAspectRatio",
+    "inline",
+];
+
+impl FileArguments<'_> {
+    /// Arguments for an image of `size` bytes to be displayed inline.
+    #[must_use]
+    pub fn inline_image(size: u64) -> Self {
+        Self {
+            size: Some(size),
+            inline: Some(true),
+            ..Default::default()
+        }
+    }
+
+    /// Return `true` if the file is to be displayed inline.
+    #[must_use]
+    pub fn is_inline(&self) -> bool {
+        self.inline.unwrap_or(false)
+    }
+
+    /// Return `true` if the aspect ratio is to be preserved.
+    #[must_use]
+    pub fn preserves_aspect_ratio(&self) -> bool {
+        self.preserve_aspect_ratio.unwrap_or(true)
+    }
+
+    /// Return the decoded file name.
+    ///
+    /// # Errors
+    ///
+    /// Return [`ParseError::InvalidValue`] if the name is not valid
+    /// base64.
+    pub fn decoded_name(&self) -> Result<Option<Vec<u8>>, ParseError> {
+        self.name
+            .map(|name| {
+                BASE64.decode(name).map_err(|err| {
+                    ParseError::InvalidValue(format!(
+                        "iTerm2 file: invalid name: {err}"
+                    ))
+                })
+            })
+            .transpose()
+    }
+}
+
+fn parse_file_flag(key: &str, value: &[u8]) -> Result<bool, ParseError> {
+    match value {
+        b"0" => Ok(false),
+        b"1" => Ok(true),
+        _ => Err(ParseError::InvalidValue(format!(
+            "iTerm2 file: {key} must be 0 or 1"
+        ))),
+    }
+}
+
+impl<'a> TryFromAnsi<'a> for FileArguments<'a> {
+    fn try_from_ansi(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        let mut args = Self::default();
+        if bytes.is_empty() {
+            return Ok(args);
+        }
+        for pair in vtansi::parse_keyvalue_pairs(bytes, b';') {
+            let (key, value) = pair?;
+            args.order.push(<&str>::try_from_ansi(key)?);
+            match key {
+                b"name" => args.name = Some(<&str>::try_from_ansi(value)?),
+                b"size" => args.size = Some(u64::try_from_ansi(value)?),
+                b"width" => {
+                    args.width = Some(FileDimension::try_from_ansi(value)?);
+                }
+                b"height" => {
+                    args.height = Some(FileDimension::try_from_ansi(value)?);
+                }
+                b"preserveAspectRatio" => {
+                    args.preserve_aspect_ratio =
+                        Some(parse_file_flag("preserveAspectRatio", value)?);
+                }
+                b"inline" => {
+                    args.inline = Some(parse_file_flag("inline", value)?);
+                }
+                _ => args.other.push((
+                    <&str>::try_from_ansi(key)?,
+                    <&str>::try_from_ansi(value)?,
+                )),
+            }
+        }
+        Ok(args)
+    }
+}
+
+impl FileArguments<'_> {
+    /// Encode the known key at `index` in [`FILE_ARGUMENT_KEYS`], if set.
+    fn encode_known_into<W: std::io::Write + ?Sized>(
+        &self,
+        index: usize,
+        sink: &mut W,
+        first: &mut bool,
+    ) -> Result<usize, EncodeError> {
+        let flag = |v: bool| if v { "1" } else { "0" };
+        let key = FILE_ARGUMENT_KEYS[index];
+        match index {
+            0 => self
+                .name
+                .map_or(Ok(0), |name| encode_pair(sink, first, key, &name)),
+            1 => self
+                .size
+                .map_or(Ok(0), |size| encode_pair(sink, first, key, &size)),
+            2 => self
+                .width
+                .as_ref()
+                .map_or(Ok(0), |width| encode_pair(sink, first, key, width)),
+            3 => self
+                .height
+                .as_ref()
+                .map_or(Ok(0), |height| encode_pair(sink, first, key, height)),
+            4 => self.preserve_aspect_ratio.map_or(Ok(0), |preserve| {
+                encode_pair(sink, first, key, &flag(preserve))
+            }),
+            _ => self.inline.map_or(Ok(0), |inline| {
+                encode_pair(sink, first, key, &flag(inline))
+            }),
+        }
+    }
+}
+
+fn encode_pair<W: std::io::Write + ?Sized>(
+    sink: &mut W,
+    first: &mut bool,
+    key: &str,
+    value: &impl AnsiEncode,
+) -> Result<usize, EncodeError> {
+    let mut written = 0;
+    if !std::mem::take(first) {
+        written += write_byte_into(sink, b';')?;
+    }
+    written += write_str_into(sink, key)?;
+    written += write_byte_into(sink, b'=')?;
+    written += value.encode_ansi_into(sink)?;
+    Ok(written)
+}
+
+impl AnsiEncode for FileArguments<'_> {
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        let mut first = true;
+        let mut written = 0;
+        let mut known_written = [false; FILE_ARGUMENT_KEYS.len()];
+        // Entries of `other` appear in `order` in the same sequence, so a
+        // single cursor is enough to match them up.
+        let mut other = self.other.iter().peekable();
+        for &key in &self.order {
+            if let Some(index) =
+                FILE_ARGUMENT_KEYS.iter().position(|&k| k == key)
+            {
+                if !std::mem::replace(&mut known_written[index], true) {
+                    written +=
+                        self.encode_known_into(index, sink, &mut first)?;
+                }
+            } else if let Some((_, value)) =
+                other.next_if(|(other_key, _)| *other_key == key)
+            {
+                written += encode_pair(sink, &mut first, key, value)?;
+            }
+        }
+        for (index, done) in known_written.into_iter().enumerate() {
+            if !done {
+                written += self.encode_known_into(index, sink, &mut first)?;
+            }
+        }
+        for (key, value) in other {
+            written += encode_pair(sink, &mut first, key, value)?;
+        }
+        Ok(written)
+    }
+}
+
+/// Arguments and base64 payload of a [`File`] (`[arguments]:base64`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct FileContent<'a> {
+    pub args: FileArguments<'a>,
+    /// Base64-encoded file contents.
+    pub payload: &'a str,
+}
+
+impl<'a> TryFromAnsi<'a> for FileContent<'a> {
+    fn try_from_ansi(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        // Neither the arguments nor base64 contain colons, so the first
+        // one ends the arguments.
+        let colon = bytes.iter().position(|&b| b == b':').ok_or_else(|| {
+            ParseError::InvalidValue(
+                "iTerm2 file: missing payload separator".to_string(),
+            )
+        })?;
+        Ok(Self {
+            args: FileArguments::try_from_ansi(&bytes[..colon])?,
+            payload: <&str>::try_from_ansi(&bytes[colon + 1..])?,
+        })
+    }
+}
+
+impl AnsiEncode for FileContent<'_> {
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        Ok(self.args.encode_ansi_into(sink)?
+            + write_byte_into(sink, b':')?
+            + write_str_into(sink, self.payload)?)
+    }
+}
+
+/// Transfer a file in a single sequence.
+///
+/// The wire format is: `OSC 1337;File=[arguments]:base64 ST`.  With
+/// `inline=1` the file is displayed in the terminal (this is what `imgcat`
+/// uses), otherwise it is downloaded.
+///
+/// See <https://iterm2.com/documentation-images.html> for details.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput)]
+#[vtansi(osc, number = "1337", data = "File", data_delimiter = '=')]
+pub struct File<'a> {
+    pub content: FileContent<'a>,
+}
+
+impl<'a> File<'a> {
+    /// Create a file transfer with the given base64-encoded contents.
+    #[must_use]
+    pub fn new(args: FileArguments<'a>, payload: &'a str) -> Self {
+        Self {
+            content: FileContent { args, payload },
+        }
+    }
+}
+
+/// Start a file transfer split over several sequences.
+///
+/// The wire format is: `OSC 1337;MultipartFile=[arguments] ST`.  It is
+/// followed by [`FilePart`] sequences carrying the base64 payload and a
+/// final [`FileEnd`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput)]
+#[vtansi(osc, number = "1337", data = "MultipartFile", data_delimiter = '=')]
+pub struct MultipartFile<'a> {
+    pub args: FileArguments<'a>,
+}
+
+/// A chunk of a [`MultipartFile`] transfer.
+///
+/// The wire format is: `OSC 1337;FilePart=base64 ST`.  Chunks may be split
+/// at any point of the base64 payload.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput)]
+#[vtansi(osc, number = "1337", data = "FilePart", data_delimiter = '=')]
+pub struct FilePart<'a> {
+    pub payload: &'a str,
+}
+
+impl<'a> FilePart<'a> {
+    /// Split a base64 payload into chunks of at most `chunk_len` bytes.
+    ///
+    /// A `chunk_len` of zero is treated as one.  Base64 is ASCII; should
+    /// the payload contain other characters, they are never split, which
+    /// can make a chunk longer than `chunk_len`.
+    pub fn split(
+        payload: &'a str,
+        chunk_len: usize,
+    ) -> impl Iterator<Item = FilePart<'a>> + 'a {
+        let chunk_len = chunk_len.max(1);
+        let mut rest = payload;
+        std::iter::from_fn(move || {
+            if rest.is_empty() {
+                return None;
+            }
+            let mut end = chunk_len.min(rest.len());
+            while !rest.is_char_boundary(end) {
+                end += 1;
+            }
+            let (chunk, tail) = rest.split_at(end);
+            rest = tail;
+            Some(Self { payload: chunk })
+        })
+    }
+}
+
+/// End a [`MultipartFile`] transfer.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput,
+)]
+#[vtansi(osc, number = "1337", data = "FileEnd")]
+pub struct FileEnd;
+
+/// Incremental decoder for base64 file payloads.
+///
+/// Feed the payload of a [`File`] or of every [`FilePart`] to
+/// [`feed`](Self::feed) as it arrives; decoded bytes are appended to the
+/// output buffer, so that the whole file never has to be kept in memory.
+/// Chunks may be split at arbitrary positions, and ASCII whitespace is
+/// ignored.
+#[derive(Debug, Clone, Default)]
+pub struct FilePayloadDecoder {
+    /// Base64 characters not yet forming a complete quantum.
+    pending: Vec<u8>,
+    /// Total number of decoded bytes.
+    decoded: u64,
+}
+
+impl FilePayloadDecoder {
+    /// Create a new decoder.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the number of bytes decoded so far.
+    #[must_use]
+    pub fn decoded_len(&self) -> u64 {
+        self.decoded
+    }
+
+    /// Decode a piece of base64 payload, appending the result to `out`.
+    ///
+    /// # Errors
+    ///
+    /// Return [`ParseError::InvalidValue`] if the payload is not valid
+    /// base64.
+    pub fn feed(
+        &mut self,
+        payload: &[u8],
+        out: &mut Vec<u8>,
+    ) -> Result<(), ParseError> {
+        self.pending.extend(
+            payload.iter().filter(|b| !b.is_ascii_whitespace()).copied(),
+        );
+        let complete = self.pending.len() / 4 * 4;
+        if complete > 0 {
+            self.decode(complete, out)?;
+            self.pending.drain(..complete);
+        }
+        Ok(())
+    }
+
+    /// Decode any remaining unpadded payload, appending it to `out`.
+    ///
+    /// # Errors
+    ///
+    /// Return [`ParseError::InvalidValue`] if the remaining payload is not
+    /// valid base64.
+    pub fn finish(mut self, out: &mut Vec<u8>) -> Result<u64, ParseError> {
+        if !self.pending.is_empty() {
+            self.decode(self.pending.len(), out)?;
+        }
+        Ok(self.decoded)
+    }
+
+    fn decode(
+        &mut self,
+        len: usize,
+        out: &mut Vec<u8>,
+    ) -> Result<(), ParseError> {
+        let start = out.len();
+        BASE64
+            .decode_vec(&self.pending[..len], out)
+            .map_err(|err| {
+                ParseError::InvalidValue(format!(
+                    "iTerm2 file: invalid payload: {err}"
+                ))
+            })?;
+        self.decoded += (out.len() - start) as u64;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::TerminalOutputParser;
+    use better_any::TidExt;
+
+    fn reencode(input: &[u8]) -> Vec<String> {
+        let mut events = Vec::new();
+        TerminalOutputParser::new().feed_with(input, &mut |event| {
+            let name = if event.is::<File>() {
+                "File"
+            } else if event.is::<MultipartFile>() {
+                "MultipartFile"
+            } else if event.is::<FilePart>() {
+                "FilePart"
+            } else if event.is::<FileEnd>() {
+                "FileEnd"
+            } else {
+                "Other"
+            };
+            events.push(format!(
+                "{name}: {}",
+                String::from_utf8(event.encode_ansi().unwrap()).unwrap()
+            ));
+        });
+        events
+    }
+
+    #[test]
+    fn test_file_round_trip() {
+        let input = b"\x1b]1337;File=name=YS5wbmc=;size=3;width=10px;\
+                      height=50%;preserveAspectRatio=0;inline=1;type=png:\
+                      Zm9v\x07\
+                      \x1b]1337;MultipartFile=inline=1;width=auto\x07\
+                      \x1b]1337;FilePart=Zm\x07\
+                      \x1b]1337;FileEnd\x07";
+        assert_eq!(
+            reencode(input),
+            vec![
+                "File: \x1b]1337;File=name=YS5wbmc=;size=3;width=10px;\
+                 height=50%;preserveAspectRatio=0;inline=1;type=png:\
+                 Zm9v\x1b\\",
+                "MultipartFile: \x1b]1337;MultipartFile=inline=1;width=auto\
+                 \x1b\\",
+                "FilePart: \x1b]1337;FilePart=Zm\x1b\\",
+                "FileEnd: \x1b]1337;FileEnd\x1b\\",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_file_arguments() {
+        let mut parsed = None;
+        TerminalOutputParser::new().feed_with(
+            b"\x1b]1337;File=name=YS5wbmc=;width=4;inline=1:Zm9v\x07",
+            &mut |event| {
+                if let Some(file) = event.downcast_ref::<File>() {
+                    let args = &file.content.args;
+                    parsed = Some((
+                        args.decoded_name().unwrap(),
+                        args.width,
+                        args.is_inline(),
+                        args.preserves_aspect_ratio(),
+                    ));
+                }
+            },
+        );
+        assert_eq!(
+            parsed,
+            Some((
+                Some(b"a.png".to_vec()),
+                Some(FileDimension::Cells(4)),
+                true,
+                true
+            ))
+        );
+        assert!(FileArguments::try_from_ansi(b"inline=yes").is_err());
+        assert!(FileArguments::try_from_ansi(b"width=x").is_err());
+        assert!(FileContent::try_from_ansi(b"inline=1").is_err());
+    }
+
+    #[test]
+    fn test_file_encode() {
+        let payload = BASE64.encode(b"hello, world");
+        let file = File::new(FileArguments::inline_image(12), &payload);
+        assert_eq!(
+            file.encode_ansi().unwrap(),
+            b"\x1b]1337;File=size=12;inline=1:aGVsbG8sIHdvcmxk\x1b\\"
+        );
+        let parts: Vec<_> = FilePart::split(&payload, 5)
+            .map(|part| part.encode_ansi().unwrap())
+            .collect();
+        assert_eq!(parts.len(), 4);
+        assert_eq!(parts[3], b"\x1b]1337;FilePart=k\x1b\\");
+        assert_eq!(FilePart::split("abc", 0).count(), 3);
+        let parts: Vec<_> = FilePart::split("aé€b", 2)
+            .map(|part| part.payload)
+            .collect();
+        assert_eq!(parts, ["aé", "€", "b"]);
+    }
+
+    #[test]
+    fn test_file_arguments_order() {
+        let input: &[u8] = b"inline=1;foo=bar;name=YQ==;baz=1;size=3;width=2px";
+        let args = FileArguments::try_from_ansi(input).unwrap();
+        assert_eq!(args.encode_ansi().unwrap(), input);
+
+        let args = FileArguments {
+            other: vec![("x", "y")],
+            ..FileArguments::inline_image(3)
+        };
+        assert_eq!(args.encode_ansi().unwrap(), b"size=3;inline=1;x=y");
+    }
+
+    #[test]
+    fn test_payload_decoder() {
+        let data: Vec<u8> = (0..=255).collect();
+        let payload = BASE64.encode(&data);
+        for chunk_len in [1, 2, 3, 5, 7, 64] {
+            let mut decoder = FilePayloadDecoder::new();
+            let mut out = Vec::new();
+            for part in FilePart::split(&payload, chunk_len) {
+                decoder.feed(part.payload.as_bytes(), &mut out).unwrap();
+            }
+            assert_eq!(decoder.finish(&mut out).unwrap(), 256);
+            assert_eq!(out, data);
+        }
+
+        let mut decoder = FilePayloadDecoder::new();
+        let mut out = Vec::new();
+        decoder.feed(b"Zm9v\nYg", &mut out).unwrap();
+        assert_eq!(out, b"foo");
+        assert_eq!(decoder.finish(&mut out).unwrap(), 4);
+        assert_eq!(out, b"foob");
+
+        let mut decoder = FilePayloadDecoder::new();
+        assert!(decoder.feed(b"Zm9!", &mut Vec::new()).is_err());
+    }
+}
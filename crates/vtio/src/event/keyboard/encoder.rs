@@ -0,0 +1,351 @@
+//! Mode-aware key event encoder.
+
+use vtansi::EncodeError;
+
+use super::encoding::{encode_kitty_key_into, encode_legacy_key_into};
+use super::{KeyEvent, KeyboardEnhancementFlags, KeyboardModeFlags};
+
+/// Maximum depth of the keyboard enhancement flags stack.
+///
+/// When the stack is full, pushing evicts the oldest entry, as kitty does.
+pub(crate) const MAX_ENHANCEMENT_STACK_DEPTH: usize = 8;
+
+/// Encodes key events the way an application asked to receive them.
+///
+/// A terminal multiplexer receives keys from the outer terminal and has to
+/// re-encode them for the program running in each pane.  `KeyEncoder`
+/// tracks the keyboard modes that program has set — the
+/// [`KeyboardModeFlags`] (`DECCKM`, `DECNKM`, `DECBKM`, alt-sends-escape,
+/// ...) and the kitty [`KeyboardEnhancementFlags`] stack — and produces the
+/// bytes the program expects for each [`KeyEvent`].
+///
+/// With no enhancement flags active, keys are encoded as a legacy terminal
+/// would: releases are dropped, repeats are sent as presses and keys that
+/// produce text send it verbatim.  Otherwise the kitty keyboard protocol
+/// rules for the active flags apply.
+///
+/// # Example
+///
+/// ```
+/// use vtio::event::keyboard::{
+///     KeyEncoder, KeyboardEnhancementFlags, KeyboardModeFlags,
+/// };
+/// use vtio::event::{KeyCode, KeyEvent, KeyModifiers};
+///
+/// let mut encoder = KeyEncoder::new();
+/// let up = KeyEvent::new(KeyCode::Up, KeyModifiers::empty());
+/// assert_eq!(encoder.encode(&up).unwrap(), b"\x1b[A");
+///
+/// encoder.set_mode(KeyboardModeFlags::CURSOR_KEYS, true);
+/// assert_eq!(encoder.encode(&up).unwrap(), b"\x1bOA");
+///
+/// let esc = KeyEvent::new(KeyCode::Esc, KeyModifiers::empty());
+/// encoder.push_keyboard_enhancement_flags(
+///     KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES,
+/// );
+/// assert_eq!(encoder.encode(&esc).unwrap(), b"\x1b[27u");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyEncoder {
+    mode_flags: KeyboardModeFlags,
+    enhancement_flags: KeyboardEnhancementFlags,
+    enhancement_stack: Vec<KeyboardEnhancementFlags>,
+}
+
+impl KeyEncoder {
+    /// Create an encoder with all modes reset.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an encoder with the given keyboard mode flags.
+    #[must_use]
+    pub fn with_mode_flags(mode_flags: KeyboardModeFlags) -> Self {
+        Self {
+            mode_flags,
+            ..Self::default()
+        }
+    }
+
+    /// Get the current keyboard mode flags.
+    #[must_use]
+    pub fn mode_flags(&self) -> KeyboardModeFlags {
+        self.mode_flags
+    }
+
+    /// Replace the keyboard mode flags.
+    pub fn set_mode_flags(&mut self, mode_flags: KeyboardModeFlags) {
+        self.mode_flags = mode_flags;
+    }
+
+    /// Set or reset a single keyboard mode.
+    pub fn set_mode(&mut self, flag: KeyboardModeFlags, enabled: bool) {
+        self.mode_flags.set(flag, enabled);
+    }
+
+    /// Get the active keyboard enhancement flags.
+    #[must_use]
+    pub fn keyboard_enhancement_flags(&self) -> KeyboardEnhancementFlags {
+        self.enhancement_flags
+    }
+
+    /// Set the active keyboard enhancement flags without touching the
+    /// stack (`CSI = flags u`).
+    pub fn set_keyboard_enhancement_flags(
+        &mut self,
+        flags: KeyboardEnhancementFlags,
+    ) {
+        self.enhancement_flags = flags;
+    }
+
+    /// Push the active flags onto the stack and activate `flags`
+    /// (`CSI > flags u`).
+    pub fn push_keyboard_enhancement_flags(
+        &mut self,
+        flags: KeyboardEnhancementFlags,
+    ) {
+        if self.enhancement_stack.len() == MAX_ENHANCEMENT_STACK_DEPTH {
+            self.enhancement_stack.remove(0);
+        }
+        self.enhancement_stack.push(self.enhancement_flags);
+        self.enhancement_flags = flags;
+    }
+
+    /// Pop `count` entries off the stack (`CSI < count u`).
+    ///
+    /// Popping more entries than were pushed resets all flags.
+    pub fn pop_keyboard_enhancement_flags(&mut self, count: usize) {
+        for _ in 0..count {
+            let Some(flags) = self.enhancement_stack.pop() else {
+                self.enhancement_flags = KeyboardEnhancementFlags::empty();
+                break;
+            };
+            self.enhancement_flags = flags;
+        }
+    }
+
+    /// Encode a key event into `sink`, returning the number of bytes
+    /// written.
+    ///
+    /// Events that the application did not ask for (e.g. key releases
+    /// without [`KeyboardEnhancementFlags::REPORT_EVENT_TYPES`]) write
+    /// nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key cannot be encoded in the active mode or
+    /// writing to `sink` fails.
+    pub fn encode_into<W: std::io::Write + ?Sized>(
+        &self,
+        event: &KeyEvent,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        if self.enhancement_flags.is_empty() {
+            encode_legacy_key_into(event, self.mode_flags, sink)
+        } else {
+            encode_kitty_key_into(
+                event,
+                self.enhancement_flags,
+                self.mode_flags,
+                sink,
+            )
+        }
+    }
+
+    /// Encode a key event into a new buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key cannot be encoded in the active mode.
+    pub fn encode(&self, event: &KeyEvent) -> Result<Vec<u8>, EncodeError> {
+        let mut buf = Vec::new();
+        self.encode_into(event, &mut buf)?;
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::keyboard::{
+        KeyCode, KeyEventKind, KeyEventState, KeyModifiers, ModifierKeyCode,
+    };
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    fn kind(code: KeyCode, kind: KeyEventKind) -> KeyEvent {
+        KeyEvent::builder(code, KeyModifiers::empty())
+            .kind(kind)
+            .build()
+    }
+
+    #[test]
+    fn test_legacy_modes() {
+        let mut encoder = KeyEncoder::new();
+        let up = key(KeyCode::Up, KeyModifiers::empty());
+        let backspace = key(KeyCode::Backspace, KeyModifiers::empty());
+        let alt_a = key(KeyCode::Char('a'), KeyModifiers::ALT);
+
+        assert_eq!(encoder.encode(&up).unwrap(), b"\x1b[A");
+        assert_eq!(encoder.encode(&backspace).unwrap(), b"\x7f");
+        assert_eq!(encoder.encode(&alt_a).unwrap(), b"\x1ba");
+
+        encoder.set_mode(KeyboardModeFlags::CURSOR_KEYS, true);
+        assert_eq!(encoder.encode(&up).unwrap(), b"\x1bOA");
+        let ctrl_up = key(KeyCode::Up, KeyModifiers::CONTROL);
+        assert_eq!(encoder.encode(&ctrl_up).unwrap(), b"\x1b[1;5A");
+
+        encoder.set_mode(KeyboardModeFlags::BACKSPACE_SENDS_DELETE, true);
+        assert_eq!(encoder.encode(&backspace).unwrap(), b"\x08");
+
+        encoder.set_mode(KeyboardModeFlags::ALT_KEY_HIGH_BIT_SET, true);
+        assert_eq!(encoder.encode(&alt_a).unwrap(), [0xe1]);
+        encoder.set_mode(KeyboardModeFlags::ALT_KEY_SENDS_ESC_PREFIX, true);
+        assert_eq!(encoder.encode(&alt_a).unwrap(), b"\x1ba");
+    }
+
+    #[test]
+    fn test_legacy_event_kinds() {
+        let encoder = KeyEncoder::new();
+        let release = kind(KeyCode::Char('a'), KeyEventKind::Release);
+        let repeat = kind(KeyCode::Char('a'), KeyEventKind::Repeat);
+        assert!(encoder.encode(&release).unwrap().is_empty());
+        assert_eq!(encoder.encode(&repeat).unwrap(), b"a");
+    }
+
+    #[test]
+    fn test_legacy_associated_text() {
+        let encoder = KeyEncoder::new();
+        let event =
+            KeyEvent::builder(KeyCode::Char('e'), KeyModifiers::empty())
+                .text("é".to_string())
+                .build();
+        assert_eq!(encoder.encode(&event).unwrap(), "é".as_bytes());
+    }
+
+    #[test]
+    fn test_enhancement_stack() {
+        let mut encoder = KeyEncoder::new();
+        let flags = KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES;
+        encoder.push_keyboard_enhancement_flags(flags);
+        encoder.push_keyboard_enhancement_flags(
+            flags | KeyboardEnhancementFlags::REPORT_EVENT_TYPES,
+        );
+        encoder.pop_keyboard_enhancement_flags(1);
+        assert_eq!(encoder.keyboard_enhancement_flags(), flags);
+        encoder.pop_keyboard_enhancement_flags(5);
+        assert!(encoder.keyboard_enhancement_flags().is_empty());
+
+        for _ in 0..=MAX_ENHANCEMENT_STACK_DEPTH {
+            encoder.push_keyboard_enhancement_flags(flags);
+        }
+        encoder.pop_keyboard_enhancement_flags(MAX_ENHANCEMENT_STACK_DEPTH);
+        assert_eq!(encoder.keyboard_enhancement_flags(), flags);
+    }
+
+    #[test]
+    fn test_kitty_disambiguate() {
+        let mut encoder = KeyEncoder::new();
+        encoder.push_keyboard_enhancement_flags(
+            KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES,
+        );
+
+        let cases: &[(KeyEvent, &[u8])] = &[
+            (key(KeyCode::Esc, KeyModifiers::empty()), b"\x1b[27u"),
+            (key(KeyCode::Char('a'), KeyModifiers::empty()), b"a"),
+            (key(KeyCode::Char('A'), KeyModifiers::SHIFT), b"A"),
+            (
+                key(KeyCode::Char('a'), KeyModifiers::CONTROL),
+                b"\x1b[97;5u",
+            ),
+            (key(KeyCode::Enter, KeyModifiers::empty()), b"\r"),
+            (key(KeyCode::Enter, KeyModifiers::SHIFT), b"\x1b[13;2u"),
+            (key(KeyCode::BackTab, KeyModifiers::empty()), b"\x1b[9;2u"),
+            (key(KeyCode::Up, KeyModifiers::empty()), b"\x1b[A"),
+            (key(KeyCode::Up, KeyModifiers::ALT), b"\x1b[1;3A"),
+            (key(KeyCode::F(3), KeyModifiers::empty()), b"\x1bOR"),
+            (key(KeyCode::F(3), KeyModifiers::CONTROL), b"\x1b[13;5~"),
+            (key(KeyCode::F(5), KeyModifiers::CONTROL), b"\x1b[15;5~"),
+            (key(KeyCode::F(13), KeyModifiers::empty()), b"\x1b[57376u"),
+            (
+                KeyEvent::builder(KeyCode::Char('5'), KeyModifiers::empty())
+                    .state(KeyEventState::KEYPAD)
+                    .build(),
+                b"\x1b[57404u",
+            ),
+            (
+                key(
+                    KeyCode::Modifier(ModifierKeyCode::LeftShift),
+                    KeyModifiers::SHIFT,
+                ),
+                b"",
+            ),
+        ];
+        for (event, expected) in cases {
+            assert_eq!(
+                encoder.encode(event).unwrap(),
+                *expected,
+                "encoding {event:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_kitty_event_types() {
+        let mut encoder = KeyEncoder::new();
+        encoder.set_keyboard_enhancement_flags(
+            KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                | KeyboardEnhancementFlags::REPORT_EVENT_TYPES,
+        );
+
+        let esc_repeat = kind(KeyCode::Esc, KeyEventKind::Repeat);
+        let esc_release = kind(KeyCode::Esc, KeyEventKind::Release);
+        let up_release = kind(KeyCode::Up, KeyEventKind::Release);
+        assert_eq!(encoder.encode(&esc_repeat).unwrap(), b"\x1b[27;1:2u");
+        assert_eq!(encoder.encode(&esc_release).unwrap(), b"\x1b[27;1:3u");
+        assert_eq!(encoder.encode(&up_release).unwrap(), b"\x1b[1;1:3A");
+
+        // Text keys and Enter never report releases unless all keys are
+        // reported as escape codes
+        let a_release = kind(KeyCode::Char('a'), KeyEventKind::Release);
+        let enter_release = kind(KeyCode::Enter, KeyEventKind::Release);
+        assert!(encoder.encode(&a_release).unwrap().is_empty());
+        assert!(encoder.encode(&enter_release).unwrap().is_empty());
+
+        encoder.set_keyboard_enhancement_flags(
+            KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+                | KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES,
+        );
+        assert_eq!(encoder.encode(&a_release).unwrap(), b"\x1b[97;1:3u");
+        assert_eq!(encoder.encode(&enter_release).unwrap(), b"\x1b[13;1:3u");
+    }
+
+    #[test]
+    fn test_kitty_alternate_keys_and_text() {
+        let mut encoder = KeyEncoder::new();
+        encoder.set_keyboard_enhancement_flags(
+            KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES
+                | KeyboardEnhancementFlags::REPORT_ALTERNATE_KEYS
+                | KeyboardEnhancementFlags::REPORT_ASSOCIATED_TEXT,
+        );
+
+        let a = key(KeyCode::Char('a'), KeyModifiers::empty());
+        let shift_a =
+            KeyEvent::builder(KeyCode::Char('A'), KeyModifiers::SHIFT)
+                .text("A".to_string())
+                .build();
+        let ctrl_a = key(KeyCode::Char('a'), KeyModifiers::CONTROL);
+        let cyrillic =
+            KeyEvent::builder(KeyCode::Char('ф'), KeyModifiers::empty())
+                .base_layout_key(KeyCode::Char('a'))
+                .text("ф".to_string())
+                .build();
+        assert_eq!(encoder.encode(&a).unwrap(), b"\x1b[97u");
+        assert_eq!(encoder.encode(&shift_a).unwrap(), b"\x1b[97:65;2;65u");
+        assert_eq!(encoder.encode(&ctrl_a).unwrap(), b"\x1b[97;5u");
+        assert_eq!(encoder.encode(&cyrillic).unwrap(), b"\x1b[1092::97;;1092u");
+    }
+}
@@ -1,5 +1,7 @@
 //! Key event encoding and decoding.
 
+use std::borrow::Cow;
+
 use super::KeyboardModeFlags;
 
 use vtansi::{
@@ -13,7 +15,7 @@ use super::event::{KeyEvent, KeyEventBuilder};
 use super::keycode::{KeyCode, MediaKeyCode};
 use super::modifier::{
     CsiKeyModifiers, KeyEventKind, KeyEventState, KeyModifiers,
-    ModifierKeyCode, parse_csi_u_modifiers,
+    ModifierKeyCode, csi_u_modifier_param, parse_csi_u_modifiers,
 };
 use super::util::parse_colon_separated;
 
@@ -44,7 +46,7 @@ pub(crate) fn control_code_for(c: char) -> u8 {
 ///
 /// This is the inverse of [`control_code_for`].
 #[inline]
-fn char_from_control_code(code: u8) -> Option<char> {
+pub(crate) fn char_from_control_code(code: u8) -> Option<char> {
     match code {
         0x00 => Some(' '), // Ctrl+Space or Ctrl+@
         0x01..=0x1A => Some((code - 1 + b'a') as char), // Ctrl+A through Ctrl+Z
@@ -457,7 +459,7 @@ enum KeyEncoding {
     CsiModTilde { code: u8, mods: u8 },
     /// CSI-u format for keys with modifiers: ESC [ <code> ; <mods> u.
     CsiU { code: u8, mods: u8 },
-    /// No encoding (unsupported keys like Media, Modifier, or non-press events).
+    /// No encoding (unsupported keys like Media, Modifier, or release events).
     None,
 }
 
@@ -485,12 +487,14 @@ impl KeyEncoding {
     /// - `BACKSPACE_SENDS_DELETE`: When set, Backspace sends BS (0x08) instead of DEL (0x7F)
     /// - `ALT_KEY_HIGH_BIT_SET`: When set, Alt sets high bit instead of ESC prefix
     /// - `DELETE_KEY_SENDS_DEL`: When set, Delete key sends DEL (0x7F) instead of escape sequence
+    #[allow(clippy::too_many_lines)]
     fn from_key_event_with_modes(
         event: &KeyEvent,
         mode_flags: KeyboardModeFlags,
     ) -> Self {
-        // Only encode press events
-        if event.kind != KeyEventKind::Press {
+        // Key releases are not reported; auto-repeats are reported as
+        // presses
+        if event.kind == KeyEventKind::Release {
             return Self::None;
         }
 
@@ -501,9 +505,12 @@ impl KeyEncoding {
 
         // Determine Alt key behavior based on mode flags
         // ALT_KEY_HIGH_BIT_SET: set high bit instead of ESC prefix
-        // Default (or ALT_KEY_SENDS_ESC_PREFIX): use ESC prefix
-        let alt_high_bit =
-            mode_flags.contains(KeyboardModeFlags::ALT_KEY_HIGH_BIT_SET);
+        // Default (or ALT_KEY_SENDS_ESC_PREFIX, which takes precedence):
+        // use ESC prefix
+        let alt_high_bit = mode_flags
+            .contains(KeyboardModeFlags::ALT_KEY_HIGH_BIT_SET)
+            && !mode_flags
+                .contains(KeyboardModeFlags::ALT_KEY_SENDS_ESC_PREFIX);
         let alt_prefix = mods.contains(KeyModifiers::ALT) && !alt_high_bit;
         let alt_set_high_bit = mods.contains(KeyModifiers::ALT) && alt_high_bit;
 
@@ -511,9 +518,21 @@ impl KeyEncoding {
         let cursor_keys_mode =
             mode_flags.contains(KeyboardModeFlags::CURSOR_KEYS);
 
-        // Note: APPLICATION_KEYPAD mode (DECNKM) is not handled here because
-        // we cannot distinguish keypad keys from main keyboard keys at the
-        // KeyCode level - both appear as KeyCode::Char('5'), etc.
+        // Application keypad mode (DECNKM): keypad keys use SS3.  Keypad
+        // keys are only recognized by their KEYPAD state; at the KeyCode
+        // level they look like main keyboard keys (KeyCode::Char('5'), etc.)
+        if event.state.contains(KeyEventState::KEYPAD)
+            && !has_mods
+            && matches!(code, KeyCode::Char(_) | KeyCode::Enter)
+            && mode_flags.contains(KeyboardModeFlags::APPLICATION_KEYPAD)
+            && !(event.state.contains(KeyEventState::NUM_LOCK)
+                && mode_flags.contains(
+                    KeyboardModeFlags::IGNORE_KEYPAD_APP_MODE_ON_NUMLOCK,
+                ))
+            && let Some(byte) = key_to_ss3_byte(code)
+        {
+            return Self::Ss3(byte);
+        }
 
         // Character keys: control codes, shifted chars, or plain UTF-8
         if let KeyCode::Char(c) = code {
@@ -525,7 +544,9 @@ impl KeyEncoding {
             KeyCode::Enter => {
                 // In application keypad mode, Enter from keypad uses SS3 M
                 // For regular Enter, use CR or CSI-u with modifiers
-                return if has_mods {
+                return if mods == KeyModifiers::ALT {
+                    Self::raw_with_alt(b'\r', alt_prefix)
+                } else if has_mods {
                     Self::CsiU {
                         code: 13,
                         mods: mod_param,
@@ -538,13 +559,14 @@ impl KeyEncoding {
                 // BACKSPACE_SENDS_DELETE mode: when SET, backspace sends BS (0x08)
                 // When RESET (default), backspace sends DEL (0x7F)
                 // Note: The mode name is confusing - "delete" here means the DEL character
-                return if mode_flags
+                let byte = if mode_flags
                     .contains(KeyboardModeFlags::BACKSPACE_SENDS_DELETE)
                 {
-                    Self::Raw(0x08) // BS
+                    0x08 // BS
                 } else {
-                    Self::Raw(0x7f) // DEL
+                    0x7f // DEL
                 };
+                return Self::raw_with_alt(byte, alt_prefix);
             }
             KeyCode::Delete => {
                 // DELETE_KEY_SENDS_DEL mode: when set, Delete sends DEL (0x7F)
@@ -559,10 +581,10 @@ impl KeyEncoding {
                 return if mods.contains(KeyModifiers::SHIFT) {
                     Self::CsiFinal(b'Z') // BackTab
                 } else {
-                    Self::Raw(b'\t')
+                    Self::raw_with_alt(b'\t', alt_prefix)
                 };
             }
-            KeyCode::Esc => return Self::Raw(0x1b),
+            KeyCode::Esc => return Self::raw_with_alt(0x1b, alt_prefix),
             _ => {}
         }
 
@@ -616,6 +638,19 @@ impl KeyEncoding {
         Self::None
     }
 
+    /// Encode a raw control byte, prefixed with ESC if Alt is held.
+    #[inline]
+    fn raw_with_alt(byte: u8, alt_prefix: bool) -> Self {
+        if alt_prefix {
+            Self::Char {
+                alt_prefix,
+                ch: byte as char,
+            }
+        } else {
+            Self::Raw(byte)
+        }
+    }
+
     /// Encode a character key based on modifiers.
     ///
     /// - With CONTROL: produces control code (0x00-0x1F, 0x7F)
@@ -797,6 +832,286 @@ pub fn get_key_event_encoding(
     }
 }
 
+// ============================================================================
+// Key event forwarding
+// ============================================================================
+
+/// Return the text to send for a key event, if the key produces text.
+///
+/// This is the associated text of the event or, for character keys, the
+/// character itself (shifted if Shift is held).  Keys held with modifiers
+/// other than Shift do not produce text.
+fn key_text(event: &KeyEvent) -> Option<Cow<'_, str>> {
+    if event.modifiers.intersects(!KeyModifiers::SHIFT) {
+        return None;
+    }
+
+    let text = match (&event.text, event.code) {
+        (Some(text), _) => Cow::Borrowed(text.as_str()),
+        (None, KeyCode::Char(c)) => {
+            let c = if event.modifiers.contains(KeyModifiers::SHIFT) {
+                c.to_uppercase().next().unwrap_or(c)
+            } else {
+                c
+            };
+            Cow::Owned(c.to_string())
+        }
+        (None, _) => return None,
+    };
+
+    if text.is_empty() || text.chars().any(char::is_control) {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+/// Encode a key event as a legacy terminal would report it.
+///
+/// Key releases are not reported and auto-repeats are reported as presses.
+/// Keys that produce text send it verbatim.
+pub(super) fn encode_legacy_key_into<W: std::io::Write + ?Sized>(
+    event: &KeyEvent,
+    mode_flags: KeyboardModeFlags,
+    sink: &mut W,
+) -> Result<usize, EncodeError> {
+    if event.kind == KeyEventKind::Release {
+        return Ok(0);
+    }
+
+    // Composed text has no key code to encode
+    if event.text.is_some()
+        && let Some(text) = key_text(event)
+    {
+        return vtansi::write_bytes_into(sink, text.as_bytes());
+    }
+
+    KeyEncoding::from_key_event_with_modes(event, mode_flags)
+        .encode_ansi_into(sink)
+}
+
+/// Return the kitty functional key code for a keypad key.
+fn keypad_csi_u_code(code: KeyCode) -> Option<u32> {
+    (57399..=57427).find(|&kp| csi_u_code_to_key(kp) == Some(code))
+}
+
+/// Encode a key event using the kitty keyboard protocol.
+///
+/// See <https://sw.kovidgoyal.net/kitty/keyboard-protocol/> for the rules
+/// this follows.
+#[allow(clippy::too_many_lines)]
+pub(super) fn encode_kitty_key_into<W: std::io::Write + ?Sized>(
+    event: &KeyEvent,
+    flags: KeyboardEnhancementFlags,
+    mode_flags: KeyboardModeFlags,
+    sink: &mut W,
+) -> Result<usize, EncodeError> {
+    /// Sequence shape for a key.
+    enum Form {
+        /// `CSI 1 ; mods <letter>`
+        Letter(u8),
+        /// `CSI <number> ; mods ~`
+        Tilde(u8),
+        /// `CSI <code> ; mods u`
+        U(u32),
+    }
+
+    let report_all = flags
+        .contains(KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES);
+    let report_events =
+        flags.contains(KeyboardEnhancementFlags::REPORT_EVENT_TYPES);
+
+    // Without event types, releases are dropped and repeats look like
+    // presses
+    let kind = match event.kind {
+        KeyEventKind::Release if !report_events => return Ok(0),
+        kind if report_events => kind,
+        _ => KeyEventKind::Press,
+    };
+
+    let keypad = event.state.contains(KeyEventState::KEYPAD);
+    let mut mods = event.modifiers;
+    let mut code = event.code;
+    if code == KeyCode::BackTab {
+        code = KeyCode::Tab;
+        mods |= KeyModifiers::SHIFT;
+    }
+
+    // The modifier bit of a modifier key is set while the key is held and
+    // cleared when it is released
+    if let KeyCode::Modifier(modifier) = code {
+        mods.set(modifier.into(), kind != KeyEventKind::Release);
+    }
+
+    if !report_all && !keypad {
+        // Keys that produce text are sent as text, without release events
+        if matches!(code, KeyCode::Char(_))
+            && (mods - KeyModifiers::SHIFT).is_empty()
+        {
+            return match key_text(event) {
+                Some(text) if kind != KeyEventKind::Release => {
+                    vtansi::write_bytes_into(sink, text.as_bytes())
+                }
+                _ => Ok(0),
+            };
+        }
+
+        // Enter, Tab and Backspace keep their legacy encoding so that
+        // the shell stays usable if a program forgets to reset the flags
+        if matches!(code, KeyCode::Enter | KeyCode::Tab | KeyCode::Backspace)
+            && mods.is_empty()
+        {
+            return if kind == KeyEventKind::Release {
+                Ok(0)
+            } else {
+                encode_legacy_key_into(event, mode_flags, sink)
+            };
+        }
+
+        // Modifier and lock keys are only reported with all keys
+        if matches!(
+            code,
+            KeyCode::Modifier(_)
+                | KeyCode::CapsLock
+                | KeyCode::NumLock
+                | KeyCode::ScrollLock
+        ) {
+            return Ok(0);
+        }
+    }
+
+    let unsupported =
+        || EncodeError::Unencodeable(format!("unsupported key code: {code:?}"));
+    let mut shifted = None;
+    let form = match code {
+        _ if keypad && keypad_csi_u_code(code).is_some() => {
+            Form::U(keypad_csi_u_code(code).unwrap_or_default())
+        }
+        KeyCode::F(3) => Form::Tilde(13),
+        KeyCode::Insert
+        | KeyCode::Delete
+        | KeyCode::PageUp
+        | KeyCode::PageDown
+        | KeyCode::F(5..=12) => {
+            Form::Tilde(key_to_csi_tilde_code(code).ok_or_else(unsupported)?)
+        }
+        KeyCode::Up
+        | KeyCode::Down
+        | KeyCode::Left
+        | KeyCode::Right
+        | KeyCode::Home
+        | KeyCode::End
+        | KeyCode::F(1 | 2 | 4) => {
+            Form::Letter(key_to_csi_final_byte(code).ok_or_else(unsupported)?)
+        }
+        KeyCode::KeypadBegin => Form::Letter(b'E'),
+        KeyCode::Char(c) => {
+            // The key code is the unshifted key; an uppercase character
+            // implies Shift
+            let base = c.to_lowercase().next().unwrap_or(c);
+            if base != c {
+                mods |= KeyModifiers::SHIFT;
+                shifted = Some(c as u32);
+            }
+            Form::U(base as u32)
+        }
+        code => Form::U(key_to_csi_u_code(code).ok_or_else(unsupported)?),
+    };
+
+    let lock_state = if report_all {
+        event.state
+    } else {
+        KeyEventState::NONE
+    };
+    let mod_param = csi_u_modifier_param(mods, lock_state);
+    let event_type = match kind {
+        KeyEventKind::Press => None,
+        KeyEventKind::Repeat => Some(2u8),
+        KeyEventKind::Release => Some(3u8),
+    };
+
+    // Unmodified functional keys keep their legacy encoding
+    if !report_all
+        && mod_param == 1
+        && event_type.is_none()
+        && matches!(form, Form::Letter(_) | Form::Tilde(_))
+    {
+        return encode_legacy_key_into(event, mode_flags, sink);
+    }
+
+    let text = if report_all
+        && flags.contains(KeyboardEnhancementFlags::REPORT_ASSOCIATED_TEXT)
+        && kind != KeyEventKind::Release
+        && event.text.is_some()
+    {
+        key_text(event)
+    } else {
+        None
+    };
+    let has_mods = mod_param > 1 || event_type.is_some();
+
+    let mut written = vtansi::write_bytes_into(sink, b"\x1b[")?;
+    let final_byte = match form {
+        Form::Letter(final_byte) => {
+            if has_mods {
+                written += write_byte_into(sink, b'1')?;
+            }
+            final_byte
+        }
+        Form::Tilde(number) => {
+            written += <u8 as AnsiEncode>::encode_ansi_into(&number, sink)?;
+            b'~'
+        }
+        Form::U(number) => {
+            written += <u32 as AnsiEncode>::encode_ansi_into(&number, sink)?;
+            if flags.contains(KeyboardEnhancementFlags::REPORT_ALTERNATE_KEYS) {
+                let base = event
+                    .base_layout_key
+                    .and_then(keycode_to_csi_u)
+                    .filter(|&base| base != number);
+                if shifted.is_some() || base.is_some() {
+                    written += write_byte_into(sink, b':')?;
+                    if let Some(shifted) = shifted {
+                        written += <u32 as AnsiEncode>::encode_ansi_into(
+                            &shifted, sink,
+                        )?;
+                    }
+                }
+                if let Some(base) = base {
+                    written += write_byte_into(sink, b':')?
+                        + <u32 as AnsiEncode>::encode_ansi_into(&base, sink)?;
+                }
+            }
+            b'u'
+        }
+    };
+
+    if has_mods || text.is_some() {
+        written += write_byte_into(sink, b';')?;
+        if has_mods {
+            written += <u16 as AnsiEncode>::encode_ansi_into(&mod_param, sink)?;
+            if let Some(event_type) = event_type {
+                written += write_byte_into(sink, b':')?
+                    + <u8 as AnsiEncode>::encode_ansi_into(&event_type, sink)?;
+            }
+        }
+    }
+
+    if let Some(text) = text {
+        written += write_byte_into(sink, b';')?;
+        for (i, c) in text.chars().enumerate() {
+            if i > 0 {
+                written += write_byte_into(sink, b':')?;
+            }
+            written +=
+                <u32 as AnsiEncode>::encode_ansi_into(&(c as u32), sink)?;
+        }
+    }
+
+    written += write_byte_into(sink, final_byte)?;
+    Ok(written)
+}
+
 // ============================================================================
 // Key type macros and definitions
 // ============================================================================
@@ -1249,6 +1564,16 @@ fn csi_u_to_keycode(code: u32) -> Option<KeyCode> {
     char::from_u32(code).map(KeyCode::Char)
 }
 
+/// Convert a `KeyCode` to a CSI u key code.
+///
+/// This is the inverse of [`csi_u_to_keycode`].
+fn keycode_to_csi_u(code: KeyCode) -> Option<u32> {
+    match code {
+        KeyCode::Char(c) => Some(c as u32),
+        code => key_to_csi_u_code(code),
+    }
+}
+
 /// Parse associated text from colon-separated Unicode codepoints.
 fn parse_csi_u_text(bytes: &[u8]) -> Option<String> {
     let codepoints: Vec<char> = parse_colon_separated(bytes)
@@ -1345,11 +1670,13 @@ impl<'a> TryFromAnsiIter<'a> for CsiUKeyEvent {
             modifiers |= modifier.into();
         }
 
-        // Parse third parameter: alternate keycodes (currently unused/ignored)
-        let _ = params.next();
-
-        // Parse fourth parameter: text as codepoints
-        let text = params.next().and_then(parse_csi_u_text);
+        // Parse third parameter: text as codepoints.  Earlier versions of
+        // this decoder read the text from a fourth parameter, after an
+        // empty third one; keep accepting that form.
+        let text = params
+            .next()
+            .and_then(parse_csi_u_text)
+            .or_else(|| params.next().and_then(parse_csi_u_text));
 
         // Build the KeyEvent
         let mut builder = KeyEventBuilder::new(key_code, modifiers)
@@ -1404,23 +1731,12 @@ impl AnsiEncode for CsiUKeyEvent {
         // Encode the key event in CSI u format
         // This is a simplified encoding - full round-trip would need more work
         let event = &self.0;
-        let code = match event.code {
-            KeyCode::Char(c) => c as u32,
-            KeyCode::Esc => 27,
-            KeyCode::Enter => 13,
-            KeyCode::Tab => 9,
-            KeyCode::Backspace => 127,
-            event_code => {
-                // For other keys, try to find the CSI u code
-                if let Some(code) = key_to_csi_u_code(event_code) {
-                    code
-                } else {
-                    return Err(EncodeError::Unencodeable(format!(
-                        "unsupported key code: {event_code:?}",
-                    )));
-                }
-            }
-        };
+        let code = keycode_to_csi_u(event.code).ok_or_else(|| {
+            EncodeError::Unencodeable(format!(
+                "unsupported key code: {:?}",
+                event.code
+            ))
+        })?;
 
         let mut written = <u32 as AnsiEncode>::encode_ansi_into(&code, sink)?;
 
@@ -1449,6 +1765,84 @@ impl AnsiEncode for CsiUKeyEvent {
     }
 }
 
+// ============================================================================
+// xterm modifyOtherKeys parsing
+// ============================================================================
+
+/// Key code reported by xterm's `modifyOtherKeys`.
+///
+/// This is the Unicode codepoint of the key, or one of the legacy control
+/// codes for Enter (13), Tab (9), Escape (27) and Backspace (127).
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct OtherKeyCode(pub KeyCode);
+
+impl<'a> TryFromAnsi<'a> for OtherKeyCode {
+    fn try_from_ansi(bytes: &'a [u8]) -> Result<Self, vtansi::ParseError> {
+        let code = <u32 as TryFromAnsi>::try_from_ansi(bytes)?;
+
+        csi_u_to_keycode(code).map(Self).ok_or_else(|| {
+            vtansi::ParseError::InvalidValue(format!(
+                "modifyOtherKeys: invalid key code {code}"
+            ))
+        })
+    }
+}
+
+impl AnsiEncode for OtherKeyCode {
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        let code = keycode_to_csi_u(self.0).ok_or_else(|| {
+            EncodeError::Unencodeable(format!(
+                "unsupported key code: {:?}",
+                self.0
+            ))
+        })?;
+
+        <u32 as AnsiEncode>::encode_ansi_into(&code, sink)
+    }
+}
+
+/// Modified key reported by xterm's `modifyOtherKeys` (`CSI 27 ; Pm ; Pc ~`).
+///
+/// Sent by xterm (and terminals emulating it) for modified keys that have
+/// no unambiguous legacy encoding once `modifyOtherKeys` has been enabled
+/// with [`SetModifyOtherKeys`](super::mode::SetModifyOtherKeys).  With
+/// `formatOtherKeys` set to 1 the same keys are reported as
+/// `CSI Pc ; Pm u`, which is decoded by [`CsiUKeyEventSeq`].
+///
+/// See <https://invisible-island.net/xterm/modified-keys.html>
+#[derive(
+    Debug,
+    PartialOrd,
+    PartialEq,
+    Eq,
+    Clone,
+    Copy,
+    Hash,
+    vtansi::derive::AnsiInput,
+)]
+#[vtansi(csi, params = ["27"], into = KeyEvent, finalbyte = '~')]
+pub struct ModifyOtherKeysSeq {
+    pub modifiers: CsiKeyModifiers,
+    pub key: OtherKeyCode,
+}
+
+impl From<ModifyOtherKeysSeq> for KeyEvent {
+    fn from(key: ModifyOtherKeysSeq) -> Self {
+        let modifiers: KeyModifiers = key.modifiers.into();
+
+        // Report Shift+Tab as BackTab, as the CSI u form does
+        if key.key.0 == KeyCode::Tab && modifiers.contains(KeyModifiers::SHIFT)
+        {
+            Self::new(KeyCode::BackTab, modifiers - KeyModifiers::SHIFT)
+        } else {
+            Self::new(key.key.0, modifiers)
+        }
+    }
+}
+
 key_event!(FnKey);
 
 impl AnsiEncode for FnKey {
@@ -1809,6 +2203,43 @@ mod tests {
         assert_eq!(&buf[..len], b"\r");
     }
 
+    #[test]
+    fn test_encode_key_event_kinds() {
+        // Legacy encodings can't tell a repeat from a press: a held key
+        // resends its press sequence.  Releases are not reported.
+        let press = KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE);
+        let repeat = KeyEvent::builder(KeyCode::Char('a'), KeyModifiers::NONE)
+            .kind(KeyEventKind::Repeat)
+            .build();
+        let release = KeyEvent::builder(KeyCode::Char('a'), KeyModifiers::NONE)
+            .kind(KeyEventKind::Release)
+            .build();
+        assert_eq!(repeat.encode_ansi().unwrap(), press.encode_ansi().unwrap());
+        assert_eq!(release.encode_ansi().unwrap(), b"");
+    }
+
+    #[test]
+    fn test_encode_key_event_alt_control_keys() {
+        // Alt prefixes the control byte of Enter, Backspace, Tab and Esc
+        // with ESC, like any other key
+        for (code, expected) in [
+            (KeyCode::Enter, b"\x1b\r"),
+            (KeyCode::Backspace, b"\x1b\x7f"),
+            (KeyCode::Tab, b"\x1b\t"),
+            (KeyCode::Esc, b"\x1b\x1b"),
+        ] {
+            let event = KeyEvent::new(code, KeyModifiers::ALT);
+            assert_eq!(event.encode_ansi().unwrap(), expected, "{code:?}");
+        }
+
+        // Other modifiers keep their encoding
+        let event = KeyEvent::new(
+            KeyCode::Enter,
+            KeyModifiers::ALT | KeyModifiers::SHIFT,
+        );
+        assert_eq!(event.encode_ansi().unwrap(), b"\x1b[13;4u");
+    }
+
     #[test]
     fn test_encode_key_event_arrow() {
         let event = KeyEvent::new(KeyCode::Up, KeyModifiers::NONE);
@@ -2167,6 +2598,35 @@ mod tests {
         assert_eq!(event.text, Some("A".to_string()));
     }
 
+    #[test]
+    fn test_csi_u_text_third_param() {
+        // The protocol puts the text in the third field
+        let event: KeyEvent =
+            CsiUKeyEvent::try_from_ansi(b"97;2;65").unwrap().into();
+        assert_eq!(event.code, KeyCode::Char('a'));
+        assert_eq!(event.modifiers, KeyModifiers::SHIFT);
+        assert_eq!(event.text, Some("A".to_string()));
+
+        // Alternate keys are sub-parameters of the first field, so they
+        // don't shift the text field
+        let event: KeyEvent =
+            CsiUKeyEvent::try_from_ansi(b"1089:1057:99;2;1057")
+                .unwrap()
+                .into();
+        assert_eq!(event.base_layout_key, Some(KeyCode::Char('c')));
+        assert_eq!(event.text, Some("С".to_string()));
+
+        // Multiple codepoints and no modifiers
+        let event: KeyEvent =
+            CsiUKeyEvent::try_from_ansi(b"97;;65:66").unwrap().into();
+        assert_eq!(event.text, Some("AB".to_string()));
+
+        // The third field takes precedence over a fourth one
+        let event: KeyEvent =
+            CsiUKeyEvent::try_from_ansi(b"97;2;65;66").unwrap().into();
+        assert_eq!(event.text, Some("A".to_string()));
+    }
+
     #[test]
     fn test_csi_u_unicode_char() {
         // 'é' (Unicode codepoint 233)
@@ -2191,6 +2651,19 @@ mod tests {
         assert_eq!(event.text, Some("A".to_string()));
     }
 
+    #[test]
+    fn test_modify_other_keys_round_trip() {
+        let seq = ModifyOtherKeysSeq {
+            modifiers: CsiKeyModifiers(KeyModifiers::CONTROL),
+            key: OtherKeyCode(KeyCode::Char('i')),
+        };
+        assert_eq!(seq.encode_ansi().unwrap(), b"\x1b[27;5;105~");
+
+        let event: KeyEvent = seq.into();
+        assert_eq!(event.code, KeyCode::Char('i'));
+        assert_eq!(event.modifiers, KeyModifiers::CONTROL);
+    }
+
     #[test]
     fn test_csi_u_lock_keys() {
         // CapsLock (code 57358)
@@ -2354,6 +2827,26 @@ mod tests {
         assert_eq!(enc, KeyEncoding::Raw(0x01 | 0x80));
     }
 
+    #[test]
+    fn test_mode_flags_alt_sends_esc_prefix_precedence() {
+        use super::KeyboardModeFlags;
+
+        // xterm's metaSendsEscape overrides eightBitInput
+        let event = KeyEvent::new(KeyCode::Char('a'), KeyModifiers::ALT);
+        let enc = KeyEncoding::from_key_event_with_modes(
+            &event,
+            KeyboardModeFlags::ALT_KEY_HIGH_BIT_SET
+                | KeyboardModeFlags::ALT_KEY_SENDS_ESC_PREFIX,
+        );
+        assert_eq!(
+            enc,
+            KeyEncoding::Char {
+                alt_prefix: true,
+                ch: 'a'
+            }
+        );
+    }
+
     #[test]
     fn test_mode_flags_application_keypad() {
         use super::KeyboardModeFlags;
@@ -2381,8 +2874,8 @@ mod tests {
             }
         );
 
-        // Even with APPLICATION_KEYPAD, regular Char still encodes as char
-        // because we can't tell it's from the keypad
+        // Even with APPLICATION_KEYPAD, a Char without the KEYPAD state
+        // still encodes as char because we can't tell it's from the keypad
         let enc = KeyEncoding::from_key_event_with_modes(
             &event,
             KeyboardModeFlags::APPLICATION_KEYPAD,
@@ -2399,6 +2892,30 @@ mod tests {
         // (used when parsing SS3 sequences from terminal)
         assert_eq!(ss3_byte_to_key(b'u'), Some(KeyCode::Char('5')));
         assert_eq!(key_to_ss3_byte(KeyCode::Char('5')), Some(b'u'));
+
+        // Keys marked as keypad keys use SS3 in application keypad mode
+        let keypad = KeyEvent::builder(KeyCode::Char('5'), KeyModifiers::NONE)
+            .state(KeyEventState::KEYPAD | KeyEventState::NUM_LOCK)
+            .build();
+        let enc = KeyEncoding::from_key_event_with_modes(
+            &keypad,
+            KeyboardModeFlags::APPLICATION_KEYPAD,
+        );
+        assert_eq!(enc, KeyEncoding::Ss3(b'u'));
+
+        // ...unless Num Lock is on and the terminal ignores the mode then
+        let enc = KeyEncoding::from_key_event_with_modes(
+            &keypad,
+            KeyboardModeFlags::APPLICATION_KEYPAD
+                | KeyboardModeFlags::IGNORE_KEYPAD_APP_MODE_ON_NUMLOCK,
+        );
+        assert_eq!(
+            enc,
+            KeyEncoding::Char {
+                alt_prefix: false,
+                ch: '5'
+            }
+        );
     }
 
     #[test]
@@ -1,24 +1,31 @@
 //! Keyboard-related messages.
 
+mod encoder;
 mod encoding;
 mod event;
 mod keycode;
 mod mode;
 mod modifier;
 mod util;
+mod win32;
 
+pub use encoder::KeyEncoder;
+pub(crate) use encoder::MAX_ENHANCEMENT_STACK_DEPTH;
 pub use encoding::{bytes_to_events, get_key_event_encoding};
 pub use event::{KeyEvent, KeyEventBuilder};
 pub use keycode::{KeyCode, MediaKeyCode};
 pub use mode::{
     KeyboardEnhancementFlags, KeyboardEnhancementFlagsQuery,
-    KeyboardEnhancementFlagsResponse, PopKeyboardEnhancementFlags,
-    PushKeyboardEnhancementFlags, ResetApplicationKeypadMode,
-    SetApplicationKeypadMode, SetKeyboardEnhancementFlags,
+    KeyboardEnhancementFlagsResponse, ModifyOtherKeys, ModifyOtherKeysReport,
+    PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    QueryModifyOtherKeys, ResetApplicationKeypadMode, SetApplicationKeypadMode,
+    SetKeyboardEnhancementFlags, SetModifyOtherKeys,
 };
 pub use modifier::{
     KeyEventKind, KeyEventState, KeyModifiers, ModifierKeyCode,
 };
+pub use win32::{ControlKeyState, Win32KeyRecord};
+pub(crate) use win32::{Win32KeyRecordSeq, Win32SurrogatePairs};
 
 use vtansi::bitflags;
 
@@ -218,3 +225,15 @@ terminal_mode!(
     /// terminal support specifics.
     AdditionalModifierKeySendsEscPrefix, private = '?', params = ["1039"], flag = KeyboardModeFlags::ADDITIONAL_MODIFIER_KEY_SENDS_ESC_PREFIX
 );
+
+terminal_mode!(
+    /// Win32 Input Mode.
+    ///
+    /// When this mode is active Windows Terminal and `ConPTY` report every
+    /// key press and release as a serialized Win32 key record
+    /// (`CSI Vk ; Sc ; Uc ; Kd ; Cs ; Rc _`).
+    ///
+    /// See <https://github.com/microsoft/terminal/blob/main/doc/specs/%234999%20-%20Improved%20keyboard%20handling%20in%20Conpty.md>
+    /// for more information.
+    Win32InputMode, private = '?', params = ["9001"]
+);
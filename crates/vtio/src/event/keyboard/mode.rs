@@ -122,6 +122,96 @@ pub struct SetApplicationKeypadMode;
 #[vtansi(esc, finalbyte = '>')]
 pub struct ResetApplicationKeypadMode;
 
+/// xterm `modifyOtherKeys` level.
+///
+/// Controls whether the terminal reports modified keys that would
+/// otherwise be ambiguous (e.g. `Ctrl+i` and `Tab`) using
+/// `CSI 27 ; Pm ; Pc ~` sequences (or `CSI Pc ; Pm u` when xterm's
+/// `formatOtherKeys` resource is set).
+///
+/// See <https://invisible-island.net/xterm/modified-keys.html>
+/// for more information.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Default,
+    vtansi::derive::ToAnsi,
+    vtansi::derive::FromAnsi,
+    num_enum::TryFromPrimitive,
+    num_enum::IntoPrimitive,
+)]
+#[repr(u8)]
+pub enum ModifyOtherKeys {
+    /// Modified keys are reported using their legacy encoding.
+    #[default]
+    Disabled = 0,
+    /// Report modified keys, except those with a well-known legacy
+    /// encoding (such as `Ctrl+a`).
+    Enabled = 1,
+    /// Report all modified keys, including control characters.
+    All = 2,
+}
+
+/// Set `modifyOtherKeys` (`XTMODKEYS`).
+///
+/// Set the xterm `modifyOtherKeys` key modifier option, or reset it to
+/// the value of the `modifyOtherKeys` resource if the level is `None`
+/// (`CSI > 4 m`).
+///
+/// See <https://invisible-island.net/xterm/ctlseqs/ctlseqs.html#h3-Functions-using-CSI-_-ordered-by-the-final-character_s_>
+/// for more information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, vtansi::derive::AnsiOutput)]
+#[vtansi(csi, private = '>', params = ["4"], finalbyte = 'm')]
+pub struct SetModifyOtherKeys(pub Option<ModifyOtherKeys>);
+
+impl SetModifyOtherKeys {
+    /// Create a command setting `modifyOtherKeys` to `level`.
+    #[must_use]
+    pub const fn new(level: ModifyOtherKeys) -> Self {
+        Self(Some(level))
+    }
+
+    /// Create a command resetting `modifyOtherKeys` to its default.
+    #[must_use]
+    pub const fn reset() -> Self {
+        Self(None)
+    }
+}
+
+/// Query `modifyOtherKeys` (`XTQMODKEYS`).
+///
+/// Query the current xterm `modifyOtherKeys` key modifier option.
+///
+/// The terminal will respond with [`ModifyOtherKeysReport`].
+///
+/// See <https://invisible-island.net/xterm/ctlseqs/ctlseqs.html#h3-Functions-using-CSI-_-ordered-by-the-final-character_s_>
+/// for more information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, vtansi::derive::AnsiOutput)]
+#[vtansi(csi, private = '?', params = ["4"], finalbyte = 'm')]
+pub struct QueryModifyOtherKeys;
+
+/// `modifyOtherKeys` report.
+///
+/// Response to [`QueryModifyOtherKeys`].
+#[derive(
+    Debug,
+    PartialOrd,
+    PartialEq,
+    Eq,
+    Clone,
+    Copy,
+    Hash,
+    vtansi::derive::AnsiInput,
+)]
+#[vtansi(csi, private = '>', params = ["4"], finalbyte = 'm')]
+pub struct ModifyOtherKeysReport(pub ModifyOtherKeys);
+
 impl fmt::Display for KeyboardEnhancementFlags {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if self.is_empty() {
@@ -154,6 +244,7 @@ impl fmt::Display for KeyboardEnhancementFlags {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use better_any::TidExt;
     use vtansi::AnsiEncode;
 
     #[test]
@@ -205,6 +296,61 @@ mod tests {
         assert_eq!(buf, b"\x1b[<u");
     }
 
+    #[test]
+    fn test_modify_other_keys_encoding() {
+        assert_eq!(
+            SetModifyOtherKeys::new(ModifyOtherKeys::All)
+                .encode_ansi()
+                .unwrap(),
+            b"\x1b[>4;2m"
+        );
+        assert_eq!(
+            SetModifyOtherKeys::new(ModifyOtherKeys::Disabled)
+                .encode_ansi()
+                .unwrap(),
+            b"\x1b[>4;0m"
+        );
+        assert_eq!(
+            SetModifyOtherKeys::reset().encode_ansi().unwrap(),
+            b"\x1b[>4m"
+        );
+        assert_eq!(QueryModifyOtherKeys.encode_ansi().unwrap(), b"\x1b[?4m");
+    }
+
+    #[test]
+    fn test_modify_other_keys_parse_requests() {
+        let mut levels = Vec::new();
+        let mut queries = 0;
+        crate::parser::TerminalOutputParser::decode_buffer(
+            b"\x1b[>4;1m\x1b[?4m\x1b[1m\x1b[>4m",
+            &mut |event| {
+                if let Some(set) = event.downcast_ref::<SetModifyOtherKeys>() {
+                    levels.push(set.0);
+                } else if event.is::<QueryModifyOtherKeys>() {
+                    queries += 1;
+                }
+            },
+        );
+        assert_eq!(levels, vec![Some(ModifyOtherKeys::Enabled), None]);
+        assert_eq!(queries, 1);
+    }
+
+    #[test]
+    fn test_modify_other_keys_report() {
+        let mut reports = Vec::new();
+        crate::TerminalInputParser::decode_buffer(
+            b"\x1b[>4;2m",
+            &mut |event| {
+                if let Some(report) =
+                    event.downcast_ref::<ModifyOtherKeysReport>()
+                {
+                    reports.push(*report);
+                }
+            },
+        );
+        assert_eq!(reports, vec![ModifyOtherKeysReport(ModifyOtherKeys::All)]);
+    }
+
     #[test]
     fn test_keyboard_enhancement_flags_display() {
         let flags = KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
@@ -175,6 +175,29 @@ pub(crate) fn parse_csi_u_modifiers(
     (modifiers, kind, state)
 }
 
+/// Encode modifiers and lock state in the Kitty keyboard protocol format.
+///
+/// This is the inverse of [`parse_csi_u_modifiers`], without the event
+/// type: the result is `1 + modifier_bits`.
+pub(crate) fn csi_u_modifier_param(
+    modifiers: KeyModifiers,
+    state: KeyEventState,
+) -> u16 {
+    let mut bits = 0u16;
+    for (kitty_bit, our_modifier) in KITTY_MODIFIER_BITS {
+        if modifiers.contains(*our_modifier) {
+            bits |= u16::from(*kitty_bit);
+        }
+    }
+    if state.contains(KeyEventState::CAPS_LOCK) {
+        bits |= 0x40;
+    }
+    if state.contains(KeyEventState::NUM_LOCK) {
+        bits |= 0x80;
+    }
+    1 + bits
+}
+
 impl KeyModifiers {
     /// Encode modifiers as an xterm-style parameter.
     ///
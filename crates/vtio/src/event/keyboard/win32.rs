@@ -0,0 +1,619 @@
+//! Windows Terminal `win32-input-mode` key records.
+//!
+//! With [`Win32InputMode`](super::Win32InputMode) enabled, Windows Terminal
+//! and `ConPTY` report every key press and release as a serialized Win32
+//! `KEY_EVENT_RECORD`:
+//!
+//! ```text
+//! CSI Vk ; Sc ; Uc ; Kd ; Cs ; Rc _
+//! ```
+//!
+//! See <https://github.com/microsoft/terminal/blob/main/doc/specs/%234999%20-%20Improved%20keyboard%20handling%20in%20Conpty.md>
+
+use vtansi::{AnsiEncode, EncodeError, TryFromAnsi, TryFromAnsiIter};
+
+use super::encoding::char_from_control_code;
+use super::event::{KeyEvent, KeyEventBuilder};
+use super::keycode::{KeyCode, MediaKeyCode};
+use super::modifier::{
+    KeyEventKind, KeyEventState, KeyModifiers, ModifierKeyCode,
+};
+
+vtansi::bitflags! {
+    /// Win32 control key state (`dwControlKeyState`).
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
+    #[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash, Default)]
+    pub struct ControlKeyState: u16 {
+        /// The right Alt key is pressed.
+        const RIGHT_ALT_PRESSED = 0x0001;
+        /// The left Alt key is pressed.
+        const LEFT_ALT_PRESSED = 0x0002;
+        /// The right Ctrl key is pressed.
+        const RIGHT_CTRL_PRESSED = 0x0004;
+        /// The left Ctrl key is pressed.
+        const LEFT_CTRL_PRESSED = 0x0008;
+        /// A Shift key is pressed.
+        const SHIFT_PRESSED = 0x0010;
+        /// Num Lock is on.
+        const NUMLOCK_ON = 0x0020;
+        /// Scroll Lock is on.
+        const SCROLLLOCK_ON = 0x0040;
+        /// Caps Lock is on.
+        const CAPSLOCK_ON = 0x0080;
+        /// The key is an enhanced key (e.g. the arrow keys outside the
+        /// numeric keypad, or the keypad Enter key).
+        const ENHANCED_KEY = 0x0100;
+    }
+}
+
+impl ControlKeyState {
+    /// Return the key modifiers held in this state.
+    #[must_use]
+    pub fn modifiers(self) -> KeyModifiers {
+        let mut modifiers = KeyModifiers::NONE;
+        if self.contains(Self::SHIFT_PRESSED) {
+            modifiers |= KeyModifiers::SHIFT;
+        }
+        if self.intersects(Self::LEFT_CTRL_PRESSED | Self::RIGHT_CTRL_PRESSED) {
+            modifiers |= KeyModifiers::CONTROL;
+        }
+        if self.intersects(Self::LEFT_ALT_PRESSED | Self::RIGHT_ALT_PRESSED) {
+            modifiers |= KeyModifiers::ALT;
+        }
+        modifiers
+    }
+
+    /// Return the lock key state held in this state.
+    #[must_use]
+    pub fn key_event_state(self) -> KeyEventState {
+        let mut state = KeyEventState::NONE;
+        if self.contains(Self::CAPSLOCK_ON) {
+            state |= KeyEventState::CAPS_LOCK;
+        }
+        if self.contains(Self::NUMLOCK_ON) {
+            state |= KeyEventState::NUM_LOCK;
+        }
+        state
+    }
+}
+
+/// Convert a Win32 virtual-key code to a `KeyCode`.
+///
+/// Returns the key code and whether the key is on the numeric keypad.
+/// Keys whose meaning depends on the keyboard layout (letters, digits and
+/// punctuation) are not handled here.
+#[allow(clippy::cast_possible_truncation)]
+fn virtual_key_to_keycode(
+    vk: u16,
+    scan_code: u16,
+    state: ControlKeyState,
+) -> Option<(KeyCode, bool)> {
+    let enhanced = state.contains(ControlKeyState::ENHANCED_KEY);
+    let code = match vk {
+        0x08 => KeyCode::Backspace,
+        0x09 => KeyCode::Tab,
+        // The keypad Enter key is reported as an enhanced Enter key.
+        0x0D => return Some((KeyCode::Enter, enhanced)),
+        0x13 => KeyCode::Pause,
+        0x14 => KeyCode::CapsLock,
+        0x1B => KeyCode::Esc,
+        0x20 => KeyCode::Char(' '),
+        // Navigation keys on the numeric keypad are not enhanced keys.
+        0x21..=0x28 | 0x2D | 0x2E => {
+            let code = match vk {
+                0x21 => KeyCode::PageUp,
+                0x22 => KeyCode::PageDown,
+                0x23 => KeyCode::End,
+                0x24 => KeyCode::Home,
+                0x25 => KeyCode::Left,
+                0x26 => KeyCode::Up,
+                0x27 => KeyCode::Right,
+                0x28 => KeyCode::Down,
+                0x2D => KeyCode::Insert,
+                _ => KeyCode::Delete,
+            };
+            return Some((code, !enhanced));
+        }
+        0x0C => return Some((KeyCode::KeypadBegin, true)),
+        0x2C => KeyCode::PrintScreen,
+        0x5B => KeyCode::Modifier(ModifierKeyCode::LeftSuper),
+        0x5C => KeyCode::Modifier(ModifierKeyCode::RightSuper),
+        0x5D => KeyCode::Menu,
+        0x60..=0x69 => {
+            let digit = char::from(b'0' + (vk - 0x60) as u8);
+            return Some((KeyCode::Char(digit), true));
+        }
+        0x6A..=0x6F => {
+            let ch = match vk {
+                0x6A => '*',
+                0x6B => '+',
+                0x6C => ',',
+                0x6D => '-',
+                0x6E => '.',
+                _ => '/',
+            };
+            return Some((KeyCode::Char(ch), true));
+        }
+        0x70..=0x87 => KeyCode::F((vk - 0x6F) as u8),
+        0x90 => KeyCode::NumLock,
+        0x91 => KeyCode::ScrollLock,
+        // Scan code 0x36 is the right Shift key.
+        0x10 if scan_code == 0x36 => {
+            KeyCode::Modifier(ModifierKeyCode::RightShift)
+        }
+        0x10 | 0xA0 => KeyCode::Modifier(ModifierKeyCode::LeftShift),
+        0xA1 => KeyCode::Modifier(ModifierKeyCode::RightShift),
+        0x11 if enhanced => KeyCode::Modifier(ModifierKeyCode::RightControl),
+        0x11 | 0xA2 => KeyCode::Modifier(ModifierKeyCode::LeftControl),
+        0xA3 => KeyCode::Modifier(ModifierKeyCode::RightControl),
+        0x12 if enhanced => KeyCode::Modifier(ModifierKeyCode::RightAlt),
+        0x12 | 0xA4 => KeyCode::Modifier(ModifierKeyCode::LeftAlt),
+        0xA5 => KeyCode::Modifier(ModifierKeyCode::RightAlt),
+        0xAD => KeyCode::Media(MediaKeyCode::MuteVolume),
+        0xAE => KeyCode::Media(MediaKeyCode::LowerVolume),
+        0xAF => KeyCode::Media(MediaKeyCode::RaiseVolume),
+        0xB0 => KeyCode::Media(MediaKeyCode::TrackNext),
+        0xB1 => KeyCode::Media(MediaKeyCode::TrackPrevious),
+        0xB2 => KeyCode::Media(MediaKeyCode::Stop),
+        0xB3 => KeyCode::Media(MediaKeyCode::PlayPause),
+        _ => return None,
+    };
+    Some((code, false))
+}
+
+/// Serialized Win32 `KEY_EVENT_RECORD`.
+///
+/// Parses the parameters of `CSI Vk ; Sc ; Uc ; Kd ; Cs ; Rc _`.  Omitted
+/// parameters take their default values: zero, except for the repeat
+/// count which defaults to 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Win32KeyRecord {
+    /// Virtual-key code (`wVirtualKeyCode`).
+    pub virtual_key_code: u16,
+    /// Virtual scan code (`wVirtualScanCode`).
+    pub virtual_scan_code: u16,
+    /// UTF-16 code unit of the translated character (`uChar`).
+    pub unicode_char: u16,
+    /// Whether the key was pressed (`bKeyDown`).
+    pub key_down: bool,
+    /// Control key state (`dwControlKeyState`).
+    pub control_key_state: ControlKeyState,
+    /// Number of times the keystroke is repeated (`wRepeatCount`).
+    pub repeat_count: u16,
+}
+
+impl Default for Win32KeyRecord {
+    fn default() -> Self {
+        Self {
+            virtual_key_code: 0,
+            virtual_scan_code: 0,
+            unicode_char: 0,
+            key_down: false,
+            control_key_state: ControlKeyState::empty(),
+            repeat_count: 1,
+        }
+    }
+}
+
+impl<'a> TryFromAnsiIter<'a> for Win32KeyRecord {
+    fn try_from_ansi_iter<I>(params: &mut I) -> Result<Self, vtansi::ParseError>
+    where
+        I: Iterator<Item = &'a [u8]>,
+    {
+        fn next_param<'a>(
+            params: &mut impl Iterator<Item = &'a [u8]>,
+        ) -> Result<Option<u16>, vtansi::ParseError> {
+            match params.next() {
+                Some(param) if !param.is_empty() => {
+                    <u16 as TryFromAnsi>::try_from_ansi(param).map(Some)
+                }
+                _ => Ok(None),
+            }
+        }
+
+        let mut record = Self::default();
+        if let Some(vk) = next_param(params)? {
+            record.virtual_key_code = vk;
+        }
+        if let Some(sc) = next_param(params)? {
+            record.virtual_scan_code = sc;
+        }
+        if let Some(uc) = next_param(params)? {
+            record.unicode_char = uc;
+        }
+        if let Some(kd) = next_param(params)? {
+            record.key_down = kd != 0;
+        }
+        if let Some(cs) = next_param(params)? {
+            record.control_key_state = ControlKeyState::from_bits_retain(cs);
+        }
+        if let Some(rc) = next_param(params)? {
+            record.repeat_count = rc;
+        }
+        if params.next().is_some() {
+            return Err(vtansi::ParseError::InvalidValue(
+                "win32-input-mode: too many parameters".to_string(),
+            ));
+        }
+
+        Ok(record)
+    }
+}
+
+impl<'a> TryFromAnsi<'a> for Win32KeyRecord {
+    #[inline]
+    fn try_from_ansi(bytes: &'a [u8]) -> Result<Self, vtansi::ParseError> {
+        <Self as TryFromAnsiIter>::try_from_ansi_iter(
+            &mut bytes.split(|&c| c == b';'),
+        )
+    }
+}
+
+impl AnsiEncode for Win32KeyRecord {
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        let params = [
+            self.virtual_key_code,
+            self.virtual_scan_code,
+            self.unicode_char,
+            u16::from(self.key_down),
+            self.control_key_state.bits(),
+            self.repeat_count,
+        ];
+
+        let mut written = 0;
+        for (i, param) in params.iter().enumerate() {
+            if i > 0 {
+                written += vtansi::write_byte_into(sink, b';')?;
+            }
+            written += <u16 as AnsiEncode>::encode_ansi_into(param, sink)?;
+        }
+        Ok(written)
+    }
+}
+
+impl Win32KeyRecord {
+    /// Return `true` if the record carries the high (leading) surrogate of
+    /// a UTF-16 surrogate pair.
+    const fn has_high_surrogate(&self) -> bool {
+        matches!(self.unicode_char, 0xD800..=0xDBFF)
+    }
+
+    /// Return `true` if the record carries the low (trailing) surrogate of
+    /// a UTF-16 surrogate pair.
+    const fn has_low_surrogate(&self) -> bool {
+        matches!(self.unicode_char, 0xDC00..=0xDFFF)
+    }
+
+    /// Convert the record to a key event producing `text`, the translated
+    /// character.
+    fn to_key_event(
+        self,
+        text: Option<char>,
+    ) -> Result<KeyEvent, vtansi::ParseError> {
+        let record = self;
+        let state = record.control_key_state;
+        let mut modifiers = state.modifiers();
+        let mut key_state = state.key_event_state();
+
+        let text = text.filter(|ch| !ch.is_control());
+
+        let vk = record.virtual_key_code;
+        let mut code = if let Some((code, keypad)) =
+            virtual_key_to_keycode(vk, record.virtual_scan_code, state)
+        {
+            if keypad {
+                key_state |= KeyEventState::KEYPAD;
+            }
+            code
+        } else if let Some(ch) = text {
+            // AltGr is reported as Ctrl+Alt; drop both when the key
+            // produced a character.
+            if modifiers.contains(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+                modifiers -= KeyModifiers::CONTROL | KeyModifiers::ALT;
+            }
+            KeyCode::Char(ch)
+        } else if let Ok(byte @ (b'0'..=b'9' | b'A'..=b'Z')) = u8::try_from(vk)
+        {
+            KeyCode::Char(char::from(byte).to_ascii_lowercase())
+        } else if let Some(ch) = u8::try_from(record.unicode_char)
+            .ok()
+            .and_then(char_from_control_code)
+            .filter(|_| modifiers.contains(KeyModifiers::CONTROL))
+        {
+            KeyCode::Char(ch)
+        } else {
+            return Err(vtansi::ParseError::InvalidValue(format!(
+                "win32-input-mode: unrecognized key (vk {vk}, char {})",
+                record.unicode_char
+            )));
+        };
+
+        if code == KeyCode::Tab && modifiers.contains(KeyModifiers::SHIFT) {
+            code = KeyCode::BackTab;
+            modifiers -= KeyModifiers::SHIFT;
+        }
+
+        if let KeyCode::Modifier(modifier) = code {
+            modifiers |= modifier.into();
+        }
+
+        let kind = if !record.key_down {
+            KeyEventKind::Release
+        } else if record.repeat_count > 1 {
+            KeyEventKind::Repeat
+        } else {
+            KeyEventKind::Press
+        };
+
+        let mut builder = KeyEventBuilder::new(code, modifiers)
+            .kind(kind)
+            .state(key_state);
+        if let Some(ch) = text
+            && record.key_down
+        {
+            let count = usize::from(record.repeat_count.max(1));
+            builder = builder.text(std::iter::repeat_n(ch, count).collect());
+        }
+
+        Ok(builder.build())
+    }
+}
+
+impl TryFrom<Win32KeyRecord> for KeyEvent {
+    type Error = vtansi::ParseError;
+
+    /// Convert a key record to a key event.
+    ///
+    /// Key-down records with a repeat count above one are reported as
+    /// [`KeyEventKind::Repeat`], with the translated character repeated
+    /// accordingly in the associated text.
+    ///
+    /// A character outside the Basic Multilingual Plane is sent as two
+    /// records carrying one UTF-16 surrogate each.  These cannot be
+    /// decoded on their own and are rejected; the input parser joins them
+    /// into one key event.
+    fn try_from(record: Win32KeyRecord) -> Result<Self, Self::Error> {
+        record.to_key_event(char::from_u32(u32::from(record.unicode_char)))
+    }
+}
+
+/// Joins key records carrying the two halves of a UTF-16 surrogate pair.
+///
+/// win32-input-mode sends a character outside the Basic Multilingual
+/// Plane, such as an emoji, as a record carrying the high surrogate
+/// followed by a record carrying the low surrogate.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Win32SurrogatePairs {
+    high: Option<Win32KeyRecord>,
+}
+
+impl Win32SurrogatePairs {
+    pub(crate) const fn new() -> Self {
+        Self { high: None }
+    }
+
+    /// Convert `record` to a key event.
+    ///
+    /// Return `None` if `record` carries a high surrogate, which is held
+    /// until the record carrying the low surrogate arrives.  A high
+    /// surrogate that is not followed by a matching low surrogate is
+    /// dropped, and a low surrogate without a preceding high surrogate is
+    /// rejected.
+    pub(crate) fn decode(
+        &mut self,
+        record: Win32KeyRecord,
+    ) -> Option<Result<KeyEvent, vtansi::ParseError>> {
+        if let Some(high) = self.high.take()
+            && record.has_low_surrogate()
+            && record.key_down == high.key_down
+        {
+            let text =
+                char::decode_utf16([high.unicode_char, record.unicode_char])
+                    .next()
+                    .and_then(Result::ok);
+            return Some(high.to_key_event(text));
+        }
+        if record.has_high_surrogate() {
+            self.high = Some(record);
+            return None;
+        }
+        Some(record.try_into())
+    }
+}
+
+/// Win32 key record input sequence (`CSI Vk ; Sc ; Uc ; Kd ; Cs ; Rc _`).
+///
+/// The input parser converts it to a [`KeyEvent`], joining the records of
+/// surrogate pairs with [`Win32SurrogatePairs`].
+#[derive(Debug, Clone, PartialEq, Eq, vtansi::derive::AnsiInput)]
+#[vtansi(csi, finalbyte = '_')]
+pub struct Win32KeyRecordSeq(#[vtansi(flatten)] pub Win32KeyRecord);
+
+impl TryFrom<Win32KeyRecordSeq> for KeyEvent {
+    type Error = vtansi::ParseError;
+
+    #[inline]
+    fn try_from(seq: Win32KeyRecordSeq) -> Result<Self, Self::Error> {
+        seq.0.try_into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(params: &[u8]) -> KeyEvent {
+        Win32KeyRecord::try_from_ansi(params)
+            .unwrap()
+            .try_into()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_character_key() {
+        // 'a' key down
+        let event = decode(b"65;30;97;1;0;1");
+        assert_eq!(event.code, KeyCode::Char('a'));
+        assert_eq!(event.modifiers, KeyModifiers::NONE);
+        assert_eq!(event.kind, KeyEventKind::Press);
+        assert_eq!(event.text.as_deref(), Some("a"));
+
+        // 'a' key up
+        let event = decode(b"65;30;97;0;0;1");
+        assert_eq!(event.kind, KeyEventKind::Release);
+        assert_eq!(event.text, None);
+    }
+
+    #[test]
+    fn test_control_character() {
+        // Ctrl+A reports the control code as the character
+        let event = decode(b"65;30;1;1;8;1");
+        assert_eq!(event.code, KeyCode::Char('a'));
+        assert_eq!(event.modifiers, KeyModifiers::CONTROL);
+        assert_eq!(event.text, None);
+
+        // Ctrl+[ on a layout-dependent key
+        let event = decode(b"219;26;27;1;4;1");
+        assert_eq!(event.code, KeyCode::Char('['));
+        assert_eq!(event.modifiers, KeyModifiers::CONTROL);
+    }
+
+    #[test]
+    fn test_altgr_character() {
+        // AltGr+q on a German layout produces '@'
+        let event = decode(b"81;16;64;1;9;1");
+        assert_eq!(event.code, KeyCode::Char('@'));
+        assert_eq!(event.modifiers, KeyModifiers::NONE);
+    }
+
+    #[test]
+    fn test_repeat_count() {
+        let event = decode(b"65;30;97;1;0;3");
+        assert_eq!(event.kind, KeyEventKind::Repeat);
+        assert_eq!(event.text.as_deref(), Some("aaa"));
+    }
+
+    #[test]
+    fn test_keypad_and_enhanced_keys() {
+        // Arrow key cluster
+        let event = decode(b"38;72;0;1;256;1");
+        assert_eq!(event.code, KeyCode::Up);
+        assert!(!event.state.contains(KeyEventState::KEYPAD));
+
+        // Keypad 8 with Num Lock off
+        let event = decode(b"38;72;0;1;0;1");
+        assert_eq!(event.code, KeyCode::Up);
+        assert!(event.state.contains(KeyEventState::KEYPAD));
+
+        // Keypad Enter
+        let event = decode(b"13;28;13;1;256;1");
+        assert_eq!(event.code, KeyCode::Enter);
+        assert!(event.state.contains(KeyEventState::KEYPAD));
+
+        // Keypad 5 with Num Lock on
+        let event = decode(b"101;76;53;1;32;1");
+        assert_eq!(event.code, KeyCode::Char('5'));
+        assert!(event.state.contains(KeyEventState::KEYPAD));
+        assert!(event.state.contains(KeyEventState::NUM_LOCK));
+    }
+
+    #[test]
+    fn test_modifier_keys() {
+        let event = decode(b"16;54;0;1;16;1");
+        assert_eq!(event.code, KeyCode::Modifier(ModifierKeyCode::RightShift));
+        assert_eq!(event.modifiers, KeyModifiers::SHIFT);
+
+        let event = decode(b"17;29;0;0;256;1");
+        assert_eq!(
+            event.code,
+            KeyCode::Modifier(ModifierKeyCode::RightControl)
+        );
+        assert_eq!(event.kind, KeyEventKind::Release);
+    }
+
+    #[test]
+    fn test_function_keys() {
+        let event = decode(b"112;59;0;1;0;1");
+        assert_eq!(event.code, KeyCode::F(1));
+
+        let event = decode(b"123;88;0;1;2;1");
+        assert_eq!(event.code, KeyCode::F(12));
+        assert_eq!(event.modifiers, KeyModifiers::ALT);
+    }
+
+    #[test]
+    fn test_shift_tab() {
+        let event = decode(b"9;15;9;1;16;1");
+        assert_eq!(event.code, KeyCode::BackTab);
+        assert_eq!(event.modifiers, KeyModifiers::NONE);
+    }
+
+    #[test]
+    fn test_default_params() {
+        let record = Win32KeyRecord::try_from_ansi(b"65;;97;1").unwrap();
+        assert_eq!(record.virtual_scan_code, 0);
+        assert_eq!(record.control_key_state, ControlKeyState::empty());
+        assert_eq!(record.repeat_count, 1);
+    }
+
+    #[test]
+    fn test_lone_surrogate_rejected() {
+        let record = Win32KeyRecord::try_from_ansi(b"0;0;55357;1;0;1").unwrap();
+        assert!(KeyEvent::try_from(record).is_err());
+
+        // A low surrogate without a high surrogate
+        let mut pairs = Win32SurrogatePairs::default();
+        let low = Win32KeyRecord::try_from_ansi(b"0;0;56832;1;0;1").unwrap();
+        assert!(matches!(pairs.decode(low), Some(Err(_))));
+
+        // A high surrogate followed by another key is dropped
+        let high = Win32KeyRecord::try_from_ansi(b"0;0;55357;1;0;1").unwrap();
+        assert_eq!(pairs.decode(high), None);
+        let a = Win32KeyRecord::try_from_ansi(b"65;30;97;1;0;1").unwrap();
+        let event = pairs.decode(a).unwrap().unwrap();
+        assert_eq!(event.code, KeyCode::Char('a'));
+        assert!(matches!(pairs.decode(low), Some(Err(_))));
+    }
+
+    #[test]
+    fn test_surrogate_pair() {
+        // U+1F600 GRINNING FACE is sent as U+D83D U+DE00
+        let mut pairs = Win32SurrogatePairs::default();
+        let mut decode = |params: &[u8]| {
+            pairs.decode(Win32KeyRecord::try_from_ansi(params).unwrap())
+        };
+        assert_eq!(decode(b"0;0;55357;1;0;1"), None);
+        let event = decode(b"0;0;56832;1;0;1").unwrap().unwrap();
+        assert_eq!(event.code, KeyCode::Char('\u{1F600}'));
+        assert_eq!(event.kind, KeyEventKind::Press);
+        assert_eq!(event.text.as_deref(), Some("\u{1F600}"));
+
+        assert_eq!(decode(b"0;0;55357;0;0;1"), None);
+        let event = decode(b"0;0;56832;0;0;1").unwrap().unwrap();
+        assert_eq!(event.code, KeyCode::Char('\u{1F600}'));
+        assert_eq!(event.kind, KeyEventKind::Release);
+        assert_eq!(event.text, None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let record = Win32KeyRecord {
+            virtual_key_code: 65,
+            virtual_scan_code: 30,
+            unicode_char: 65,
+            key_down: true,
+            control_key_state: ControlKeyState::SHIFT_PRESSED,
+            repeat_count: 1,
+        };
+        let seq = Win32KeyRecordSeq(record);
+        let bytes = seq.encode_ansi().unwrap();
+        assert_eq!(bytes, b"\x1b[65;30;65;1;16;1_");
+        assert_eq!(
+            Win32KeyRecord::try_from_ansi(&bytes[2..bytes.len() - 1]).unwrap(),
+            record
+        );
+    }
+}
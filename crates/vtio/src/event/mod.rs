@@ -1,21 +1,29 @@
 //! Terminal event sequences.
 
 pub mod charset;
+pub mod clipboard;
 pub mod color;
 pub mod cursor;
 pub mod dsr;
+pub mod graphics;
+pub mod hyperlink;
+pub mod input;
 pub mod iterm;
 pub mod keyboard;
 pub mod mode;
 pub mod mouse;
+pub mod notification;
 pub mod screen;
 pub mod scroll;
+pub mod sgr;
 pub mod shell;
+pub mod status;
 pub mod terminal;
 pub mod text;
 pub mod window;
 
 // Re-export module-level input event enums
+pub use input::InputEvent;
 pub use mouse::MouseEvent;
 pub use text::PlainText;
 
@@ -1,5 +1,15 @@
 //! Definitions and helpers for terminal modes.
 
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use better_any::TidExt;
+use vt_push_parser::event::{CSI, VTEvent};
+use vtansi::{AnsiControlFunctionKind, AnsiEvent};
+
+use crate::event::{UnrecognizedInputEvent, UnrecognizedOutputEvent};
+
 /// Represents state of terminal mode as reported in `DECRPM` responses.
 ///
 /// See <https://vt100.net/docs/vt510-rm/DECRPM.html> for more information.
@@ -26,6 +36,185 @@ pub enum TerminalModeState {
     PermanentlyReset = 4,
 }
 
+/// A mode defined with [`terminal_mode!`](crate::terminal_mode).
+///
+/// Implemented by the mode state report type, and by the `Enable*`,
+/// `Disable*` and `Request*` types of the mode.
+pub trait TerminalMode {
+    /// Whether this is a DEC private mode.
+    const PRIVATE: bool;
+    /// The mode number.
+    const MODE: u16;
+}
+
+/// A mode state request (`DECRQM`) generated by
+/// [`terminal_mode!`](crate::terminal_mode).
+pub trait TerminalModeRequest:
+    TerminalMode + vtansi::AnsiEncode + Default
+{
+}
+
+/// What a mode control function does with the modes it names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModeFunction {
+    /// Set the modes (`SM`, `DECSET`).
+    Set,
+    /// Reset the modes (`RM`, `DECRST`).
+    Reset,
+    /// Request the state of a mode (`DECRQM`).
+    Request,
+    /// Report the state of a mode (`DECRPM`).
+    Report(TerminalModeState),
+}
+
+/// Registry entry of a mode defined with
+/// [`terminal_mode!`](crate::terminal_mode).
+#[doc(hidden)]
+pub struct TerminalModeEntry {
+    pub private: bool,
+    pub mode: u16,
+    /// Return the type ids of the mode's control function types.
+    pub type_ids: fn() -> [TypeId; 4],
+    /// Return what `event` does with the mode, if it is one of the mode's
+    /// control functions.
+    pub function: fn(&dyn AnsiEvent<'_>) -> Option<ModeFunction>,
+}
+
+#[doc(hidden)]
+#[linkme::distributed_slice]
+pub static TERMINAL_MODE_REGISTRY: [TerminalModeEntry];
+
+/// [`TERMINAL_MODE_REGISTRY`] entries by the type ids of their control
+/// function types, so that decoding an event doesn't have to try every
+/// mode.
+static TERMINAL_MODES_BY_TYPE: LazyLock<
+    HashMap<TypeId, &'static TerminalModeEntry>,
+> = LazyLock::new(|| {
+    TERMINAL_MODE_REGISTRY
+        .iter()
+        .flat_map(|entry| {
+            (entry.type_ids)().into_iter().map(move |id| (id, entry))
+        })
+        .collect()
+});
+
+/// A mode control function decoded from a parser event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ModeControl {
+    /// Whether the modes are DEC private modes.
+    pub private: bool,
+    /// The mode numbers, in order.  Requests and reports name exactly one.
+    pub modes: Vec<u16>,
+    pub function: ModeFunction,
+}
+
+impl ModeControl {
+    /// Decode a mode control function from a typed
+    /// [`terminal_mode!`](crate::terminal_mode) event, or from an
+    /// unrecognized control sequence naming a mode the parser does not
+    /// know.
+    pub(crate) fn from_event(event: &dyn AnsiEvent<'_>) -> Option<Self> {
+        if event.ansi_control_kind() != Some(AnsiControlFunctionKind::Csi) {
+            return None;
+        }
+        if let Some(UnrecognizedInputEvent(VTEvent::Csi(csi))) =
+            event.downcast_ref::<UnrecognizedInputEvent>()
+        {
+            return Self::from_csi(csi);
+        }
+        if let Some(UnrecognizedOutputEvent(VTEvent::Csi(csi))) =
+            event.downcast_ref::<UnrecognizedOutputEvent>()
+        {
+            return Self::from_csi(csi);
+        }
+        let entry = TERMINAL_MODES_BY_TYPE.get(&event.self_id())?;
+        Some(Self {
+            private: entry.private,
+            modes: vec![entry.mode],
+            function: (entry.function)(event)?,
+        })
+    }
+
+    fn from_csi(csi: &CSI<'_>) -> Option<Self> {
+        let private = match csi.private {
+            None => false,
+            Some(b'?') => true,
+            Some(_) => return None,
+        };
+        let function = match (csi.intermediates.as_ref(), csi.final_byte) {
+            (b"", b'h') => ModeFunction::Set,
+            (b"", b'l') => ModeFunction::Reset,
+            (b"$", b'p') if csi.params.len() == 1 => ModeFunction::Request,
+            (b"$", b'y') if csi.params.len() == 2 => ModeFunction::Report(
+                TerminalModeState::try_from(csi.params.try_parse::<u8>(1)?)
+                    .ok()?,
+            ),
+            _ => return None,
+        };
+        let modes = match function {
+            ModeFunction::Report(_) => vec![csi.params.try_parse(0)?],
+            _ => (0..csi.params.len())
+                .map(|index| csi.params.try_parse(index))
+                .collect::<Option<_>>()?,
+        };
+        Some(Self {
+            private,
+            modes,
+            function,
+        })
+    }
+
+    /// Decode a mode state request (`DECRQM`), returning whether the mode
+    /// is a DEC private mode and its number.
+    pub(crate) fn request(event: &dyn AnsiEvent<'_>) -> Option<(bool, u16)> {
+        let control = Self::from_event(event)?;
+        (control.function == ModeFunction::Request)
+            .then(|| (control.private, control.modes[0]))
+    }
+}
+
+/// DEC private modes that are set in a freshly reset terminal: autowrap
+/// (`DECAWM`) and cursor visibility (`DECTCEM`).
+const DEFAULT_SET_MODES: &[u16] = &[7, 25];
+
+/// The state of a mode in a freshly reset terminal.
+///
+/// Modes defined with [`terminal_mode!`](crate::terminal_mode) are reset,
+/// except autowrap and cursor visibility, which are set.  Other modes are
+/// not recognized.
+pub(crate) fn default_mode_state(
+    private: bool,
+    mode: u16,
+) -> TerminalModeState {
+    if private && DEFAULT_SET_MODES.contains(&mode) {
+        TerminalModeState::Set
+    } else if TERMINAL_MODE_REGISTRY
+        .iter()
+        .any(|entry| entry.private == private && entry.mode == mode)
+    {
+        TerminalModeState::Reset
+    } else {
+        TerminalModeState::NotRecognized
+    }
+}
+
+/// Parse the mode number of a [`terminal_mode!`](crate::terminal_mode)
+/// parameter at compile time.
+#[doc(hidden)]
+#[must_use]
+pub const fn parse_mode_number(param: &str) -> u16 {
+    let bytes = param.as_bytes();
+    assert!(!bytes.is_empty(), "empty mode number");
+    let mut mode: u16 = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        assert!(bytes[i].is_ascii_digit(), "invalid mode number");
+        mode = mode * 10 + (bytes[i] - b'0') as u16;
+        i += 1;
+    }
+    mode
+}
+
 /// Generate terminal mode control sequences.
 ///
 /// This macro generates four control sequence structs for a terminal mode:
@@ -36,6 +225,10 @@ pub enum TerminalModeState {
 /// - `{Name}`: CSI sequence with '$' intermediate and 'y' final byte
 ///   representing the mode state response (with `enabled: bool` field)
 ///
+/// All four implement [`TerminalMode`](crate::event::mode::TerminalMode),
+/// and `Request{Name}` implements
+/// [`TerminalModeRequest`](crate::event::mode::TerminalModeRequest).
+///
 /// # Syntax
 ///
 /// ```ignore
@@ -62,6 +255,62 @@ pub enum TerminalModeState {
 /// - `RelativeCursorOriginMode` → CSI ? $ y (with `state` field)
 #[macro_export]
 macro_rules! terminal_mode {
+    (@impls $base_name:ident, private = $private:expr, params = [$($params:literal),*]) => {
+        $crate::__private::paste::paste! {
+            impl $crate::event::mode::TerminalMode for [<$base_name>] {
+                const PRIVATE: bool = $private;
+                const MODE: u16 = $crate::event::mode::parse_mode_number(concat!($($params),*));
+            }
+
+            impl $crate::event::mode::TerminalMode for [<Enable $base_name>] {
+                const PRIVATE: bool = $private;
+                const MODE: u16 = <[<$base_name>] as $crate::event::mode::TerminalMode>::MODE;
+            }
+
+            impl $crate::event::mode::TerminalMode for [<Disable $base_name>] {
+                const PRIVATE: bool = $private;
+                const MODE: u16 = <[<$base_name>] as $crate::event::mode::TerminalMode>::MODE;
+            }
+
+            impl $crate::event::mode::TerminalMode for [<Request $base_name>] {
+                const PRIVATE: bool = $private;
+                const MODE: u16 = <[<$base_name>] as $crate::event::mode::TerminalMode>::MODE;
+            }
+
+            impl $crate::event::mode::TerminalModeRequest for [<Request $base_name>] {}
+
+            const _: () = {
+                use $crate::__private::better_any::TidExt as _;
+
+                #[$crate::__private::linkme::distributed_slice($crate::event::mode::TERMINAL_MODE_REGISTRY)]
+                #[linkme(crate = $crate::__private::linkme)]
+                static ENTRY: $crate::event::mode::TerminalModeEntry = $crate::event::mode::TerminalModeEntry {
+                    private: $private,
+                    mode: <[<$base_name>] as $crate::event::mode::TerminalMode>::MODE,
+                    type_ids: || [
+                        <[<$base_name>] as $crate::__private::better_any::Tid>::id(),
+                        <[<Enable $base_name>] as $crate::__private::better_any::Tid>::id(),
+                        <[<Disable $base_name>] as $crate::__private::better_any::Tid>::id(),
+                        <[<Request $base_name>] as $crate::__private::better_any::Tid>::id(),
+                    ],
+                    function: |event| {
+                        if let Some(report) = event.downcast_ref::<[<$base_name>]>() {
+                            Some($crate::event::mode::ModeFunction::Report(report.state))
+                        } else if event.is::<[<Enable $base_name>]>() {
+                            Some($crate::event::mode::ModeFunction::Set)
+                        } else if event.is::<[<Disable $base_name>]>() {
+                            Some($crate::event::mode::ModeFunction::Reset)
+                        } else if event.is::<[<Request $base_name>]>() {
+                            Some($crate::event::mode::ModeFunction::Request)
+                        } else {
+                            None
+                        }
+                    },
+                };
+            };
+        }
+    };
+
     ($(#[$meta:meta])* $base_name:ident, private = $private:literal, params = [$($params:literal),* $(,)?]) => {
         $crate::__private::paste::paste! {
             $(#[$meta])*
@@ -82,10 +331,12 @@ macro_rules! terminal_mode {
             pub struct [<Disable $base_name>];
 
             #[doc = concat!("Query state of [`", stringify!($base_name), "`].")]
-            #[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash, ::vtansi::derive::AnsiOutput)]
+            #[derive(Debug, Default, PartialOrd, PartialEq, Eq, Clone, Copy, Hash, ::vtansi::derive::AnsiOutput)]
             #[vtansi(csi, private = $private, params = [$($params),*], intermediate = "$", finalbyte = 'p')]
             pub struct [<Request $base_name>];
         }
+
+        $crate::terminal_mode!(@impls $base_name, private = true, params = [$($params),*]);
     };
 
     ($(#[$meta:meta])* $base_name:ident, params = [$($params:literal),* $(,)?]) => {
@@ -108,10 +359,12 @@ macro_rules! terminal_mode {
             pub struct [<Disable $base_name>];
 
             #[doc = concat!("Query state of [`", stringify!($base_name), "`].")]
-            #[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash, ::vtansi::derive::AnsiOutput)]
+            #[derive(Debug, Default, PartialOrd, PartialEq, Eq, Clone, Copy, Hash, ::vtansi::derive::AnsiOutput)]
             #[vtansi(csi, params = [$($params),*], intermediate = "$", finalbyte = 'p')]
             pub struct [<Request $base_name>];
         }
+
+        $crate::terminal_mode!(@impls $base_name, private = false, params = [$($params),*]);
     };
 
     // With flag specifier (private mode)
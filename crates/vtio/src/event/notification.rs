@@ -0,0 +1,792 @@
+//! Desktop notification sequences.
+//!
+//! Several incompatible protocols exist for posting desktop notifications
+//! from terminal applications:
+//!
+//! - **OSC 9** (iTerm2, `ConEmu`, Windows Terminal):
+//!   `OSC 9 ; message ST`.  `ConEmu` also uses `OSC 9 ; n ; ... ST` for
+//!   numbered subcommands, of which only progress reporting
+//!   (`OSC 9 ; 4 ; st ; pr ST`, see [`SetProgress`]) is supported; the
+//!   others are not mistaken for notifications.
+//! - **OSC 777** (rxvt-unicode, `foot`, `Ghostty`):
+//!   `OSC 777 ; notify ; title ; body ST`.
+//! - **OSC 99** (kitty): `OSC 99 ; metadata ; payload ST`, where metadata
+//!   is a `:`-separated list of `key=value` pairs and the payload may be
+//!   base64-encoded and split over several sequences (see
+//!   [`KittyNotification`] and [`KittyNotificationAssembler`]).
+//!
+//! See <https://sw.kovidgoyal.net/kitty/desktop-notifications/> and
+//! <https://conemu.github.io/en/AnsiEscapeCodes.html#ConEmu_specific_OSC>
+//! for details.
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::ops::Deref;
+
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use vtansi::{
+    AnsiEncode, EncodeError, ParseError, TryFromAnsi, write_byte_into,
+    write_str_into,
+};
+
+/// The text of an OSC 9 [`Notification`].
+///
+/// Text starting with a number followed by `;` (or consisting of only a
+/// number) is a `ConEmu` subcommand such as `OSC 9 ; 9 ; cwd ST`, not a
+/// notification, and is rejected when parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct NotificationMessage<'a>(pub &'a str);
+
+impl<'a> NotificationMessage<'a> {
+    /// Return the message text.
+    #[must_use]
+    pub const fn as_str(&self) -> &'a str {
+        self.0
+    }
+}
+
+impl Deref for NotificationMessage<'_> {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl<'a> From<&'a str> for NotificationMessage<'a> {
+    fn from(message: &'a str) -> Self {
+        Self(message)
+    }
+}
+
+impl<'a> TryFromAnsi<'a> for NotificationMessage<'a> {
+    fn try_from_ansi(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        let subcommand = bytes
+            .split(|&b| b == b';')
+            .next()
+            .is_some_and(|n| !n.is_empty() && n.iter().all(u8::is_ascii_digit));
+        if subcommand {
+            return Err(ParseError::InvalidValue(
+                "OSC 9: ConEmu subcommand, not a notification".to_string(),
+            ));
+        }
+        <&str>::try_from_ansi(bytes).map(Self)
+    }
+}
+
+impl AnsiEncode for NotificationMessage<'_> {
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        write_str_into(sink, self.0)
+    }
+}
+
+/// Post a desktop notification (`OSC 9 ; message ST`).
+///
+/// See <https://iterm2.com/documentation-escape-codes.html> for terminal
+/// support specifics.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput)]
+#[vtansi(osc, number = "9")]
+pub struct Notification<'a> {
+    pub message: NotificationMessage<'a>,
+}
+
+impl<'a> Notification<'a> {
+    /// Create a notification with the given text.
+    #[must_use]
+    pub const fn new(message: &'a str) -> Self {
+        Self {
+            message: NotificationMessage(message),
+        }
+    }
+}
+
+/// Progress indicator state for [`SetProgress`].
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    num_enum::TryFromPrimitive,
+    num_enum::IntoPrimitive,
+    vtansi::derive::FromAnsi,
+    vtansi::derive::ToAnsi,
+)]
+#[repr(u8)]
+pub enum ProgressState {
+    /// Remove the progress indicator.
+    #[default]
+    Remove = 0,
+    /// Show progress in the normal state.
+    Normal = 1,
+    /// Show progress in the error state.
+    Error = 2,
+    /// Show an indeterminate progress indicator.
+    Indeterminate = 3,
+    /// Show progress in the paused (warning) state.
+    Paused = 4,
+}
+
+/// Set the taskbar progress indicator (`OSC 9 ; 4 ; st ; pr ST`).
+///
+/// `progress` is a percentage between 0 and 100 and is ignored by terminals
+/// for the [`ProgressState::Remove`] and [`ProgressState::Indeterminate`]
+/// states.
+///
+/// See <https://conemu.github.io/en/AnsiEscapeCodes.html#ConEmu_specific_OSC>
+/// for terminal support specifics.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput,
+)]
+#[vtansi(osc, number = "9", data = "4")]
+pub struct SetProgress {
+    pub state: ProgressState,
+    pub progress: Option<u8>,
+}
+
+impl SetProgress {
+    /// Create a command showing `progress` percent in the normal state.
+    #[must_use]
+    pub const fn normal(progress: u8) -> Self {
+        Self {
+            state: ProgressState::Normal,
+            progress: Some(progress),
+        }
+    }
+
+    /// Create a command removing the progress indicator.
+    #[must_use]
+    pub const fn remove() -> Self {
+        Self {
+            state: ProgressState::Remove,
+            progress: None,
+        }
+    }
+}
+
+/// Post a desktop notification with a title (`OSC 777 ; notify ; title ;
+/// body ST`).
+///
+/// The title cannot contain `;`, the body can.
+///
+/// See <https://github.com/exg/rxvt-unicode/blob/master/src/perl/notify>
+/// for details.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput)]
+#[vtansi(osc, number = "777", data = "notify")]
+pub struct RxvtNotification<'a> {
+    pub title: &'a str,
+    pub body: &'a str,
+}
+
+/// The kind of payload carried by a [`KittyNotification`] (`p=`).
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum::IntoStaticStr,
+    strum::Display,
+    strum::EnumString,
+    vtansi::derive::ToAnsi,
+    vtansi::derive::FromAnsi,
+)]
+#[strum(serialize_all = "lowercase")]
+pub enum KittyPayloadType {
+    /// The notification title.
+    #[default]
+    Title,
+    /// The notification body.
+    Body,
+    /// Close a previously shown notification.
+    Close,
+    /// Icon image data.
+    Icon,
+    /// Query whether a notification is still displayed.
+    Alive,
+    /// Button labels, separated by U+2028.
+    Buttons,
+    /// Query terminal support (`?`).
+    #[strum(serialize = "?")]
+    Query,
+}
+
+/// When a kitty notification should be displayed (`o=`).
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum::IntoStaticStr,
+    strum::Display,
+    strum::EnumString,
+    vtansi::derive::ToAnsi,
+    vtansi::derive::FromAnsi,
+)]
+#[strum(serialize_all = "lowercase")]
+pub enum KittyNotificationOccasion {
+    /// Always display the notification.
+    Always,
+    /// Only display the notification if the window is not focused.
+    Unfocused,
+    /// Only display the notification if the window is not visible.
+    Invisible,
+}
+
+/// Notification urgency (`u=`).
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    num_enum::TryFromPrimitive,
+    num_enum::IntoPrimitive,
+    vtansi::derive::FromAnsi,
+    vtansi::derive::ToAnsi,
+)]
+#[repr(u8)]
+pub enum KittyNotificationUrgency {
+    Low = 0,
+    Normal = 1,
+    Critical = 2,
+}
+
+/// Metadata of a [`KittyNotification`].
+///
+/// Keys without a dedicated field are preserved in `other` so that the
+/// notification can be re-emitted unchanged.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct KittyNotificationMetadata<'a> {
+    /// Notification identifier (`i=`), used to group chunks and to refer to
+    /// the notification later.
+    pub id: Option<&'a str>,
+    /// Whether this is the last chunk (`d=`).  Defaults to `true`.
+    pub done: Option<bool>,
+    /// The kind of payload (`p=`).  Defaults to the title.
+    pub payload_type: Option<KittyPayloadType>,
+    /// Whether the payload is base64-encoded (`e=`).
+    pub encoded: Option<bool>,
+    /// Actions to perform on activation (`a=`).
+    pub actions: Option<&'a str>,
+    /// When to display the notification (`o=`).
+    pub occasion: Option<KittyNotificationOccasion>,
+    /// Notification urgency (`u=`).
+    pub urgency: Option<KittyNotificationUrgency>,
+    /// Any other `key=value` pairs, in the order they appeared.
+    pub other: Vec<(&'a str, &'a str)>,
+}
+
+impl KittyNotificationMetadata<'_> {
+    /// Return `true` if this is the last chunk of the notification.
+    #[must_use]
+    pub fn is_done(&self) -> bool {
+        self.done.unwrap_or(true)
+    }
+
+    /// Return the payload type, applying the protocol default.
+    #[must_use]
+    pub fn payload_type(&self) -> KittyPayloadType {
+        self.payload_type.unwrap_or_default()
+    }
+
+    /// Return `true` if the payload is base64-encoded.
+    #[must_use]
+    pub fn is_encoded(&self) -> bool {
+        self.encoded.unwrap_or(false)
+    }
+}
+
+fn parse_flag(key: &str, value: &[u8]) -> Result<bool, ParseError> {
+    match value {
+        b"0" => Ok(false),
+        b"1" => Ok(true),
+        _ => Err(ParseError::InvalidValue(format!(
+            "kitty notification: {key} must be 0 or 1"
+        ))),
+    }
+}
+
+impl<'a> TryFromAnsi<'a> for KittyNotificationMetadata<'a> {
+    fn try_from_ansi(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        let mut metadata = Self::default();
+        if bytes.is_empty() {
+            return Ok(metadata);
+        }
+        for pair in vtansi::parse_keyvalue_pairs(bytes, b':') {
+            let (key, value) = pair?;
+            match key {
+                b"i" => metadata.id = Some(<&str>::try_from_ansi(value)?),
+                b"d" => metadata.done = Some(parse_flag("d", value)?),
+                b"p" => {
+                    metadata.payload_type =
+                        Some(KittyPayloadType::try_from_ansi(value)?);
+                }
+                b"e" => metadata.encoded = Some(parse_flag("e", value)?),
+                b"a" => metadata.actions = Some(<&str>::try_from_ansi(value)?),
+                b"o" => {
+                    metadata.occasion =
+                        Some(KittyNotificationOccasion::try_from_ansi(value)?);
+                }
+                b"u" => {
+                    metadata.urgency =
+                        Some(KittyNotificationUrgency::try_from_ansi(value)?);
+                }
+                _ => metadata.other.push((
+                    <&str>::try_from_ansi(key)?,
+                    <&str>::try_from_ansi(value)?,
+                )),
+            }
+        }
+        Ok(metadata)
+    }
+}
+
+impl AnsiEncode for KittyNotificationMetadata<'_> {
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        let flag = |v: bool| if v { "1" } else { "0" };
+        let urgency = match self.urgency {
+            Some(KittyNotificationUrgency::Low) => Some("0"),
+            Some(KittyNotificationUrgency::Normal) => Some("1"),
+            Some(KittyNotificationUrgency::Critical) => Some("2"),
+            None => None,
+        };
+        let known = [
+            ("i", self.id),
+            ("d", self.done.map(flag)),
+            ("p", self.payload_type.map(<&'static str>::from)),
+            ("e", self.encoded.map(flag)),
+            ("a", self.actions),
+            ("o", self.occasion.map(<&'static str>::from)),
+            ("u", urgency),
+        ];
+        let pairs = known
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|value| (key, value)))
+            .chain(self.other.iter().copied());
+
+        let mut written = 0;
+        for (i, (key, value)) in pairs.enumerate() {
+            if i > 0 {
+                written += write_byte_into(sink, b':')?;
+            }
+            written += write_str_into(sink, key)?;
+            written += write_byte_into(sink, b'=')?;
+            written += write_str_into(sink, value)?;
+        }
+        Ok(written)
+    }
+}
+
+/// Kitty desktop notification (`OSC 99 ; metadata ; payload ST`).
+///
+/// A single notification may be split over several sequences sharing the
+/// same `i=` identifier, with all but the last carrying `d=0`.  Use
+/// [`KittyNotificationAssembler`] to combine them.
+///
+/// See <https://sw.kovidgoyal.net/kitty/desktop-notifications/> for
+/// details.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput)]
+#[vtansi(osc, number = "99")]
+pub struct KittyNotification<'a> {
+    pub metadata: KittyNotificationMetadata<'a>,
+    pub payload: &'a str,
+}
+
+impl<'a> KittyNotification<'a> {
+    /// Create a single-chunk notification with the given title.
+    #[must_use]
+    pub fn title(title: &'a str) -> Self {
+        Self {
+            metadata: KittyNotificationMetadata::default(),
+            payload: title,
+        }
+    }
+
+    /// Return the payload, decoding base64 if `e=1`.
+    ///
+    /// # Errors
+    ///
+    /// Return [`ParseError::InvalidValue`] if the payload is not valid
+    /// base64.
+    pub fn decoded_payload(&self) -> Result<Cow<'a, [u8]>, ParseError> {
+        if self.metadata.is_encoded() {
+            BASE64.decode(self.payload).map(Cow::Owned).map_err(|err| {
+                ParseError::InvalidValue(format!(
+                    "kitty notification: invalid payload: {err}"
+                ))
+            })
+        } else {
+            Ok(Cow::Borrowed(self.payload.as_bytes()))
+        }
+    }
+}
+
+/// Maximum combined payload size of a notification accepted by
+/// [`KittyNotificationAssembler`].
+pub const MAX_KITTY_NOTIFICATION_LEN: usize = 64 * 1024;
+
+/// Maximum number of partially received notifications kept by
+/// [`KittyNotificationAssembler`].
+pub const MAX_KITTY_PENDING_NOTIFICATIONS: usize = 16;
+
+/// A kitty notification combined from one or more chunks.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct KittyNotificationContent {
+    /// The notification identifier, if any.
+    pub id: Option<String>,
+    /// The notification title.
+    pub title: String,
+    /// The notification body.
+    pub body: String,
+    /// When to display the notification.
+    pub occasion: Option<KittyNotificationOccasion>,
+    /// Notification urgency.
+    pub urgency: Option<KittyNotificationUrgency>,
+}
+
+#[derive(Debug, Default)]
+struct PendingNotification {
+    content: KittyNotificationContent,
+    title: Vec<u8>,
+    body: Vec<u8>,
+}
+
+impl PendingNotification {
+    fn len(&self) -> usize {
+        self.title.len() + self.body.len()
+    }
+}
+
+/// Reassemble chunked kitty notifications.
+///
+/// Feed every [`KittyNotification`] to [`feed`](Self::feed); once the last
+/// chunk (`d=1`) of a notification arrives, the combined title and body are
+/// returned.  Chunks are grouped by their `i=` identifier.  Payloads other
+/// than the title and body (e.g. `p=close` or `p=?`) are not part of the
+/// notification content and are ignored.
+///
+/// At most [`MAX_KITTY_PENDING_NOTIFICATIONS`] notifications are assembled
+/// at once; starting another one discards the oldest.
+#[derive(Debug, Default)]
+pub struct KittyNotificationAssembler {
+    /// Partially received notifications, oldest first.
+    pending: VecDeque<(Option<String>, PendingNotification)>,
+}
+
+impl KittyNotificationAssembler {
+    /// Create a new, empty assembler.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a notification chunk.
+    ///
+    /// Return the complete notification once its last chunk is fed.
+    ///
+    /// # Errors
+    ///
+    /// Return [`ParseError::InvalidValue`] if the payload cannot be
+    /// decoded, or if the combined payload exceeds
+    /// [`MAX_KITTY_NOTIFICATION_LEN`].  The partial notification is
+    /// discarded in that case.
+    pub fn feed(
+        &mut self,
+        chunk: &KittyNotification<'_>,
+    ) -> Result<Option<KittyNotificationContent>, ParseError> {
+        let payload_type = chunk.metadata.payload_type();
+        if !matches!(
+            payload_type,
+            KittyPayloadType::Title | KittyPayloadType::Body
+        ) {
+            return Ok(None);
+        }
+
+        let id = chunk.metadata.id;
+        let index = if let Some(index) = self
+            .pending
+            .iter()
+            .position(|(key, _)| key.as_deref() == id)
+        {
+            index
+        } else {
+            if self.pending.len() == MAX_KITTY_PENDING_NOTIFICATIONS {
+                self.pending.pop_front();
+            }
+            self.pending.push_back((
+                id.map(str::to_string),
+                PendingNotification::default(),
+            ));
+            self.pending.len() - 1
+        };
+        let pending = &mut self.pending[index].1;
+        let result = chunk.decoded_payload().and_then(|payload| {
+            if pending.len() + payload.len() > MAX_KITTY_NOTIFICATION_LEN {
+                return Err(ParseError::InvalidValue(format!(
+                    "kitty notification exceeds \
+                     {MAX_KITTY_NOTIFICATION_LEN} bytes"
+                )));
+            }
+            match payload_type {
+                KittyPayloadType::Body => {
+                    pending.body.extend_from_slice(&payload);
+                }
+                _ => pending.title.extend_from_slice(&payload),
+            }
+            Ok(())
+        });
+        if let Err(err) = result {
+            self.pending.remove(index);
+            return Err(err);
+        }
+
+        if chunk.metadata.occasion.is_some() {
+            pending.content.occasion = chunk.metadata.occasion;
+        }
+        if chunk.metadata.urgency.is_some() {
+            pending.content.urgency = chunk.metadata.urgency;
+        }
+        if !chunk.metadata.is_done() {
+            return Ok(None);
+        }
+
+        let Some((id, mut pending)) = self.pending.remove(index) else {
+            return Ok(None);
+        };
+        pending.content.id = id;
+        pending.content.title =
+            String::from_utf8_lossy(&pending.title).into_owned();
+        pending.content.body =
+            String::from_utf8_lossy(&pending.body).into_owned();
+        Ok(Some(pending.content))
+    }
+
+    /// Discard all partially received notifications.
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::TerminalOutputParser;
+    use better_any::TidExt;
+
+    fn reencode(input: &[u8]) -> Vec<String> {
+        let mut events = Vec::new();
+        TerminalOutputParser::new().feed_with(input, &mut |event| {
+            let name = if event.is::<Notification>() {
+                "Notification"
+            } else if event.is::<SetProgress>() {
+                "SetProgress"
+            } else if event.is::<RxvtNotification>() {
+                "RxvtNotification"
+            } else if event.is::<KittyNotification>() {
+                "KittyNotification"
+            } else {
+                "Other"
+            };
+            events.push(format!(
+                "{name}: {}",
+                String::from_utf8(event.encode_ansi().unwrap()).unwrap()
+            ));
+        });
+        events
+    }
+
+    #[test]
+    fn test_osc9() {
+        assert_eq!(
+            Notification::new("done; ok").encode_ansi().unwrap(),
+            b"\x1b]9;done; ok\x1b\\"
+        );
+        assert_eq!(
+            reencode(b"\x1b]9;build finished; 0 errors\x07"),
+            vec!["Notification: \x1b]9;build finished; 0 errors\x1b\\"]
+        );
+    }
+
+    #[test]
+    fn test_osc9_progress() {
+        assert_eq!(
+            SetProgress::normal(42).encode_ansi().unwrap(),
+            b"\x1b]9;4;1;42\x1b\\"
+        );
+        assert_eq!(
+            SetProgress::remove().encode_ansi().unwrap(),
+            b"\x1b]9;4;0\x1b\\"
+        );
+        assert_eq!(
+            reencode(b"\x1b]9;4;3\x07\x1b]9;4;2;80\x07"),
+            vec![
+                "SetProgress: \x1b]9;4;3\x1b\\",
+                "SetProgress: \x1b]9;4;2;80\x1b\\",
+            ]
+        );
+        // Not a valid progress command, but not a notification either.
+        assert_eq!(reencode(b"\x1b]9;4;x\x07"), vec!["Other: 9;4;x\x07"]);
+    }
+
+    #[test]
+    fn test_osc9_conemu_subcommands() {
+        assert_eq!(
+            reencode(b"\x1b]9;9;C:\\Users\x07\x1b]9;12\x07\x1b]9;1.5;ok\x07"),
+            vec![
+                "Other: 9;9;C:\\Users\x07",
+                "Other: 9;12\x07",
+                "Notification: \x1b]9;1.5;ok\x1b\\",
+            ]
+        );
+        assert!(NotificationMessage::try_from_ansi(b"2;hi").is_err());
+        assert!(NotificationMessage::try_from_ansi(b"5").is_err());
+        assert_eq!(
+            NotificationMessage::try_from_ansi(b"2 new messages; hi")
+                .unwrap()
+                .as_str(),
+            "2 new messages; hi"
+        );
+    }
+
+    #[test]
+    fn test_osc777() {
+        let n = RxvtNotification {
+            title: "Title",
+            body: "Body; more",
+        };
+        assert_eq!(
+            n.encode_ansi().unwrap(),
+            b"\x1b]777;notify;Title;Body; more\x1b\\"
+        );
+        assert_eq!(
+            reencode(b"\x1b]777;notify;Title;Body; more\x07"),
+            vec!["RxvtNotification: \x1b]777;notify;Title;Body; more\x1b\\"]
+        );
+    }
+
+    #[test]
+    fn test_osc99_round_trip() {
+        let input = b"\x1b]99;i=1:d=0:p=title:o=unfocused:u=2:x=y;Hello\x1b\\\
+                      \x1b]99;;Simple\x1b\\\
+                      \x1b]99;i=1:p=?;\x1b\\";
+        assert_eq!(
+            reencode(input),
+            vec![
+                "KittyNotification: \x1b]99;i=1:d=0:p=title:o=unfocused:u=2:x=y;Hello\x1b\\",
+                "KittyNotification: \x1b]99;;Simple\x1b\\",
+                "KittyNotification: \x1b]99;i=1:p=?;\x1b\\",
+            ]
+        );
+        assert!(KittyNotificationMetadata::try_from_ansi(b"d=2").is_err());
+        assert!(KittyNotificationMetadata::try_from_ansi(b"p=nope").is_err());
+    }
+
+    #[test]
+    fn test_osc99_assembler() {
+        let input = b"\x1b]99;i=7:d=0:u=1;Hel\x1b\\\
+                      \x1b]99;i=7:d=0;lo\x1b\\\
+                      \x1b]99;i=8;Other\x1b\\\
+                      \x1b]99;i=7:d=0:p=body:e=1;V29y\x1b\\\
+                      \x1b]99;i=7:p=body:e=1;bGQ=\x1b\\";
+        let mut assembler = KittyNotificationAssembler::new();
+        let mut done = Vec::new();
+        TerminalOutputParser::new().feed_with(input, &mut |event| {
+            if let Some(chunk) = event.downcast_ref::<KittyNotification>()
+                && let Some(content) = assembler.feed(chunk).unwrap()
+            {
+                done.push(content);
+            }
+        });
+        assert_eq!(
+            done,
+            vec![
+                KittyNotificationContent {
+                    id: Some("8".to_string()),
+                    title: "Other".to_string(),
+                    ..Default::default()
+                },
+                KittyNotificationContent {
+                    id: Some("7".to_string()),
+                    title: "Hello".to_string(),
+                    body: "World".to_string(),
+                    urgency: Some(KittyNotificationUrgency::Normal),
+                    occasion: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_osc99_assembler_errors() {
+        let mut assembler = KittyNotificationAssembler::new();
+        let bad = KittyNotification {
+            metadata: KittyNotificationMetadata {
+                encoded: Some(true),
+                ..Default::default()
+            },
+            payload: "!!!",
+        };
+        assert!(assembler.feed(&bad).is_err());
+
+        let big = "x".repeat(MAX_KITTY_NOTIFICATION_LEN);
+        let chunk = KittyNotification {
+            metadata: KittyNotificationMetadata {
+                done: Some(false),
+                ..Default::default()
+            },
+            payload: &big,
+        };
+        assert!(assembler.feed(&chunk).unwrap().is_none());
+        assert!(assembler.feed(&chunk).is_err());
+        assert!(assembler.pending.is_empty());
+    }
+
+    #[test]
+    fn test_osc99_assembler_evicts_oldest() {
+        let mut assembler = KittyNotificationAssembler::new();
+        let ids: Vec<String> = (0..=MAX_KITTY_PENDING_NOTIFICATIONS)
+            .map(|i| i.to_string())
+            .collect();
+        for id in &ids {
+            let chunk = KittyNotification {
+                metadata: KittyNotificationMetadata {
+                    id: Some(id),
+                    done: Some(false),
+                    ..Default::default()
+                },
+                payload: "partial",
+            };
+            assert!(assembler.feed(&chunk).unwrap().is_none());
+        }
+        assert_eq!(assembler.pending.len(), MAX_KITTY_PENDING_NOTIFICATIONS);
+
+        let last = |id| KittyNotification {
+            metadata: KittyNotificationMetadata {
+                id: Some(id),
+                ..Default::default()
+            },
+            payload: "!",
+        };
+        let kept = assembler.feed(&last("1")).unwrap().unwrap();
+        assert_eq!(kept.title, "partial!");
+        // The first notification was evicted, so only the final chunk is left
+        let evicted = assembler.feed(&last("0")).unwrap().unwrap();
+        assert_eq!(evicted.title, "!");
+    }
+}
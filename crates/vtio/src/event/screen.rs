@@ -355,6 +355,59 @@ pub struct SelectiveEraseLineLeft;
 #[vtansi(csi, private = '?', params = ["2"], finalbyte = 'K')]
 pub struct SelectiveEraseLineComplete;
 
+/// Character protection attribute set by
+/// [`SelectCharacterProtectionAttribute`].
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Default,
+    vtansi::derive::ToAnsi,
+    vtansi::derive::FromAnsi,
+    num_enum::TryFromPrimitive,
+    num_enum::IntoPrimitive,
+)]
+#[repr(u8)]
+pub enum CharacterProtection {
+    /// Characters are not protected (the default).
+    #[default]
+    Default = 0,
+    /// Characters are protected from selective erase.
+    Protected = 1,
+    /// Characters are not protected.
+    Unprotected = 2,
+}
+
+/// Select Character Protection Attribute (`DECSCA`).
+///
+/// Mark subsequently written characters as protected or unprotected.
+/// Protected cells are not erased by the selective erase commands
+/// ([`SelectiveEraseDisplayBelow`], [`SelectiveEraseLineRight`], etc.).
+///
+/// See <https://vt100.net/docs/vt510-rm/DECSCA.html> for more
+/// information.
+#[derive(
+    Debug,
+    PartialOrd,
+    PartialEq,
+    Eq,
+    Clone,
+    Copy,
+    Hash,
+    vtansi::derive::AnsiOutput,
+)]
+#[vtansi(csi, intermediate = "\"", finalbyte = 'q')]
+pub struct SelectCharacterProtectionAttribute {
+    /// Protection attribute of the characters written from now on.
+    #[vtansi(default)]
+    pub protection: CharacterProtection,
+}
+
 /// Insert Line (`IL`).
 ///
 /// Insert `amount` lines at the current cursor row.
@@ -394,7 +447,7 @@ pub struct SelectiveEraseLineComplete;
     vtansi::derive::AnsiOutput,
 )]
 #[vtansi(csi, finalbyte = 'L')]
-pub struct InsertLine(pub u16);
+pub struct InsertLine(#[vtansi(default)] pub u16);
 
 /// Delete Line (`DL`).
 ///
@@ -433,7 +486,7 @@ pub struct InsertLine(pub u16);
     vtansi::derive::AnsiOutput,
 )]
 #[vtansi(csi, finalbyte = 'M')]
-pub struct DeleteLine(pub u16);
+pub struct DeleteLine(#[vtansi(default)] pub u16);
 
 /// Delete Character (`DCH`).
 ///
@@ -473,7 +526,73 @@ pub struct DeleteLine(pub u16);
     vtansi::derive::AnsiOutput,
 )]
 #[vtansi(csi, finalbyte = 'P')]
-pub struct DeleteCharacter(pub u16);
+pub struct DeleteCharacter(#[vtansi(default)] pub u16);
+
+/// Insert Character (`ICH`).
+///
+/// Insert `amount` blank characters at the current cursor position.
+///
+/// This is the same as `CSI Ps @`.
+///
+/// The character at the cursor position and the characters to its right
+/// are shifted right by `amount`.  Characters shifted past the right margin
+/// are lost.
+///
+/// If `amount` is greater than the remaining number of columns in the
+/// scrolling region, it is adjusted down.
+///
+/// All inserted space is colored according to the current SGR state.
+///
+/// Does not change the cursor position.
+///
+/// This unsets the pending wrap state without wrapping.
+///
+/// See <https://terminalguide.namepad.de/seq/csi_x40_at/> for
+/// terminal support specifics.
+#[derive(
+    Debug,
+    PartialOrd,
+    PartialEq,
+    Eq,
+    Clone,
+    Copy,
+    Hash,
+    vtansi::derive::AnsiOutput,
+)]
+#[vtansi(csi, finalbyte = '@')]
+pub struct InsertCharacter(#[vtansi(default)] pub u16);
+
+/// Erase Character (`ECH`).
+///
+/// Erase `amount` characters from the current cursor position to the
+/// right.
+///
+/// This is the same as `CSI Ps X`.
+///
+/// Unlike [`DeleteCharacter`], the remaining characters are not shifted.
+/// If `amount` is greater than the remaining number of columns, it is
+/// adjusted down.
+///
+/// All erased space is colored according to the current SGR state.
+///
+/// Does not change the cursor position.
+///
+/// This unsets the pending wrap state without wrapping.
+///
+/// See <https://terminalguide.namepad.de/seq/csi_cx/> for
+/// terminal support specifics.
+#[derive(
+    Debug,
+    PartialOrd,
+    PartialEq,
+    Eq,
+    Clone,
+    Copy,
+    Hash,
+    vtansi::derive::AnsiOutput,
+)]
+#[vtansi(csi, finalbyte = 'X')]
+pub struct EraseCharacter(#[vtansi(default)] pub u16);
 
 /// Insert Column (`DECIC`).
 ///
@@ -514,7 +633,7 @@ pub struct DeleteCharacter(pub u16);
     vtansi::derive::AnsiOutput,
 )]
 #[vtansi(csi, intermediate = "'", finalbyte = '}')]
-pub struct InsertColumn(pub u16);
+pub struct InsertColumn(#[vtansi(default)] pub u16);
 
 /// Delete Column (`DECDC`).
 ///
@@ -553,7 +672,7 @@ pub struct InsertColumn(pub u16);
     vtansi::derive::AnsiOutput,
 )]
 #[vtansi(csi, intermediate = "'", finalbyte = '~')]
-pub struct DeleteColumn(pub u16);
+pub struct DeleteColumn(#[vtansi(default)] pub u16);
 
 /// Fill Screen with E (`DECALN`).
 ///
@@ -109,7 +109,9 @@ crate::terminal_mode!(
 )]
 #[vtansi(csi, finalbyte = 'r')]
 pub struct SetTopAndBottomMargins {
+    #[vtansi(default)]
     pub top: u16,
+    #[vtansi(default)]
     pub bottom: u16,
 }
 
@@ -152,8 +154,12 @@ pub struct SetLeftAndRightMargins {
 /// margins) from the terminal using DECRQSS (Request Selection or
 /// Setting) to query the DECSTBM (Set Top and Bottom Margins) setting.
 ///
-/// The terminal responds with a DCS sequence containing the current
-/// margin settings.
+/// The terminal responds with a
+/// [`StatusStringReport`](crate::event::status::StatusStringReport)
+/// containing the current margin settings.
+///
+/// This is an encode-only alias of
+/// [`RequestStatusString`](crate::event::status::RequestStatusString).
 ///
 /// The response format is:
 /// `DCS 1 $ r Pt ; Pb r ST`
@@ -173,7 +179,13 @@ pub struct SetLeftAndRightMargins {
     Hash,
     vtansi::derive::AnsiOutput,
 )]
-#[vtansi(dcs, intermediate = "$", finalbyte = 'r')]
+#[vtansi(
+    dcs,
+    intermediate = "$",
+    finalbyte = 'q',
+    data = "r",
+    alias_of = crate::event::status::RequestStatusString
+)]
 pub struct RequestTopBottomMargins;
 
 /// Request left and right margins (`DECRQSS` - `DECSLRM`).
@@ -182,8 +194,12 @@ pub struct RequestTopBottomMargins;
 /// margins) from the terminal using DECRQSS (Request Selection or
 /// Setting) to query the DECSLRM (Set Left and Right Margins) setting.
 ///
-/// The terminal responds with a DCS sequence containing the current
-/// margin settings.
+/// The terminal responds with a
+/// [`StatusStringReport`](crate::event::status::StatusStringReport)
+/// containing the current margin settings.
+///
+/// This is an encode-only alias of
+/// [`RequestStatusString`](crate::event::status::RequestStatusString).
 ///
 /// The response format is:
 /// `DCS 1 $ r Pl ; Pr s ST`
@@ -203,7 +219,13 @@ pub struct RequestTopBottomMargins;
     Hash,
     vtansi::derive::AnsiOutput,
 )]
-#[vtansi(dcs, intermediate = "$", finalbyte = 's')]
+#[vtansi(
+    dcs,
+    intermediate = "$",
+    finalbyte = 'q',
+    data = "s",
+    alias_of = crate::event::status::RequestStatusString
+)]
 pub struct RequestLeftRightMargins;
 
 /// Scroll Up (`SU`).
@@ -231,7 +253,7 @@ pub struct RequestLeftRightMargins;
     vtansi::derive::AnsiOutput,
 )]
 #[vtansi(csi, finalbyte = 'S')]
-pub struct ScrollUp(pub u16);
+pub struct ScrollUp(#[vtansi(default)] pub u16);
 
 /// Scroll Down (`SD`).
 ///
@@ -258,7 +280,7 @@ pub struct ScrollUp(pub u16);
     vtansi::derive::AnsiOutput,
 )]
 #[vtansi(csi, finalbyte = 'T')]
-pub struct ScrollDown(pub u16);
+pub struct ScrollDown(#[vtansi(default)] pub u16);
 
 /// Scroll Left (`SL`).
 ///
@@ -287,7 +309,7 @@ pub struct ScrollDown(pub u16);
     vtansi::derive::AnsiOutput,
 )]
 #[vtansi(csi, intermediate = " ", finalbyte = '@')]
-pub struct ScrollLeft(pub u16);
+pub struct ScrollLeft(#[vtansi(default)] pub u16);
 
 /// Scroll Right (`SR`).
 ///
@@ -316,4 +338,4 @@ pub struct ScrollLeft(pub u16);
     vtansi::derive::AnsiOutput,
 )]
 #[vtansi(csi, intermediate = " ", finalbyte = 'A')]
-pub struct ScrollRight(pub u16);
+pub struct ScrollRight(#[vtansi(default)] pub u16);
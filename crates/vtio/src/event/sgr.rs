@@ -0,0 +1,851 @@
+//! Select Graphic Rendition (`SGR`) sequences.
+//!
+//! `SGR` (`CSI Pm m`) sets the character attributes used for subsequently
+//! written text: intensity, italic, underline, blink, inverse, conceal,
+//! strikethrough, overline, alternative fonts and the foreground,
+//! background and underline colors.
+//!
+//! Both the ITU T.416 colon form of extended colors (`38:2::r:g:b`,
+//! `38:5:n`, `4:3`) and the legacy semicolon form (`38;2;r;g;b`,
+//! `38;5;n`) are accepted.  When encoding, foreground and background
+//! colors use the semicolon form, which has the widest support, while
+//! underline styles and underline colors use the colon form, which is the
+//! only form understood by terminals that implement them.
+//!
+//! See <https://terminalguide.namepad.de/seq/csi_sm/> for terminal
+//! support specifics.
+
+use std::ops::{Deref, DerefMut};
+
+use vtansi::{
+    AnsiEncode, EncodeError, ParseError, TryFromAnsi, TryFromAnsiIter,
+    write_byte_into, write_int,
+};
+
+/// One of the 16 basic ANSI colors.
+///
+/// The first eight colors are selected with `SGR 30`–`37` (foreground) and
+/// `SGR 40`–`47` (background), the bright variants with `SGR 90`–`97` and
+/// `SGR 100`–`107`.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum AnsiColor {
+    Black = 0,
+    Red = 1,
+    Green = 2,
+    Yellow = 3,
+    Blue = 4,
+    Magenta = 5,
+    Cyan = 6,
+    White = 7,
+    BrightBlack = 8,
+    BrightRed = 9,
+    BrightGreen = 10,
+    BrightYellow = 11,
+    BrightBlue = 12,
+    BrightMagenta = 13,
+    BrightCyan = 14,
+    BrightWhite = 15,
+}
+
+impl AnsiColor {
+    /// Return the color for the given palette index (0–15).
+    #[must_use]
+    pub const fn from_index(index: u8) -> Option<Self> {
+        Some(match index {
+            0 => Self::Black,
+            1 => Self::Red,
+            2 => Self::Green,
+            3 => Self::Yellow,
+            4 => Self::Blue,
+            5 => Self::Magenta,
+            6 => Self::Cyan,
+            7 => Self::White,
+            8 => Self::BrightBlack,
+            9 => Self::BrightRed,
+            10 => Self::BrightGreen,
+            11 => Self::BrightYellow,
+            12 => Self::BrightBlue,
+            13 => Self::BrightMagenta,
+            14 => Self::BrightCyan,
+            15 => Self::BrightWhite,
+            _ => return None,
+        })
+    }
+
+    /// Return the palette index (0–15) of this color.
+    #[must_use]
+    pub const fn index(self) -> u8 {
+        self as u8
+    }
+
+    /// Return `true` if this is one of the bright colors (8–15).
+    #[must_use]
+    pub const fn is_bright(self) -> bool {
+        self.index() >= 8
+    }
+}
+
+/// A color in an `SGR` sequence.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SgrColor {
+    /// One of the 16 basic colors (`SGR 30`–`37`, `90`–`97`, etc).
+    Ansi(AnsiColor),
+    /// A color from the 256-color palette (`SGR 38;5;n`).
+    Indexed(u8),
+    /// A direct RGB color (`SGR 38;2;r;g;b`).
+    Rgb { r: u8, g: u8, b: u8 },
+}
+
+impl SgrColor {
+    /// Create an RGB color.
+    #[must_use]
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self::Rgb { r, g, b }
+    }
+}
+
+impl From<AnsiColor> for SgrColor {
+    #[inline]
+    fn from(value: AnsiColor) -> Self {
+        Self::Ansi(value)
+    }
+}
+
+/// Text intensity (`SGR 1`, `SGR 2`, `SGR 22`).
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Intensity {
+    /// Normal intensity (`SGR 22`).
+    #[default]
+    Normal,
+    /// Bold or increased intensity (`SGR 1`).
+    Bold,
+    /// Faint or decreased intensity (`SGR 2`).
+    Faint,
+}
+
+/// Underline style (`SGR 4`, `SGR 4:n`, `SGR 21`, `SGR 24`).
+///
+/// The styles other than [`UnderlineStyle::Single`] are selected with the
+/// colon sub-parameter form `SGR 4:n` popularized by kitty and VTE.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum UnderlineStyle {
+    /// No underline (`SGR 24` or `SGR 4:0`).
+    #[default]
+    None,
+    /// Single underline (`SGR 4` or `SGR 4:1`).
+    Single,
+    /// Double underline (`SGR 21` or `SGR 4:2`).
+    Double,
+    /// Curly underline (`SGR 4:3`).
+    Curly,
+    /// Dotted underline (`SGR 4:4`).
+    Dotted,
+    /// Dashed underline (`SGR 4:5`).
+    Dashed,
+}
+
+impl UnderlineStyle {
+    fn from_subparam(value: u16) -> Result<Self, ParseError> {
+        Ok(match value {
+            0 => Self::None,
+            1 => Self::Single,
+            2 => Self::Double,
+            3 => Self::Curly,
+            4 => Self::Dotted,
+            5 => Self::Dashed,
+            n => {
+                return Err(ParseError::InvalidValue(format!(
+                    "invalid SGR underline style: {n}"
+                )));
+            }
+        })
+    }
+
+    const fn subparam(self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Single => 1,
+            Self::Double => 2,
+            Self::Curly => 3,
+            Self::Dotted => 4,
+            Self::Dashed => 5,
+        }
+    }
+}
+
+/// Blink mode (`SGR 5`, `SGR 6`, `SGR 25`).
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Blink {
+    /// Not blinking (`SGR 25`).
+    #[default]
+    None,
+    /// Slow blink (`SGR 5`).
+    Slow,
+    /// Rapid blink (`SGR 6`).
+    Rapid,
+}
+
+/// A single `SGR` attribute.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SgrAttribute {
+    /// Reset all attributes to their defaults (`SGR 0`).
+    Reset,
+    /// Set text intensity (`SGR 1`, `SGR 2`, `SGR 22`).
+    Intensity(Intensity),
+    /// Enable (`SGR 3`) or disable (`SGR 23`) italic.
+    Italic(bool),
+    /// Set the underline style (`SGR 4`, `SGR 4:n`, `SGR 21`, `SGR 24`).
+    Underline(UnderlineStyle),
+    /// Set the blink mode (`SGR 5`, `SGR 6`, `SGR 25`).
+    Blink(Blink),
+    /// Enable (`SGR 7`) or disable (`SGR 27`) inverse video.
+    Inverse(bool),
+    /// Enable (`SGR 8`) or disable (`SGR 28`) concealed text.
+    Conceal(bool),
+    /// Enable (`SGR 9`) or disable (`SGR 29`) strikethrough.
+    Strike(bool),
+    /// Enable (`SGR 53`) or disable (`SGR 55`) overline.
+    Overline(bool),
+    /// Select the primary font (`0`, `SGR 10`) or an alternative font
+    /// (`1`–`9`, `SGR 11`–`19`).
+    ///
+    /// Encoding a font number greater than `9` fails with
+    /// [`EncodeError::Unencodeable`].
+    Font(u8),
+    /// Set the foreground color (`SGR 30`–`38`, `SGR 90`–`97`).
+    Foreground(SgrColor),
+    /// Reset the foreground color to the default (`SGR 39`).
+    DefaultForeground,
+    /// Set the background color (`SGR 40`–`48`, `SGR 100`–`107`).
+    Background(SgrColor),
+    /// Reset the background color to the default (`SGR 49`).
+    DefaultBackground,
+    /// Set the underline color (`SGR 58`).
+    ///
+    /// Basic ANSI colors are encoded as the equivalent palette index,
+    /// since `SGR 58` has no short form.
+    UnderlineColor(SgrColor),
+    /// Reset the underline color to the default (`SGR 59`).
+    DefaultUnderlineColor,
+    /// An attribute code not known to this library.
+    Unrecognized(u16),
+}
+
+/// Parse a numeric `SGR` value, treating an empty value as zero.
+fn parse_num<T>(bytes: &[u8]) -> Result<T, ParseError>
+where
+    T: Default + for<'a> TryFromAnsi<'a>,
+{
+    if bytes.is_empty() {
+        Ok(T::default())
+    } else {
+        T::try_from_ansi(bytes)
+    }
+}
+
+/// Return the basic color for an index in the 0–15 range.
+fn basic_color(index: u16) -> SgrColor {
+    #[allow(clippy::cast_possible_truncation)]
+    let color = AnsiColor::from_index(index as u8).unwrap_or(AnsiColor::Black);
+    SgrColor::Ansi(color)
+}
+
+/// Parse the color selector and arguments of an extended color
+/// (`38`, `48` or `58`) given as colon-separated sub-parameters.
+fn parse_extended_color_subparams<'a>(
+    subparams: &mut impl Iterator<Item = &'a [u8]>,
+) -> Result<SgrColor, ParseError> {
+    let selector: u16 = parse_num(subparams.next().unwrap_or_default())?;
+    match selector {
+        5 => {
+            let index = subparams.next().ok_or_else(|| {
+                ParseError::InvalidValue(
+                    "SGR color: missing palette index".to_string(),
+                )
+            })?;
+            Ok(SgrColor::Indexed(parse_num(index)?))
+        }
+        2 => {
+            // ITU T.416 specifies a color space identifier before the
+            // components (`38:2:cs:r:g:b`), but many emitters omit it
+            // (`38:2:r:g:b`).
+            let rest: smallvec::SmallVec<[&[u8]; 4]> =
+                subparams.by_ref().take(4).collect();
+            let components = match rest.len() {
+                4 => &rest[1..],
+                3 => &rest[..],
+                n => {
+                    return Err(ParseError::InvalidValue(format!(
+                        "SGR color: expected 3 or 4 RGB sub-parameters, got {n}"
+                    )));
+                }
+            };
+            Ok(SgrColor::Rgb {
+                r: parse_num(components[0])?,
+                g: parse_num(components[1])?,
+                b: parse_num(components[2])?,
+            })
+        }
+        n => Err(ParseError::InvalidValue(format!(
+            "SGR color: unsupported color selector {n}"
+        ))),
+    }
+}
+
+/// Parse the color selector and arguments of an extended color
+/// (`38`, `48` or `58`) given as subsequent semicolon-separated parameters.
+fn parse_extended_color_params<'a>(
+    params: &mut impl Iterator<Item = &'a [u8]>,
+) -> Result<SgrColor, ParseError> {
+    let missing =
+        || ParseError::InvalidValue("SGR color: missing parameter".to_string());
+    let selector: u16 = parse_num(params.next().ok_or_else(missing)?)?;
+    match selector {
+        5 => Ok(SgrColor::Indexed(parse_num(
+            params.next().ok_or_else(missing)?,
+        )?)),
+        2 => Ok(SgrColor::Rgb {
+            r: parse_num(params.next().ok_or_else(missing)?)?,
+            g: parse_num(params.next().ok_or_else(missing)?)?,
+            b: parse_num(params.next().ok_or_else(missing)?)?,
+        }),
+        n => Err(ParseError::InvalidValue(format!(
+            "SGR color: unsupported color selector {n}"
+        ))),
+    }
+}
+
+impl SgrAttribute {
+    /// Parse a single attribute starting at `param`, consuming any
+    /// additional semicolon-separated parameters from `rest` that belong
+    /// to it.
+    fn parse<'a>(
+        param: &'a [u8],
+        rest: &mut impl Iterator<Item = &'a [u8]>,
+    ) -> Result<Self, ParseError> {
+        let mut subparams = param.split(|&b| b == b':');
+        let code: u16 = parse_num(subparams.next().unwrap_or_default())?;
+        let has_subparams = param.contains(&b':');
+
+        let attr = match code {
+            4 if has_subparams => {
+                let style: u16 =
+                    parse_num(subparams.next().unwrap_or_default())?;
+                Self::Underline(UnderlineStyle::from_subparam(style)?)
+            }
+            38 | 48 | 58 => {
+                let color = if has_subparams {
+                    parse_extended_color_subparams(&mut subparams)?
+                } else {
+                    parse_extended_color_params(rest)?
+                };
+                match code {
+                    38 => Self::Foreground(color),
+                    48 => Self::Background(color),
+                    _ => Self::UnderlineColor(color),
+                }
+            }
+            _ if has_subparams => {
+                return Err(ParseError::InvalidValue(format!(
+                    "SGR attribute {code} does not take sub-parameters"
+                )));
+            }
+            0 => Self::Reset,
+            1 => Self::Intensity(Intensity::Bold),
+            2 => Self::Intensity(Intensity::Faint),
+            3 => Self::Italic(true),
+            4 => Self::Underline(UnderlineStyle::Single),
+            5 => Self::Blink(Blink::Slow),
+            6 => Self::Blink(Blink::Rapid),
+            7 => Self::Inverse(true),
+            8 => Self::Conceal(true),
+            9 => Self::Strike(true),
+            #[allow(clippy::cast_possible_truncation)]
+            10..=19 => Self::Font((code - 10) as u8),
+            21 => Self::Underline(UnderlineStyle::Double),
+            22 => Self::Intensity(Intensity::Normal),
+            23 => Self::Italic(false),
+            24 => Self::Underline(UnderlineStyle::None),
+            25 => Self::Blink(Blink::None),
+            27 => Self::Inverse(false),
+            28 => Self::Conceal(false),
+            29 => Self::Strike(false),
+            30..=37 => Self::Foreground(basic_color(code - 30)),
+            39 => Self::DefaultForeground,
+            40..=47 => Self::Background(basic_color(code - 40)),
+            49 => Self::DefaultBackground,
+            53 => Self::Overline(true),
+            55 => Self::Overline(false),
+            59 => Self::DefaultUnderlineColor,
+            90..=97 => Self::Foreground(basic_color(code - 90 + 8)),
+            100..=107 => Self::Background(basic_color(code - 100 + 8)),
+            _ => Self::Unrecognized(code),
+        };
+
+        Ok(attr)
+    }
+}
+
+/// Encode the arguments of an extended color, either as colon-separated
+/// sub-parameters or as semicolon-separated parameters.
+fn encode_extended_color<W: std::io::Write + ?Sized>(
+    sink: &mut W,
+    code: u8,
+    color: SgrColor,
+    subparams: bool,
+) -> Result<usize, EncodeError> {
+    let sep = if subparams { b':' } else { b';' };
+    let mut written = write_int(sink, code)?;
+    written += write_byte_into(sink, sep)?;
+    match color {
+        SgrColor::Ansi(c) => {
+            written += write_byte_into(sink, b'5')?;
+            written += write_byte_into(sink, sep)?;
+            written += write_int(sink, c.index())?;
+        }
+        SgrColor::Indexed(index) => {
+            written += write_byte_into(sink, b'5')?;
+            written += write_byte_into(sink, sep)?;
+            written += write_int(sink, index)?;
+        }
+        SgrColor::Rgb { r, g, b } => {
+            written += write_byte_into(sink, b'2')?;
+            written += write_byte_into(sink, sep)?;
+            if subparams {
+                // Empty color space identifier.
+                written += write_byte_into(sink, sep)?;
+            }
+            written += write_int(sink, r)?;
+            written += write_byte_into(sink, sep)?;
+            written += write_int(sink, g)?;
+            written += write_byte_into(sink, sep)?;
+            written += write_int(sink, b)?;
+        }
+    }
+    Ok(written)
+}
+
+impl AnsiEncode for SgrAttribute {
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        let code: u16 = match *self {
+            Self::Reset => 0,
+            Self::Intensity(Intensity::Bold) => 1,
+            Self::Intensity(Intensity::Faint) => 2,
+            Self::Intensity(Intensity::Normal) => 22,
+            Self::Italic(true) => 3,
+            Self::Italic(false) => 23,
+            Self::Underline(UnderlineStyle::Single) => 4,
+            Self::Underline(UnderlineStyle::None) => 24,
+            Self::Underline(style) => {
+                let mut written = write_byte_into(sink, b'4')?;
+                written += write_byte_into(sink, b':')?;
+                written += write_int(sink, style.subparam())?;
+                return Ok(written);
+            }
+            Self::Blink(Blink::Slow) => 5,
+            Self::Blink(Blink::Rapid) => 6,
+            Self::Blink(Blink::None) => 25,
+            Self::Inverse(true) => 7,
+            Self::Inverse(false) => 27,
+            Self::Conceal(true) => 8,
+            Self::Conceal(false) => 28,
+            Self::Strike(true) => 9,
+            Self::Strike(false) => 29,
+            Self::Overline(true) => 53,
+            Self::Overline(false) => 55,
+            Self::Font(n @ 0..=9) => 10 + u16::from(n),
+            Self::Font(n) => {
+                return Err(EncodeError::Unencodeable(format!(
+                    "SGR font number {n} is out of range (0-9)"
+                )));
+            }
+            Self::Foreground(SgrColor::Ansi(c)) if c.is_bright() => {
+                90 + u16::from(c.index() - 8)
+            }
+            Self::Foreground(SgrColor::Ansi(c)) => 30 + u16::from(c.index()),
+            Self::Foreground(color) => {
+                return encode_extended_color(sink, 38, color, false);
+            }
+            Self::DefaultForeground => 39,
+            Self::Background(SgrColor::Ansi(c)) if c.is_bright() => {
+                100 + u16::from(c.index() - 8)
+            }
+            Self::Background(SgrColor::Ansi(c)) => 40 + u16::from(c.index()),
+            Self::Background(color) => {
+                return encode_extended_color(sink, 48, color, false);
+            }
+            Self::DefaultBackground => 49,
+            Self::UnderlineColor(color) => {
+                return encode_extended_color(sink, 58, color, true);
+            }
+            Self::DefaultUnderlineColor => 59,
+            Self::Unrecognized(code) => code,
+        };
+        write_int(sink, code)
+    }
+}
+
+/// A list of `SGR` attributes.
+///
+/// An empty list is equivalent to [`SgrAttribute::Reset`].
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct SgrAttributes(pub Vec<SgrAttribute>);
+
+impl Deref for SgrAttributes {
+    type Target = Vec<SgrAttribute>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for SgrAttributes {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl From<Vec<SgrAttribute>> for SgrAttributes {
+    #[inline]
+    fn from(value: Vec<SgrAttribute>) -> Self {
+        Self(value)
+    }
+}
+
+impl FromIterator<SgrAttribute> for SgrAttributes {
+    fn from_iter<T: IntoIterator<Item = SgrAttribute>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl<'a> IntoIterator for &'a SgrAttributes {
+    type Item = &'a SgrAttribute;
+    type IntoIter = std::slice::Iter<'a, SgrAttribute>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl<'a> TryFromAnsiIter<'a> for SgrAttributes {
+    fn try_from_ansi_iter<I>(params: &mut I) -> Result<Self, ParseError>
+    where
+        I: Iterator<Item = &'a [u8]>,
+    {
+        // Malformed attributes are skipped, as terminals do, so that one
+        // bad attribute does not discard the valid ones around it.
+        let mut attrs = Vec::new();
+        let mut error = None;
+        while let Some(param) = params.next() {
+            match SgrAttribute::parse(param, params) {
+                Ok(attr) => attrs.push(attr),
+                Err(err) => error = Some(err),
+            }
+        }
+        match error {
+            // An empty list would read as `CSI m`, which resets.
+            Some(err) if attrs.is_empty() => Err(err),
+            _ => Ok(Self(attrs)),
+        }
+    }
+}
+
+impl<'a> TryFromAnsi<'a> for SgrAttributes {
+    #[inline]
+    fn try_from_ansi(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        if bytes.is_empty() {
+            return Ok(Self::default());
+        }
+        <SgrAttributes as TryFromAnsiIter>::try_from_ansi_iter(
+            &mut bytes.split(|&c| c == b';'),
+        )
+    }
+}
+
+impl AnsiEncode for SgrAttributes {
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        let mut written = 0;
+        for (i, attr) in self.0.iter().enumerate() {
+            if i > 0 {
+                written += write_byte_into(sink, b';')?;
+            }
+            written += attr.encode_ansi_into(sink)?;
+        }
+        Ok(written)
+    }
+}
+
+/// Select Graphic Rendition (`SGR`).
+///
+/// Set the character attributes for subsequently written text.  An empty
+/// attribute list (`CSI m`) resets all attributes, like `CSI 0 m`.
+///
+/// See <https://terminalguide.namepad.de/seq/csi_sm/> for terminal
+/// support specifics.
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, Default, vtansi::derive::AnsiOutput,
+)]
+#[vtansi(csi, finalbyte = 'm')]
+pub struct SelectGraphicRendition(#[vtansi(flatten)] pub SgrAttributes);
+
+impl SelectGraphicRendition {
+    /// Create an `SGR` sequence setting the given attributes.
+    #[must_use]
+    pub fn new(attrs: impl IntoIterator<Item = SgrAttribute>) -> Self {
+        Self(attrs.into_iter().collect())
+    }
+
+    /// Create an `SGR` sequence resetting all attributes (`CSI 0 m`).
+    #[must_use]
+    pub fn reset() -> Self {
+        Self::new([SgrAttribute::Reset])
+    }
+
+    /// Return the attributes set by this sequence.
+    #[must_use]
+    pub fn attributes(&self) -> &[SgrAttribute] {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::TerminalOutputParser;
+    use better_any::TidExt;
+
+    fn parse_sgr(input: &[u8]) -> Vec<SgrAttribute> {
+        let mut attrs = None;
+        TerminalOutputParser::new().feed_with(input, &mut |event| {
+            if let Some(sgr) = event.downcast_ref::<SelectGraphicRendition>() {
+                attrs = Some(sgr.attributes().to_vec());
+            }
+        });
+        attrs.expect("expected an SGR event")
+    }
+
+    fn round_trip(attrs: &[SgrAttribute]) {
+        let sgr = SelectGraphicRendition::new(attrs.iter().copied());
+        let encoded = sgr.encode_ansi().unwrap();
+        assert_eq!(parse_sgr(&encoded), attrs, "{encoded:?}");
+    }
+
+    #[test]
+    fn test_basic_attributes() {
+        assert_eq!(
+            parse_sgr(b"\x1b[1;3;4;5;7;8;9;53m"),
+            vec![
+                SgrAttribute::Intensity(Intensity::Bold),
+                SgrAttribute::Italic(true),
+                SgrAttribute::Underline(UnderlineStyle::Single),
+                SgrAttribute::Blink(Blink::Slow),
+                SgrAttribute::Inverse(true),
+                SgrAttribute::Conceal(true),
+                SgrAttribute::Strike(true),
+                SgrAttribute::Overline(true),
+            ]
+        );
+        assert_eq!(
+            parse_sgr(b"\x1b[22;23;24;25;27;28;29;55;39;49;59m"),
+            vec![
+                SgrAttribute::Intensity(Intensity::Normal),
+                SgrAttribute::Italic(false),
+                SgrAttribute::Underline(UnderlineStyle::None),
+                SgrAttribute::Blink(Blink::None),
+                SgrAttribute::Inverse(false),
+                SgrAttribute::Conceal(false),
+                SgrAttribute::Strike(false),
+                SgrAttribute::Overline(false),
+                SgrAttribute::DefaultForeground,
+                SgrAttribute::DefaultBackground,
+                SgrAttribute::DefaultUnderlineColor,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reset() {
+        assert_eq!(parse_sgr(b"\x1b[m"), vec![]);
+        assert_eq!(parse_sgr(b"\x1b[0m"), vec![SgrAttribute::Reset]);
+        assert_eq!(
+            parse_sgr(b"\x1b[;1m"),
+            vec![
+                SgrAttribute::Reset,
+                SgrAttribute::Intensity(Intensity::Bold)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fonts() {
+        assert_eq!(
+            parse_sgr(b"\x1b[10;11;19m"),
+            vec![
+                SgrAttribute::Font(0),
+                SgrAttribute::Font(1),
+                SgrAttribute::Font(9),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_font_out_of_range() {
+        assert!(
+            SelectGraphicRendition::new([SgrAttribute::Font(10)])
+                .encode_ansi()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_basic_colors() {
+        assert_eq!(
+            parse_sgr(b"\x1b[31;42;97;100m"),
+            vec![
+                SgrAttribute::Foreground(AnsiColor::Red.into()),
+                SgrAttribute::Background(AnsiColor::Green.into()),
+                SgrAttribute::Foreground(AnsiColor::BrightWhite.into()),
+                SgrAttribute::Background(AnsiColor::BrightBlack.into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extended_colors_semicolon() {
+        assert_eq!(
+            parse_sgr(b"\x1b[38;5;208;48;2;10;20;30;1m"),
+            vec![
+                SgrAttribute::Foreground(SgrColor::Indexed(208)),
+                SgrAttribute::Background(SgrColor::rgb(10, 20, 30)),
+                SgrAttribute::Intensity(Intensity::Bold),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extended_colors_colon() {
+        assert_eq!(
+            parse_sgr(b"\x1b[38:2::255:0:0;48:5:17;58:2:1:2:3m"),
+            vec![
+                SgrAttribute::Foreground(SgrColor::rgb(255, 0, 0)),
+                SgrAttribute::Background(SgrColor::Indexed(17)),
+                SgrAttribute::UnderlineColor(SgrColor::rgb(1, 2, 3)),
+            ]
+        );
+        // With an explicit color space identifier.
+        assert_eq!(
+            parse_sgr(b"\x1b[38:2:0:1:2:3m"),
+            vec![SgrAttribute::Foreground(SgrColor::rgb(1, 2, 3))]
+        );
+    }
+
+    #[test]
+    fn test_underline_styles() {
+        assert_eq!(
+            parse_sgr(b"\x1b[4:0;4:1;4:2;4:3;4:4;4:5;21m"),
+            vec![
+                SgrAttribute::Underline(UnderlineStyle::None),
+                SgrAttribute::Underline(UnderlineStyle::Single),
+                SgrAttribute::Underline(UnderlineStyle::Double),
+                SgrAttribute::Underline(UnderlineStyle::Curly),
+                SgrAttribute::Underline(UnderlineStyle::Dotted),
+                SgrAttribute::Underline(UnderlineStyle::Dashed),
+                SgrAttribute::Underline(UnderlineStyle::Double),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut events = 0;
+        let mut sgr = 0;
+        TerminalOutputParser::new().feed_with(
+            b"\x1b[38;5m\x1b[4:9m\x1b[1:2m",
+            &mut |event| {
+                events += 1;
+                if event.downcast_ref::<SelectGraphicRendition>().is_some() {
+                    sgr += 1;
+                }
+            },
+        );
+        assert_eq!(events, 3);
+        assert_eq!(sgr, 0);
+    }
+
+    #[test]
+    fn test_skip_invalid() {
+        assert_eq!(
+            parse_sgr(b"\x1b[1;4:9;31m"),
+            [
+                SgrAttribute::Intensity(Intensity::Bold),
+                SgrAttribute::Foreground(AnsiColor::Red.into()),
+            ]
+        );
+        assert_eq!(parse_sgr(b"\x1b[1:2;3m"), [SgrAttribute::Italic(true)]);
+        assert_eq!(
+            parse_sgr(b"\x1b[73;9m"),
+            [SgrAttribute::Unrecognized(73), SgrAttribute::Strike(true)]
+        );
+    }
+
+    #[test]
+    fn test_encode() {
+        let sgr = SelectGraphicRendition::new([
+            SgrAttribute::Intensity(Intensity::Bold),
+            SgrAttribute::Foreground(AnsiColor::BrightRed.into()),
+            SgrAttribute::Background(SgrColor::rgb(1, 2, 3)),
+            SgrAttribute::Underline(UnderlineStyle::Curly),
+            SgrAttribute::UnderlineColor(SgrColor::Indexed(4)),
+        ]);
+        assert_eq!(
+            sgr.encode_ansi().unwrap(),
+            b"\x1b[1;91;48;2;1;2;3;4:3;58:5:4m"
+        );
+        assert_eq!(
+            SelectGraphicRendition::default().encode_ansi().unwrap(),
+            b"\x1b[m"
+        );
+        assert_eq!(
+            SelectGraphicRendition::reset().encode_ansi().unwrap(),
+            b"\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip(&[
+            SgrAttribute::Reset,
+            SgrAttribute::Intensity(Intensity::Faint),
+            SgrAttribute::Underline(UnderlineStyle::Dashed),
+            SgrAttribute::Blink(Blink::Rapid),
+            SgrAttribute::Font(3),
+        ]);
+        round_trip(&[
+            SgrAttribute::Foreground(SgrColor::Indexed(1)),
+            SgrAttribute::Foreground(AnsiColor::Red.into()),
+            SgrAttribute::Background(AnsiColor::BrightCyan.into()),
+            SgrAttribute::UnderlineColor(SgrColor::rgb(9, 8, 7)),
+            SgrAttribute::Unrecognized(73),
+        ]);
+    }
+}
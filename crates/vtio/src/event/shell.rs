@@ -13,6 +13,14 @@
 //! These sequences are supported by modern terminal emulators including
 //! `iTerm2`, `VSCode`, `WezTerm`, and others.
 //!
+//! Besides the basic `A`/`B`/`C`/`D` markers, the `P` (prompt property),
+//! `L` (fresh line) and `N` (new command) markers are supported, and
+//! markers carry their `key=value` options as [`SemanticPromptOptions`].
+//!
+//! The module also provides [`CurrentWorkingDirectory`] (OSC 7), which
+//! shells use to report their working directory so that terminals can open
+//! new windows or panes in the same location.
+//!
 //! ## Positional Parameters
 //!
 //! Some shell integration sequences support optional positional parameters.
@@ -23,6 +31,186 @@
 //! When encoding, optional parameters that are `None` are omitted, along with
 //! any subsequent parameters.
 
+use std::borrow::Cow;
+use std::ops::Deref;
+
+use vtansi::{
+    AnsiEncode, EncodeError, ParseError, TryFromAnsi, write_byte_into,
+    write_str_into,
+};
+
+/// How the terminal may move the cursor in response to clicks (`cl=`).
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum::IntoStaticStr,
+    strum::Display,
+    strum::EnumString,
+    vtansi::derive::ToAnsi,
+    vtansi::derive::FromAnsi,
+)]
+pub enum ClickMode {
+    /// Clicks may move the cursor within the current line (`line`).
+    #[strum(serialize = "line")]
+    Line,
+    /// Clicks may move the cursor across lines of a multi-line input (`m`).
+    #[strum(serialize = "m")]
+    Multiple,
+    /// Like `Multiple`, using vertical cursor movement (`v`).
+    #[strum(serialize = "v")]
+    ConservativeVertical,
+    /// Like `Multiple`, using vertical movement and wrapping (`w`).
+    #[strum(serialize = "w")]
+    SmartVertical,
+}
+
+/// The kind of prompt being drawn (`k=`).
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum::IntoStaticStr,
+    strum::Display,
+    strum::EnumString,
+    vtansi::derive::ToAnsi,
+    vtansi::derive::FromAnsi,
+)]
+pub enum PromptKind {
+    /// The primary prompt (`i`).
+    #[strum(serialize = "i")]
+    Initial,
+    /// A continuation prompt for multi-line input (`c`).
+    #[strum(serialize = "c")]
+    Continuation,
+    /// A secondary prompt, e.g. a `select` menu (`s`).
+    #[strum(serialize = "s")]
+    Secondary,
+    /// A right-aligned prompt (`r`).
+    #[strum(serialize = "r")]
+    Right,
+}
+
+/// Options attached to OSC 133 markers.
+///
+/// Options are `key=value` pairs separated by `;` following the marker
+/// letter, e.g. `OSC 133 ; A ; aid=42 ; cl=m ST`.  Unrecognized keys, and
+/// known keys with a value this library does not understand, are
+/// preserved in `other` so that they survive a re-emit and do not cost
+/// the marker itself.
+///
+/// Encoding does not preserve the original key order: the known keys are
+/// written first, in the order of the fields below, followed by `other`
+/// in the order the keys appeared.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct SemanticPromptOptions<'a> {
+    /// Application identifier used to match nested shells (`aid=`).
+    pub aid: Option<&'a str>,
+    /// Permitted click-to-move behavior (`cl=`).
+    pub click_mode: Option<ClickMode>,
+    /// The kind of prompt (`k=`).
+    pub kind: Option<PromptKind>,
+    /// Whether the shell handles mouse clicks itself (`click_events=`).
+    pub click_events: Option<bool>,
+    /// Error indication for the finished command (`err=`).
+    pub error: Option<&'a str>,
+    /// Any other `key=value` pairs, in the order they appeared.
+    pub other: Vec<(&'a str, &'a str)>,
+}
+
+impl SemanticPromptOptions<'_> {
+    /// Return `true` if no options are set.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.aid.is_none()
+            && self.click_mode.is_none()
+            && self.kind.is_none()
+            && self.click_events.is_none()
+            && self.error.is_none()
+            && self.other.is_empty()
+    }
+}
+
+impl<'a> TryFromAnsi<'a> for SemanticPromptOptions<'a> {
+    fn try_from_ansi(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        let mut options = Self::default();
+        if bytes.is_empty() {
+            return Ok(options);
+        }
+        for pair in vtansi::parse_keyvalue_pairs(bytes, b';') {
+            let (key, value) = pair?;
+            let recognized = match key {
+                b"aid" => <&str>::try_from_ansi(value)
+                    .map(|aid| options.aid = Some(aid))
+                    .is_ok(),
+                b"cl" => ClickMode::try_from_ansi(value)
+                    .map(|mode| options.click_mode = Some(mode))
+                    .is_ok(),
+                b"k" => PromptKind::try_from_ansi(value)
+                    .map(|kind| options.kind = Some(kind))
+                    .is_ok(),
+                b"click_events" => match value {
+                    b"0" | b"1" => {
+                        options.click_events = Some(value == b"1");
+                        true
+                    }
+                    _ => false,
+                },
+                b"err" => <&str>::try_from_ansi(value)
+                    .map(|error| options.error = Some(error))
+                    .is_ok(),
+                _ => false,
+            };
+            if !recognized {
+                options.other.push((
+                    <&str>::try_from_ansi(key)?,
+                    <&str>::try_from_ansi(value)?,
+                ));
+            }
+        }
+        Ok(options)
+    }
+}
+
+impl AnsiEncode for SemanticPromptOptions<'_> {
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        let known = [
+            ("aid", self.aid),
+            ("cl", self.click_mode.map(<&'static str>::from)),
+            ("k", self.kind.map(<&'static str>::from)),
+            (
+                "click_events",
+                self.click_events.map(|v| if v { "1" } else { "0" }),
+            ),
+            ("err", self.error),
+        ];
+        let pairs = known
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|value| (key, value)))
+            .chain(self.other.iter().copied());
+
+        let mut written = 0;
+        for (i, (key, value)) in pairs.enumerate() {
+            if i > 0 {
+                written += write_byte_into(sink, b';')?;
+            }
+            written += write_str_into(sink, key)?;
+            written += write_byte_into(sink, b'=')?;
+            written += write_str_into(sink, value)?;
+        }
+        Ok(written)
+    }
+}
+
 /// A command that marks the beginning of a shell prompt.
 ///
 /// This sequence (OSC 133;A) indicates where a new prompt starts. Terminal
@@ -32,11 +220,15 @@
 ///
 /// - This should be emitted at the very start of drawing the prompt.
 /// - Must be paired with `PromptEnd` to mark where the prompt ends.
+/// - Terminals that support it perform a fresh-line (see [`FreshLine`])
+///   before starting the prompt.
 #[derive(
-    Debug, Clone, Copy, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput,
+    Debug, Clone, Default, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput,
 )]
 #[vtansi(osc, number = "133", data = "A")]
-pub struct PromptStart;
+pub struct PromptStart<'a> {
+    pub options: Option<SemanticPromptOptions<'a>>,
+}
 
 /// A command that marks the end of a shell prompt and the beginning of user
 /// input.
@@ -67,15 +259,18 @@ pub struct PromptEnd;
 /// - Should follow a `PromptEnd` sequence.
 /// - Must be paired with `CommandEnd` to mark where output ends.
 #[derive(
-    Debug, Clone, Copy, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput,
+    Debug, Clone, Default, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput,
 )]
 #[vtansi(osc, number = "133", data = "C")]
-pub struct CommandStart;
+pub struct CommandStart<'a> {
+    pub options: Option<SemanticPromptOptions<'a>>,
+}
 
 /// A command that marks the end of command output.
 ///
 /// This sequence (`OSC 133;D` or OSC `133;D;exit_code`) indicates where the
-/// command output ends. It can optionally include the command's exit code.
+/// command output ends. It can optionally include the command's exit code
+/// followed by options such as `err=` and `aid=`.
 /// Terminal emulators can use this to track command execution status and
 /// enable features like showing success/failure indicators.
 ///
@@ -88,9 +283,12 @@ pub struct CommandStart;
 /// # Positional Parameters
 ///
 /// The `exit_code` field is marked as a positional parameter. When encoded:
-/// - `CommandEnd { exit_code: None }` produces `OSC 133;D ST`
-/// - `CommandEnd { exit_code: Some(0) }` produces `OSC 133;D;0 ST`
-/// - `CommandEnd { exit_code: Some(1) }` produces `OSC 133;D;1 ST`
+/// - `CommandEnd { exit_code: None, .. }` produces `OSC 133;D ST`
+/// - `CommandEnd { exit_code: Some(0), .. }` produces `OSC 133;D;0 ST`
+/// - `CommandEnd { exit_code: Some(1), .. }` produces `OSC 133;D;1 ST`
+///
+/// Options are written after the exit code.  If options are present
+/// without an exit code, the exit code is left empty (`OSC 133;D;;aid=1`).
 ///
 /// # Example
 ///
@@ -98,30 +296,293 @@ pub struct CommandStart;
 /// use vtio::event::shell::CommandEnd;
 ///
 /// // Report command completion without exit code
-/// let end = CommandEnd { exit_code: None };
+/// let end = CommandEnd::default();
 ///
 /// // Report successful command completion
-/// let end = CommandEnd { exit_code: Some(0) };
+/// let end = CommandEnd::with_exit_code(0);
 ///
 /// // Report command failure
-/// let end = CommandEnd { exit_code: Some(1) };
+/// let end = CommandEnd::with_exit_code(1);
 /// ```
 #[derive(
-    Debug, Clone, Copy, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput,
+    Debug, Clone, Default, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput,
 )]
 #[vtansi(osc, number = "133", data = "D")]
-pub struct CommandEnd {
+pub struct CommandEnd<'a> {
     pub exit_code: Option<i32>,
+    pub options: Option<SemanticPromptOptions<'a>>,
+}
+
+impl CommandEnd<'_> {
+    /// Create a command end marker with the given exit code.
+    #[must_use]
+    pub const fn with_exit_code(exit_code: i32) -> Self {
+        Self {
+            exit_code: Some(exit_code),
+            options: None,
+        }
+    }
+}
+
+/// Set properties of the current prompt (`OSC 133;P`).
+///
+/// Used by shells to change the kind of prompt (`k=`) being drawn without
+/// starting a new prompt, e.g. when switching to a right-aligned prompt.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput,
+)]
+#[vtansi(osc, number = "133", data = "P")]
+pub struct PromptProperty<'a> {
+    pub options: Option<SemanticPromptOptions<'a>>,
+}
+
+/// Perform a fresh-line (`OSC 133;L`).
+///
+/// If the cursor is not at the start of a line, the terminal moves it to the
+/// start of the next line.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput,
+)]
+#[vtansi(osc, number = "133", data = "L")]
+pub struct FreshLine;
+
+/// Start a new command (`OSC 133;N`).
+///
+/// Equivalent to [`PromptStart`], but also implicitly ends the previous
+/// command if it was not closed with [`CommandEnd`].
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput,
+)]
+#[vtansi(osc, number = "133", data = "N")]
+pub struct NewCommand<'a> {
+    pub options: Option<SemanticPromptOptions<'a>>,
+}
+
+/// A `file://` URL as reported by [`CurrentWorkingDirectory`].
+///
+/// The path is percent-decoded on parse and is borrowed from the input
+/// unless it contained escapes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct FileUrl<'a> {
+    /// The host name (may be empty, meaning the local host).
+    pub host: &'a str,
+    /// The decoded absolute path.
+    pub path: Cow<'a, str>,
+}
+
+impl FileUrl<'_> {
+    /// Return the path as a [`std::path::Path`].
+    #[must_use]
+    pub fn as_path(&self) -> &std::path::Path {
+        std::path::Path::new(self.path.as_ref())
+    }
+}
+
+/// Decode `%XX` escapes in `bytes`, borrowing if there are none.
+fn percent_decode(bytes: &[u8]) -> Result<Cow<'_, str>, ParseError> {
+    if !bytes.contains(&b'%') {
+        return <&str>::try_from_ansi(bytes).map(Cow::Borrowed);
+    }
+
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut iter = bytes.iter();
+    while let Some(&b) = iter.next() {
+        if b == b'%' {
+            let hex = iter.as_slice().get(..2).ok_or_else(|| {
+                ParseError::InvalidValue("truncated percent escape".to_string())
+            })?;
+            // `from_str_radix` accepts a leading `+`, so check the digits
+            // explicitly.
+            if !hex.iter().all(u8::is_ascii_hexdigit) {
+                return Err(ParseError::InvalidValue(
+                    "invalid percent escape".to_string(),
+                ));
+            }
+            out.push(hex_digit(hex[0]) << 4 | hex_digit(hex[1]));
+            iter.nth(1);
+        } else {
+            out.push(b);
+        }
+    }
+
+    String::from_utf8(out).map(Cow::Owned).map_err(|_| {
+        ParseError::InvalidValue("path is not valid UTF-8".to_string())
+    })
+}
+
+/// Return the value of the ASCII hex digit `b`.
+const fn hex_digit(b: u8) -> u8 {
+    match b {
+        b'0'..=b'9' => b - b'0',
+        b'a'..=b'f' => b - b'a' + 10,
+        _ => b - b'A' + 10,
+    }
+}
+
+/// Return `true` if `b` can appear unescaped in a URL path.
+const fn is_path_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric()
+        || matches!(
+            b,
+            b'/' | b'-'
+                | b'.'
+                | b'_'
+                | b'~'
+                | b'!'
+                | b'$'
+                | b'&'
+                | b'\''
+                | b'('
+                | b')'
+                | b'*'
+                | b'+'
+                | b','
+                | b';'
+                | b'='
+                | b':'
+                | b'@'
+        )
+}
+
+impl<'a> TryFromAnsi<'a> for FileUrl<'a> {
+    fn try_from_ansi(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        let rest = bytes.strip_prefix(b"file://").ok_or_else(|| {
+            ParseError::InvalidValue(
+                "working directory: expected file:// URL".to_string(),
+            )
+        })?;
+        let sep = rest.iter().position(|&b| b == b'/').ok_or_else(|| {
+            ParseError::InvalidValue(
+                "working directory: missing path".to_string(),
+            )
+        })?;
+        Ok(Self {
+            host: <&str>::try_from_ansi(&rest[..sep])?,
+            path: percent_decode(&rest[sep..])?,
+        })
+    }
+}
+
+impl AnsiEncode for FileUrl<'_> {
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+        let mut written = write_str_into(sink, "file://")?;
+        written += write_str_into(sink, self.host)?;
+        if !self.path.starts_with('/') {
+            written += write_byte_into(sink, b'/')?;
+        }
+        for &b in self.path.as_bytes() {
+            if is_path_char(b) {
+                written += write_byte_into(sink, b)?;
+            } else {
+                written += write_byte_into(sink, b'%')?;
+                written += write_byte_into(sink, HEX[usize::from(b >> 4)])?;
+                written += write_byte_into(sink, HEX[usize::from(b & 0xf)])?;
+            }
+        }
+        Ok(written)
+    }
+}
+
+/// Report the shell's current working directory (`OSC 7 ; file://host/path
+/// ST`).
+///
+/// Shells emit this sequence after every directory change (typically from
+/// the prompt hook). Terminals and multiplexers use it to open new windows
+/// or panes in the same directory.
+///
+/// The path is percent-encoded on the wire; [`FileUrl::path`] holds the
+/// decoded form.
+///
+/// See <https://gitlab.freedesktop.org/Per_Bothner/specifications/-/blob/master/proposals/semantic-prompts.md>
+/// for details.
+///
+/// # Example
+///
+/// ```
+/// use vtansi::AnsiEncode;
+/// use vtio::event::shell::CurrentWorkingDirectory;
+///
+/// let cwd = CurrentWorkingDirectory::new("myhost", "/home/user/my dir");
+/// assert_eq!(
+///     cwd.encode_ansi().unwrap(),
+///     b"\x1b]7;file://myhost/home/user/my%20dir\x1b\\"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, vtansi::derive::AnsiOutput)]
+#[vtansi(osc, number = "7")]
+pub struct CurrentWorkingDirectory<'a>(pub FileUrl<'a>);
+
+impl<'a> CurrentWorkingDirectory<'a> {
+    /// Create a working directory report for `path` on `host`.
+    #[must_use]
+    pub fn new(host: &'a str, path: &'a str) -> Self {
+        Self(FileUrl {
+            host,
+            path: Cow::Borrowed(path),
+        })
+    }
+
+    /// Convert to an owned version.
+    #[must_use]
+    pub fn to_owned(&self) -> CurrentWorkingDirectoryOwned {
+        CurrentWorkingDirectoryOwned {
+            host: self.host.to_string(),
+            path: self.path.to_string(),
+        }
+    }
+}
+
+impl<'a> Deref for CurrentWorkingDirectory<'a> {
+    type Target = FileUrl<'a>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Owned version of [`CurrentWorkingDirectory`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CurrentWorkingDirectoryOwned {
+    pub host: String,
+    pub path: String,
+}
+
+impl<'a> From<CurrentWorkingDirectory<'a>> for CurrentWorkingDirectoryOwned {
+    fn from(value: CurrentWorkingDirectory<'a>) -> Self {
+        Self {
+            host: value.0.host.to_string(),
+            path: value.0.path.into_owned(),
+        }
+    }
+}
+
+impl CurrentWorkingDirectoryOwned {
+    /// Borrow this owned struct as a borrowed version.
+    #[must_use]
+    pub fn borrow(&self) -> CurrentWorkingDirectory<'_> {
+        CurrentWorkingDirectory::new(&self.host, &self.path)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use vtansi::{AnsiEncode, StaticAnsiEncode};
+    use crate::parser::TerminalOutputParser;
+    use better_any::TidExt;
+    use vtansi::StaticAnsiEncode;
 
     #[test]
     fn test_prompt_start() {
-        assert_eq!(PromptStart::BYTES, b"\x1b]133;A\x1b\\");
+        assert_eq!(
+            PromptStart::default().encode_ansi().unwrap(),
+            b"\x1b]133;A\x1b\\"
+        );
     }
 
     #[test]
@@ -131,12 +592,15 @@ mod tests {
 
     #[test]
     fn test_command_start() {
-        assert_eq!(CommandStart::BYTES, b"\x1b]133;C\x1b\\");
+        assert_eq!(
+            CommandStart::default().encode_ansi().unwrap(),
+            b"\x1b]133;C\x1b\\"
+        );
     }
 
     #[test]
     fn test_command_end_without_exit_code() {
-        let cmd = CommandEnd { exit_code: None };
+        let cmd = CommandEnd::default();
         let mut buf = Vec::new();
         let result = cmd.encode_ansi_into(&mut buf);
         assert!(result.is_ok());
@@ -145,7 +609,7 @@ mod tests {
 
     #[test]
     fn test_command_end_with_exit_code_zero() {
-        let cmd = CommandEnd { exit_code: Some(0) };
+        let cmd = CommandEnd::with_exit_code(0);
         let mut buf = Vec::new();
         let result = cmd.encode_ansi_into(&mut buf);
         assert!(result.is_ok());
@@ -154,7 +618,7 @@ mod tests {
 
     #[test]
     fn test_command_end_with_exit_code_nonzero() {
-        let cmd = CommandEnd { exit_code: Some(1) };
+        let cmd = CommandEnd::with_exit_code(1);
         let mut buf = Vec::new();
         let result = cmd.encode_ansi_into(&mut buf);
         assert!(result.is_ok());
@@ -163,12 +627,177 @@ mod tests {
 
     #[test]
     fn test_command_end_with_large_exit_code() {
-        let cmd = CommandEnd {
-            exit_code: Some(127),
-        };
+        let cmd = CommandEnd::with_exit_code(127);
         let mut buf = Vec::new();
         let result = cmd.encode_ansi_into(&mut buf);
         assert!(result.is_ok());
         assert_eq!(String::from_utf8(buf).unwrap(), "\x1b]133;D;127\x1b\\");
     }
+
+    #[test]
+    fn test_fresh_line() {
+        assert_eq!(FreshLine::BYTES, b"\x1b]133;L\x1b\\");
+    }
+
+    #[test]
+    fn test_prompt_start_with_options() {
+        let cmd = PromptStart {
+            options: Some(SemanticPromptOptions {
+                aid: Some("42"),
+                click_mode: Some(ClickMode::Multiple),
+                kind: Some(PromptKind::Initial),
+                click_events: Some(true),
+                ..Default::default()
+            }),
+        };
+        assert_eq!(
+            cmd.encode_ansi().unwrap(),
+            b"\x1b]133;A;aid=42;cl=m;k=i;click_events=1\x1b\\"
+        );
+    }
+
+    #[test]
+    fn test_command_end_options_without_exit_code() {
+        let cmd = CommandEnd {
+            exit_code: None,
+            options: Some(SemanticPromptOptions {
+                aid: Some("7"),
+                ..Default::default()
+            }),
+        };
+        assert_eq!(cmd.encode_ansi().unwrap(), b"\x1b]133;D;;aid=7\x1b\\");
+    }
+
+    #[test]
+    fn test_parse_semantic_prompt_markers() {
+        let input = b"\x1b]133;A;aid=1;cl=line;k=s;x=y\x07\
+                      \x1b]133;P;k=r\x07\
+                      \x1b]133;B\x07\
+                      \x1b]133;C\x07\
+                      \x1b]133;D;2;err=CANCEL;aid=1\x07\
+                      \x1b]133;L\x07\
+                      \x1b]133;N;aid=2\x07";
+        let mut events = Vec::new();
+        TerminalOutputParser::new().feed_with(input, &mut |event| {
+            let encoded = event.encode_ansi().unwrap();
+            if let Some(ps) = event.downcast_ref::<PromptStart>() {
+                let options = ps.options.as_ref().unwrap();
+                assert_eq!(options.aid, Some("1"));
+                assert_eq!(options.click_mode, Some(ClickMode::Line));
+                assert_eq!(options.kind, Some(PromptKind::Secondary));
+                assert_eq!(options.other, vec![("x", "y")]);
+            } else if let Some(pp) = event.downcast_ref::<PromptProperty>() {
+                assert_eq!(
+                    pp.options.as_ref().unwrap().kind,
+                    Some(PromptKind::Right)
+                );
+            } else if let Some(ce) = event.downcast_ref::<CommandEnd>() {
+                assert_eq!(ce.exit_code, Some(2));
+                let options = ce.options.as_ref().unwrap();
+                assert_eq!(options.error, Some("CANCEL"));
+                assert_eq!(options.aid, Some("1"));
+            } else if let Some(nc) = event.downcast_ref::<NewCommand>() {
+                assert_eq!(nc.options.as_ref().unwrap().aid, Some("2"));
+            }
+            events.push(String::from_utf8(encoded).unwrap());
+        });
+        assert_eq!(
+            events,
+            vec![
+                "\x1b]133;A;aid=1;cl=line;k=s;x=y\x1b\\",
+                "\x1b]133;P;k=r\x1b\\",
+                "\x1b]133;B\x1b\\",
+                "\x1b]133;C\x1b\\",
+                "\x1b]133;D;2;aid=1;err=CANCEL\x1b\\",
+                "\x1b]133;L\x1b\\",
+                "\x1b]133;N;aid=2\x1b\\",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_options() {
+        assert!(SemanticPromptOptions::try_from_ansi(b"aid").is_err());
+    }
+
+    #[test]
+    fn test_unknown_option_values() {
+        let options =
+            SemanticPromptOptions::try_from_ansi(b"cl=x;k=z;click_events=2")
+                .unwrap();
+        assert_eq!(options.click_mode, None);
+        assert_eq!(options.kind, None);
+        assert_eq!(options.click_events, None);
+        assert_eq!(
+            options.other,
+            [("cl", "x"), ("k", "z"), ("click_events", "2")]
+        );
+
+        // The marker survives, with the options it understands
+        let mut starts = Vec::new();
+        TerminalOutputParser::new().feed_with(
+            b"\x1b]133;A;k=z;aid=7\x1b\\",
+            &mut |event| {
+                if let Some(ps) = event.downcast_ref::<PromptStart>() {
+                    starts.push(ps.encode_ansi().unwrap());
+                }
+            },
+        );
+        assert_eq!(starts, [b"\x1b]133;A;aid=7;k=z\x1b\\".to_vec()]);
+    }
+
+    fn parse_cwd(input: &[u8]) -> Option<CurrentWorkingDirectoryOwned> {
+        let mut result = None;
+        TerminalOutputParser::new().feed_with(input, &mut |event| {
+            if let Some(cwd) = event.downcast_ref::<CurrentWorkingDirectory>() {
+                assert!(result.is_none());
+                result = Some(cwd.to_owned());
+            }
+        });
+        result
+    }
+
+    #[test]
+    fn test_cwd_parse_borrowed() {
+        let url = FileUrl::try_from_ansi(b"file://host/home/user").unwrap();
+        assert_eq!(url.host, "host");
+        assert!(matches!(url.path, Cow::Borrowed("/home/user")));
+    }
+
+    #[test]
+    fn test_cwd_parse_percent_decoded() {
+        let cwd =
+            parse_cwd(b"\x1b]7;file://box/tmp/a%20b/%C3%A9;x\x07").unwrap();
+        assert_eq!(cwd.host, "box");
+        assert_eq!(cwd.path, "/tmp/a b/\u{e9};x");
+    }
+
+    #[test]
+    fn test_cwd_parse_empty_host() {
+        let cwd = parse_cwd(b"\x1b]7;file:///var/log\x1b\\").unwrap();
+        assert_eq!(cwd.host, "");
+        assert_eq!(cwd.path, "/var/log");
+    }
+
+    #[test]
+    fn test_cwd_parse_invalid() {
+        assert!(parse_cwd(b"\x1b]7;/no/scheme\x1b\\").is_none());
+        assert!(FileUrl::try_from_ansi(b"file://hostonly").is_err());
+        assert!(FileUrl::try_from_ansi(b"file://h/a%2").is_err());
+        assert!(FileUrl::try_from_ansi(b"file://h/a%zz").is_err());
+        assert!(FileUrl::try_from_ansi(b"file://h/a%+1").is_err());
+        assert!(FileUrl::try_from_ansi(b"file://h/a%-1").is_err());
+        assert!(FileUrl::try_from_ansi(b"file://h/%ff").is_err());
+    }
+
+    #[test]
+    fn test_cwd_round_trip() {
+        let owned = CurrentWorkingDirectoryOwned {
+            host: "h".to_string(),
+            path: "/50% done/#1?".to_string(),
+        };
+        let encoded = owned.borrow().encode_ansi().unwrap();
+        assert_eq!(encoded, b"\x1b]7;file://h/50%25%20done/%231%3F\x1b\\");
+        assert_eq!(parse_cwd(&encoded), Some(owned));
+    }
 }
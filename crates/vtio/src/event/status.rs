@@ -0,0 +1,496 @@
+//! Status string requests (`DECRQSS`) and reports (`DECRPSS`).
+//!
+//! The host asks for the current value of a setting by sending
+//! `DCS $ q <selector> ST`, where the selector is the intermediate and
+//! final bytes of the control function that changes the setting (e.g.
+//! `r` for `DECSTBM`).  The terminal replies with `DCS 1 $ r <payload> ST`,
+//! where the payload is the body of that control function, e.g.
+//! `1;24r`, or with `DCS 0 $ r ST` if it does not support the request.
+
+use better_any::TidExt;
+use vtansi::{EncodeError, ParseError};
+
+use crate::event::cursor::SetCursorStyle;
+use crate::event::screen::SelectCharacterProtectionAttribute;
+use crate::event::scroll::{SetLeftAndRightMargins, SetTopAndBottomMargins};
+use crate::event::sgr::SelectGraphicRendition;
+use crate::event::terminal::SelectVTConformanceLevel;
+use crate::parser::TerminalOutputParser;
+
+/// Setting queried by [`RequestStatusString`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StatusStringSelector {
+    /// Text attributes (`SGR`, selector `m`).
+    TextAttributes,
+    /// Top and bottom margins (`DECSTBM`, selector `r`).
+    TopAndBottomMargins,
+    /// Left and right margins (`DECSLRM`, selector `s`).
+    LeftAndRightMargins,
+    /// Cursor style (`DECSCUSR`, selector `SP q`).
+    CursorStyle,
+    /// Character protection attribute (`DECSCA`, selector `" q`).
+    CharacterProtection,
+    /// Conformance level (`DECSCL`, selector `" p`).
+    ConformanceLevel,
+    /// Lines per page (`DECSLPP`, selector `t`).
+    LinesPerPage,
+}
+
+impl StatusStringSelector {
+    /// All selectors, longest first, so that suffix matching finds the
+    /// most specific one.
+    const ALL: [Self; 7] = [
+        Self::CursorStyle,
+        Self::CharacterProtection,
+        Self::ConformanceLevel,
+        Self::TextAttributes,
+        Self::TopAndBottomMargins,
+        Self::LeftAndRightMargins,
+        Self::LinesPerPage,
+    ];
+
+    /// Return the selector bytes sent in the request.
+    #[must_use]
+    pub const fn as_bytes(self) -> &'static [u8] {
+        match self {
+            Self::TextAttributes => b"m",
+            Self::TopAndBottomMargins => b"r",
+            Self::LeftAndRightMargins => b"s",
+            Self::CursorStyle => b" q",
+            Self::CharacterProtection => b"\"q",
+            Self::ConformanceLevel => b"\"p",
+            Self::LinesPerPage => b"t",
+        }
+    }
+
+    /// Return the selector whose bytes end `payload`.
+    fn from_payload(payload: &[u8]) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|selector| payload.ends_with(selector.as_bytes()))
+    }
+}
+
+impl vtansi::AnsiEncode for StatusStringSelector {
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        vtansi::write_bytes_into(sink, self.as_bytes())
+    }
+}
+
+impl<'a> vtansi::TryFromAnsi<'a> for StatusStringSelector {
+    fn try_from_ansi(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        Self::ALL
+            .into_iter()
+            .find(|selector| selector.as_bytes() == bytes)
+            .ok_or_else(|| {
+                ParseError::InvalidValue(format!(
+                    "unknown status string selector: {}",
+                    String::from_utf8_lossy(bytes)
+                ))
+            })
+    }
+}
+
+/// Request Status String (`DECRQSS`).
+///
+/// Request the current value of the setting named by `selector`.
+///
+/// The terminal replies with [`StatusStringReport`].
+///
+/// See <https://vt100.net/docs/vt510-rm/DECRQSS.html> for more
+/// information.
+#[derive(
+    Debug,
+    PartialOrd,
+    PartialEq,
+    Eq,
+    Clone,
+    Copy,
+    Hash,
+    vtansi::derive::AnsiOutput,
+)]
+#[vtansi(dcs, intermediate = "$", finalbyte = 'q')]
+pub struct RequestStatusString {
+    /// The setting to query.
+    #[vtansi(locate = "data")]
+    pub selector: StatusStringSelector,
+}
+
+impl RequestStatusString {
+    /// Create a request for the setting named by `selector`.
+    #[must_use]
+    pub const fn new(selector: StatusStringSelector) -> Self {
+        Self { selector }
+    }
+}
+
+/// Setting value reported in a [`StatusStringReport`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum StatusString {
+    /// No payload, as sent with invalid requests.
+    #[default]
+    Empty,
+    /// Current text attributes.
+    TextAttributes(SelectGraphicRendition),
+    /// Current top and bottom margins.
+    TopAndBottomMargins(SetTopAndBottomMargins),
+    /// Current left and right margins.
+    LeftAndRightMargins(SetLeftAndRightMargins),
+    /// Current cursor style.
+    CursorStyle(SetCursorStyle),
+    /// Current character protection attribute.
+    CharacterProtection(SelectCharacterProtectionAttribute),
+    /// Current conformance level.
+    ConformanceLevel(SelectVTConformanceLevel),
+    /// Current number of lines per page.
+    LinesPerPage(u16),
+    /// A payload that could not be decoded, as is.
+    Other(Vec<u8>),
+}
+
+impl StatusString {
+    /// Return the selector this value answers, if known.
+    #[must_use]
+    pub fn selector(&self) -> Option<StatusStringSelector> {
+        match self {
+            Self::Empty => None,
+            Self::TextAttributes(_) => {
+                Some(StatusStringSelector::TextAttributes)
+            }
+            Self::TopAndBottomMargins(_) => {
+                Some(StatusStringSelector::TopAndBottomMargins)
+            }
+            Self::LeftAndRightMargins(_) => {
+                Some(StatusStringSelector::LeftAndRightMargins)
+            }
+            Self::CursorStyle(_) => Some(StatusStringSelector::CursorStyle),
+            Self::CharacterProtection(_) => {
+                Some(StatusStringSelector::CharacterProtection)
+            }
+            Self::ConformanceLevel(_) => {
+                Some(StatusStringSelector::ConformanceLevel)
+            }
+            Self::LinesPerPage(_) => Some(StatusStringSelector::LinesPerPage),
+            Self::Other(payload) => StatusStringSelector::from_payload(payload),
+        }
+    }
+
+    /// Decode the payload as the control function it was taken from.
+    fn decode(payload: &[u8]) -> Option<Self> {
+        let selector = StatusStringSelector::from_payload(payload)?;
+        if selector == StatusStringSelector::LinesPerPage {
+            let lines = &payload[..payload.len() - 1];
+            return vtansi::TryFromAnsi::try_from_ansi(lines)
+                .ok()
+                .map(Self::LinesPerPage);
+        }
+
+        let mut seq = Vec::with_capacity(payload.len() + 2);
+        seq.extend_from_slice(b"\x1b[");
+        seq.extend_from_slice(payload);
+        let mut decoded = None;
+        TerminalOutputParser::decode_buffer(&seq, &mut |event| {
+            decoded = match selector {
+                StatusStringSelector::TextAttributes => event
+                    .downcast_ref::<SelectGraphicRendition>()
+                    .cloned()
+                    .map(Self::TextAttributes),
+                StatusStringSelector::TopAndBottomMargins => event
+                    .downcast_ref::<SetTopAndBottomMargins>()
+                    .copied()
+                    .map(Self::TopAndBottomMargins),
+                StatusStringSelector::LeftAndRightMargins => event
+                    .downcast_ref::<SetLeftAndRightMargins>()
+                    .copied()
+                    .map(Self::LeftAndRightMargins),
+                StatusStringSelector::CursorStyle => event
+                    .downcast_ref::<SetCursorStyle>()
+                    .copied()
+                    .map(Self::CursorStyle),
+                StatusStringSelector::CharacterProtection => event
+                    .downcast_ref::<SelectCharacterProtectionAttribute>()
+                    .copied()
+                    .map(Self::CharacterProtection),
+                StatusStringSelector::ConformanceLevel => event
+                    .downcast_ref::<SelectVTConformanceLevel>()
+                    .copied()
+                    .map(Self::ConformanceLevel),
+                StatusStringSelector::LinesPerPage => None,
+            };
+        });
+        decoded
+    }
+}
+
+impl vtansi::AnsiEncode for StatusString {
+    fn encode_ansi_into<W: std::io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        let seq = match self {
+            Self::Empty => return Ok(0),
+            Self::TextAttributes(sgr) => sgr.encode_ansi()?,
+            Self::TopAndBottomMargins(margins) => margins.encode_ansi()?,
+            Self::LeftAndRightMargins(margins) => margins.encode_ansi()?,
+            Self::CursorStyle(style) => style.encode_ansi()?,
+            Self::CharacterProtection(attr) => attr.encode_ansi()?,
+            Self::ConformanceLevel(level) => level.encode_ansi()?,
+            Self::LinesPerPage(lines) => {
+                let mut len = vtansi::write_int(sink, *lines)?;
+                len += vtansi::write_byte_into(sink, b't')?;
+                return Ok(len);
+            }
+            Self::Other(payload) => {
+                return vtansi::write_bytes_into(sink, payload);
+            }
+        };
+        // The payload is the control function without its `CSI`.
+        vtansi::write_bytes_into(
+            sink,
+            seq.strip_prefix(b"\x1b[").unwrap_or(&seq),
+        )
+    }
+}
+
+impl<'a> vtansi::TryFromAnsi<'a> for StatusString {
+    fn try_from_ansi(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        if bytes.is_empty() {
+            return Ok(Self::Empty);
+        }
+        Ok(Self::decode(bytes).unwrap_or_else(|| Self::Other(bytes.to_vec())))
+    }
+}
+
+/// Report Status String (`DECRPSS`).
+///
+/// Response from the terminal to [`RequestStatusString`].
+///
+/// The response format is `DCS Ps $ r <payload> ST`, where `Ps` is `1` if
+/// the request was valid and `0` otherwise.
+///
+/// See <https://vt100.net/docs/vt510-rm/DECRPSS.html> for more
+/// information.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, vtansi::derive::AnsiInput)]
+#[vtansi(dcs, intermediate = "$", finalbyte = 'r')]
+pub struct StatusStringReport {
+    /// Whether the request was valid (`true` = `1`, `false` = `0`).
+    #[vtansi(locate = "params")]
+    pub valid: bool,
+    /// The reported setting, [`StatusString::Empty`] if the request was
+    /// invalid.
+    #[vtansi(locate = "data")]
+    pub status: StatusString,
+}
+
+impl StatusStringReport {
+    /// Create a valid report.
+    #[must_use]
+    pub fn new(status: StatusString) -> Self {
+        Self {
+            valid: true,
+            status,
+        }
+    }
+
+    /// Create an invalid-request report (`DCS 0 $ r ST`).
+    #[must_use]
+    pub fn invalid() -> Self {
+        Self {
+            valid: false,
+            status: StatusString::Empty,
+        }
+    }
+
+    /// Return the reported setting, or `None` if the request was invalid.
+    #[must_use]
+    pub fn setting(&self) -> Option<&StatusString> {
+        self.valid.then_some(&self.status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TerminalInputParser;
+    use crate::event::cursor::CursorStyle;
+    use crate::event::screen::CharacterProtection;
+    use crate::event::sgr::{Intensity, SgrAttribute};
+    use vtansi::AnsiEncode;
+
+    fn parse_report(input: &[u8]) -> StatusStringReport {
+        let mut reports = Vec::new();
+        TerminalInputParser::decode_buffer(input, &mut |event| {
+            if let Some(report) = event.downcast_ref::<StatusStringReport>() {
+                reports.push(report.clone());
+            }
+        });
+        assert_eq!(reports.len(), 1, "{input:?}");
+        reports.pop().unwrap()
+    }
+
+    #[test]
+    fn test_request_encoding() {
+        let cases = [
+            (StatusStringSelector::TextAttributes, "\x1bP$qm\x1b\\"),
+            (StatusStringSelector::TopAndBottomMargins, "\x1bP$qr\x1b\\"),
+            (StatusStringSelector::CursorStyle, "\x1bP$q q\x1b\\"),
+            (
+                StatusStringSelector::CharacterProtection,
+                "\x1bP$q\"q\x1b\\",
+            ),
+            (StatusStringSelector::LinesPerPage, "\x1bP$qt\x1b\\"),
+        ];
+        for (selector, expected) in cases {
+            assert_eq!(
+                RequestStatusString::new(selector).encode_ansi().unwrap(),
+                expected.as_bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn test_margins_request_encoding() {
+        use crate::event::scroll::{
+            RequestLeftRightMargins, RequestTopBottomMargins,
+        };
+
+        assert_eq!(
+            RequestTopBottomMargins.encode_ansi().unwrap(),
+            RequestStatusString::new(StatusStringSelector::TopAndBottomMargins)
+                .encode_ansi()
+                .unwrap()
+        );
+        assert_eq!(
+            RequestLeftRightMargins.encode_ansi().unwrap(),
+            RequestStatusString::new(StatusStringSelector::LeftAndRightMargins)
+                .encode_ansi()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_request() {
+        let mut requests = Vec::new();
+        TerminalOutputParser::decode_buffer(
+            b"\x1bP$qr\x1b\\\x1bP$q q\x1b\\",
+            &mut |event| {
+                if let Some(request) =
+                    event.downcast_ref::<RequestStatusString>()
+                {
+                    requests.push(request.selector);
+                }
+            },
+        );
+        assert_eq!(
+            requests,
+            vec![
+                StatusStringSelector::TopAndBottomMargins,
+                StatusStringSelector::CursorStyle
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_reports() {
+        let cases = [
+            (
+                &b"\x1bP1$r0;1m\x1b\\"[..],
+                StatusString::TextAttributes(SelectGraphicRendition::new([
+                    SgrAttribute::Reset,
+                    SgrAttribute::Intensity(Intensity::Bold),
+                ])),
+            ),
+            (
+                b"\x1bP1$r2;20r\x1b\\",
+                StatusString::TopAndBottomMargins(SetTopAndBottomMargins {
+                    top: 2,
+                    bottom: 20,
+                }),
+            ),
+            (
+                b"\x1bP1$r5;70s\x1b\\",
+                StatusString::LeftAndRightMargins(SetLeftAndRightMargins {
+                    left: 5,
+                    right: 70,
+                }),
+            ),
+            (
+                b"\x1bP1$r2 q\x1b\\",
+                StatusString::CursorStyle(SetCursorStyle {
+                    style: CursorStyle::SteadyBlock,
+                }),
+            ),
+            (
+                b"\x1bP1$r1\"q\x1b\\",
+                StatusString::CharacterProtection(
+                    SelectCharacterProtectionAttribute {
+                        protection: CharacterProtection::Protected,
+                    },
+                ),
+            ),
+            (
+                b"\x1bP1$r64;1\"p\x1b\\",
+                StatusString::ConformanceLevel(SelectVTConformanceLevel {
+                    level: 64,
+                    c1_encoding: Some(1),
+                }),
+            ),
+            (b"\x1bP1$r24t\x1b\\", StatusString::LinesPerPage(24)),
+            (b"\x1bP1$r9;9x\x1b\\", StatusString::Other(b"9;9x".to_vec())),
+        ];
+        for (input, status) in cases {
+            let report = parse_report(input);
+            assert!(report.valid);
+            assert_eq!(report.setting(), Some(&status));
+            assert_eq!(report.encode_ansi().unwrap(), input);
+        }
+    }
+
+    #[test]
+    fn test_invalid_report() {
+        let report = parse_report(b"\x1bP0$r\x1b\\");
+        assert_eq!(report, StatusStringReport::invalid());
+        assert_eq!(report.setting(), None);
+        assert_eq!(report.encode_ansi().unwrap(), b"\x1bP0$r\x1b\\");
+    }
+
+    #[test]
+    fn test_selector() {
+        assert_eq!(
+            StatusString::LinesPerPage(24).selector(),
+            Some(StatusStringSelector::LinesPerPage)
+        );
+        assert_eq!(
+            StatusString::Other(b"1 q".to_vec()).selector(),
+            Some(StatusStringSelector::CursorStyle)
+        );
+        assert_eq!(StatusString::Empty.selector(), None);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_cursor_style_report_from_parsed_report() {
+        use crate::event::cursor::{CursorStyleReport, CursorStyleReportData};
+
+        // The parser never produces the deprecated CursorStyleReport.
+        let input = b"\x1bP1$r4 q\x1b\\";
+        TerminalInputParser::decode_buffer(input, &mut |event| {
+            assert!(event.downcast_ref::<CursorStyleReport>().is_none());
+        });
+
+        let report = parse_report(input);
+        assert_eq!(
+            CursorStyleReport::try_from(&report).unwrap(),
+            CursorStyleReport {
+                style: CursorStyleReportData(CursorStyle::SteadyUnderline),
+            }
+        );
+        assert!(
+            CursorStyleReport::try_from(&parse_report(b"\x1bP1$r2t\x1b\\"))
+                .is_err()
+        );
+    }
+}
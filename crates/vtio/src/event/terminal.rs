@@ -213,6 +213,30 @@ terminal_mode!(
     ReportFocusChangeMode, private = '?', params = ["1004"]
 );
 
+/// Terminal gained focus (`CSI I`).
+///
+/// Reported when [`ReportFocusChangeMode`] is enabled.
+///
+/// See <https://terminalguide.namepad.de/mode/p1004/> for terminal
+/// support specifics.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, vtansi::derive::AnsiInput,
+)]
+#[vtansi(csi, finalbyte = 'I')]
+pub struct FocusGained;
+
+/// Terminal lost focus (`CSI O`).
+///
+/// Reported when [`ReportFocusChangeMode`] is enabled.
+///
+/// See <https://terminalguide.namepad.de/mode/p1004/> for terminal
+/// support specifics.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, vtansi::derive::AnsiInput,
+)]
+#[vtansi(csi, finalbyte = 'O')]
+pub struct FocusLost;
+
 terminal_mode!(
     /// Inhibit scroll on application output.
     ///
@@ -281,7 +305,7 @@ terminal_mode!(
     /// See <https://gitlab.com/gnachman/iterm2/-/wikis/synchronized-updates-spec>
     /// for more details and <https://terminalguide.namepad.de/mode/p2026/>
     /// for terminal support specifics.
-    SynchronizedUpdateMode, private = '?', params = ["2006"]
+    SynchronizedUpdateMode, private = '?', params = ["2026"]
 );
 
 /// Bracketed paste start.
@@ -377,7 +401,12 @@ pub struct Bell;
 ///
 /// Query SGR state using DEC Request Status String.
 ///
-/// The terminal replies with the current SGR attributes.
+/// The terminal replies with a
+/// [`StatusStringReport`](crate::event::status::StatusStringReport)
+/// containing the current SGR attributes.
+///
+/// This is an encode-only alias of
+/// [`RequestStatusString`](crate::event::status::RequestStatusString).
 #[derive(
     Debug,
     PartialOrd,
@@ -388,7 +417,13 @@ pub struct Bell;
     Hash,
     vtansi::derive::AnsiOutput,
 )]
-#[vtansi(dcs, intermediate = "$", finalbyte = 'q', data = "m")]
+#[vtansi(
+    dcs,
+    intermediate = "$",
+    finalbyte = 'q',
+    data = "m",
+    alias_of = crate::event::status::RequestStatusString
+)]
 pub struct RequestTextAttributes;
 
 /// Full Reset (`RIS`).
@@ -487,7 +522,7 @@ impl RequestPrimaryDeviceAttributes {
     Hash,
     vtansi::derive::AnsiOutput,
 )]
-#[vtansi(csi, intermediate = ">", finalbyte = 'c')]
+#[vtansi(csi, private = '>', finalbyte = 'c')]
 pub struct RequestSecondaryDeviceAttributes;
 
 /// Request tertiary device attributes (`DA3`).
@@ -510,7 +545,7 @@ pub struct RequestSecondaryDeviceAttributes;
     Hash,
     vtansi::derive::AnsiOutput,
 )]
-#[vtansi(csi, params = ["=0"], finalbyte = 'c')]
+#[vtansi(csi, private = '=', finalbyte = 'c')]
 pub struct RequestTerminalUnitId;
 
 /// Terminal conformance level for DA1 response.
@@ -843,7 +878,13 @@ pub struct SelectVTConformanceLevel {
     Hash,
     vtansi::derive::AnsiOutput,
 )]
-#[vtansi(dcs, intermediate = "$", finalbyte = 'q', data = "\"p")]
+#[vtansi(
+    dcs,
+    intermediate = "$",
+    finalbyte = 'q',
+    data = "\"p",
+    alias_of = crate::event::status::RequestStatusString
+)]
 pub struct RequestVTConformanceLevel;
 
 /// Hex-encoded string.
@@ -1452,7 +1493,55 @@ mod tests {
         request.encode_ansi_into(&mut buf).unwrap();
         let encoded = String::from_utf8(buf).unwrap();
 
-        assert_eq!(encoded, "\x1b[=0c");
+        assert_eq!(encoded, "\x1b[=c");
+    }
+
+    #[test]
+    fn test_request_secondary_device_attributes_encoding() {
+        let mut buf = Vec::new();
+        RequestSecondaryDeviceAttributes
+            .encode_ansi_into(&mut buf)
+            .unwrap();
+
+        assert_eq!(buf, b"\x1b[>c");
+    }
+
+    #[test]
+    fn test_device_attributes_requests_parse() {
+        use crate::parser::TerminalOutputParser;
+        use better_any::TidExt;
+
+        let mut events = Vec::new();
+        TerminalOutputParser::new().feed_with(
+            b"\x1b[>c\x1b[>0c\x1b[=c\x1b[=0c",
+            &mut |event| {
+                events.push((
+                    event.is::<RequestSecondaryDeviceAttributes>(),
+                    event.is::<RequestTerminalUnitId>(),
+                ));
+            },
+        );
+
+        assert_eq!(
+            events,
+            [(true, false), (true, false), (false, true), (false, true)]
+        );
+    }
+
+    #[test]
+    fn test_synchronized_update_mode_encoding() {
+        assert_eq!(
+            EnableSynchronizedUpdateMode.encode_ansi().unwrap(),
+            b"\x1b[?2026h"
+        );
+        assert_eq!(
+            DisableSynchronizedUpdateMode.encode_ansi().unwrap(),
+            b"\x1b[?2026l"
+        );
+        assert_eq!(
+            RequestSynchronizedUpdateMode.encode_ansi().unwrap(),
+            b"\x1b[?2026$p"
+        );
     }
 
     #[test]
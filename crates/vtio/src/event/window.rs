@@ -1,5 +1,7 @@
 //! Window control commands.
 
+use crate::terminal_mode;
+
 /// Title stack target.
 ///
 /// Specifies which title(s) to push or pop from the stack.
@@ -646,6 +648,60 @@ pub struct ScreenSizeReport {
     pub cols: u16,
 }
 
+terminal_mode!(
+    /// In-band window resize notifications.
+    ///
+    /// When enabled, the terminal reports its size with
+    /// [`InBandResizeReport`] whenever it changes, and once immediately.
+    /// Unlike `SIGWINCH`, the notification travels with the data stream,
+    /// so it survives SSH and serial links.
+    ///
+    /// See <https://gist.github.com/rockorager/e695fb2924d36b2bcf1fff4a3704bd83>
+    /// for details.
+    InBandResizeMode, private = '?', params = ["2048"]
+);
+
+/// In-band window resize notification.
+///
+/// Sent as `CSI 48 ; rows ; cols ; height ; width t` when
+/// [`InBandResizeMode`] is enabled.  Combine a [`SizeReport`] (size in
+/// character cells) and a [`WindowSizePixelsReport`] (text area size in
+/// pixels).
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, vtansi::derive::AnsiInput,
+)]
+#[vtansi(csi, params = ["48"], finalbyte = 't')]
+pub struct InBandResizeReport {
+    /// Number of rows.
+    pub rows: u16,
+    /// Number of columns.
+    pub cols: u16,
+    /// Height in pixels.
+    pub height: u16,
+    /// Width in pixels.
+    pub width: u16,
+}
+
+impl InBandResizeReport {
+    /// Return the size in character cells.
+    #[must_use]
+    pub fn size(&self) -> SizeReport {
+        SizeReport {
+            rows: self.rows,
+            cols: self.cols,
+        }
+    }
+
+    /// Return the size in pixels.
+    #[must_use]
+    pub fn pixels(&self) -> WindowSizePixelsReport {
+        WindowSizePixelsReport {
+            height: self.height,
+            width: self.width,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -935,4 +991,39 @@ mod tests {
         report.encode_ansi_into(&mut buf).unwrap();
         assert_eq!(String::from_utf8(buf).unwrap(), "\x1b[9;40;160t");
     }
+
+    #[test]
+    fn test_in_band_resize_mode() {
+        assert_eq!(EnableInBandResizeMode::BYTES, b"\x1b[?2048h");
+        assert_eq!(DisableInBandResizeMode::BYTES, b"\x1b[?2048l");
+    }
+
+    #[test]
+    fn test_in_band_resize_report() {
+        use better_any::TidExt;
+
+        let mut reports = Vec::new();
+        crate::parser::TerminalInputParser::new().feed_with(
+            b"\x1b[48;24;80;384;640t\x1b[8;30;120t",
+            &mut |event| {
+                if let Some(report) = event.downcast_ref::<InBandResizeReport>()
+                {
+                    reports.push(*report);
+                }
+            },
+        );
+        assert_eq!(reports.len(), 1);
+        let report = reports[0];
+        assert_eq!(report.size(), SizeReport { rows: 24, cols: 80 });
+        assert_eq!(
+            report.pixels(),
+            WindowSizePixelsReport {
+                height: 384,
+                width: 640,
+            }
+        );
+        let mut buf = Vec::new();
+        report.encode_ansi_into(&mut buf).unwrap();
+        assert_eq!(buf, b"\x1b[48;24;80;384;640t");
+    }
 }
@@ -2,7 +2,16 @@
 
 pub mod event;
 pub mod parser;
+pub mod probe;
+pub mod responder;
+pub mod screen;
+pub mod state;
+#[cfg(feature = "tokio")]
+pub mod stream;
+#[cfg(unix)]
+pub mod tty;
 
+pub use crate::event::InputEvent;
 pub use crate::parser::TerminalInputParser;
 
 /// Re-export of [`better_any::TidExt`] as `AnyEvent` for convenient event downcasting.
@@ -25,6 +34,8 @@ pub use vtansi::{AnsiEncode, AnsiEvent};
 
 #[doc(hidden)]
 pub mod __private {
+    pub use better_any;
+    pub use linkme;
     pub use paste;
 }
 
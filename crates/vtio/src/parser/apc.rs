@@ -0,0 +1,285 @@
+//! APC string extraction.
+//!
+//! `vt-push-parser` consumes SOS, PM and APC strings without reporting
+//! them.  [`ApcScanner`] tracks just enough of the parser state to find APC
+//! strings (`ESC _ ... ST`) alongside it, so that APC-based protocols such
+//! as kitty graphics can be dispatched through the registry.
+//!
+//! Only the 7-bit introducer (`ESC _`) and terminator (`ESC \\`) are
+//! recognized.  Like the sequence parser, the scanner does not treat the
+//! 8-bit C1 controls (`0x9F` APC, `0x9C` ST) as introducers or
+//! terminators, since those bytes are UTF-8 continuation bytes in the
+//! input streams this crate handles.
+
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+const CAN: u8 = 0x18;
+const SUB: u8 = 0x1a;
+const DEL: u8 = 0x7f;
+
+/// Maximum size of an APC string; longer strings are dropped.
+pub const MAX_APC_LEN: usize = 1024 * 1024;
+
+const PASTE_START: &[u8] = b"\x1b[200~";
+const PASTE_END: &[u8] = b"\x1b[201~";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum ScanState {
+    #[default]
+    Ground,
+    Escape,
+    Osc,
+    OscEscape,
+    Dcs,
+    DcsEscape,
+    SosPm,
+    SosPmEscape,
+    Apc,
+    ApcEscape,
+    Paste,
+}
+
+/// A boundary of an APC string found by [`ApcScanner::scan`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApcBoundary {
+    /// `ESC _` was consumed.
+    Start,
+    /// The string terminator was consumed and the APC data is available
+    /// from [`ApcScanner::data`].
+    End,
+}
+
+/// Find APC strings in a byte stream.
+///
+/// The scanner mirrors the string states of the sequence parser, so that
+/// `ESC _` inside OSC, DCS, SOS and PM strings is not mistaken for the
+/// start of an APC string.  Like the sequence parser, CAN and SUB abort an
+/// APC string.
+#[derive(Debug, Default)]
+pub struct ApcScanner {
+    state: ScanState,
+    buffer: Vec<u8>,
+    overflow: bool,
+    bracketed_paste: bool,
+    /// Number of bytes of the bracketed paste start or end marker seen.
+    paste_matched: usize,
+}
+
+impl ApcScanner {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            state: ScanState::Ground,
+            buffer: Vec::new(),
+            overflow: false,
+            bracketed_paste: false,
+            paste_matched: 0,
+        }
+    }
+
+    /// Create a scanner that skips bracketed paste content.
+    ///
+    /// The input parser captures everything between `CSI 200 ~` and
+    /// `CSI 201 ~` verbatim, so nothing in there may start a string.
+    #[must_use]
+    pub const fn with_bracketed_paste() -> Self {
+        let mut scanner = Self::new();
+        scanner.bracketed_paste = true;
+        scanner
+    }
+
+    /// Scan `input` up to and including the next APC boundary.
+    ///
+    /// Return the number of bytes consumed, which should be passed on to
+    /// the sequence parser before handling the boundary, if any.
+    pub fn scan(&mut self, input: &[u8]) -> (usize, Option<ApcBoundary>) {
+        for (i, &byte) in input.iter().enumerate() {
+            if let Some(boundary) = self.advance(byte) {
+                return (i + 1, Some(boundary));
+            }
+        }
+        (input.len(), None)
+    }
+
+    /// Return the data of the last complete APC string.
+    ///
+    /// Return `None` if the string exceeded [`MAX_APC_LEN`].
+    #[must_use]
+    pub fn data(&self) -> Option<&[u8]> {
+        (!self.overflow).then_some(self.buffer.as_slice())
+    }
+
+    /// Stop collecting the current APC string.
+    ///
+    /// Used when the sequence parser did not interpret `ESC _` as an APC
+    /// introducer (e.g. while capturing bracketed paste content).
+    pub fn cancel(&mut self) {
+        self.state = ScanState::Ground;
+        self.buffer.clear();
+    }
+
+    fn push(&mut self, byte: u8) {
+        if self.buffer.len() < MAX_APC_LEN {
+            self.buffer.push(byte);
+        } else {
+            self.overflow = true;
+        }
+    }
+
+    /// Advance the bracketed paste marker expected in the current state.
+    ///
+    /// Return `true` once the whole marker has been seen.
+    fn match_paste_marker(&mut self, byte: u8) -> bool {
+        let marker = match self.state {
+            ScanState::Ground | ScanState::Escape => PASTE_START,
+            ScanState::Paste => PASTE_END,
+            _ => {
+                self.paste_matched = 0;
+                return false;
+            }
+        };
+        if byte == marker[self.paste_matched] {
+            self.paste_matched += 1;
+        } else {
+            self.paste_matched = usize::from(byte == ESC);
+        }
+        if self.paste_matched == marker.len() {
+            self.paste_matched = 0;
+            return true;
+        }
+        false
+    }
+
+    fn advance(&mut self, byte: u8) -> Option<ApcBoundary> {
+        use ScanState::{
+            Apc, ApcEscape, Dcs, DcsEscape, Escape, Ground, Osc, OscEscape,
+            Paste, SosPm, SosPmEscape,
+        };
+
+        if self.bracketed_paste && self.match_paste_marker(byte) {
+            self.state = if self.state == Paste { Ground } else { Paste };
+            return None;
+        }
+
+        self.state = match (self.state, byte) {
+            (Paste, _) => Paste,
+            (Apc | ApcEscape, CAN | SUB) => {
+                self.buffer.clear();
+                Ground
+            }
+            (Escape, b'_') => {
+                self.buffer.clear();
+                self.overflow = false;
+                self.state = Apc;
+                return Some(ApcBoundary::Start);
+            }
+            (ApcEscape, b'\\') => {
+                self.state = Ground;
+                return Some(ApcBoundary::End);
+            }
+            (Ground | Escape, ESC) => Escape,
+            (Escape, b']') => Osc,
+            (Escape, b'P') => Dcs,
+            (Escape, b'X' | b'^') => SosPm,
+            (Ground | Escape, _)
+            | (_, CAN | SUB)
+            | (Osc, BEL)
+            | (OscEscape | DcsEscape | SosPmEscape, b'\\') => Ground,
+            (Osc, ESC) => OscEscape,
+            (Dcs, ESC) => DcsEscape,
+            (SosPm, ESC) => SosPmEscape,
+            (Apc, ESC) => ApcEscape,
+            (OscEscape | DcsEscape | SosPmEscape | ApcEscape, ESC | DEL)
+            | (Osc | Dcs | SosPm, _)
+            | (Apc, DEL) => self.state,
+            (OscEscape, _) => Osc,
+            (DcsEscape, _) => Dcs,
+            (SosPmEscape, _) => SosPm,
+            (Apc, _) => {
+                self.push(byte);
+                Apc
+            }
+            (ApcEscape, _) => {
+                self.push(ESC);
+                self.push(byte);
+                Apc
+            }
+        };
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect(chunks: &[&[u8]]) -> (Vec<u8>, Vec<Vec<u8>>) {
+        let mut scanner = ApcScanner::new();
+        let mut passed = Vec::new();
+        let mut strings = Vec::new();
+        for chunk in chunks {
+            let mut input = *chunk;
+            while !input.is_empty() {
+                let (consumed, boundary) = scanner.scan(input);
+                passed.extend_from_slice(&input[..consumed]);
+                input = &input[consumed..];
+                if boundary == Some(ApcBoundary::End) {
+                    strings.push(scanner.data().unwrap().to_vec());
+                }
+            }
+        }
+        (passed, strings)
+    }
+
+    #[test]
+    fn test_apc_strings() {
+        let input = b"a\x1b_Gi=1;AAAA\x1b\\b\x1b_x\x1by\x1b\x1b\\";
+        let (passed, strings) = collect(&[input]);
+        assert_eq!(passed, input);
+        assert_eq!(strings, vec![b"Gi=1;AAAA".to_vec(), b"x\x1by".to_vec()]);
+    }
+
+    #[test]
+    fn test_split_input() {
+        let (_, strings) =
+            collect(&[b"\x1b", b"_Ga=q", b";\x1b", b"\\", b"\x1b_\x18"]);
+        assert_eq!(strings, vec![b"Ga=q;".to_vec()]);
+    }
+
+    #[test]
+    fn test_c1_apc_not_recognized() {
+        let (_, strings) = collect(&[b"\x9fGi=1;AAAA\x9c\x1b\\"]);
+        assert!(strings.is_empty());
+    }
+
+    #[test]
+    fn test_apc_inside_strings() {
+        let (_, strings) = collect(&[
+            b"\x1b]0;\x1b_x\x07",
+            b"\x1bPq\x1b_x\x1b\\",
+            b"\x1b^\x1b_x\x1b\\",
+            b"\x1b(_",
+        ]);
+        assert!(strings.is_empty());
+    }
+
+    #[test]
+    fn test_bracketed_paste() {
+        let mut scanner = ApcScanner::with_bracketed_paste();
+        let mut strings = Vec::new();
+        for chunk in [
+            b"\x1b[200~foo\x1b]bar\x1b_x\x1b\\\x1b[20".as_slice(),
+            b"1~\x1b_Gi=1;OK\x1b\\",
+        ] {
+            let mut input = chunk;
+            while !input.is_empty() {
+                let (consumed, boundary) = scanner.scan(input);
+                input = &input[consumed..];
+                if boundary == Some(ApcBoundary::End) {
+                    strings.push(scanner.data().unwrap().to_vec());
+                }
+            }
+        }
+        assert_eq!(strings, vec![b"Gi=1;OK".to_vec()]);
+    }
+}
@@ -111,10 +111,19 @@ where
         return false;
     }
 
+    // Sequences with a fixed final byte (e.g. `ESC 7`, `ESC # 8`)
+    let mut exact = cursor;
+    if let Answer::Match(handler) | Answer::PrefixAndMatch(handler) =
+        exact.advance(seq.final_byte)
+        && handler(&AnsiEventData::new(), cb).is_ok()
+    {
+        return true;
+    }
+
+    let finalbyte_slice = std::slice::from_ref(&seq.final_byte);
+    let data = AnsiEventData::new_with_finalbyte(finalbyte_slice);
     match cursor.deref() {
         Answer::Match(handler) | Answer::PrefixAndMatch(handler) => {
-            let finalbyte_slice = std::slice::from_ref(&seq.final_byte);
-            let data = AnsiEventData::new_with_finalbyte(finalbyte_slice);
             if handler(&data, cb).is_ok() {
                 return true;
             }
@@ -122,6 +131,18 @@ where
         Answer::DeadEnd | Answer::Prefix => (),
     }
 
+    // Sequences taking their final byte as a field (e.g. `ESC ( Pf`)
+    // are keyed with a `\0` placeholder after the intermediates
+    if !intermediates.is_empty() {
+        let mut dynamic = cursor;
+        if let Answer::Match(handler) | Answer::PrefixAndMatch(handler) =
+            dynamic.advance(0)
+            && handler(&data, cb).is_ok()
+        {
+            return true;
+        }
+    }
+
     // Build suffix: \0 placeholder + final byte (for sequences without intermediates in key)
     let mut suffix = [0u8; 2];
     suffix[1] = seq.final_byte;
@@ -253,6 +274,15 @@ where
         }
     }
 
+    // An explicit default parameter is equivalent to an omitted one
+    // (e.g. `CSI 0 J` is `CSI J`)
+    if !all_params.is_empty()
+        && all_params.iter().all(|p| p.is_empty() || *p == b"0")
+        && parse_csi_with_marker(seq, &base_cursor, 0, &[], &data, cb)
+    {
+        return true;
+    }
+
     false
 }
 
@@ -372,22 +402,66 @@ where
     // Static data from the trie key (like `data = "A"`) is matched during trie walk.
     //
     // Strategy:
-    // 1. Use longest match to find how much of osc_data is static (number + static data)
-    // 2. Only Match or PrefixAndMatch are valid - DeadEnd or Prefix means unrecognized
-    // 3. Pass the remaining data directly to the handler via AnsiEventData::new_with_data
+    // 1. Walk osc_data through the trie, remembering every key that matches
+    //    along the way (number + static data)
+    // 2. Try the handlers from the longest match to the shortest, passing the
+    //    remaining data directly via AnsiEventData::new_with_data, so that a
+    //    shorter key can claim data rejected by a longer one (e.g. `8;` vs `8;;`)
+    // 3. No match at all means the sequence is unrecognized
+    parse_string_data(osc_data, cursor_factory(), cb)
+}
 
-    let mut cursor = cursor_factory();
+/// Parse an APC sequence using the provided cursor factory.
+///
+/// `apc_data` is everything between `ESC _` and the string terminator.
+/// Static data (such as the `G` of kitty graphics commands) is matched the
+/// same way as for OSC sequences.
+///
+/// Returns `true` if the event was handled, `false` if unrecognized.
+pub fn parse_apc<F>(
+    apc_data: &[u8],
+    cursor_factory: impl FnOnce() -> AnsiControlFunctionTrieCursor,
+    cb: &mut F,
+) -> bool
+where
+    F: FnMut(&dyn vtansi::AnsiEvent),
+{
+    parse_string_data(apc_data, cursor_factory(), cb)
+}
 
-    let (answer, consumed) = cursor.advance_longest_match(osc_data);
+/// Match string sequence data against the trie, trying the longest
+/// matching key first.
+fn parse_string_data<F>(
+    data: &[u8],
+    mut cursor: AnsiControlFunctionTrieCursor,
+    cb: &mut F,
+) -> bool
+where
+    F: FnMut(&dyn vtansi::AnsiEvent),
+{
+    let mut matches: smallvec::SmallVec<
+        [(vtansi::registry::Handler, usize); 4],
+    > = smallvec::SmallVec::new();
 
-    match answer {
-        Answer::Match(handler) | Answer::PrefixAndMatch(handler) => {
-            let remaining = &osc_data[consumed..];
-            let event_data = AnsiEventData::new_with_data(remaining);
-            handler(&event_data, cb).is_ok()
+    for (i, &byte) in data.iter().enumerate() {
+        match cursor.advance(byte) {
+            Answer::DeadEnd => break,
+            Answer::Match(handler) => {
+                matches.push((*handler, i + 1));
+                break;
+            }
+            Answer::PrefixAndMatch(handler) => {
+                matches.push((*handler, i + 1));
+            }
+            Answer::Prefix => (),
         }
-        Answer::DeadEnd | Answer::Prefix => false,
     }
+
+    matches.iter().rev().any(|&(handler, consumed)| {
+        let remaining = &data[consumed..];
+        let event_data = AnsiEventData::new_with_data(remaining);
+        handler(&event_data, cb).is_ok()
+    })
 }
 
 /// Parse a DCS sequence header using the provided cursor factory.
@@ -1,4 +1,6 @@
 use crate::event::UnrecognizedInputEvent;
+use crate::event::input::InputEvent;
+use crate::event::keyboard::{Win32KeyRecordSeq, Win32SurrogatePairs};
 use crate::event::mouse::parse_mouse_event_bytes;
 use crate::event::terminal::{BracketedPasteEnd, BracketedPasteStart};
 use better_any::TidExt;
@@ -10,6 +12,7 @@ use vt_push_parser::{
 use vtansi::registry::{AnsiEventData, Answer};
 use vtansi::{StaticAnsiEncode, format_csi};
 
+use super::apc::{ApcBoundary, ApcScanner};
 use super::common;
 
 const MAX_UTF8_CHAR_BYTES: usize = 4;
@@ -36,6 +39,7 @@ struct ParserState {
     accum_buffer: Vec<u8>,
     capture_mode: CaptureMode,
     dcs_header: Option<DCSOwned>,
+    win32_surrogates: Win32SurrogatePairs,
 }
 
 impl ParserState {
@@ -46,6 +50,7 @@ impl ParserState {
             accum_buffer: Vec::new(),
             capture_mode: CaptureMode::None,
             dcs_header: None,
+            win32_surrogates: Win32SurrogatePairs::new(),
         }
     }
 
@@ -65,6 +70,7 @@ impl ParserState {
 
 pub struct TerminalInputParser {
     seq_parser: VTCapturePushParser<VT_PARSER_INTEREST_ALL>,
+    apc_scanner: ApcScanner,
     state: ParserState,
 }
 
@@ -81,6 +87,7 @@ impl TerminalInputParser {
             seq_parser: VTCapturePushParser::new_with_interest::<
                 VT_PARSER_INTEREST_ALL,
             >(),
+            apc_scanner: ApcScanner::with_bracketed_paste(),
             state: ParserState::new(),
         }
     }
@@ -110,10 +117,37 @@ impl TerminalInputParser {
     where
         F: FnMut(&dyn vtansi::AnsiEvent),
     {
-        self.seq_parser
-            .feed_with(input, |vt_event: VTCaptureEvent| {
-                Self::process_vt_event(&vt_event, &mut self.state, cb)
-            });
+        // The sequence parser swallows APC strings, so they are extracted
+        // separately and dispatched once the parser has seen the preceding
+        // bytes, which keeps events in stream order.
+        let mut input = input;
+        while !input.is_empty() {
+            let (consumed, boundary) = self.apc_scanner.scan(input);
+            self.seq_parser.feed_with(
+                &input[..consumed],
+                |vt_event: VTCaptureEvent| {
+                    Self::process_vt_event(&vt_event, &mut self.state, cb)
+                },
+            );
+            input = &input[consumed..];
+            match boundary {
+                Some(ApcBoundary::Start)
+                    if matches!(
+                        self.state.capture_mode,
+                        CaptureMode::BracketedPaste | CaptureMode::MouseEvent
+                    ) =>
+                {
+                    // `ESC _` is part of captured content.
+                    self.apc_scanner.cancel();
+                }
+                Some(ApcBoundary::End) => {
+                    if let Some(apc_data) = self.apc_scanner.data() {
+                        parse_apc(apc_data, cb);
+                    }
+                }
+                Some(ApcBoundary::Start) | None => (),
+            }
+        }
     }
 
     #[allow(clippy::too_many_lines)]
@@ -306,6 +340,34 @@ impl TerminalInputParser {
             false
         }
     }
+
+    // =====================
+    // Owned event API
+    // =====================
+
+    /// Feed bytes into the parser and return the resulting events as owned
+    /// [`InputEvent`]s.
+    ///
+    /// Unlike [`feed_with`](Self::feed_with), the returned events do not
+    /// borrow the parser, so they can be queued or sent to other threads.
+    pub fn feed_owned(
+        &mut self,
+        input: &[u8],
+    ) -> std::vec::IntoIter<InputEvent> {
+        let mut events = Vec::new();
+        self.feed_with(input, &mut |event| {
+            events.push(InputEvent::from_event(event));
+        });
+        events.into_iter()
+    }
+
+    /// Handle idle state like [`idle`](Self::idle), returning owned
+    /// [`InputEvent`]s.
+    pub fn idle_owned(&mut self) -> std::vec::IntoIter<InputEvent> {
+        let mut events = Vec::new();
+        self.idle(&mut |event| events.push(InputEvent::from_event(event)));
+        events.into_iter()
+    }
 }
 
 fn parse_c0<F>(
@@ -387,12 +449,21 @@ where
         return VTInputCapture::CountUtf8(3);
     }
 
-    // Wrapper callback that intercepts BracketedPasteStart to set up capture mode
+    // Wrapper callback that intercepts BracketedPasteStart to set up
+    // capture mode, and converts win32-input-mode records to key events.
     let mut intercept_cb = |event: &dyn vtansi::AnsiEvent| {
         if event.downcast_ref::<BracketedPasteStart>().is_some() {
             state.accum_buffer.clear();
             state.capture_mode = CaptureMode::BracketedPaste;
             capture = VTInputCapture::Terminator(BracketedPasteEnd::BYTES);
+        } else if let Some(Win32KeyRecordSeq(record)) =
+            event.downcast_ref::<Win32KeyRecordSeq>()
+        {
+            match state.win32_surrogates.decode(*record) {
+                Some(Ok(key)) => cb(&key),
+                Some(Err(_)) => cb(&UnrecognizedInputEvent(vt_event)),
+                None => {}
+            }
         } else {
             cb(event);
         }
@@ -532,6 +603,20 @@ where
     }
 }
 
+fn parse_apc<F>(apc_data: &[u8], cb: &mut F)
+where
+    F: FnMut(&dyn vtansi::AnsiEvent),
+{
+    if !common::parse_apc(
+        apc_data,
+        vtansi::registry::ansi_input_apc_trie_cursor,
+        cb,
+    ) {
+        let raw = [b"\x1b_".as_slice(), apc_data, b"\x1b\\"].concat();
+        cb(&UnrecognizedInputEvent(&VTEvent::Raw(&raw)));
+    }
+}
+
 fn parse_dcs<F>(
     vt_event: &VTEvent,
     dcs_header: &DCSOwned,
@@ -600,6 +685,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::event::keyboard::KeyEventKind;
     use crate::event::mouse::{MouseButton, MouseEvent, MouseEventKind};
     use crate::event::terminal::BracketedPaste;
     use crate::event::{KeyCode, KeyEvent, KeyModifiers};
@@ -864,6 +950,22 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_win32_emoji_input() {
+        // U+1F923 is sent as two records, U+D83E and U+DD23, each pressed
+        // and released.
+        let events = collect_key_events(
+            b"\x1b[0;0;55358;1;0;1_\x1b[0;0;56611;1;0;1_\
+              \x1b[0;0;55358;0;0;1_\x1b[0;0;56611;0;0;1_",
+        );
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].code, KeyCode::Char('🤣'));
+        assert_eq!(events[0].kind, KeyEventKind::Press);
+        assert_eq!(events[0].text.as_deref(), Some("🤣"));
+        assert_eq!(events[1].code, KeyCode::Char('🤣'));
+        assert_eq!(events[1].kind, KeyEventKind::Release);
+    }
+
     #[test]
     fn test_mouse_events_sgr_format() {
         let mut parser = TerminalInputParser::new();
@@ -1,3 +1,4 @@
+pub mod apc;
 pub mod common;
 pub mod input;
 pub mod output;
@@ -12,6 +12,7 @@ use vtansi::registry::AnsiEventData;
 
 use crate::event::{PlainText, UnrecognizedOutputEvent};
 
+use super::apc::{ApcBoundary, ApcScanner};
 use super::common;
 
 const MAX_UTF8_CHAR_BYTES: usize = 4;
@@ -69,6 +70,7 @@ impl ParserState {
 /// typed events via callbacks.
 pub struct TerminalOutputParser {
     seq_parser: VTPushParser<VT_PARSER_INTEREST_ALL>,
+    apc_scanner: ApcScanner,
     state: ParserState,
 }
 
@@ -84,6 +86,7 @@ impl TerminalOutputParser {
         Self {
             seq_parser: VTPushParser::new_with_interest::<VT_PARSER_INTEREST_ALL>(
             ),
+            apc_scanner: ApcScanner::new(),
             state: ParserState::new(),
         }
     }
@@ -109,9 +112,25 @@ impl TerminalOutputParser {
     where
         F: FnMut(&dyn vtansi::AnsiEvent),
     {
-        self.seq_parser.feed_with(input, |vt_event: VTEvent| {
-            Self::process_vt_event(&vt_event, &mut self.state, cb);
-        });
+        // The sequence parser swallows APC strings, so they are extracted
+        // separately and dispatched once the parser has seen the preceding
+        // bytes, which keeps events in stream order.
+        let mut input = input;
+        while !input.is_empty() {
+            let (consumed, boundary) = self.apc_scanner.scan(input);
+            self.seq_parser.feed_with(
+                &input[..consumed],
+                |vt_event: VTEvent| {
+                    Self::process_vt_event(&vt_event, &mut self.state, cb);
+                },
+            );
+            input = &input[consumed..];
+            if boundary == Some(ApcBoundary::End)
+                && let Some(apc_data) = self.apc_scanner.data()
+            {
+                parse_apc(apc_data, cb);
+            }
+        }
     }
 
     #[allow(clippy::too_many_lines)]
@@ -297,6 +316,20 @@ fn parse_esc_invalid<F>(
     }
 }
 
+fn parse_apc<F>(apc_data: &[u8], cb: &mut F)
+where
+    F: FnMut(&dyn vtansi::AnsiEvent),
+{
+    if !common::parse_apc(
+        apc_data,
+        vtansi::registry::ansi_output_apc_trie_cursor,
+        cb,
+    ) {
+        let raw = [b"\x1b_".as_slice(), apc_data, b"\x1b\\"].concat();
+        cb(&UnrecognizedOutputEvent(&VTEvent::Raw(&raw)));
+    }
+}
+
 fn parse_ss3<F>(vt_event: &VTEvent, ss3: vt_push_parser::event::SS3, cb: &mut F)
 where
     F: FnMut(&dyn vtansi::AnsiEvent),
@@ -499,6 +532,128 @@ mod tests {
         assert_eq!(count, 1);
     }
 
+    #[test]
+    fn test_osc_title_with_semicolon() {
+        // The last data field receives the remaining data verbatim
+        let mut title = None;
+        let mut parser = TerminalOutputParser::new();
+        parser.feed_with(b"\x1b]2;a;b\x1b\\", &mut |event| {
+            if let Some(t) =
+                event.downcast_ref::<crate::event::window::SetTitle>()
+            {
+                title = Some(t.title.to_string());
+            }
+        });
+        assert_eq!(title.as_deref(), Some("a;b"));
+    }
+
+    #[test]
+    fn test_osc_title_round_trip_with_semicolon() {
+        // A title containing the delimiter is encoded verbatim, so it must
+        // also be parsed back verbatim
+        let encoded = vtansi::AnsiEncode::encode_ansi(
+            &crate::event::window::SetTitleAndIconName { title: "a;b;c" },
+        )
+        .unwrap();
+        assert_eq!(encoded, b"\x1b]0;a;b;c\x1b\\");
+        let mut title = None;
+        let mut parser = TerminalOutputParser::new();
+        parser.feed_with(&encoded, &mut |event| {
+            if let Some(t) = event
+                .downcast_ref::<crate::event::window::SetTitleAndIconName>()
+            {
+                title = Some(t.title.to_string());
+            }
+        });
+        assert_eq!(title.as_deref(), Some("a;b;c"));
+    }
+
+    #[test]
+    fn test_osc_exact_match_rejects_trailing_data() {
+        // `PromptStart` is `OSC 133 ; A`, so `OSC 133 ; Ax` is unrecognized
+        let mut unrecognized = 0;
+        let mut parser = TerminalOutputParser::new();
+        parser.feed_with(b"\x1b]133;Ax\x1b\\", &mut |event| {
+            if event
+                .downcast_ref::<crate::event::UnrecognizedOutputEvent>()
+                .is_some()
+            {
+                unrecognized += 1;
+            }
+        });
+        assert_eq!(unrecognized, 1);
+    }
+
+    #[test]
+    fn test_osc_exact_match_without_trailing_data() {
+        // The exact form of a unit OSC is still recognized
+        let mut matched = 0;
+        let mut parser = TerminalOutputParser::new();
+        parser.feed_with(b"\x1b]133;B\x1b\\", &mut |event| {
+            if event
+                .downcast_ref::<crate::event::shell::PromptEnd>()
+                .is_some()
+            {
+                matched += 1;
+            }
+        });
+        assert_eq!(matched, 1);
+    }
+
+    #[test]
+    fn test_osc_falls_back_to_shorter_key() {
+        // `OSC 8 ; ; URI` walks past the `HyperlinkEnd` key (`8;;`), which
+        // rejects the trailing URI, and must fall back to `Hyperlink` (`8;`)
+        let mut uri = None;
+        let mut parser = TerminalOutputParser::new();
+        parser.feed_with(b"\x1b]8;;http://x\x1b\\", &mut |event| {
+            if let Some(link) =
+                event.downcast_ref::<crate::event::hyperlink::Hyperlink>()
+            {
+                uri = Some(link.uri.to_string());
+            }
+        });
+        assert_eq!(uri.as_deref(), Some("http://x"));
+    }
+
+    #[test]
+    fn test_osc_empty_data_field() {
+        use crate::event::color::RequestOrSetSpecialTextForegroundColor;
+        use crate::event::window::{
+            SetIconName, SetTitle, SetTitleAndIconName,
+        };
+
+        // An empty title clears it, and the field type decides whether an
+        // empty value is valid
+        let mut events = Vec::new();
+        let mut parser = TerminalOutputParser::new();
+        parser.feed_with(
+            b"\x1b]0;\x1b\\\x1b]1;\x07\x1b]2;\x1b\\\x1b]10;\x1b\\",
+            &mut |event| {
+                if let Some(set) = event.downcast_ref::<SetTitleAndIconName>() {
+                    events.push(format!("title and icon {:?}", set.title));
+                } else if let Some(set) = event.downcast_ref::<SetIconName>() {
+                    events.push(format!("icon {:?}", set.name));
+                } else if let Some(set) = event.downcast_ref::<SetTitle>() {
+                    events.push(format!("title {:?}", set.title));
+                } else if event.is::<RequestOrSetSpecialTextForegroundColor>() {
+                    events.push("foreground".to_string());
+                } else if event.is::<crate::event::UnrecognizedOutputEvent>() {
+                    events.push("unrecognized".to_string());
+                }
+            },
+        );
+        assert_eq!(
+            events,
+            [
+                "title and icon \"\"",
+                "icon \"\"",
+                "title \"\"",
+                "unrecognized"
+            ]
+        );
+    }
+
     #[test]
     fn test_esc_save_cursor() {
         // ESC 7 - save cursor
@@ -514,21 +669,319 @@ mod tests {
         assert!(count >= 3);
     }
 
+    #[test]
+    fn test_default_field_decoding() {
+        use vtansi::TryFromAnsi;
+
+        #[derive(Debug, PartialEq, vtansi::derive::FromAnsi)]
+        #[vtansi(format = "vector")]
+        struct Position {
+            #[vtansi(default)]
+            row: u16,
+            #[vtansi(default)]
+            col: u16,
+        }
+
+        #[derive(Debug, PartialEq, vtansi::derive::FromAnsi)]
+        #[vtansi(format = "map")]
+        struct Named {
+            #[vtansi(default)]
+            a: u16,
+            b: u16,
+        }
+
+        // Omitted and empty parameters of `#[vtansi(default)]` fields
+        // decode as the default
+        for (input, row, col) in [
+            (&b""[..], 0, 0),
+            (b";", 0, 0),
+            (b"5", 5, 0),
+            (b"5;", 5, 0),
+            (b";7", 0, 7),
+            (b"2;3", 2, 3),
+        ] {
+            assert_eq!(
+                Position::try_from_ansi(input).unwrap(),
+                Position { row, col },
+                "{input:?}"
+            );
+        }
+        assert!(Position::try_from_ansi(b"x;1").is_err());
+
+        assert_eq!(Named::try_from_ansi(b"b=1").unwrap(), Named { a: 0, b: 1 });
+        assert_eq!(
+            Named::try_from_ansi(b"a=2;b=3").unwrap(),
+            Named { a: 2, b: 3 }
+        );
+        // Fields without the attribute are still required
+        assert!(Named::try_from_ansi(b"a=2").is_err());
+    }
+
     #[test]
     fn test_scroll_down_default_param() {
         use crate::event::scroll::ScrollDown;
 
-        // CSI 1 T - scroll down with explicit param 1 (the default)
-        // Note: CSI T without params won't match ScrollDown since it requires a u16 param
+        // CSI 1 T - scroll down with explicit param 1 (the default), and
+        // CSI T with the param omitted, which decodes as 0
         let mut scroll_events: Vec<ScrollDown> = Vec::new();
         let mut parser = TerminalOutputParser::new();
-        parser.feed_with(b"\x1b[1T", &mut |event: &dyn vtansi::AnsiEvent| {
-            if let Some(sd) = event.downcast_ref::<ScrollDown>() {
-                scroll_events.push(*sd);
+        parser.feed_with(
+            b"\x1b[1T\x1b[T",
+            &mut |event: &dyn vtansi::AnsiEvent| {
+                if let Some(sd) = event.downcast_ref::<ScrollDown>() {
+                    scroll_events.push(*sd);
+                }
+            },
+        );
+        assert_eq!(scroll_events, vec![ScrollDown(1), ScrollDown(0)]);
+    }
+
+    #[test]
+    fn test_omitted_and_empty_params() {
+        use crate::event::cursor::{CursorUp, SetCursorPosition};
+
+        let mut positions = Vec::new();
+        let mut parser = TerminalOutputParser::new();
+        parser.feed_with(
+            b"\x1b[H\x1b[5H\x1b[;7H\x1b[2;3H\x1b[A",
+            &mut |event: &dyn vtansi::AnsiEvent| {
+                if let Some(p) = event.downcast_ref::<SetCursorPosition>() {
+                    positions.push((p.row, p.col));
+                } else {
+                    assert_eq!(event.downcast_ref(), Some(&CursorUp(0)));
+                }
+            },
+        );
+        assert_eq!(positions, vec![(0, 0), (5, 0), (0, 7), (2, 3)]);
+    }
+
+    #[test]
+    fn test_default_fields_round_trip() {
+        use crate::event::cursor::SetCursorPosition;
+        use crate::event::scroll::SetTopAndBottomMargins;
+        use vtansi::AnsiEncode;
+
+        // Trailing empty parameters of `#[vtansi(default)]` fields decode
+        // as the default, and explicit values survive a round trip
+        let mut decoded = Vec::new();
+        let mut parser = TerminalOutputParser::new();
+        let position = SetCursorPosition { row: 2, col: 3 };
+        let margins = SetTopAndBottomMargins { top: 4, bottom: 20 };
+        let mut input = b"\x1b[5;H\x1b[0;0H".to_vec();
+        input.extend(position.encode_ansi().unwrap());
+        input.extend(margins.encode_ansi().unwrap());
+        input.extend(b"\x1b[;r");
+        parser.feed_with(&input, &mut |event: &dyn vtansi::AnsiEvent| {
+            if let Some(p) = event.downcast_ref::<SetCursorPosition>() {
+                decoded.push((p.row, p.col));
+            } else if let Some(m) =
+                event.downcast_ref::<SetTopAndBottomMargins>()
+            {
+                decoded.push((m.top, m.bottom));
             }
         });
-        assert_eq!(scroll_events.len(), 1);
-        assert_eq!(scroll_events[0].0, 1);
+        assert_eq!(decoded, vec![(5, 0), (0, 0), (2, 3), (4, 20), (0, 0)]);
+    }
+
+    #[test]
+    fn test_omitted_params_decode_as_default() {
+        use crate::event::screen::{
+            CharacterProtection, DeleteCharacter, DeleteLine, EraseCharacter,
+            InsertCharacter, InsertLine, SelectCharacterProtectionAttribute,
+        };
+        use crate::event::scroll::{ScrollLeft, SetTopAndBottomMargins};
+
+        let mut events = Vec::new();
+        let mut parser = TerminalOutputParser::new();
+        parser.feed_with(
+            b"\x1b[L\x1b[M\x1b[P\x1b[@\x1b[X\x1b[ @\x1b[r\x1b[\"q",
+            &mut |event: &dyn vtansi::AnsiEvent| {
+                if event.downcast_ref() == Some(&InsertLine(0)) {
+                    events.push("IL");
+                } else if event.downcast_ref() == Some(&DeleteLine(0)) {
+                    events.push("DL");
+                } else if event.downcast_ref() == Some(&DeleteCharacter(0)) {
+                    events.push("DCH");
+                } else if event.downcast_ref() == Some(&InsertCharacter(0)) {
+                    events.push("ICH");
+                } else if event.downcast_ref() == Some(&EraseCharacter(0)) {
+                    events.push("ECH");
+                } else if event.downcast_ref() == Some(&ScrollLeft(0)) {
+                    events.push("SL");
+                } else if event.downcast_ref()
+                    == Some(&SetTopAndBottomMargins { top: 0, bottom: 0 })
+                {
+                    events.push("DECSTBM");
+                } else if event.downcast_ref()
+                    == Some(&SelectCharacterProtectionAttribute {
+                        protection: CharacterProtection::default(),
+                    })
+                {
+                    events.push("DECSCA");
+                }
+            },
+        );
+        assert_eq!(
+            events,
+            ["IL", "DL", "DCH", "ICH", "ECH", "SL", "DECSTBM", "DECSCA"]
+        );
+    }
+
+    #[test]
+    fn test_esc_dispatch() {
+        use crate::event::charset::{Charset94Code, DesignateG0};
+        use crate::event::cursor::{Index, RestoreCursor, SaveCursor};
+        use crate::event::screen::FillScreenWithE;
+
+        let mut names = Vec::new();
+        let mut parser = TerminalOutputParser::new();
+        parser.feed_with(
+            b"\x1b7\x1b8\x1bD\x1b#8\x1b(0",
+            &mut |event: &dyn vtansi::AnsiEvent| {
+                if event.is::<SaveCursor>() {
+                    names.push("DECSC");
+                } else if event.is::<RestoreCursor>() {
+                    names.push("DECRC");
+                } else if event.is::<Index>() {
+                    names.push("IND");
+                } else if event.is::<FillScreenWithE>() {
+                    names.push("DECALN");
+                } else if let Some(g0) = event.downcast_ref::<DesignateG0>() {
+                    assert_eq!(g0.charset, Charset94Code::DecSpecialGraphic);
+                    names.push("SCS");
+                }
+            },
+        );
+        assert_eq!(names, ["DECSC", "DECRC", "IND", "DECALN", "SCS"]);
+    }
+
+    #[test]
+    fn test_esc_dispatch_with_intermediates() {
+        use crate::event::charset::{
+            Charset94Code, Charset96Code, DesignateG1, DesignateG1_96,
+            DesignateG2, DesignateG3, DisableUTF8Mode, EnableUTF8Mode,
+        };
+
+        // Fixed final bytes after an intermediate, and final bytes
+        // decoded as a field, must not shadow each other
+        let mut names = Vec::new();
+        let mut parser = TerminalOutputParser::new();
+        parser.feed_with(
+            b"\x1b%G\x1b%@\x1b)0\x1b*B\x1b+A\x1b-A",
+            &mut |event: &dyn vtansi::AnsiEvent| {
+                if event.is::<EnableUTF8Mode>() {
+                    names.push("UTF8 on");
+                } else if event.is::<DisableUTF8Mode>() {
+                    names.push("UTF8 off");
+                } else if let Some(g1) = event.downcast_ref::<DesignateG1>() {
+                    assert_eq!(g1.charset, Charset94Code::DecSpecialGraphic);
+                    names.push("G1");
+                } else if let Some(g2) = event.downcast_ref::<DesignateG2>() {
+                    assert_eq!(g2.charset, Charset94Code::Ascii);
+                    names.push("G2");
+                } else if let Some(g3) = event.downcast_ref::<DesignateG3>() {
+                    assert_eq!(g3.charset, Charset94Code::British);
+                    names.push("G3");
+                } else if let Some(g1) = event.downcast_ref::<DesignateG1_96>()
+                {
+                    assert_eq!(g1.charset, Charset96Code::Latin1Supplemental);
+                    names.push("G1 96");
+                }
+            },
+        );
+        assert_eq!(names, ["UTF8 on", "UTF8 off", "G1", "G2", "G3", "G1 96"]);
+    }
+
+    #[test]
+    fn test_esc_unknown_final_unrecognized() {
+        // A final byte that no sequence accepts, either fixed or as a
+        // field, is reported as unrecognized
+        let mut unrecognized = 0;
+        let mut parser = TerminalOutputParser::new();
+        parser.feed_with(
+            b"\x1b(~\x1b#9",
+            &mut |event: &dyn vtansi::AnsiEvent| {
+                assert!(event.is::<UnrecognizedOutputEvent>(), "{event:?}");
+                unrecognized += 1;
+            },
+        );
+        assert_eq!(unrecognized, 2);
+    }
+
+    #[test]
+    fn test_explicit_default_param() {
+        use crate::event::screen::{EraseDisplayBelow, EraseLineRight};
+
+        let mut erases = 0;
+        let mut parser = TerminalOutputParser::new();
+        parser.feed_with(
+            b"\x1b[0J\x1b[0K",
+            &mut |event: &dyn vtansi::AnsiEvent| {
+                if event.is::<EraseDisplayBelow>()
+                    || event.is::<EraseLineRight>()
+                {
+                    erases += 1;
+                }
+            },
+        );
+        assert_eq!(erases, 2);
+    }
+
+    #[test]
+    fn test_explicit_default_param_retry() {
+        use crate::event::screen::{
+            EraseDisplayAbove, EraseDisplayBelow, EraseDisplayComplete,
+        };
+
+        // Only all-default parameters are retried as omitted; other
+        // values keep their own meaning, and every sequence produces
+        // exactly one event
+        let mut names = Vec::new();
+        let mut parser = TerminalOutputParser::new();
+        parser.feed_with(
+            b"\x1b[J\x1b[0J\x1b[;J\x1b[1J\x1b[2J\x1b[7J",
+            &mut |event: &dyn vtansi::AnsiEvent| {
+                if event.is::<EraseDisplayBelow>() {
+                    names.push("below");
+                } else if event.is::<EraseDisplayAbove>() {
+                    names.push("above");
+                } else if event.is::<EraseDisplayComplete>() {
+                    names.push("complete");
+                } else if event.is::<UnrecognizedOutputEvent>() {
+                    names.push("unrecognized");
+                }
+            },
+        );
+        assert_eq!(
+            names,
+            [
+                "below",
+                "below",
+                "below",
+                "above",
+                "complete",
+                "unrecognized"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_explicit_default_param_direct_match() {
+        use crate::event::scroll::ScrollUp;
+
+        // Sequences that accept the parameter decode it directly instead
+        // of being retried without it
+        let mut scrolls = Vec::new();
+        let mut parser = TerminalOutputParser::new();
+        parser.feed_with(
+            b"\x1b[0S\x1b[;S\x1b[3S",
+            &mut |event: &dyn vtansi::AnsiEvent| {
+                if let Some(su) = event.downcast_ref::<ScrollUp>() {
+                    scrolls.push(*su);
+                }
+            },
+        );
+        assert_eq!(scrolls, vec![ScrollUp(0), ScrollUp(0), ScrollUp(3)]);
     }
 
     #[test]
@@ -665,13 +1118,13 @@ mod tests {
         };
 
         // Test OSC 133;A (PromptStart)
-        let mut prompt_starts: Vec<PromptStart> = Vec::new();
+        let mut prompt_starts: Vec<bool> = Vec::new();
         let mut parser = TerminalOutputParser::new();
         parser.feed_with(
             b"\x1b]133;A\x1b\\",
             &mut |event: &dyn vtansi::AnsiEvent| {
                 if let Some(ps) = event.downcast_ref::<PromptStart>() {
-                    prompt_starts.push(*ps);
+                    prompt_starts.push(ps.options.is_none());
                 }
             },
         );
@@ -699,13 +1152,13 @@ mod tests {
         );
 
         // Test OSC 133;C (CommandStart)
-        let mut command_starts: Vec<CommandStart> = Vec::new();
+        let mut command_starts: Vec<bool> = Vec::new();
         let mut parser = TerminalOutputParser::new();
         parser.feed_with(
             b"\x1b]133;C\x1b\\",
             &mut |event: &dyn vtansi::AnsiEvent| {
                 if let Some(cs) = event.downcast_ref::<CommandStart>() {
-                    command_starts.push(*cs);
+                    command_starts.push(cs.options.is_none());
                 }
             },
         );
@@ -716,13 +1169,13 @@ mod tests {
         );
 
         // Test OSC 133;D (CommandEnd without exit code)
-        let mut command_ends: Vec<CommandEnd> = Vec::new();
+        let mut command_ends: Vec<Option<i32>> = Vec::new();
         let mut parser = TerminalOutputParser::new();
         parser.feed_with(
             b"\x1b]133;D\x1b\\",
             &mut |event: &dyn vtansi::AnsiEvent| {
                 if let Some(ce) = event.downcast_ref::<CommandEnd>() {
-                    command_ends.push(*ce);
+                    command_ends.push(ce.exit_code);
                 }
             },
         );
@@ -732,18 +1185,18 @@ mod tests {
             "OSC 133;D (CommandEnd) should be recognized"
         );
         assert_eq!(
-            command_ends[0].exit_code, None,
+            command_ends[0], None,
             "CommandEnd without exit code should have None"
         );
 
         // Test OSC 133;D;0 (CommandEnd with exit code 0)
-        let mut command_ends: Vec<CommandEnd> = Vec::new();
+        let mut command_ends: Vec<Option<i32>> = Vec::new();
         let mut parser = TerminalOutputParser::new();
         parser.feed_with(
             b"\x1b]133;D;0\x1b\\",
             &mut |event: &dyn vtansi::AnsiEvent| {
                 if let Some(ce) = event.downcast_ref::<CommandEnd>() {
-                    command_ends.push(*ce);
+                    command_ends.push(ce.exit_code);
                 }
             },
         );
@@ -753,19 +1206,19 @@ mod tests {
             "OSC 133;D;0 (CommandEnd with exit code) should be recognized"
         );
         assert_eq!(
-            command_ends[0].exit_code,
+            command_ends[0],
             Some(0),
             "CommandEnd should have exit_code = Some(0)"
         );
 
         // Test OSC 133;D;127 (CommandEnd with exit code 127)
-        let mut command_ends: Vec<CommandEnd> = Vec::new();
+        let mut command_ends: Vec<Option<i32>> = Vec::new();
         let mut parser = TerminalOutputParser::new();
         parser.feed_with(
             b"\x1b]133;D;127\x1b\\",
             &mut |event: &dyn vtansi::AnsiEvent| {
                 if let Some(ce) = event.downcast_ref::<CommandEnd>() {
-                    command_ends.push(*ce);
+                    command_ends.push(ce.exit_code);
                 }
             },
         );
@@ -775,7 +1228,7 @@ mod tests {
             "OSC 133;D;127 (CommandEnd with exit code) should be recognized"
         );
         assert_eq!(
-            command_ends[0].exit_code,
+            command_ends[0],
             Some(127),
             "CommandEnd should have exit_code = Some(127)"
         );
@@ -799,11 +1252,11 @@ mod tests {
                 events.push("RequestTerminalNameAndVersion");
             }
             if let Some(u) = event.downcast_ref::<UnrecognizedOutputEvent>() {
-                println!("Unrecognized event: {:?}", u);
-                unrecognized.push(format!("{:?}", u));
+                println!("Unrecognized event: {u:?}");
+                unrecognized.push(format!("{u:?}"));
             }
         });
-        println!("Unrecognized events: {:?}", unrecognized);
+        println!("Unrecognized events: {unrecognized:?}");
         assert_eq!(
             events.len(),
             1,
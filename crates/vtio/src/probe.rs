@@ -0,0 +1,502 @@
+//! Terminal capability probing.
+//!
+//! A [`Probe`] sends a batch of queries to the terminal, followed by a
+//! primary device attributes request (`DA1`).  Every terminal answers
+//! `DA1`, and answers queries in order, so once the `DA1` response arrives
+//! all queries the terminal understood have been answered and the rest can
+//! be assumed unsupported.  The answers are collected into
+//! [`TerminalCapabilities`].
+//!
+//! The probe is sans-IO: the caller writes the queries with
+//! [`Probe::write_queries`] and feeds terminal input to [`Probe::feed`]
+//! (or individual parser events to [`Probe::handle_event`]) until
+//! [`Probe::is_done`].  Input that is not an answer to one of the queries,
+//! such as keys typed during the handshake, is handed back to the caller.
+//!
+//! ```ignore
+//! use vtio::probe::Probe;
+//! use vtio::event::terminal::RequestBracketedPasteMode;
+//!
+//! let mut probe = Probe::new()
+//!     .with_terminal_version()
+//!     .with_keyboard_enhancement_flags()
+//!     .with_mode::<RequestBracketedPasteMode>()
+//!     .with_palette(0..16)
+//!     .with_termcap(["colors", "RGB"]);
+//! probe.write_queries(&mut tty)?;
+//!
+//! let mut parser = TerminalInputParser::new();
+//! while !probe.is_done() {
+//!     let n = tty.read(&mut buf)?;
+//!     pending.extend(probe.feed(&mut parser, &buf[..n]));
+//! }
+//! let caps = probe.finish();
+//! ```
+
+use std::collections::BTreeMap;
+use std::io;
+
+use better_any::TidExt;
+use vtansi::{AnsiEncode, EncodeError};
+
+use crate::event::InputEvent;
+use crate::event::color::{
+    RequestOrSetTerminalPaletteColors, TerminalPaletteColor,
+    TerminalPaletteColorResponse,
+};
+use crate::event::input::ModeReport;
+use crate::event::keyboard::{
+    KeyboardEnhancementFlags, KeyboardEnhancementFlagsQuery,
+    KeyboardEnhancementFlagsResponse,
+};
+use crate::event::mode::{
+    TerminalMode, TerminalModeRequest, TerminalModeState,
+};
+use crate::event::terminal::{
+    PrimaryDeviceAttributesResponse, RequestPrimaryDeviceAttributes,
+    RequestTermcap, RequestTerminalNameAndVersion, TermcapQueryResponse,
+    TermcapQueryResult, TerminalNameAndVersionResponse,
+};
+use crate::parser::TerminalInputParser;
+
+/// A mode to query with `DECRQM`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ModeQuery {
+    /// Whether this is a DEC private mode.
+    pub private: bool,
+    /// The mode number.
+    pub mode: u16,
+}
+
+impl ModeQuery {
+    /// The mode of a [`terminal_mode!`](crate::terminal_mode) type.
+    #[must_use]
+    pub const fn of<M: TerminalMode>() -> Self {
+        Self {
+            private: M::PRIVATE,
+            mode: M::MODE,
+        }
+    }
+}
+
+/// Encodes the `DECRQM` request for the mode.
+impl AnsiEncode for ModeQuery {
+    fn encode_ansi_into<W: io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        let mut n = vtansi::write_bytes_into(sink, b"\x1b[")?;
+        if self.private {
+            n += vtansi::write_byte_into(sink, b'?')?;
+        }
+        n += vtansi::write_int(sink, self.mode)?;
+        n += vtansi::write_bytes_into(sink, b"$p")?;
+        Ok(n)
+    }
+}
+
+/// A mode query added to a [`Probe`].
+#[derive(Debug, Clone, Copy)]
+enum ModeRequest {
+    /// A mode given by its `Request*` type, encoded by that type.
+    Typed(
+        ModeQuery,
+        fn(&mut dyn io::Write) -> Result<usize, EncodeError>,
+    ),
+    /// A mode given by number.
+    Number(ModeQuery),
+}
+
+impl ModeRequest {
+    fn typed<R: TerminalModeRequest>() -> Self {
+        Self::Typed(ModeQuery::of::<R>(), |sink| {
+            R::default().encode_ansi_into(sink)
+        })
+    }
+
+    fn query(self) -> ModeQuery {
+        match self {
+            Self::Typed(query, _) | Self::Number(query) => query,
+        }
+    }
+
+    fn encode_ansi_into<W: io::Write + ?Sized>(
+        self,
+        mut sink: &mut W,
+    ) -> Result<usize, EncodeError> {
+        match self {
+            Self::Typed(_, encode) => encode(&mut sink),
+            Self::Number(query) => query.encode_ansi_into(sink),
+        }
+    }
+}
+
+/// Capabilities reported by the terminal during a [`Probe`].
+///
+/// Every field is `None` (or empty) if the corresponding query was not
+/// sent, or if the terminal did not answer it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TerminalCapabilities {
+    /// Primary device attributes (`DA1`).
+    pub primary_device_attributes: Option<PrimaryDeviceAttributesResponse>,
+    /// Terminal name and version (`XTVERSION`), e.g. `kitty(0.32.2)`.
+    pub version: Option<String>,
+    /// Current keyboard enhancement flags, if the terminal supports the
+    /// kitty keyboard protocol.
+    pub keyboard_enhancement_flags: Option<KeyboardEnhancementFlags>,
+    /// State of each queried mode the terminal answered for.
+    ///
+    /// Modes the terminal does not know are reported as
+    /// [`TerminalModeState::NotRecognized`].
+    pub modes: BTreeMap<ModeQuery, TerminalModeState>,
+    /// Queried palette colors, by index.
+    pub palette: BTreeMap<i16, TerminalPaletteColor>,
+    /// Answers to termcap queries (`XTGETTCAP`) the terminal recognized.
+    pub termcap: Vec<TermcapQueryResult>,
+}
+
+impl TerminalCapabilities {
+    /// Return `true` if the terminal supports the kitty keyboard protocol.
+    #[must_use]
+    pub fn supports_kitty_keyboard(&self) -> bool {
+        self.keyboard_enhancement_flags.is_some()
+    }
+
+    /// Return the reported state of a mode.
+    #[must_use]
+    pub fn mode_state(
+        &self,
+        private: bool,
+        mode: u16,
+    ) -> Option<TerminalModeState> {
+        self.modes.get(&ModeQuery { private, mode }).copied()
+    }
+
+    /// Return `true` if the terminal recognized a mode.
+    #[must_use]
+    pub fn recognizes_mode(&self, private: bool, mode: u16) -> bool {
+        self.mode_state(private, mode)
+            .is_some_and(|state| state != TerminalModeState::NotRecognized)
+    }
+
+    /// Return the value of a termcap capability, if the terminal reported
+    /// one.
+    #[must_use]
+    pub fn termcap(&self, name: &str) -> Option<&str> {
+        self.termcap
+            .iter()
+            .find(|result| result.key_as_str() == Some(name))
+            .and_then(TermcapQueryResult::value_as_str)
+    }
+}
+
+/// Terminal capability probe.
+///
+/// See the [module documentation](self) for an overview.
+#[derive(Debug, Clone, Default)]
+pub struct Probe {
+    version: bool,
+    keyboard_enhancement_flags: bool,
+    modes: Vec<ModeRequest>,
+    palette: Vec<i16>,
+    termcap: Vec<String>,
+    capabilities: TerminalCapabilities,
+    done: bool,
+}
+
+impl Probe {
+    /// Create a probe that only sends `DA1`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Query the terminal name and version (`XTVERSION`).
+    #[must_use]
+    pub fn with_terminal_version(mut self) -> Self {
+        self.version = true;
+        self
+    }
+
+    /// Query the kitty keyboard protocol flags.
+    #[must_use]
+    pub fn with_keyboard_enhancement_flags(mut self) -> Self {
+        self.keyboard_enhancement_flags = true;
+        self
+    }
+
+    /// Query the state of a mode, given its `Request*` type generated by
+    /// [`terminal_mode!`](crate::terminal_mode).
+    #[must_use]
+    pub fn with_mode<R: TerminalModeRequest>(mut self) -> Self {
+        self.modes.push(ModeRequest::typed::<R>());
+        self
+    }
+
+    /// Query the state of a mode by number.
+    #[must_use]
+    pub fn with_mode_number(mut self, private: bool, mode: u16) -> Self {
+        self.modes
+            .push(ModeRequest::Number(ModeQuery { private, mode }));
+        self
+    }
+
+    /// Query palette colors.
+    ///
+    /// Indices `-1` and `-2` query the default foreground and background
+    /// colors on terminals supporting that extension.
+    #[must_use]
+    pub fn with_palette(
+        mut self,
+        indices: impl IntoIterator<Item = i16>,
+    ) -> Self {
+        self.palette.extend(indices);
+        self
+    }
+
+    /// Query termcap capabilities (`XTGETTCAP`), e.g. `colors` or `RGB`.
+    ///
+    /// Each capability is sent as a separate request, because xterm stops
+    /// answering at the first one it does not recognize.
+    #[must_use]
+    pub fn with_termcap(
+        mut self,
+        names: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.termcap.extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// Write all queries, terminated by `DA1`.
+    ///
+    /// # Errors
+    ///
+    /// Return an error if writing fails.
+    pub fn write_queries<W: io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> io::Result<()> {
+        if self.version {
+            RequestTerminalNameAndVersion.encode_ansi_into(sink)?;
+        }
+        if self.keyboard_enhancement_flags {
+            KeyboardEnhancementFlagsQuery.encode_ansi_into(sink)?;
+        }
+        for request in &self.modes {
+            request.encode_ansi_into(sink)?;
+        }
+        for &index in &self.palette {
+            RequestOrSetTerminalPaletteColors::query([index])
+                .encode_ansi_into(sink)?;
+        }
+        for name in &self.termcap {
+            RequestTermcap::new(name.as_str()).encode_ansi_into(sink)?;
+        }
+        RequestPrimaryDeviceAttributes::new().encode_ansi_into(sink)?;
+        sink.flush()
+    }
+
+    /// Return `true` once the `DA1` response has been received.
+    #[must_use]
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Return the capabilities collected so far.
+    #[must_use]
+    pub fn capabilities(&self) -> &TerminalCapabilities {
+        &self.capabilities
+    }
+
+    /// Consume the probe, returning the collected capabilities.
+    #[must_use]
+    pub fn finish(self) -> TerminalCapabilities {
+        self.capabilities
+    }
+
+    /// Feed terminal input through `parser`, returning the events that are
+    /// not answers to the probe's queries.
+    pub fn feed(
+        &mut self,
+        parser: &mut TerminalInputParser,
+        input: &[u8],
+    ) -> Vec<InputEvent> {
+        let mut passthrough = Vec::new();
+        parser.feed_with(input, &mut |event| {
+            if !self.handle_event(event) {
+                passthrough.push(InputEvent::from_event(event));
+            }
+        });
+        passthrough
+    }
+
+    /// Record `event` if it answers one of the probe's queries.
+    ///
+    /// Return `true` if the event was consumed by the probe, `false` if it
+    /// should be handled by the caller.  After the `DA1` response has been
+    /// received, no further events are consumed.
+    pub fn handle_event(&mut self, event: &dyn vtansi::AnsiEvent<'_>) -> bool {
+        if self.done {
+            return false;
+        }
+        let caps = &mut self.capabilities;
+        if let Some(da) =
+            event.downcast_ref::<PrimaryDeviceAttributesResponse>()
+        {
+            caps.primary_device_attributes = Some(da.clone());
+            self.done = true;
+        } else if let Some(version) =
+            event.downcast_ref::<TerminalNameAndVersionResponse>()
+            && self.version
+            && caps.version.is_none()
+        {
+            caps.version = Some(version.version.to_string());
+        } else if let Some(flags) =
+            event.downcast_ref::<KeyboardEnhancementFlagsResponse>()
+            && self.keyboard_enhancement_flags
+            && caps.keyboard_enhancement_flags.is_none()
+        {
+            caps.keyboard_enhancement_flags = Some(flags.0.unwrap_or_default());
+        } else if let Some(color) =
+            event.downcast_ref::<TerminalPaletteColorResponse>()
+            && self.palette.contains(&color.index)
+        {
+            caps.palette.insert(color.index, color.color);
+        } else if let Some(response) =
+            event.downcast_ref::<TermcapQueryResponse>()
+            && !self.termcap.is_empty()
+        {
+            caps.termcap.extend(response.results.iter().cloned());
+        } else if let Some(report) = Self::mode_report(&self.modes, event) {
+            caps.modes.insert(
+                ModeQuery {
+                    private: report.private,
+                    mode: report.mode,
+                },
+                report.state,
+            );
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Return `event` as a mode report if it answers a queried mode.
+    fn mode_report(
+        modes: &[ModeRequest],
+        event: &dyn vtansi::AnsiEvent<'_>,
+    ) -> Option<ModeReport> {
+        if modes.is_empty() {
+            return None;
+        }
+        let report = ModeReport::from_event(event)?;
+        let query = ModeQuery {
+            private: report.private,
+            mode: report.mode,
+        };
+        modes
+            .iter()
+            .any(|request| request.query() == query)
+            .then_some(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::terminal::{ConformanceLevel, RequestBracketedPasteMode};
+    use crate::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    fn probe() -> Probe {
+        Probe::new()
+            .with_terminal_version()
+            .with_keyboard_enhancement_flags()
+            .with_mode::<RequestBracketedPasteMode>()
+            .with_mode_number(false, 4)
+            .with_mode_number(true, 9999)
+            .with_palette([1])
+            .with_termcap(["colors", "XX"])
+    }
+
+    #[test]
+    fn test_write_queries() {
+        let mut out = Vec::new();
+        probe().write_queries(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\x1b[>0q\x1b[?u\x1b[?2004$p\x1b[4$p\x1b[?9999$p\
+             \x1b]4;1;?\x1b\\\x1bP+q636F6C6F7273\x1b\\\x1bP+q5858\x1b\\\
+             \x1b[0c"
+        );
+    }
+
+    #[test]
+    fn test_collect_responses() {
+        let mut probe = probe();
+        let mut parser = TerminalInputParser::new();
+        let mut passthrough = Vec::new();
+        let responses: &[&[u8]] = &[
+            b"\x1bP>|kitty(0.32.2)\x1b\\",
+            b"\x1b[?1u",
+            b"x",
+            b"\x1b[?2004;2$y\x1b[4;1$y\x1b[?9999;0$y",
+            b"\x1b]4;1;rgb:cdcd/0000/0000\x1b\\",
+            b"\x1bP1+r636F6C6F7273=323536\x1b\\\x1bP0+r\x1b\\",
+            b"\x1b[?62;22c",
+            b"y",
+        ];
+        for (i, chunk) in responses.iter().enumerate() {
+            // The DA1 response is the second to last chunk.
+            assert_eq!(probe.is_done(), i == responses.len() - 1);
+            passthrough.extend(probe.feed(&mut parser, chunk));
+        }
+        assert!(probe.is_done());
+        assert_eq!(
+            passthrough,
+            vec![
+                InputEvent::Key(KeyEvent::new(
+                    KeyCode::Char('x'),
+                    KeyModifiers::empty()
+                )),
+                InputEvent::Key(KeyEvent::new(
+                    KeyCode::Char('y'),
+                    KeyModifiers::empty()
+                )),
+            ]
+        );
+
+        let caps = probe.finish();
+        assert_eq!(caps.version.as_deref(), Some("kitty(0.32.2)"));
+        assert!(caps.supports_kitty_keyboard());
+        assert_eq!(
+            caps.keyboard_enhancement_flags,
+            Some(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+        );
+        assert_eq!(caps.mode_state(true, 2004), Some(TerminalModeState::Reset));
+        assert_eq!(caps.mode_state(false, 4), Some(TerminalModeState::Set));
+        assert!(caps.recognizes_mode(true, 2004));
+        assert!(!caps.recognizes_mode(true, 9999));
+        assert_eq!(caps.palette.len(), 1);
+        assert_eq!(caps.palette[&1].to_string(), "rgb:cdcd/0000/0000");
+        assert_eq!(caps.termcap("colors"), Some("256"));
+        assert_eq!(caps.termcap("XX"), None);
+        assert_eq!(
+            caps.primary_device_attributes
+                .as_ref()
+                .map(|da| da.conformance_level),
+            Some(ConformanceLevel::VT220)
+        );
+    }
+
+    #[test]
+    fn test_unrequested_responses_pass_through() {
+        let mut probe = Probe::new();
+        let mut parser = TerminalInputParser::new();
+        let passthrough =
+            probe.feed(&mut parser, b"\x1b[?1u\x1b[?2004;1$y\x1b[?1;2c");
+        assert_eq!(passthrough.len(), 2);
+        assert!(probe.is_done());
+        assert_eq!(probe.capabilities().keyboard_enhancement_flags, None);
+        assert!(probe.capabilities().modes.is_empty());
+    }
+}
@@ -0,0 +1,602 @@
+//! Answering host queries as a terminal emulator.
+//!
+//! A terminal built on top of vtio has to answer the queries applications
+//! send it: device attributes, cursor position and status reports, mode
+//! states, window size reports, palette colors and termcap capabilities.
+//! A [`Responder`] maps each recognized query event, as parsed by
+//! [`TerminalOutputParser`](crate::parser::TerminalOutputParser), to its
+//! response event and encodes it.
+//!
+//! Static answers come from the identity and capability profile the
+//! responder is configured with.  Answers that depend on the current
+//! state of the terminal, such as the cursor position, come from a
+//! [`ResponderHook`], which is implemented for
+//! [`Screen`] and [`TerminalStateTracker`].
+//!
+//! ```ignore
+//! use vtio::responder::Responder;
+//!
+//! let responder = Responder::new()
+//!     .with_version("myterm(1.0)")
+//!     .with_cell_size(16, 8)
+//!     .with_termcap([("colors", "256")]);
+//!
+//! parser.feed_with(output, &mut |event| {
+//!     // Writing to a Vec cannot fail
+//!     if !responder.respond(event, &screen, &mut replies).unwrap() {
+//!         screen.apply(event);
+//!     }
+//! });
+//! pty.write_all(&replies)?;
+//! ```
+
+use std::collections::BTreeMap;
+use std::io;
+
+use better_any::TidExt;
+use vtansi::AnsiEncode;
+use xparsecolor::XColor;
+
+use crate::event::color::{
+    RequestOrSetSpecialTextBackgroundColor,
+    RequestOrSetSpecialTextForegroundColor, RequestOrSetTerminalPaletteColors,
+    SpecialTextBackgroundColorResponse, SpecialTextForegroundColorResponse,
+    TerminalPaletteColor, TerminalPaletteColorResponse,
+};
+use crate::event::cursor::{CursorPositionReport, RequestCursorPosition};
+use crate::event::dsr::{OperatingStatusReport, RequestOperatingStatus};
+use crate::event::input::ModeReport;
+use crate::event::mode::{ModeControl, TerminalModeState, default_mode_state};
+use crate::event::terminal::{
+    Capabilities, ConformanceLevel, HexString, PrimaryDeviceAttributesResponse,
+    RequestPrimaryDeviceAttributes, RequestSecondaryDeviceAttributes,
+    RequestTermcap, RequestTerminalID, RequestTerminalNameAndVersion,
+    RequestTerminalUnitId, SecondaryDeviceAttributesResponse,
+    TermcapQueryResponse, TermcapQueryResult, TerminalCapability,
+    TerminalNameAndVersionResponse, TertiaryDeviceAttributesResponse,
+};
+use crate::event::window::{
+    CellSizePixelsReport, ReportCellSizePixels, ReportSize, SizeReport,
+};
+use crate::screen::Screen;
+use crate::state::TerminalStateTracker;
+
+/// Source of answers that depend on the current state of the terminal.
+///
+/// Every method returns `None` by default, in which case the
+/// [`Responder`] falls back to its profile, or leaves the query
+/// unanswered if the profile cannot answer it either.
+///
+/// A pair of hooks consults the first one, then the second.
+pub trait ResponderHook {
+    /// The cursor position to report (`CPR`), one-based.
+    fn cursor_position(&self) -> Option<CursorPositionReport> {
+        None
+    }
+
+    /// The size of the text area in cells.
+    fn size(&self) -> Option<SizeReport> {
+        None
+    }
+
+    /// The answer to a mode state request (`DECRQM`).
+    fn mode_report(
+        &self,
+        _request: &dyn vtansi::AnsiEvent<'_>,
+    ) -> Option<ModeReport> {
+        None
+    }
+}
+
+impl ResponderHook for () {}
+
+impl<T: ResponderHook + ?Sized> ResponderHook for &T {
+    fn cursor_position(&self) -> Option<CursorPositionReport> {
+        (**self).cursor_position()
+    }
+
+    fn size(&self) -> Option<SizeReport> {
+        (**self).size()
+    }
+
+    fn mode_report(
+        &self,
+        request: &dyn vtansi::AnsiEvent<'_>,
+    ) -> Option<ModeReport> {
+        (**self).mode_report(request)
+    }
+}
+
+impl<A: ResponderHook, B: ResponderHook> ResponderHook for (A, B) {
+    fn cursor_position(&self) -> Option<CursorPositionReport> {
+        self.0
+            .cursor_position()
+            .or_else(|| self.1.cursor_position())
+    }
+
+    fn size(&self) -> Option<SizeReport> {
+        self.0.size().or_else(|| self.1.size())
+    }
+
+    fn mode_report(
+        &self,
+        request: &dyn vtansi::AnsiEvent<'_>,
+    ) -> Option<ModeReport> {
+        self.0
+            .mode_report(request)
+            .or_else(|| self.1.mode_report(request))
+    }
+}
+
+/// Reports the cursor position, relative to the scrolling region in
+/// origin mode, the screen size and the modes the screen models.
+impl ResponderHook for Screen {
+    fn cursor_position(&self) -> Option<CursorPositionReport> {
+        let (mut row, col) = self.cursor_position();
+        if self.mode(true, 6) == Some(true) {
+            row -= self.scroll_region().0;
+        }
+        Some(CursorPositionReport {
+            row: row + 1,
+            col: col + 1,
+        })
+    }
+
+    fn size(&self) -> Option<SizeReport> {
+        Some(SizeReport {
+            rows: self.rows(),
+            cols: self.cols(),
+        })
+    }
+
+    fn mode_report(
+        &self,
+        request: &dyn vtansi::AnsiEvent<'_>,
+    ) -> Option<ModeReport> {
+        let (private, mode) = ModeControl::request(request)?;
+        let set = self.mode(private, mode)?;
+        Some(ModeReport {
+            private,
+            mode,
+            state: if set {
+                TerminalModeState::Set
+            } else {
+                TerminalModeState::Reset
+            },
+        })
+    }
+}
+
+/// Answers mode state requests with [`TerminalStateTracker::answer`].
+impl ResponderHook for TerminalStateTracker {
+    fn mode_report(
+        &self,
+        request: &dyn vtansi::AnsiEvent<'_>,
+    ) -> Option<ModeReport> {
+        self.answer(request)
+    }
+}
+
+/// Answers host queries from an identity and capability profile.
+///
+/// See the [module documentation](self) for an overview.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Responder {
+    primary_device_attributes: PrimaryDeviceAttributesResponse,
+    secondary_device_attributes: SecondaryDeviceAttributesResponse,
+    unit_id: HexString,
+    version: Option<String>,
+    size: Option<SizeReport>,
+    cell_size: Option<CellSizePixelsReport>,
+    palette: BTreeMap<i16, TerminalPaletteColor>,
+    default_foreground: Option<XColor>,
+    default_background: Option<XColor>,
+    termcap: BTreeMap<String, String>,
+}
+
+impl Default for Responder {
+    fn default() -> Self {
+        Self {
+            primary_device_attributes: PrimaryDeviceAttributesResponse {
+                conformance_level: ConformanceLevel::VT220,
+                capabilities: Capabilities::new(vec![
+                    TerminalCapability::Color,
+                ]),
+            },
+            secondary_device_attributes: SecondaryDeviceAttributesResponse {
+                terminal_type: 1,
+                version: 0,
+                extra: Some(0),
+            },
+            unit_id: HexString::new(vec![0; 4]),
+            version: None,
+            size: None,
+            cell_size: None,
+            palette: BTreeMap::new(),
+            default_foreground: None,
+            default_background: None,
+            termcap: BTreeMap::new(),
+        }
+    }
+}
+
+impl Responder {
+    /// Create a responder identifying as a color VT220.
+    ///
+    /// It answers device attributes, status and mode state requests, and
+    /// cursor position requests when the hook knows the position.  Other
+    /// queries are left unanswered until the profile is configured.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer `DA1` (and `DECID`) with `response`.
+    #[must_use]
+    pub fn with_primary_device_attributes(
+        mut self,
+        response: PrimaryDeviceAttributesResponse,
+    ) -> Self {
+        self.primary_device_attributes = response;
+        self
+    }
+
+    /// Answer `DA2` with `response`.
+    #[must_use]
+    pub fn with_secondary_device_attributes(
+        mut self,
+        response: SecondaryDeviceAttributesResponse,
+    ) -> Self {
+        self.secondary_device_attributes = response;
+        self
+    }
+
+    /// Answer `DA3` with `unit_id`.
+    #[must_use]
+    pub fn with_unit_id(mut self, unit_id: impl Into<HexString>) -> Self {
+        self.unit_id = unit_id.into();
+        self
+    }
+
+    /// Answer `XTVERSION` with `version`, e.g. `myterm(1.0)`.
+    #[must_use]
+    pub fn with_version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Report a text area of `rows` by `cols` cells, unless the hook
+    /// knows the size.
+    #[must_use]
+    pub fn with_size(mut self, rows: u16, cols: u16) -> Self {
+        self.size = Some(SizeReport { rows, cols });
+        self
+    }
+
+    /// Report a cell size of `height` by `width` pixels.
+    #[must_use]
+    pub fn with_cell_size(mut self, height: u16, width: u16) -> Self {
+        self.cell_size = Some(CellSizePixelsReport { height, width });
+        self
+    }
+
+    /// Answer queries for palette colors.
+    #[must_use]
+    pub fn with_palette(
+        mut self,
+        colors: impl IntoIterator<Item = (i16, XColor)>,
+    ) -> Self {
+        self.palette.extend(
+            colors
+                .into_iter()
+                .map(|(index, color)| (index, TerminalPaletteColor(color))),
+        );
+        self
+    }
+
+    /// Answer default foreground color queries (`OSC 10`) with `color`.
+    #[must_use]
+    pub const fn with_default_foreground(mut self, color: XColor) -> Self {
+        self.default_foreground = Some(color);
+        self
+    }
+
+    /// Answer default background color queries (`OSC 11`) with `color`.
+    #[must_use]
+    pub const fn with_default_background(mut self, color: XColor) -> Self {
+        self.default_background = Some(color);
+        self
+    }
+
+    /// Answer termcap queries (`XTGETTCAP`) for the given capabilities,
+    /// e.g. `("colors", "256")`.
+    #[must_use]
+    pub fn with_termcap(
+        mut self,
+        capabilities: impl IntoIterator<
+            Item = (impl Into<String>, impl Into<String>),
+        >,
+    ) -> Self {
+        self.termcap.extend(
+            capabilities
+                .into_iter()
+                .map(|(name, value)| (name.into(), value.into())),
+        );
+        self
+    }
+
+    /// Write the response to `event` into `sink`.
+    ///
+    /// Return `true` if `event` is a query that was answered.  Setting a
+    /// palette color does not change the profile.
+    ///
+    /// # Errors
+    ///
+    /// Return an error if writing fails.
+    pub fn respond<W: io::Write + ?Sized>(
+        &self,
+        event: &dyn vtansi::AnsiEvent<'_>,
+        hook: &impl ResponderHook,
+        sink: &mut W,
+    ) -> io::Result<bool> {
+        if event.is::<RequestPrimaryDeviceAttributes>()
+            || event.is::<RequestTerminalID>()
+        {
+            self.primary_device_attributes.encode_ansi_into(sink)?;
+        } else if event.is::<RequestSecondaryDeviceAttributes>() {
+            self.secondary_device_attributes.encode_ansi_into(sink)?;
+        } else if event.is::<RequestTerminalUnitId>() {
+            TertiaryDeviceAttributesResponse {
+                data: self.unit_id.clone(),
+            }
+            .encode_ansi_into(sink)?;
+        } else if event.is::<RequestOperatingStatus>() {
+            OperatingStatusReport.encode_ansi_into(sink)?;
+        } else if event.is::<RequestCursorPosition>() {
+            let Some(report) = hook.cursor_position() else {
+                return Ok(false);
+            };
+            report.encode_ansi_into(sink)?;
+        } else if event.is::<RequestTerminalNameAndVersion>() {
+            let Some(version) = &self.version else {
+                return Ok(false);
+            };
+            TerminalNameAndVersionResponse { version }
+                .encode_ansi_into(sink)?;
+        } else if event.is::<ReportSize>() {
+            let Some(report) = hook.size().or(self.size) else {
+                return Ok(false);
+            };
+            report.encode_ansi_into(sink)?;
+        } else if event.is::<ReportCellSizePixels>() {
+            let Some(report) = self.cell_size else {
+                return Ok(false);
+            };
+            report.encode_ansi_into(sink)?;
+        } else if let Some(request) =
+            event.downcast_ref::<RequestOrSetTerminalPaletteColors>()
+        {
+            return self.respond_palette(request, sink);
+        } else if let Some(request) =
+            event.downcast_ref::<RequestOrSetSpecialTextForegroundColor>()
+        {
+            let Some(color) =
+                self.default_foreground.filter(|_| request.is_query())
+            else {
+                return Ok(false);
+            };
+            SpecialTextForegroundColorResponse::new(color)
+                .encode_ansi_into(sink)?;
+        } else if let Some(request) =
+            event.downcast_ref::<RequestOrSetSpecialTextBackgroundColor>()
+        {
+            let Some(color) =
+                self.default_background.filter(|_| request.is_query())
+            else {
+                return Ok(false);
+            };
+            SpecialTextBackgroundColorResponse::new(color)
+                .encode_ansi_into(sink)?;
+        } else if let Some(request) = event.downcast_ref::<RequestTermcap>() {
+            self.termcap_response(request).encode_ansi_into(sink)?;
+        } else if let Some(report) = Self::mode_report(event, hook) {
+            report.encode_ansi_into(sink)?;
+        } else {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    fn respond_palette<W: io::Write + ?Sized>(
+        &self,
+        request: &RequestOrSetTerminalPaletteColors,
+        sink: &mut W,
+    ) -> io::Result<bool> {
+        let mut answered = false;
+        for action in request.iter().filter(|action| action.is_query()) {
+            if let Some(color) = self.palette.get(&action.index) {
+                TerminalPaletteColorResponse::new(action.index, color)
+                    .encode_ansi_into(sink)?;
+                answered = true;
+            }
+        }
+        Ok(answered)
+    }
+
+    /// Answer every capability, or fail as a whole at the first unknown
+    /// one, as xterm does.
+    fn termcap_response(
+        &self,
+        request: &RequestTermcap,
+    ) -> TermcapQueryResponse {
+        request
+            .queries
+            .iter()
+            .map(|key| {
+                let value = self.termcap.get(key.as_str()?)?;
+                Some(TermcapQueryResult {
+                    key: key.clone(),
+                    value: Some(HexString::from_string(value)),
+                })
+            })
+            .collect::<Option<Vec<_>>>()
+            .map_or_else(
+                TermcapQueryResponse::invalid,
+                TermcapQueryResponse::with_results,
+            )
+    }
+
+    /// Answer a mode state request from the hook, or as a freshly reset
+    /// terminal would.
+    fn mode_report(
+        event: &dyn vtansi::AnsiEvent<'_>,
+        hook: &impl ResponderHook,
+    ) -> Option<ModeReport> {
+        hook.mode_report(event).or_else(|| {
+            let (private, mode) = ModeControl::request(event)?;
+            Some(ModeReport {
+                private,
+                mode,
+                state: default_mode_state(private, mode),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::TerminalOutputParser;
+
+    fn respond(
+        responder: &Responder,
+        hook: &impl ResponderHook,
+        output: &[u8],
+    ) -> Vec<u8> {
+        let mut replies = Vec::new();
+        let mut parser = TerminalOutputParser::new();
+        parser.feed_with(output, &mut |event| {
+            responder.respond(event, hook, &mut replies).unwrap();
+        });
+        replies
+    }
+
+    #[test]
+    fn test_device_attributes() {
+        let responder = Responder::new().with_unit_id("~VIO");
+        assert_eq!(
+            respond(&responder, &(), b"\x1b[c\x1b[0c\x1bZ"),
+            b"\x1b[?62;22c\x1b[?62;22c\x1b[?62;22c"
+        );
+        assert_eq!(
+            respond(&responder, &(), b"\x1b[>c\x1b[=0c\x1b[5n"),
+            b"\x1b[>1;0;0c\x1bP!|7E56494F\x1b\\\x1b[0n"
+        );
+    }
+
+    #[test]
+    fn test_version_and_sizes() {
+        let responder = Responder::new();
+        assert!(
+            respond(&responder, &(), b"\x1b[>0q\x1b[18t\x1b[16t").is_empty()
+        );
+
+        let responder = responder
+            .with_version("vtio(1.0)")
+            .with_size(24, 80)
+            .with_cell_size(16, 8);
+        assert_eq!(
+            respond(&responder, &(), b"\x1b[>0q\x1b[18t\x1b[16t"),
+            b"\x1bP>|vtio(1.0)\x1b\\\x1b[8;24;80t\x1b[6;16;8t"
+        );
+        let screen = Screen::new(10, 40);
+        assert_eq!(respond(&responder, &screen, b"\x1b[18t"), b"\x1b[8;10;40t");
+    }
+
+    #[test]
+    fn test_cursor_position() {
+        let responder = Responder::new();
+        assert!(respond(&responder, &(), b"\x1b[6n").is_empty());
+
+        let mut screen = Screen::new(10, 40);
+        let mut parser = TerminalOutputParser::new();
+        parser.feed_with(b"\x1b[3;5H", &mut |event| screen.apply(event));
+        assert_eq!(respond(&responder, &screen, b"\x1b[6n"), b"\x1b[3;5R");
+
+        parser.feed_with(b"\x1b[2;9r\x1b[?6h\x1b[4;2H", &mut |event| {
+            screen.apply(event);
+        });
+        assert_eq!(respond(&responder, &screen, b"\x1b[6n"), b"\x1b[4;2R");
+    }
+
+    #[test]
+    fn test_mode_reports() {
+        let responder = Responder::new();
+        assert_eq!(
+            respond(&responder, &(), b"\x1b[?2004$p\x1b[?25$p\x1b[?9999$p"),
+            b"\x1b[?2004;2$y\x1b[?25;1$y\x1b[?9999;0$y"
+        );
+
+        let mut screen = Screen::new(10, 40);
+        let mut tracker = TerminalStateTracker::new();
+        let mut parser = TerminalOutputParser::new();
+        parser.feed_with(b"\x1b[?2004h\x1b[?25l\x1b[4h", &mut |event| {
+            screen.apply(event);
+            tracker.apply(event);
+        });
+        assert_eq!(
+            respond(
+                &responder,
+                &(&screen, &tracker),
+                b"\x1b[?2004$p\x1b[?25$p\x1b[4$p"
+            ),
+            b"\x1b[?2004;1$y\x1b[?25;2$y\x1b[4;1$y"
+        );
+    }
+
+    #[test]
+    fn test_palette() {
+        let red = XColor::try_from_bytes(b"rgb:ffff/0000/0000").unwrap();
+        let black = XColor::try_from_bytes(b"rgb:0000/0000/0000").unwrap();
+        let responder = Responder::new()
+            .with_palette([(1, red)])
+            .with_default_background(black);
+        let mut expected = Vec::new();
+        TerminalPaletteColorResponse::new(1, &TerminalPaletteColor(red))
+            .encode_ansi_into(&mut expected)
+            .unwrap();
+        SpecialTextBackgroundColorResponse::new(black)
+            .encode_ansi_into(&mut expected)
+            .unwrap();
+        assert_eq!(
+            respond(
+                &responder,
+                &(),
+                b"\x1b]4;1;?;2;?\x07\x1b]10;?\x07\x1b]11;?\x07\x1b]4;3;?\x07"
+            ),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_termcap() {
+        let responder =
+            Responder::new().with_termcap([("colors", "256"), ("TN", "vtio")]);
+        assert_eq!(
+            respond(&responder, &(), b"\x1bP+q636f6c6f7273;544e\x1b\\"),
+            b"\x1bP1+r636F6C6F7273=323536;544E=7674696F\x1b\\"
+        );
+        assert_eq!(
+            respond(&responder, &(), b"\x1bP+q636f6c6f7273;5858\x1b\\"),
+            b"\x1bP0+r\x1b\\"
+        );
+    }
+
+    #[test]
+    fn test_ignores_other_events() {
+        let responder = Responder::new();
+        assert!(
+            respond(&responder, &(), b"hello\x1b[1m\x1b[?2004h").is_empty()
+        );
+    }
+}
@@ -0,0 +1,192 @@
+//! Grid cells and their rendition.
+
+use smallvec::SmallVec;
+
+use crate::event::sgr::{
+    Blink, Intensity, SgrAttribute, SgrColor, UnderlineStyle,
+};
+
+/// Graphic rendition of a cell, as set by `SGR`.
+///
+/// Colors are `None` when the terminal default is in effect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct CellAttributes {
+    /// Text intensity.
+    pub intensity: Intensity,
+    /// Italic text.
+    pub italic: bool,
+    /// Underline style.
+    pub underline: UnderlineStyle,
+    /// Blink mode.
+    pub blink: Blink,
+    /// Swapped foreground and background.
+    pub inverse: bool,
+    /// Hidden text.
+    pub conceal: bool,
+    /// Crossed-out text.
+    pub strike: bool,
+    /// Overlined text.
+    pub overline: bool,
+    /// Foreground color.
+    pub foreground: Option<SgrColor>,
+    /// Background color.
+    pub background: Option<SgrColor>,
+    /// Underline color.
+    pub underline_color: Option<SgrColor>,
+}
+
+impl CellAttributes {
+    /// Apply an `SGR` attribute.
+    pub fn apply(&mut self, attr: &SgrAttribute) {
+        match *attr {
+            SgrAttribute::Reset => *self = Self::default(),
+            SgrAttribute::Intensity(intensity) => self.intensity = intensity,
+            SgrAttribute::Italic(on) => self.italic = on,
+            SgrAttribute::Underline(style) => self.underline = style,
+            SgrAttribute::Blink(blink) => self.blink = blink,
+            SgrAttribute::Inverse(on) => self.inverse = on,
+            SgrAttribute::Conceal(on) => self.conceal = on,
+            SgrAttribute::Strike(on) => self.strike = on,
+            SgrAttribute::Overline(on) => self.overline = on,
+            SgrAttribute::Foreground(color) => self.foreground = Some(color),
+            SgrAttribute::DefaultForeground => self.foreground = None,
+            SgrAttribute::Background(color) => self.background = Some(color),
+            SgrAttribute::DefaultBackground => self.background = None,
+            SgrAttribute::UnderlineColor(color) => {
+                self.underline_color = Some(color);
+            }
+            SgrAttribute::DefaultUnderlineColor => self.underline_color = None,
+            SgrAttribute::Font(_) | SgrAttribute::Unrecognized(_) => {}
+        }
+    }
+
+    /// The rendition of cells erased while these attributes are active.
+    ///
+    /// Erased cells keep the background color only.
+    #[must_use]
+    pub fn erased(&self) -> Self {
+        Self {
+            background: self.background,
+            ..Self::default()
+        }
+    }
+}
+
+/// A single character cell.
+///
+/// A wide character occupies two cells: the first holds the character
+/// and has a width of 2, the second is a spacer with a width of 0.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cell {
+    ch: char,
+    combining: SmallVec<[char; 2]>,
+    width: u8,
+    attrs: CellAttributes,
+    protected: bool,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self::blank(CellAttributes::default())
+    }
+}
+
+impl Cell {
+    /// A blank cell with the given rendition.
+    #[must_use]
+    pub fn blank(attrs: CellAttributes) -> Self {
+        Self {
+            ch: ' ',
+            combining: SmallVec::new(),
+            width: 1,
+            attrs,
+            protected: false,
+        }
+    }
+
+    pub(crate) fn new(ch: char, width: u8, attrs: CellAttributes) -> Self {
+        Self {
+            ch,
+            combining: SmallVec::new(),
+            width,
+            attrs,
+            protected: false,
+        }
+    }
+
+    /// Mark the cell as protected from selective erase.
+    pub(crate) const fn with_protected(mut self, protected: bool) -> Self {
+        self.protected = protected;
+        self
+    }
+
+    pub(crate) fn spacer(attrs: CellAttributes) -> Self {
+        Self {
+            ch: ' ',
+            combining: SmallVec::new(),
+            width: 0,
+            attrs,
+            protected: false,
+        }
+    }
+
+    /// The base character.
+    #[must_use]
+    pub const fn ch(&self) -> char {
+        self.ch
+    }
+
+    /// Combining characters following the base character.
+    #[must_use]
+    pub fn combining(&self) -> &[char] {
+        &self.combining
+    }
+
+    /// Number of columns the character occupies: 1, 2 for a wide
+    /// character, or 0 for the spacer following a wide character.
+    #[must_use]
+    pub const fn width(&self) -> u8 {
+        self.width
+    }
+
+    /// Whether this is the spacer following a wide character.
+    #[must_use]
+    pub const fn is_spacer(&self) -> bool {
+        self.width == 0
+    }
+
+    /// Whether the cell is an unprotected space without any rendition.
+    #[must_use]
+    pub fn is_blank(&self) -> bool {
+        self.ch == ' '
+            && self.width == 1
+            && self.combining.is_empty()
+            && self.attrs == CellAttributes::default()
+            && !self.protected
+    }
+
+    /// Whether the cell is protected from selective erase (`DECSCA`).
+    #[must_use]
+    pub const fn is_protected(&self) -> bool {
+        self.protected
+    }
+
+    /// The cell rendition.
+    #[must_use]
+    pub const fn attrs(&self) -> &CellAttributes {
+        &self.attrs
+    }
+
+    pub(crate) fn push_combining(&mut self, ch: char) {
+        self.combining.push(ch);
+    }
+
+    /// Append the cell's text to `out`; spacers contribute nothing.
+    pub fn push_text(&self, out: &mut String) {
+        if !self.is_spacer() {
+            out.push(self.ch);
+            out.extend(self.combining.iter());
+        }
+    }
+}
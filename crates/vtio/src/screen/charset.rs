@@ -0,0 +1,108 @@
+//! ISO 2022 character set state.
+
+use crate::event::charset::Charset94Code;
+
+/// Designated G0-G3 character sets and the shift state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct CharsetState {
+    designations: [Charset94Code; 4],
+    /// The set invoked into GL by `SI`, `SO`, `LS2` or `LS3`.
+    gl: usize,
+    /// The set invoked by `SS2` or `SS3` for the next character only.
+    single_shift: Option<usize>,
+}
+
+impl Default for CharsetState {
+    fn default() -> Self {
+        Self {
+            designations: [Charset94Code::Ascii; 4],
+            gl: 0,
+            single_shift: None,
+        }
+    }
+}
+
+impl CharsetState {
+    pub(crate) fn designate(&mut self, set: usize, charset: Charset94Code) {
+        self.designations[set] = charset;
+    }
+
+    pub(crate) fn invoke(&mut self, set: usize) {
+        self.gl = set;
+    }
+
+    pub(crate) fn single_shift(&mut self, set: usize) {
+        self.single_shift = Some(set);
+    }
+
+    /// Translate a printed character through the active set.
+    pub(crate) fn map(&mut self, ch: char) -> char {
+        let set = self.single_shift.take().unwrap_or(self.gl);
+        translate(self.designations[set], ch)
+    }
+}
+
+/// Translate `ch` through a 94-character set.
+///
+/// Only the sets in common use are translated; the national
+/// replacement sets other than British are treated as ASCII.
+fn translate(charset: Charset94Code, ch: char) -> char {
+    match charset {
+        Charset94Code::DecSpecialGraphic => dec_special_graphic(ch),
+        Charset94Code::British if ch == '#' => '£',
+        _ => ch,
+    }
+}
+
+/// The DEC Special Character and Line Drawing Set.
+fn dec_special_graphic(ch: char) -> char {
+    match ch {
+        '`' => '◆',
+        'a' => '▒',
+        'b' => '␉',
+        'c' => '␌',
+        'd' => '␍',
+        'e' => '␊',
+        'f' => '°',
+        'g' => '±',
+        'h' => '␤',
+        'i' => '␋',
+        'j' => '┘',
+        'k' => '┐',
+        'l' => '┌',
+        'm' => '└',
+        'n' => '┼',
+        'o' => '⎺',
+        'p' => '⎻',
+        'q' => '─',
+        'r' => '⎼',
+        's' => '⎽',
+        't' => '├',
+        'u' => '┤',
+        'v' => '┴',
+        'w' => '┬',
+        'x' => '│',
+        'y' => '≤',
+        'z' => '≥',
+        '{' => 'π',
+        '|' => '≠',
+        '}' => '£',
+        '~' => '·',
+        '_' => ' ',
+        _ => ch,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_shift_applies_once() {
+        let mut state = CharsetState::default();
+        state.designate(2, Charset94Code::DecSpecialGraphic);
+        state.single_shift(2);
+        assert_eq!(state.map('q'), '─');
+        assert_eq!(state.map('q'), 'q');
+    }
+}
@@ -0,0 +1,425 @@
+//! Rows and grids of cells.
+
+use std::ops::{Bound, RangeBounds, RangeInclusive};
+
+use super::cell::{Cell, CellAttributes};
+
+/// A row of cells.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Row {
+    cells: Vec<Cell>,
+    wrapped: bool,
+}
+
+impl Row {
+    /// A blank row of `cols` cells.
+    #[must_use]
+    pub fn new(cols: usize, attrs: CellAttributes) -> Self {
+        Self {
+            cells: vec![Cell::blank(attrs); cols],
+            wrapped: false,
+        }
+    }
+
+    /// The cells of the row.
+    #[must_use]
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    /// Whether the text of this row continues on the next row because it
+    /// was soft-wrapped at the right margin.
+    #[must_use]
+    pub const fn is_wrapped(&self) -> bool {
+        self.wrapped
+    }
+
+    /// The text of the row, without trailing blanks.
+    #[must_use]
+    pub fn text(&self) -> String {
+        let mut text = String::with_capacity(self.cells.len());
+        for cell in &self.cells {
+            cell.push_text(&mut text);
+        }
+        text.truncate(text.trim_end_matches(' ').len());
+        text
+    }
+
+    /// A row made of `cells`.
+    pub(crate) fn from_cells(cells: Vec<Cell>, wrapped: bool) -> Self {
+        Self { cells, wrapped }
+    }
+
+    pub(crate) fn into_cells(self) -> Vec<Cell> {
+        self.cells
+    }
+
+    /// Whether the row holds nothing but blank cells and does not wrap.
+    pub(crate) fn is_blank(&self) -> bool {
+        !self.wrapped && self.cells.iter().all(Cell::is_blank)
+    }
+
+    /// Drop the blank cells at the end of the row.
+    pub(crate) fn trim_blanks(&mut self) {
+        let len = self
+            .cells
+            .iter()
+            .rposition(|cell| !cell.is_blank())
+            .map_or(0, |i| i + 1);
+        self.cells.truncate(len);
+    }
+
+    pub(crate) fn set_wrapped(&mut self, wrapped: bool) {
+        self.wrapped = wrapped;
+    }
+
+    pub(crate) fn cell_mut(&mut self, col: usize) -> &mut Cell {
+        &mut self.cells[col]
+    }
+
+    /// Resize the row to `cols` cells, padding with blanks.
+    pub(crate) fn resize(&mut self, cols: usize, attrs: CellAttributes) {
+        self.cells.resize(cols, Cell::blank(attrs));
+        self.fix_wide_at_end(attrs);
+    }
+
+    /// Write `cell` at `col`, along with its spacer if it is wide.
+    ///
+    /// Wide characters partially overwritten are erased.
+    pub(crate) fn write(&mut self, col: usize, cell: Cell) {
+        let wide = cell.width() == 2;
+        let attrs = *cell.attrs();
+        self.split_wide_at(col);
+        if wide && col + 1 < self.cells.len() {
+            self.split_wide_at(col + 1);
+            self.cells[col + 1] =
+                Cell::spacer(attrs).with_protected(cell.is_protected());
+        }
+        let end = col + 1 + usize::from(wide);
+        if end < self.cells.len() && self.cells[end].is_spacer() {
+            self.cells[end] = Cell::blank(self.cells[end].attrs().erased());
+        }
+        self.cells[col] = cell;
+    }
+
+    /// Erase the cells in `range`, clamped to the row.
+    pub(crate) fn erase(
+        &mut self,
+        range: impl RangeBounds<usize>,
+        attrs: CellAttributes,
+    ) {
+        let (start, end) = self.clamp(range);
+        if start >= end {
+            return;
+        }
+        self.split_wide_at(start);
+        self.split_wide_at(end);
+        for cell in &mut self.cells[start..end] {
+            *cell = Cell::blank(attrs);
+        }
+    }
+
+    /// Erase the cells in `range` that are not protected (`DECSCA`),
+    /// clamped to the row.
+    pub(crate) fn selective_erase(
+        &mut self,
+        range: impl RangeBounds<usize>,
+        attrs: CellAttributes,
+    ) {
+        let (start, end) = self.clamp(range);
+        if start >= end {
+            return;
+        }
+        for col in [start, end] {
+            if col < self.cells.len() && !self.cells[col].is_protected() {
+                self.split_wide_at(col);
+            }
+        }
+        for cell in &mut self.cells[start..end] {
+            if !cell.is_protected() {
+                *cell = Cell::blank(attrs);
+            }
+        }
+    }
+
+    /// Insert `n` blank cells at `col`, shifting cells up to `end`
+    /// (exclusive) to the right.
+    pub(crate) fn insert(
+        &mut self,
+        col: usize,
+        n: usize,
+        end: usize,
+        attrs: CellAttributes,
+    ) {
+        let end = end.min(self.cells.len());
+        if col >= end {
+            return;
+        }
+        let n = n.min(end - col);
+        self.split_wide_at(col);
+        self.split_wide_at(end);
+        self.cells[col..end].rotate_right(n);
+        for cell in &mut self.cells[col..col + n] {
+            *cell = Cell::blank(attrs);
+        }
+        self.fix_wide_before(end, attrs);
+    }
+
+    /// Delete `n` cells at `col`, shifting cells up to `end` (exclusive)
+    /// to the left and filling with blanks.
+    pub(crate) fn delete(
+        &mut self,
+        col: usize,
+        n: usize,
+        end: usize,
+        attrs: CellAttributes,
+    ) {
+        let end = end.min(self.cells.len());
+        if col >= end {
+            return;
+        }
+        let n = n.min(end - col);
+        self.split_wide_at(col);
+        self.split_wide_at(col + n);
+        self.split_wide_at(end);
+        self.cells[col..end].rotate_left(n);
+        for cell in &mut self.cells[end - n..end] {
+            *cell = Cell::blank(attrs);
+        }
+    }
+
+    /// Fill the row with copies of `cell`.
+    pub(crate) fn fill(&mut self, cell: &Cell) {
+        for c in &mut self.cells {
+            c.clone_from(cell);
+        }
+        self.wrapped = false;
+    }
+
+    /// The bounds of `range`, clamped to the row.
+    fn clamp(&self, range: impl RangeBounds<usize>) -> (usize, usize) {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end.saturating_add(1),
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.cells.len(),
+        }
+        .min(self.cells.len());
+        (start, end)
+    }
+
+    /// If `col` is the spacer of a wide character, erase both halves.
+    fn split_wide_at(&mut self, col: usize) {
+        if col > 0 && col < self.cells.len() && self.cells[col].is_spacer() {
+            let attrs = self.cells[col - 1].attrs().erased();
+            self.cells[col - 1] = Cell::blank(attrs);
+            self.cells[col] = Cell::blank(attrs);
+        }
+    }
+
+    /// Erase a wide character whose spacer was shifted past `end`.
+    fn fix_wide_before(&mut self, end: usize, attrs: CellAttributes) {
+        if end > 0 && self.cells[end - 1].width() == 2 {
+            self.cells[end - 1] = Cell::blank(attrs);
+        }
+    }
+
+    fn fix_wide_at_end(&mut self, attrs: CellAttributes) {
+        let len = self.cells.len();
+        self.fix_wide_before(len, attrs);
+    }
+}
+
+/// A grid of rows.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid {
+    rows: Vec<Row>,
+    cols: usize,
+}
+
+impl Grid {
+    /// A blank grid of `rows` rows by `cols` columns.
+    #[must_use]
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Self {
+            rows: vec![Row::new(cols, CellAttributes::default()); rows],
+            cols,
+        }
+    }
+
+    /// A grid of `rows`, padded with blank rows up to `len` rows.
+    pub(crate) fn from_rows(
+        mut rows: Vec<Row>,
+        len: usize,
+        cols: usize,
+    ) -> Self {
+        rows.resize(len, Row::new(cols, CellAttributes::default()));
+        Self { rows, cols }
+    }
+
+    pub(crate) fn into_rows(self) -> Vec<Row> {
+        self.rows
+    }
+
+    /// Number of rows.
+    #[must_use]
+    pub fn rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Number of columns.
+    #[must_use]
+    pub const fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// The row at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    #[must_use]
+    pub fn row(&self, index: usize) -> &Row {
+        &self.rows[index]
+    }
+
+    /// Iterate over the rows, from top to bottom.
+    pub fn iter(&self) -> std::slice::Iter<'_, Row> {
+        self.rows.iter()
+    }
+
+    pub(crate) fn row_mut(&mut self, index: usize) -> &mut Row {
+        &mut self.rows[index]
+    }
+
+    /// Scroll the rows in `region` up by `n`, filling with blank rows.
+    ///
+    /// Returns the rows scrolled out of the region, top-most first.
+    pub(crate) fn scroll_up(
+        &mut self,
+        region: RangeInclusive<usize>,
+        n: usize,
+        attrs: CellAttributes,
+    ) -> Vec<Row> {
+        let (top, end) = (*region.start(), *region.end() + 1);
+        let n = n.min(end - top);
+        let blank = Row::new(self.cols, attrs);
+        self.rows
+            .splice(end..end, std::iter::repeat_n(blank, n))
+            .for_each(drop);
+        self.rows.drain(top..top + n).collect()
+    }
+
+    /// Scroll the rows in `region` down by `n`, filling with blank rows.
+    pub(crate) fn scroll_down(
+        &mut self,
+        region: RangeInclusive<usize>,
+        n: usize,
+        attrs: CellAttributes,
+    ) {
+        let (top, end) = (*region.start(), *region.end() + 1);
+        let n = n.min(end - top);
+        self.rows.drain(end - n..end);
+        let blank = Row::new(self.cols, attrs);
+        self.rows
+            .splice(top..top, std::iter::repeat_n(blank, n))
+            .for_each(drop);
+    }
+
+    /// Erase every cell.
+    pub(crate) fn clear(&mut self, attrs: CellAttributes) {
+        for row in &mut self.rows {
+            *row = Row::new(self.cols, attrs);
+        }
+    }
+
+    /// Resize the grid, truncating or padding rows at the bottom and
+    /// cells at the right.
+    pub(crate) fn resize(&mut self, rows: usize, cols: usize) {
+        let attrs = CellAttributes::default();
+        self.cols = cols;
+        self.rows.resize(rows, Row::new(cols, attrs));
+        for row in &mut self.rows {
+            row.resize(cols, attrs);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Grid {
+    type Item = &'a Row;
+    type IntoIter = std::slice::Iter<'a, Row>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(text: &str) -> Row {
+        let mut row = Row::new(text.chars().count(), CellAttributes::default());
+        for (col, ch) in text.chars().enumerate() {
+            row.write(col, Cell::new(ch, 1, CellAttributes::default()));
+        }
+        row
+    }
+
+    #[test]
+    fn test_insert_and_delete() {
+        let attrs = CellAttributes::default();
+        let mut r = row("abcdef");
+        r.insert(1, 2, 6, attrs);
+        assert_eq!(r.text(), "a  bcd");
+        r.delete(0, 3, 6, attrs);
+        assert_eq!(r.text(), "bcd");
+        r.delete(0, 10, 6, attrs);
+        assert_eq!(r.text(), "");
+    }
+
+    #[test]
+    fn test_overwriting_wide_character_half() {
+        let attrs = CellAttributes::default();
+        let mut r = row("abcd");
+        r.write(1, Cell::new('一', 2, attrs));
+        assert_eq!(r.text(), "a一d");
+        r.write(2, Cell::new('x', 1, attrs));
+        assert_eq!(r.text(), "a xd");
+    }
+
+    #[test]
+    fn test_selective_erase() {
+        let attrs = CellAttributes::default();
+        let mut r = row("abcdef");
+        r.write(1, Cell::new('B', 1, attrs).with_protected(true));
+        r.write(3, Cell::new('一', 2, attrs).with_protected(true));
+        r.selective_erase(.., attrs);
+        assert_eq!(r.text(), " B 一");
+
+        // A protected wide character at the edge of the range is kept
+        r.selective_erase(4.., attrs);
+        assert_eq!(r.text(), " B 一");
+        r.erase(4.., attrs);
+        assert_eq!(r.text(), " B");
+    }
+
+    #[test]
+    fn test_scroll_region() {
+        let mut grid = Grid::new(4, 3);
+        for (i, text) in ["a", "b", "c", "d"].into_iter().enumerate() {
+            *grid.row_mut(i) = row(text);
+        }
+        let out = grid.scroll_up(1..=2, 1, CellAttributes::default());
+        assert_eq!(out, vec![row("b")]);
+        let texts: Vec<_> = grid.iter().map(Row::text).collect();
+        assert_eq!(texts, ["a", "c", "", "d"]);
+        grid.scroll_down(0..=3, 2, CellAttributes::default());
+        let texts: Vec<_> = grid.iter().map(Row::text).collect();
+        assert_eq!(texts, ["", "", "a", "c"]);
+    }
+}
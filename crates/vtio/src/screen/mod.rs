@@ -0,0 +1,1124 @@
+//! Virtual screen model.
+//!
+//! [`Screen`] applies the events produced by
+//! [`TerminalOutputParser`](crate::parser::TerminalOutputParser) to a grid
+//! of cells, the way a VT100-compatible terminal does.  It tracks the
+//! cursor, the pending wrap state, the scrolling region, tab stops,
+//! character sets, the current graphic rendition and the primary and
+//! alternate screen buffers.
+//!
+//! Rows scrolled off the top of the primary buffer are kept in a
+//! [`Scrollback`], and soft-wrapped lines are rewrapped when the screen is
+//! resized.
+//!
+//! ```
+//! use vtio::parser::TerminalOutputParser;
+//! use vtio::screen::Screen;
+//!
+//! let mut screen = Screen::new(3, 10);
+//! let mut parser = TerminalOutputParser::new();
+//! parser.feed_with(b"hello\r\n\x1b[1mworld\x1b[H\x1b[2Pj", &mut |event| {
+//!     screen.apply(event);
+//! });
+//! assert_eq!(screen.contents(), "jlo\nworld\n");
+//! assert_eq!(screen.cursor_position(), (0, 1));
+//! ```
+//!
+//! Left and right margins (`DECSLRM`) and double-width lines are not
+//! modeled.
+
+mod cell;
+mod charset;
+mod grid;
+mod reflow;
+mod render;
+mod scrollback;
+
+pub use cell::{Cell, CellAttributes};
+pub use grid::{Grid, Row};
+pub use render::Renderer;
+pub use scrollback::{DEFAULT_SCROLLBACK_LINES, Scrollback};
+
+use better_any::TidExt;
+use bitflags::bitflags;
+use unicode_width::UnicodeWidthChar;
+use vt_push_parser::event::VTEvent;
+use vtansi::AnsiEvent;
+
+use std::ops::{Bound, Range, RangeBounds};
+
+use self::charset::CharsetState;
+use self::reflow::Position;
+use crate::event::UnrecognizedOutputEvent;
+use crate::event::charset::{
+    DesignateG0, DesignateG1, DesignateG2, DesignateG3, LockingShift2,
+    LockingShift3, ShiftIn, ShiftOut, SingleShift2, SingleShift3,
+};
+use crate::event::cursor::{
+    Backspace, CarriageReturn, CursorDown, CursorHorizontalAbsolute,
+    CursorHorizontalBackwardTab, CursorHorizontalForwardTab,
+    CursorHorizontalRelative, CursorLeft, CursorNextLine, CursorPreviousLine,
+    CursorRight, CursorUp, CursorVerticalAbsolute, CursorVerticalRelative,
+    DisableCursorVisibility, DisableRelativeCursorOriginMode,
+    EnableCursorVisibility, EnableRelativeCursorOriginMode, FormFeed,
+    HorizontalTab, HorizontalTabSet, Index, LineFeed, NextLine, RestoreCursor,
+    ReverseIndex, SaveCursor, SetCursorPosition, TabClearAll,
+    TabClearCurrentColumn, VerticalTab,
+};
+use crate::event::iterm::ClearScrollback;
+use crate::event::screen::{
+    CharacterProtection, DeleteCharacter, DeleteLine, EraseCharacter,
+    EraseDisplayAbove, EraseDisplayBelow, EraseDisplayComplete,
+    EraseDisplayScrollback, EraseLineComplete, EraseLineLeft, EraseLineRight,
+    FillScreenWithE, InsertCharacter, InsertLine,
+    SelectCharacterProtectionAttribute, SelectiveEraseDisplayAbove,
+    SelectiveEraseDisplayBelow, SelectiveEraseDisplayComplete,
+    SelectiveEraseLineComplete, SelectiveEraseLineLeft,
+    SelectiveEraseLineRight,
+};
+use crate::event::scroll::{ScrollDown, ScrollUp, SetTopAndBottomMargins};
+use crate::event::sgr::SelectGraphicRendition;
+use crate::event::terminal::{
+    DisableAlternateScreenBasicMode, DisableAlternateScreenClearOnExitMode,
+    DisableAlternateScreenMode, DisableHundredThirtyTwoColumnMode,
+    DisableInsertMode, DisableLineWraparoundMode, DisableLinefeedMode,
+    EnableAlternateScreenBasicMode, EnableAlternateScreenClearOnExitMode,
+    EnableAlternateScreenMode, EnableHundredThirtyTwoColumnMode,
+    EnableInsertMode, EnableLineWraparoundMode, EnableLinefeedMode, FullReset,
+};
+use crate::event::text::PlainText;
+
+/// Tab stops are initially set every 8 columns.
+const TAB_WIDTH: usize = 8;
+
+/// Cursor state, as saved and restored by `DECSC` and `DECRC`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Cursor {
+    row: usize,
+    col: usize,
+    /// Set after printing in the last column; the next printed character
+    /// wraps to the next line first.
+    pending_wrap: bool,
+    attrs: CellAttributes,
+    /// Printed characters are protected from selective erase (`DECSCA`).
+    protected: bool,
+    charsets: CharsetState,
+}
+
+/// How the alternate buffer was entered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AlternateScreen {
+    /// Modes 47 and 1047, which share the cursor with the primary buffer.
+    Shared,
+    /// Mode 1049, which saves the primary cursor on entry.
+    SavedCursor,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct SavedCursor {
+    cursor: Cursor,
+    origin: bool,
+}
+
+bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Modes: u8 {
+        /// Insert mode (`IRM`).
+        const INSERT = 1 << 0;
+        /// Linefeed/new line mode (`LNM`).
+        const LINEFEED = 1 << 1;
+        /// Autowrap mode (`DECAWM`).
+        const AUTOWRAP = 1 << 2;
+        /// Origin mode (`DECOM`).
+        const ORIGIN = 1 << 3;
+        /// Cursor visibility (`DECTCEM`).
+        const CURSOR_VISIBLE = 1 << 4;
+    }
+}
+
+impl Default for Modes {
+    fn default() -> Self {
+        Self::AUTOWRAP | Self::CURSOR_VISIBLE
+    }
+}
+
+/// A virtual terminal screen.
+///
+/// Feed it the events of a
+/// [`TerminalOutputParser`](crate::parser::TerminalOutputParser) with
+/// [`Screen::apply`].  Events that do not affect the screen contents are
+/// ignored.
+#[derive(Debug, Clone)]
+pub struct Screen {
+    primary: Grid,
+    alternate: Grid,
+    /// Set while the alternate buffer is active.
+    alternate_mode: Option<AlternateScreen>,
+    cursor: Cursor,
+    /// Saved cursors of the primary and alternate buffers.
+    saved: [SavedCursor; 2],
+    /// Scrolling region, inclusive.
+    top: usize,
+    bottom: usize,
+    tab_stops: Vec<bool>,
+    modes: Modes,
+    scrollback: Scrollback,
+}
+
+impl Screen {
+    /// Create a blank screen of `rows` by `cols` cells.
+    ///
+    /// Both dimensions are at least 1.
+    #[must_use]
+    pub fn new(rows: u16, cols: u16) -> Self {
+        let rows = usize::from(rows.max(1));
+        let cols = usize::from(cols.max(1));
+        Self {
+            primary: Grid::new(rows, cols),
+            alternate: Grid::new(rows, cols),
+            alternate_mode: None,
+            cursor: Cursor::default(),
+            saved: [SavedCursor::default(); 2],
+            top: 0,
+            bottom: rows - 1,
+            tab_stops: default_tab_stops(cols),
+            modes: Modes::default(),
+            scrollback: Scrollback::default(),
+        }
+    }
+
+    /// Use `scrollback` to keep the rows scrolled off the top of the
+    /// primary buffer.
+    #[must_use]
+    pub fn with_scrollback(mut self, scrollback: Scrollback) -> Self {
+        self.scrollback = scrollback;
+        self
+    }
+
+    /// Number of rows.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn rows(&self) -> u16 {
+        self.primary.rows() as u16
+    }
+
+    /// Number of columns.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn cols(&self) -> u16 {
+        self.primary.cols() as u16
+    }
+
+    /// The active buffer.
+    #[must_use]
+    pub const fn grid(&self) -> &Grid {
+        if self.is_alternate_screen() {
+            &self.alternate
+        } else {
+            &self.primary
+        }
+    }
+
+    /// The primary buffer, whether or not it is active.
+    #[must_use]
+    pub const fn primary_grid(&self) -> &Grid {
+        &self.primary
+    }
+
+    /// Rows scrolled off the top of the primary buffer.
+    #[must_use]
+    pub const fn scrollback(&self) -> &Scrollback {
+        &self.scrollback
+    }
+
+    /// Remove every row from the scrollback.
+    pub fn clear_scrollback(&mut self) {
+        self.scrollback.clear();
+    }
+
+    /// Whether the alternate buffer is active.
+    #[must_use]
+    pub const fn is_alternate_screen(&self) -> bool {
+        self.alternate_mode.is_some()
+    }
+
+    /// The zero-based cursor position as `(row, col)`.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn cursor_position(&self) -> (u16, u16) {
+        (self.cursor.row as u16, self.cursor.col as u16)
+    }
+
+    /// Whether the cursor is visible (`DECTCEM`).
+    #[must_use]
+    pub const fn cursor_visible(&self) -> bool {
+        self.modes.contains(Modes::CURSOR_VISIBLE)
+    }
+
+    /// The rendition applied to printed characters.
+    #[must_use]
+    pub const fn attributes(&self) -> &CellAttributes {
+        &self.cursor.attrs
+    }
+
+    /// The zero-based, inclusive scrolling region as `(top, bottom)`.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn scroll_region(&self) -> (u16, u16) {
+        (self.top as u16, self.bottom as u16)
+    }
+
+    /// The state of a mode the screen models, or `None` for other modes.
+    pub(crate) fn mode(&self, private: bool, mode: u16) -> Option<bool> {
+        let flag = match (private, mode) {
+            (false, 4) => Modes::INSERT,
+            (false, 20) => Modes::LINEFEED,
+            (true, 6) => Modes::ORIGIN,
+            (true, 7) => Modes::AUTOWRAP,
+            (true, 25) => Modes::CURSOR_VISIBLE,
+            _ => return None,
+        };
+        Some(self.modes.contains(flag))
+    }
+
+    /// The text of the active buffer, one line per row, without trailing
+    /// blanks.
+    #[must_use]
+    pub fn contents(&self) -> String {
+        self.grid()
+            .iter()
+            .map(Row::text)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Resize both buffers.
+    ///
+    /// Lines of the primary buffer and the scrollback are rewrapped to the
+    /// new width.  The primary buffer keeps the cursor in view, moving
+    /// rows to or from the scrollback as needed.  The alternate buffer is
+    /// truncated or padded at the bottom and right.  The scrolling region
+    /// is reset and the saved cursors are clamped to the new size.
+    pub fn resize(&mut self, rows: u16, cols: u16) {
+        let rows = usize::from(rows.max(1));
+        let cols = usize::from(cols.max(1));
+        let primary_cursor = self.reflow_primary(rows, cols);
+        self.alternate.resize(rows, cols);
+        let old_cols = self.tab_stops.len();
+        self.tab_stops.resize(cols, false);
+        for col in old_cols..cols {
+            self.tab_stops[col] = col % TAB_WIDTH == 0;
+        }
+        self.top = 0;
+        self.bottom = rows - 1;
+        for cursor in std::iter::once(&mut self.cursor)
+            .chain(self.saved.iter_mut().map(|s| &mut s.cursor))
+        {
+            cursor.row = cursor.row.min(rows - 1);
+            cursor.col = cursor.col.min(cols - 1);
+            cursor.pending_wrap = false;
+        }
+        // With a shared cursor, the cursor is on the alternate buffer,
+        // which is not reflowed
+        let cursor = match self.alternate_mode {
+            None => &mut self.cursor,
+            Some(AlternateScreen::SavedCursor) => &mut self.saved[0].cursor,
+            Some(AlternateScreen::Shared) => return,
+        };
+        cursor.row = primary_cursor.row;
+        cursor.col = primary_cursor.col;
+        cursor.pending_wrap = primary_cursor.pending_wrap;
+    }
+
+    /// Rewrap the primary buffer and the scrollback to `cols` columns and
+    /// fit the primary buffer in `rows` rows.
+    ///
+    /// Returns the new position of the primary cursor.
+    fn reflow_primary(&mut self, rows: usize, cols: usize) -> Position {
+        // Mode 1049 saved the primary cursor; otherwise it is the live one
+        let cursor =
+            if self.alternate_mode == Some(AlternateScreen::SavedCursor) {
+                self.saved[0].cursor
+            } else {
+                self.cursor
+            };
+        let scrolled = self.scrollback.len();
+        let mut lines =
+            std::mem::replace(&mut self.primary, Grid::new(0, cols))
+                .into_rows();
+        // Blank rows below the cursor are padding, not lines
+        let used = lines
+            .iter()
+            .rposition(|row| !row.is_blank())
+            .map_or(0, |i| i + 1)
+            .max(cursor.row + 1);
+        lines.truncate(used);
+        let (mut lines, position) = reflow::reflow(
+            self.scrollback.take().into_iter().chain(lines),
+            cols,
+            Position {
+                row: scrolled + cursor.row,
+                col: cursor.col,
+                pending_wrap: cursor.pending_wrap,
+            },
+        );
+        let start = lines.len().saturating_sub(rows).min(position.row);
+        lines.truncate(start + rows);
+        let visible = lines.split_off(start);
+        for row in lines {
+            self.scrollback.push(row);
+        }
+        self.primary = Grid::from_rows(visible, rows, cols);
+        Position {
+            row: position.row - start,
+            ..position
+        }
+    }
+
+    /// Apply a parser event.
+    pub fn apply(&mut self, event: &dyn AnsiEvent<'_>) {
+        if let Some(text) = event.downcast_ref::<PlainText>() {
+            for ch in text.chars() {
+                self.input(ch);
+            }
+        } else if let Some(sgr) = event.downcast_ref::<SelectGraphicRendition>()
+        {
+            if sgr.attributes().is_empty() {
+                self.cursor.attrs = CellAttributes::default();
+            }
+            for attr in sgr.attributes() {
+                self.cursor.attrs.apply(attr);
+            }
+        } else if let Some(sca) =
+            event.downcast_ref::<SelectCharacterProtectionAttribute>()
+        {
+            self.cursor.protected =
+                sca.protection == CharacterProtection::Protected;
+        } else if let Some(VTEvent::Ss2(ss2)) =
+            event.downcast_ref::<UnrecognizedOutputEvent>().map(|e| e.0)
+        {
+            self.cursor.charsets.single_shift(2);
+            self.print(char::from(ss2.char));
+        } else if let Some(VTEvent::Ss3(ss3)) =
+            event.downcast_ref::<UnrecognizedOutputEvent>().map(|e| e.0)
+        {
+            self.cursor.charsets.single_shift(3);
+            self.print(char::from(ss3.char));
+        } else {
+            let _ = self.apply_control(event)
+                || self.apply_cursor(event)
+                || self.apply_edit(event)
+                || self.apply_mode(event)
+                || self.apply_charset(event);
+        }
+    }
+
+    fn apply_control(&mut self, event: &dyn AnsiEvent<'_>) -> bool {
+        if event.is::<Backspace>() {
+            self.input('\x08');
+        } else if event.is::<HorizontalTab>() {
+            self.input('\t');
+        } else if event.is::<LineFeed>()
+            || event.is::<VerticalTab>()
+            || event.is::<FormFeed>()
+        {
+            self.input('\n');
+        } else if event.is::<CarriageReturn>() {
+            self.input('\r');
+        } else if event.is::<Index>() {
+            self.index();
+        } else if event.is::<ReverseIndex>() {
+            self.reverse_index();
+        } else if event.is::<NextLine>() {
+            self.carriage_return();
+            self.index();
+        } else if event.is::<HorizontalTabSet>() {
+            self.tab_stops[self.cursor.col] = true;
+        } else if event.is::<TabClearCurrentColumn>() {
+            self.tab_stops[self.cursor.col] = false;
+        } else if event.is::<TabClearAll>() {
+            self.tab_stops.fill(false);
+        } else if event.is::<SaveCursor>() {
+            self.save_cursor();
+        } else if event.is::<RestoreCursor>() {
+            self.restore_cursor();
+        } else if event.is::<FullReset>() {
+            let scrollback = std::mem::take(&mut self.scrollback);
+            *self =
+                Self::new(self.rows(), self.cols()).with_scrollback(scrollback);
+        } else {
+            return false;
+        }
+        true
+    }
+
+    fn apply_cursor(&mut self, event: &dyn AnsiEvent<'_>) -> bool {
+        let row = self.cursor.row;
+        let col = self.cursor.col;
+        if let Some(e) = event.downcast_ref::<SetCursorPosition>() {
+            self.set_position(param(e.row) - 1, param(e.col) - 1);
+        } else if let Some(e) = event.downcast_ref::<CursorUp>() {
+            self.cursor_up(param(e.0));
+        } else if let Some(e) = event.downcast_ref::<CursorDown>() {
+            self.cursor_down(param(e.0));
+        } else if let Some(e) = event.downcast_ref::<CursorVerticalRelative>() {
+            self.cursor_down(param(e.0));
+        } else if let Some(e) = event.downcast_ref::<CursorRight>() {
+            self.move_to(row, col.saturating_add(param(e.0)));
+        } else if let Some(e) = event.downcast_ref::<CursorHorizontalRelative>()
+        {
+            self.move_to(row, col.saturating_add(param(e.0)));
+        } else if let Some(e) = event.downcast_ref::<CursorLeft>() {
+            self.move_to(row, col.saturating_sub(param(e.0)));
+        } else if let Some(e) = event.downcast_ref::<CursorNextLine>() {
+            self.cursor_down(param(e.0));
+            self.carriage_return();
+        } else if let Some(e) = event.downcast_ref::<CursorPreviousLine>() {
+            self.cursor_up(param(e.0));
+            self.carriage_return();
+        } else if let Some(e) = event.downcast_ref::<CursorHorizontalAbsolute>()
+        {
+            self.move_to(row, param(e.0) - 1);
+        } else if let Some(e) = event.downcast_ref::<CursorVerticalAbsolute>() {
+            self.set_position(param(e.0) - 1, col);
+        } else if let Some(e) =
+            event.downcast_ref::<CursorHorizontalForwardTab>()
+        {
+            for _ in 0..param(e.amount) {
+                self.tab_forward();
+            }
+        } else if let Some(e) =
+            event.downcast_ref::<CursorHorizontalBackwardTab>()
+        {
+            for _ in 0..param(e.0) {
+                self.tab_backward();
+            }
+        } else {
+            return false;
+        }
+        true
+    }
+
+    fn apply_edit(&mut self, event: &dyn AnsiEvent<'_>) -> bool {
+        let erased = self.cursor.attrs.erased();
+        let rows = self.grid().rows();
+        let cols = self.grid().cols();
+        let (row, col) = (self.cursor.row, self.cursor.col);
+        // Selective erases (`DECSED`, `DECSEL`) keep protected cells
+        let selective = event.is::<SelectiveEraseDisplayBelow>()
+            || event.is::<SelectiveEraseDisplayAbove>()
+            || event.is::<SelectiveEraseDisplayComplete>()
+            || event.is::<SelectiveEraseLineRight>()
+            || event.is::<SelectiveEraseLineLeft>()
+            || event.is::<SelectiveEraseLineComplete>();
+        if event.is::<EraseDisplayBelow>()
+            || event.is::<SelectiveEraseDisplayBelow>()
+        {
+            self.erase_line(col.., selective);
+            self.erase_rows(row + 1..rows, selective);
+        } else if event.is::<EraseDisplayAbove>()
+            || event.is::<SelectiveEraseDisplayAbove>()
+        {
+            self.erase_rows(0..row, selective);
+            self.erase_line(..=col, selective);
+        } else if event.is::<EraseDisplayComplete>() {
+            self.grid_mut().clear(erased);
+            self.cursor.pending_wrap = false;
+        } else if event.is::<SelectiveEraseDisplayComplete>() {
+            self.erase_rows(0..rows, true);
+            self.cursor.pending_wrap = false;
+        } else if event.is::<EraseDisplayScrollback>()
+            || event.is::<ClearScrollback>()
+        {
+            self.scrollback.clear();
+        } else if event.is::<EraseLineRight>()
+            || event.is::<SelectiveEraseLineRight>()
+        {
+            self.erase_line(col.., selective);
+        } else if event.is::<EraseLineLeft>()
+            || event.is::<SelectiveEraseLineLeft>()
+        {
+            self.erase_line(..=col, selective);
+        } else if event.is::<EraseLineComplete>()
+            || event.is::<SelectiveEraseLineComplete>()
+        {
+            self.erase_line(.., selective);
+        } else if let Some(e) = event.downcast_ref::<EraseCharacter>() {
+            let last = col.saturating_add(param(e.0) - 1);
+            self.grid_mut().row_mut(row).erase(col..=last, erased);
+            self.cursor.pending_wrap = false;
+        } else if let Some(e) = event.downcast_ref::<InsertCharacter>() {
+            self.grid_mut()
+                .row_mut(row)
+                .insert(col, param(e.0), cols, erased);
+            self.cursor.pending_wrap = false;
+        } else if let Some(e) = event.downcast_ref::<DeleteCharacter>() {
+            self.grid_mut()
+                .row_mut(row)
+                .delete(col, param(e.0), cols, erased);
+            self.cursor.pending_wrap = false;
+        } else if let Some(e) = event.downcast_ref::<InsertLine>() {
+            if (self.top..=self.bottom).contains(&row) {
+                let region = row..=self.bottom;
+                self.grid_mut().scroll_down(region, param(e.0), erased);
+                self.carriage_return();
+            }
+        } else if let Some(e) = event.downcast_ref::<DeleteLine>() {
+            if (self.top..=self.bottom).contains(&row) {
+                let region = row..=self.bottom;
+                self.grid_mut().scroll_up(region, param(e.0), erased);
+                self.carriage_return();
+            }
+        } else if let Some(e) = event.downcast_ref::<ScrollUp>() {
+            self.scroll_up(param(e.0));
+        } else if let Some(e) = event.downcast_ref::<ScrollDown>() {
+            let region = self.top..=self.bottom;
+            self.grid_mut().scroll_down(region, param(e.0), erased);
+        } else if let Some(e) = event.downcast_ref::<SetTopAndBottomMargins>() {
+            let top = param(e.top) - 1;
+            let bottom = if e.bottom == 0 {
+                rows - 1
+            } else {
+                usize::from(e.bottom).min(rows) - 1
+            };
+            if top < bottom {
+                self.top = top;
+                self.bottom = bottom;
+                self.set_position(0, 0);
+            }
+        } else if event.is::<FillScreenWithE>() {
+            let fill = Cell::new('E', 1, CellAttributes::default());
+            for row in 0..rows {
+                self.grid_mut().row_mut(row).fill(&fill);
+            }
+            self.top = 0;
+            self.bottom = rows - 1;
+            self.modes.remove(Modes::ORIGIN);
+            self.move_to(0, 0);
+        } else {
+            return false;
+        }
+        true
+    }
+
+    fn apply_mode(&mut self, event: &dyn AnsiEvent<'_>) -> bool {
+        if event.is::<EnableInsertMode>() {
+            self.modes.insert(Modes::INSERT);
+        } else if event.is::<DisableInsertMode>() {
+            self.modes.remove(Modes::INSERT);
+        } else if event.is::<EnableLinefeedMode>() {
+            self.modes.insert(Modes::LINEFEED);
+        } else if event.is::<DisableLinefeedMode>() {
+            self.modes.remove(Modes::LINEFEED);
+        } else if event.is::<EnableLineWraparoundMode>() {
+            self.modes.insert(Modes::AUTOWRAP);
+        } else if event.is::<DisableLineWraparoundMode>() {
+            self.modes.remove(Modes::AUTOWRAP);
+        } else if event.is::<EnableRelativeCursorOriginMode>() {
+            self.modes.insert(Modes::ORIGIN);
+            self.set_position(0, 0);
+        } else if event.is::<DisableRelativeCursorOriginMode>() {
+            self.modes.remove(Modes::ORIGIN);
+            self.set_position(0, 0);
+        } else if event.is::<EnableCursorVisibility>() {
+            self.modes.insert(Modes::CURSOR_VISIBLE);
+        } else if event.is::<DisableCursorVisibility>() {
+            self.modes.remove(Modes::CURSOR_VISIBLE);
+        } else if event.is::<EnableHundredThirtyTwoColumnMode>()
+            || event.is::<DisableHundredThirtyTwoColumnMode>()
+        {
+            // The column count is left to the embedder; only the side
+            // effects of DECCOLM are applied
+            self.grid_mut().clear(CellAttributes::default());
+            self.top = 0;
+            self.bottom = self.grid().rows() - 1;
+            self.set_position(0, 0);
+        } else if event.is::<EnableAlternateScreenBasicMode>() {
+            self.enter_alternate_screen(AlternateScreen::Shared);
+        } else if event.is::<DisableAlternateScreenBasicMode>() {
+            self.exit_alternate_screen();
+        } else if event.is::<EnableAlternateScreenClearOnExitMode>() {
+            self.enter_alternate_screen(AlternateScreen::Shared);
+        } else if event.is::<DisableAlternateScreenClearOnExitMode>() {
+            if self.is_alternate_screen() {
+                self.alternate.clear(self.cursor.attrs.erased());
+            }
+            self.exit_alternate_screen();
+        } else if event.is::<EnableAlternateScreenMode>() {
+            if !self.is_alternate_screen() {
+                self.save_cursor();
+                self.enter_alternate_screen(AlternateScreen::SavedCursor);
+                self.alternate.clear(self.cursor.attrs.erased());
+            }
+        } else if event.is::<DisableAlternateScreenMode>() {
+            if self.is_alternate_screen() {
+                self.exit_alternate_screen();
+                self.restore_cursor();
+            }
+        } else {
+            return false;
+        }
+        true
+    }
+
+    fn apply_charset(&mut self, event: &dyn AnsiEvent<'_>) -> bool {
+        let charsets = &mut self.cursor.charsets;
+        if event.is::<ShiftIn>() {
+            charsets.invoke(0);
+        } else if event.is::<ShiftOut>() {
+            charsets.invoke(1);
+        } else if event.is::<LockingShift2>() {
+            charsets.invoke(2);
+        } else if event.is::<LockingShift3>() {
+            charsets.invoke(3);
+        } else if event.is::<SingleShift2>() {
+            charsets.single_shift(2);
+        } else if event.is::<SingleShift3>() {
+            charsets.single_shift(3);
+        } else if let Some(e) = event.downcast_ref::<DesignateG0>() {
+            charsets.designate(0, e.charset);
+        } else if let Some(e) = event.downcast_ref::<DesignateG1>() {
+            charsets.designate(1, e.charset);
+        } else if let Some(e) = event.downcast_ref::<DesignateG2>() {
+            charsets.designate(2, e.charset);
+        } else if let Some(e) = event.downcast_ref::<DesignateG3>() {
+            charsets.designate(3, e.charset);
+        } else {
+            return false;
+        }
+        true
+    }
+
+    fn grid_mut(&mut self) -> &mut Grid {
+        if self.is_alternate_screen() {
+            &mut self.alternate
+        } else {
+            &mut self.primary
+        }
+    }
+
+    /// Handle a character of plain text, which includes the format
+    /// effectors the parser passes through as text.
+    fn input(&mut self, ch: char) {
+        match ch {
+            '\x08' => {
+                self.move_to(
+                    self.cursor.row,
+                    self.cursor.col.saturating_sub(1),
+                );
+            }
+            '\t' => self.tab_forward(),
+            '\n' | '\x0b' | '\x0c' => {
+                self.index();
+                if self.modes.contains(Modes::LINEFEED) {
+                    self.carriage_return();
+                }
+            }
+            '\r' => self.carriage_return(),
+            c if c.is_control() => {}
+            c => self.print(c),
+        }
+    }
+
+    fn print(&mut self, ch: char) {
+        let ch = self.cursor.charsets.map(ch);
+        let cols = self.grid().cols();
+        let Some(width) = ch.width() else {
+            return;
+        };
+        if width == 0 {
+            self.combine(ch);
+            return;
+        }
+        let width = width.min(2);
+
+        if self.cursor.pending_wrap && self.modes.contains(Modes::AUTOWRAP) {
+            self.wrap();
+        }
+        if width == 2 && self.cursor.col + 1 >= cols {
+            if cols < 2 {
+                return;
+            }
+            if self.modes.contains(Modes::AUTOWRAP) {
+                let (row, col) = (self.cursor.row, self.cursor.col);
+                let erased = self.cursor.attrs.erased();
+                self.grid_mut().row_mut(row).erase(col.., erased);
+                self.wrap();
+            } else {
+                self.cursor.col = cols - 2;
+            }
+        }
+
+        let (row, col) = (self.cursor.row, self.cursor.col);
+        let attrs = self.cursor.attrs;
+        if self.modes.contains(Modes::INSERT) {
+            self.grid_mut().row_mut(row).insert(
+                col,
+                width,
+                cols,
+                attrs.erased(),
+            );
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        let cell = Cell::new(ch, width as u8, attrs)
+            .with_protected(self.cursor.protected);
+        self.grid_mut().row_mut(row).write(col, cell);
+
+        if col + width >= cols {
+            self.cursor.col = cols - width;
+            self.cursor.pending_wrap = self.modes.contains(Modes::AUTOWRAP);
+        } else {
+            self.cursor.col = col + width;
+            self.cursor.pending_wrap = false;
+        }
+    }
+
+    /// Attach a zero-width character to the previously printed cell.
+    fn combine(&mut self, ch: char) {
+        let (row, mut col) = (self.cursor.row, self.cursor.col);
+        if !self.cursor.pending_wrap {
+            if col == 0 {
+                return;
+            }
+            col -= 1;
+        }
+        let grid_row = self.grid_mut().row_mut(row);
+        if col > 0 && grid_row.cells()[col].is_spacer() {
+            col -= 1;
+        }
+        grid_row.cell_mut(col).push_combining(ch);
+    }
+
+    /// Soft-wrap to the start of the next line.
+    fn wrap(&mut self) {
+        let row = self.cursor.row;
+        self.grid_mut().row_mut(row).set_wrapped(true);
+        self.index();
+        self.cursor.col = 0;
+    }
+
+    fn carriage_return(&mut self) {
+        self.cursor.col = 0;
+        self.cursor.pending_wrap = false;
+    }
+
+    /// Move down one line, scrolling at the bottom margin.
+    fn index(&mut self) {
+        if self.cursor.row == self.bottom {
+            self.scroll_up(1);
+        } else if self.cursor.row + 1 < self.grid().rows() {
+            self.cursor.row += 1;
+        }
+        self.cursor.pending_wrap = false;
+    }
+
+    /// Move up one line, scrolling at the top margin.
+    fn reverse_index(&mut self) {
+        if self.cursor.row == self.top {
+            let region = self.top..=self.bottom;
+            let erased = self.cursor.attrs.erased();
+            self.grid_mut().scroll_down(region, 1, erased);
+        } else if self.cursor.row > 0 {
+            self.cursor.row -= 1;
+        }
+        self.cursor.pending_wrap = false;
+    }
+
+    /// Scroll the scrolling region up, saving the rows scrolled off the
+    /// top of the primary buffer to the scrollback.
+    fn scroll_up(&mut self, n: usize) {
+        let region = self.top..=self.bottom;
+        let erased = self.cursor.attrs.erased();
+        let scrolled = self.grid_mut().scroll_up(region, n, erased);
+        if self.top == 0 && !self.is_alternate_screen() {
+            for row in scrolled {
+                self.scrollback.push(row);
+            }
+        }
+    }
+
+    fn cursor_up(&mut self, n: usize) {
+        let min = if self.cursor.row >= self.top {
+            self.top
+        } else {
+            0
+        };
+        let row = self.cursor.row.saturating_sub(n).max(min);
+        self.move_to(row, self.cursor.col);
+    }
+
+    fn cursor_down(&mut self, n: usize) {
+        let max = if self.cursor.row <= self.bottom {
+            self.bottom
+        } else {
+            self.grid().rows() - 1
+        };
+        let row = self.cursor.row.saturating_add(n).min(max);
+        self.move_to(row, self.cursor.col);
+    }
+
+    /// Move to an absolute position, clamped to the screen.
+    fn move_to(&mut self, row: usize, col: usize) {
+        self.cursor.row = row.min(self.grid().rows() - 1);
+        self.cursor.col = col.min(self.grid().cols() - 1);
+        self.cursor.pending_wrap = false;
+    }
+
+    /// Move to a position relative to the origin, which is the top
+    /// margin in origin mode.
+    fn set_position(&mut self, row: usize, col: usize) {
+        if self.modes.contains(Modes::ORIGIN) {
+            let row = self.top.saturating_add(row).min(self.bottom);
+            self.move_to(row, col);
+        } else {
+            self.move_to(row, col);
+        }
+    }
+
+    fn tab_forward(&mut self) {
+        let cols = self.grid().cols();
+        let next = (self.cursor.col + 1..cols)
+            .find(|&col| self.tab_stops[col])
+            .unwrap_or(cols - 1);
+        self.move_to(self.cursor.row, next);
+    }
+
+    fn tab_backward(&mut self) {
+        let prev = (0..self.cursor.col)
+            .rev()
+            .find(|&col| self.tab_stops[col])
+            .unwrap_or(0);
+        self.move_to(self.cursor.row, prev);
+    }
+
+    /// Erase `range` of the cursor row, keeping protected cells if
+    /// `selective`.
+    fn erase_line(&mut self, range: impl RangeBounds<usize>, selective: bool) {
+        let row = self.cursor.row;
+        let erased = self.cursor.attrs.erased();
+        let clears_end = match range.end_bound() {
+            Bound::Unbounded => true,
+            Bound::Included(&end) => end + 1 >= self.grid().cols(),
+            Bound::Excluded(&end) => end >= self.grid().cols(),
+        };
+        let grid_row = self.grid_mut().row_mut(row);
+        if selective {
+            grid_row.selective_erase(range, erased);
+        } else {
+            grid_row.erase(range, erased);
+        }
+        if clears_end {
+            grid_row.set_wrapped(false);
+        }
+        self.cursor.pending_wrap = false;
+    }
+
+    /// Erase whole `rows`, keeping protected cells if `selective`.
+    fn erase_rows(&mut self, rows: Range<usize>, selective: bool) {
+        let erased = self.cursor.attrs.erased();
+        let cols = self.grid().cols();
+        for row in rows {
+            let grid_row = self.grid_mut().row_mut(row);
+            if selective {
+                grid_row.selective_erase(.., erased);
+                grid_row.set_wrapped(false);
+            } else {
+                *grid_row = Row::new(cols, erased);
+            }
+        }
+    }
+
+    fn save_cursor(&mut self) {
+        self.saved[usize::from(self.is_alternate_screen())] = SavedCursor {
+            cursor: self.cursor,
+            origin: self.modes.contains(Modes::ORIGIN),
+        };
+    }
+
+    fn restore_cursor(&mut self) {
+        let saved = self.saved[usize::from(self.is_alternate_screen())];
+        self.cursor = saved.cursor;
+        self.modes.set(Modes::ORIGIN, saved.origin);
+        let (row, col) = (self.cursor.row, self.cursor.col);
+        let pending_wrap = self.cursor.pending_wrap;
+        self.move_to(row, col);
+        self.cursor.pending_wrap = pending_wrap;
+    }
+
+    fn enter_alternate_screen(&mut self, kind: AlternateScreen) {
+        if self.alternate_mode.is_none() {
+            self.alternate_mode = Some(kind);
+        }
+    }
+
+    fn exit_alternate_screen(&mut self) {
+        self.alternate_mode = None;
+    }
+}
+
+/// A numeric parameter where 0 means the default of 1.
+fn param(value: u16) -> usize {
+    usize::from(value.max(1))
+}
+
+fn default_tab_stops(cols: usize) -> Vec<bool> {
+    (0..cols)
+        .map(|col| col > 0 && col % TAB_WIDTH == 0)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::TerminalOutputParser;
+
+    fn screen(rows: u16, cols: u16, input: &[u8]) -> Screen {
+        let mut screen = Screen::new(rows, cols);
+        let mut parser = TerminalOutputParser::new();
+        parser.feed_with(input, &mut |event| screen.apply(event));
+        screen
+    }
+
+    #[test]
+    fn test_pending_wrap() {
+        let s = screen(2, 3, b"abc");
+        assert_eq!(s.cursor_position(), (0, 2));
+        assert_eq!(s.contents(), "abc\n");
+        let s = screen(2, 3, b"abcd");
+        assert_eq!(s.contents(), "abc\nd");
+        assert!(s.grid().row(0).is_wrapped());
+        let s = screen(2, 3, b"abc\r\nd");
+        assert!(!s.grid().row(0).is_wrapped());
+    }
+
+    #[test]
+    fn test_autowrap_disabled() {
+        let s = screen(2, 3, b"\x1b[?7labcde");
+        assert_eq!(s.contents(), "abe\n");
+    }
+
+    #[test]
+    fn test_wide_character_wraps_early() {
+        let s = screen(2, 3, "ab一".as_bytes());
+        assert_eq!(s.contents(), "ab\n一");
+        assert_eq!(s.grid().row(1).cells()[1].width(), 0);
+    }
+
+    #[test]
+    fn test_combining_character() {
+        let s = screen(1, 4, "e\u{301}x".as_bytes());
+        assert_eq!(s.contents(), "e\u{301}x");
+    }
+
+    #[test]
+    fn test_scroll_region() {
+        let s = screen(4, 3, b"1\r\n2\r\n3\r\n4\x1b[2;3r\x1b[3;1H\n\nx");
+        assert_eq!(s.contents(), "1\n\nx\n4");
+        assert_eq!(s.scroll_region(), (1, 2));
+    }
+
+    #[test]
+    fn test_origin_mode() {
+        let s = screen(4, 3, b"\x1b[2;3r\x1b[?6h\x1b[5;1Hx");
+        assert_eq!(s.contents(), "\n\nx\n");
+    }
+
+    #[test]
+    fn test_alternate_screen() {
+        let mut s = screen(2, 5, b"ab\x1b[?1049hxy");
+        assert!(s.is_alternate_screen());
+        assert_eq!(s.contents(), "  xy\n");
+        assert_eq!(s.primary_grid().row(0).text(), "ab");
+        let mut parser = TerminalOutputParser::new();
+        parser.feed_with(b"\x1b[?1049lc", &mut |event| s.apply(event));
+        assert_eq!(s.contents(), "abc\n");
+    }
+
+    #[test]
+    fn test_tab_stops() {
+        let s = screen(1, 20, b"\x1b[3g\x1b[5G\x1bH\r\tx\tx");
+        assert_eq!(s.contents(), "    x              x");
+    }
+
+    #[test]
+    fn test_dec_special_graphics() {
+        let s = screen(1, 5, b"\x1b(0lqk\x1b(Bq");
+        assert_eq!(s.contents(), "┌─┐q");
+    }
+
+    #[test]
+    fn test_scrollback() {
+        let s = screen(3, 4, b"1\r\n2\r\n3\r\n4\x1b[2;3r\x1b[3H\n");
+        assert_eq!(s.scrollback().text(), "1\n");
+        let s = screen(2, 4, b"\x1b[?1049h1\r\n2\r\n3");
+        assert!(s.scrollback().is_empty());
+        let s = screen(2, 4, b"1\r\n2\r\n3\x1b[3J");
+        assert!(s.scrollback().is_empty());
+        let s = screen(2, 4, b"1\r\n2\r\n3\x1b]1337;ClearScrollback\x07");
+        assert!(s.scrollback().is_empty());
+    }
+
+    #[test]
+    fn test_resize_reflows_wrapped_lines() {
+        let mut s = screen(3, 4, b"abcdef\r\ngh");
+        assert_eq!(s.contents(), "abcd\nef\ngh");
+        s.resize(3, 2);
+        assert_eq!(s.scrollback().text(), "ab");
+        assert_eq!(s.contents(), "cd\nef\ngh");
+        assert_eq!(s.cursor_position(), (2, 1));
+        s.resize(3, 8);
+        assert!(s.scrollback().is_empty());
+        assert_eq!(s.contents(), "abcdef\ngh\n");
+        assert_eq!(s.cursor_position(), (1, 2));
+    }
+
+    #[test]
+    fn test_resize_keeps_cursor_in_view() {
+        let mut s = screen(4, 4, b"1\r\n2\r\n3");
+        s.resize(2, 4);
+        assert_eq!(s.scrollback().text(), "1\n");
+        assert_eq!(s.contents(), "2\n3");
+        assert_eq!(s.cursor_position(), (1, 1));
+        s.resize(4, 4);
+        assert_eq!(s.contents(), "1\n2\n3\n");
+        assert_eq!(s.cursor_position(), (2, 1));
+    }
+
+    #[test]
+    fn test_resize_in_alternate_screen() {
+        // 1049 saved the primary cursor, which anchors the reflow and is
+        // restored on exit
+        let mut s = screen(4, 4, b"1\r\n2\r\n3\x1b[?1049h\x1b[H");
+        s.resize(2, 4);
+        assert_eq!(s.scrollback().text(), "1\n");
+        let mut parser = TerminalOutputParser::new();
+        parser.feed_with(b"\x1b[?1049l", &mut |event| s.apply(event));
+        assert_eq!(s.contents(), "2\n3");
+        assert_eq!(s.cursor_position(), (1, 1));
+
+        // 47 and 1047 share the live cursor with the primary buffer
+        for mode in [&b"47"[..], b"1047"] {
+            let mut input = b"1\r\n2\r\n3\x1b[?".to_vec();
+            input.extend(mode);
+            input.push(b'h');
+            let mut s = screen(4, 4, &input);
+            s.resize(2, 4);
+            assert_eq!(s.scrollback().text(), "1\n");
+            let mut input = b"\x1b[?".to_vec();
+            input.extend(mode);
+            input.push(b'l');
+            let mut parser = TerminalOutputParser::new();
+            parser.feed_with(&input, &mut |event| s.apply(event));
+            assert_eq!(s.contents(), "2\n3");
+            assert_eq!(s.cursor_position(), (1, 1));
+        }
+    }
+
+    #[test]
+    fn test_erase_uses_background_color() {
+        let s = screen(1, 3, b"abc\x1b[44m\x1b[2K");
+        let cell = &s.grid().row(0).cells()[0];
+        assert!(cell.attrs().background.is_some());
+        assert!(!cell.is_blank());
+    }
+}
@@ -0,0 +1,218 @@
+//! Rewrapping of soft-wrapped lines to a new width.
+
+use super::cell::{Cell, CellAttributes};
+use super::grid::Row;
+
+/// A cursor position tracked through a reflow.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Position {
+    /// Index of the row in the reflowed rows.
+    pub(crate) row: usize,
+    pub(crate) col: usize,
+    pub(crate) pending_wrap: bool,
+}
+
+/// Rewrap `rows` to `cols` columns.
+///
+/// Rows are joined into lines at their wrapped flag and split again at the
+/// new width.  Trailing blanks of each line are dropped.  Returns the new
+/// rows, all `cols` wide, and the new position of `cursor`.
+pub(crate) fn reflow(
+    rows: impl IntoIterator<Item = Row>,
+    cols: usize,
+    cursor: Position,
+) -> (Vec<Row>, Position) {
+    let mut out = Vec::new();
+    let mut position = Position::default();
+    let mut line: Vec<Cell> = Vec::new();
+    let mut cursor_offset = None;
+    for (index, row) in rows.into_iter().enumerate() {
+        let wrapped = row.is_wrapped();
+        let mut cells = row.into_cells();
+        // A wide character that did not fit at the right margin left a
+        // blank behind when it wrapped
+        if cells.first().is_some_and(|cell| cell.width() == 2)
+            && line.last().is_some_and(Cell::is_blank)
+        {
+            line.pop();
+        }
+        if index == cursor.row {
+            cursor_offset = Some(
+                line.len() + cursor.col + usize::from(cursor.pending_wrap),
+            );
+        }
+        line.append(&mut cells);
+        if !wrapped {
+            let len = line
+                .iter()
+                .rposition(|cell| !cell.is_blank())
+                .map_or(0, |i| i + 1);
+            line.truncate(len);
+            if let Some(p) =
+                split(std::mem::take(&mut line), cols, cursor_offset, &mut out)
+            {
+                position = p;
+            }
+            cursor_offset = None;
+        }
+    }
+    if !line.is_empty() || cursor_offset.is_some() {
+        if let Some(p) = split(line, cols, cursor_offset, &mut out) {
+            position = p;
+        }
+    }
+    (out, position)
+}
+
+/// Split the cells of a line into rows of `cols` cells.
+///
+/// Returns where the cell at `cursor` ended up.
+fn split(
+    cells: Vec<Cell>,
+    cols: usize,
+    cursor: Option<usize>,
+    out: &mut Vec<Row>,
+) -> Option<Position> {
+    let blank = Cell::blank(CellAttributes::default());
+    let len = cells.len();
+    let mut position = None;
+    let mut row: Vec<Cell> = Vec::with_capacity(cols);
+    for (index, mut cell) in cells.into_iter().enumerate() {
+        if cols < 2 && cell.width() != 1 {
+            // Wide characters do not fit at all
+            if cell.is_spacer() {
+                continue;
+            }
+            cell = Cell::blank(cell.attrs().erased());
+        }
+        let width = usize::from(cell.width().max(1));
+        if row.len() + width > cols {
+            row.resize(cols, blank.clone());
+            out.push(Row::from_cells(std::mem::take(&mut row), true));
+        }
+        if cursor == Some(index) {
+            position = Some(Position {
+                row: out.len(),
+                col: row.len(),
+                pending_wrap: false,
+            });
+        }
+        row.push(cell);
+    }
+
+    if let Some(offset) = cursor.filter(|&offset| offset >= len) {
+        // The cursor is past the end of the line
+        let col = row.len() + offset - len;
+        position = Some(if col == cols && row.len() == cols {
+            Position {
+                row: out.len(),
+                col: cols - 1,
+                pending_wrap: true,
+            }
+        } else {
+            Position {
+                row: out.len() + col / cols,
+                col: col % cols,
+                pending_wrap: false,
+            }
+        });
+    }
+    row.resize(cols, blank.clone());
+    out.push(Row::from_cells(row, false));
+    if let Some(p) = position {
+        while out.len() <= p.row {
+            out.push(Row::from_cells(vec![blank.clone(); cols], false));
+        }
+    }
+    position
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(lines: &[(&str, bool)]) -> Vec<Row> {
+        lines
+            .iter()
+            .map(|&(text, wrapped)| {
+                let cells = text
+                    .chars()
+                    .map(|ch| Cell::new(ch, 1, CellAttributes::default()))
+                    .collect();
+                Row::from_cells(cells, wrapped)
+            })
+            .collect()
+    }
+
+    fn texts(rows: &[Row]) -> Vec<(String, bool)> {
+        rows.iter().map(|r| (r.text(), r.is_wrapped())).collect()
+    }
+
+    #[test]
+    fn test_rewrap_narrower_and_wider() {
+        let input = rows(&[("abcd", true), ("ef  ", false), ("gh  ", false)]);
+        let cursor = Position {
+            row: 1,
+            col: 1,
+            pending_wrap: false,
+        };
+        let (out, pos) = reflow(input, 3, cursor);
+        assert_eq!(
+            texts(&out),
+            [
+                ("abc".into(), true),
+                ("def".into(), false),
+                ("gh".into(), false)
+            ]
+        );
+        assert_eq!((pos.row, pos.col), (1, 2));
+
+        let (out, pos) = reflow(out, 8, pos);
+        assert_eq!(
+            texts(&out),
+            [("abcdef".into(), false), ("gh".into(), false)]
+        );
+        assert_eq!((pos.row, pos.col), (0, 5));
+    }
+
+    #[test]
+    fn test_cursor_past_end_of_line() {
+        let input = rows(&[("ab  ", false)]);
+        let cursor = Position {
+            row: 0,
+            col: 3,
+            pending_wrap: false,
+        };
+        let (out, pos) = reflow(input, 2, cursor);
+        assert_eq!(texts(&out), [("ab".into(), false), (String::new(), false)]);
+        assert_eq!((pos.row, pos.col, pos.pending_wrap), (1, 1, false));
+    }
+
+    #[test]
+    fn test_pending_wrap_is_kept() {
+        let input = rows(&[("abc", false)]);
+        let cursor = Position {
+            row: 0,
+            col: 2,
+            pending_wrap: true,
+        };
+        let (out, pos) = reflow(input, 3, cursor);
+        assert_eq!(out.len(), 1);
+        assert_eq!((pos.row, pos.col, pos.pending_wrap), (0, 2, true));
+    }
+
+    #[test]
+    fn test_wide_character_is_not_split() {
+        let attrs = CellAttributes::default();
+        let cells = vec![
+            Cell::new('a', 1, attrs),
+            Cell::new('一', 2, attrs),
+            Cell::spacer(attrs),
+        ];
+        let input = vec![Row::from_cells(cells, false)];
+        let (out, _) = reflow(input, 2, Position::default());
+        assert_eq!(texts(&out), [("a".into(), true), ("一".into(), false)]);
+        let (out, _) = reflow(out, 3, Position::default());
+        assert_eq!(texts(&out), [("a一".into(), false)]);
+    }
+}
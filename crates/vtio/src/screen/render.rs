@@ -0,0 +1,677 @@
+//! Damage-tracking renderer.
+
+use std::hash::{BuildHasher, RandomState};
+use std::io;
+
+use vtansi::AnsiEncode;
+
+use super::Screen;
+use super::cell::{Cell, CellAttributes};
+use super::grid::{Grid, Row};
+use crate::event::cursor::{
+    DisableCursorVisibility, EnableCursorVisibility, SetCursorPosition,
+};
+use crate::event::screen::{
+    DeleteLine, EraseDisplayComplete, EraseLineRight, InsertLine,
+};
+use crate::event::scroll::{ScrollUp, SetTopAndBottomMargins};
+use crate::event::sgr::{Intensity, SelectGraphicRendition, SgrAttribute};
+use crate::event::terminal::{
+    DisableSynchronizedUpdateMode, EnableSynchronizedUpdateMode,
+};
+
+/// Unchanged cells between two changes that are rewritten rather than
+/// skipped with a cursor movement.
+const MAX_GAP: usize = 8;
+
+/// Renders the changes between two [`Screen`] snapshots.
+///
+/// The renderer emits the output sequences that turn a terminal showing
+/// one snapshot into one showing the other: cursor positioning, text,
+/// `SGR` changes, erasure to the end of the line, and scrolling of a
+/// region when rows moved up or down as a block.  Rows and cells that did
+/// not change are left alone.
+///
+/// The terminal written to is assumed to show the old snapshot exactly,
+/// with its cursor and current rendition as in the old snapshot, no
+/// scrolling region and origin mode off.  It is left showing the new
+/// snapshot in the same way.  Soft-wrap state is not reproduced, and the
+/// cursor never rests in the pending wrap state.
+///
+/// # Example
+///
+/// ```
+/// use vtio::parser::TerminalOutputParser;
+/// use vtio::screen::{Renderer, Screen};
+///
+/// let old = Screen::new(2, 10);
+/// let mut new = old.clone();
+/// TerminalOutputParser::new().feed_with(b"hello", &mut |event| {
+///     new.apply(event);
+/// });
+///
+/// let mut out = Vec::new();
+/// Renderer::new().render(&old, &new, &mut out).unwrap();
+/// assert_eq!(out, b"hello");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Renderer {
+    synchronized: bool,
+}
+
+impl Renderer {
+    /// Create a renderer.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wrap every non-empty update in `SynchronizedUpdateMode`, so the
+    /// terminal presents it at once.
+    ///
+    /// Only enable this if the terminal recognizes mode 2026, as reported
+    /// by [`TerminalCapabilities::recognizes_mode`].
+    ///
+    /// [`TerminalCapabilities::recognizes_mode`]: crate::probe::TerminalCapabilities::recognizes_mode
+    #[must_use]
+    pub fn with_synchronized_update(mut self, enabled: bool) -> Self {
+        self.synchronized = enabled;
+        self
+    }
+
+    /// Write the sequences turning a terminal showing `old` into one
+    /// showing `new`.
+    ///
+    /// If the snapshots differ in size, the screen is cleared and redrawn.
+    ///
+    /// # Errors
+    ///
+    /// Return an error if writing fails.
+    pub fn render<W: io::Write + ?Sized>(
+        &self,
+        old: &Screen,
+        new: &Screen,
+        sink: &mut W,
+    ) -> io::Result<()> {
+        if old.rows() != new.rows() || old.cols() != new.cols() {
+            return self.render_full(new, sink);
+        }
+        let (row, col) = old.cursor_position();
+        let mut frame = Frame {
+            out: Vec::new(),
+            grid: old.grid().clone(),
+            cursor: Some((usize::from(row), usize::from(col))),
+            pen: *old.attributes(),
+        };
+        frame.draw(new, Some(old.cursor_visible()))?;
+        self.finish(&frame.out, sink)
+    }
+
+    /// Write the sequences drawing `new` on a terminal in an unknown
+    /// state, clearing the screen first.
+    ///
+    /// # Errors
+    ///
+    /// Return an error if writing fails.
+    pub fn render_full<W: io::Write + ?Sized>(
+        &self,
+        new: &Screen,
+        sink: &mut W,
+    ) -> io::Result<()> {
+        let mut frame = Frame {
+            out: Vec::new(),
+            grid: Grid::new(new.grid().rows(), new.grid().cols()),
+            cursor: None,
+            pen: CellAttributes::default(),
+        };
+        SelectGraphicRendition::reset().encode_ansi_into(&mut frame.out)?;
+        EraseDisplayComplete.encode_ansi_into(&mut frame.out)?;
+        frame.draw(new, None)?;
+        self.finish(&frame.out, sink)
+    }
+
+    fn finish<W: io::Write + ?Sized>(
+        self,
+        out: &[u8],
+        sink: &mut W,
+    ) -> io::Result<()> {
+        if out.is_empty() {
+            return Ok(());
+        }
+        if self.synchronized {
+            EnableSynchronizedUpdateMode.encode_ansi_into(sink)?;
+        }
+        sink.write_all(out)?;
+        if self.synchronized {
+            DisableSynchronizedUpdateMode.encode_ansi_into(sink)?;
+        }
+        Ok(())
+    }
+}
+
+/// The state of the terminal being drawn to.
+struct Frame {
+    out: Vec<u8>,
+    /// The contents of the terminal, as far as they are tracked.
+    grid: Grid,
+    /// The cursor position, unless it is unknown or pending a wrap.
+    cursor: Option<(usize, usize)>,
+    pen: CellAttributes,
+}
+
+impl Frame {
+    fn draw(
+        &mut self,
+        new: &Screen,
+        cursor_visible: Option<bool>,
+    ) -> io::Result<()> {
+        let target = new.grid();
+        if let Some(scroll) = find_scroll(&self.grid, target) {
+            self.scroll(scroll)?;
+        }
+        for (index, row) in target.iter().enumerate() {
+            if self.grid.row(index).cells() != row.cells() {
+                self.draw_row(index, row)?;
+            }
+        }
+
+        let (row, col) = new.cursor_position();
+        self.move_to(usize::from(row), usize::from(col))?;
+        self.set_pen(*new.attributes())?;
+        if cursor_visible != Some(new.cursor_visible()) {
+            if new.cursor_visible() {
+                EnableCursorVisibility.encode_ansi_into(&mut self.out)?;
+            } else {
+                DisableCursorVisibility.encode_ansi_into(&mut self.out)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Move the rows of a region as a block.
+    ///
+    /// Regions reaching the bottom of the screen are moved with `DL` and
+    /// `IL`, or `SU` when they span the whole screen; other regions are
+    /// temporarily made the scrolling region.
+    fn scroll(&mut self, scroll: Scroll) -> io::Result<()> {
+        let Scroll { top, bottom, up } = scroll;
+        let n = to_param(up.unsigned_abs());
+        let margins = bottom + 1 < self.grid.rows();
+        if margins {
+            encode_margins(top, bottom, &mut self.out)?;
+            // DECSTBM homes the cursor
+            self.cursor = Some((0, 0));
+        }
+        if up > 0 && (margins || top == 0) {
+            ScrollUp(n).encode_ansi_into(&mut self.out)?;
+        } else if up > 0 {
+            self.move_to(top, 0)?;
+            DeleteLine(n).encode_ansi_into(&mut self.out)?;
+        } else {
+            self.move_to(top, 0)?;
+            InsertLine(n).encode_ansi_into(&mut self.out)?;
+        }
+        if margins {
+            SetTopAndBottomMargins { top: 0, bottom: 0 }
+                .encode_ansi_into(&mut self.out)?;
+            self.cursor = Some((0, 0));
+        }
+
+        let region = top..=bottom;
+        let erased = self.pen.erased();
+        if up > 0 {
+            self.grid.scroll_up(region, up.unsigned_abs(), erased);
+        } else {
+            self.grid.scroll_down(region, up.unsigned_abs(), erased);
+        }
+        Ok(())
+    }
+
+    fn draw_row(&mut self, index: usize, row: &Row) -> io::Result<()> {
+        let cols = self.grid.cols();
+        let old = self.grid.row(index).cells().to_vec();
+        let new = row.cells();
+        let (blank_from, blank) = trailing_blanks(new);
+
+        let mut col = 0;
+        while col < blank_from {
+            if old[col] == new[col] {
+                col += 1;
+                continue;
+            }
+            if new[col].is_spacer() && col > 0 {
+                col -= 1;
+            }
+            // Extend the run over changed cells and short gaps
+            let mut end = col;
+            let mut gap = 0;
+            for i in col..blank_from {
+                if old[i] == new[i] {
+                    gap += 1;
+                    if gap > MAX_GAP {
+                        break;
+                    }
+                } else {
+                    gap = 0;
+                    end = i + usize::from(new[i].width().max(1));
+                }
+            }
+            self.write_cells(index, col, &new[col..end.min(cols)])?;
+            col = end;
+        }
+
+        if blank_from < cols && old[blank_from..] != new[blank_from..] {
+            self.move_to(index, blank_from)?;
+            self.set_pen(blank)?;
+            EraseLineRight.encode_ansi_into(&mut self.out)?;
+        }
+        self.grid.row_mut(index).clone_from(row);
+        Ok(())
+    }
+
+    /// Write `cells` starting at `row`, `col`.
+    fn write_cells(
+        &mut self,
+        row: usize,
+        mut col: usize,
+        cells: &[Cell],
+    ) -> io::Result<()> {
+        self.move_to(row, col)?;
+        let mut text = String::new();
+        for cell in cells {
+            if cell.is_spacer() {
+                continue;
+            }
+            if *cell.attrs() != self.pen {
+                self.out.extend_from_slice(text.as_bytes());
+                text.clear();
+                self.set_pen(*cell.attrs())?;
+            }
+            cell.push_text(&mut text);
+            col += usize::from(cell.width());
+        }
+        self.out.extend_from_slice(text.as_bytes());
+        self.cursor = (col < self.grid.cols()).then_some((row, col));
+        Ok(())
+    }
+
+    fn move_to(&mut self, row: usize, col: usize) -> io::Result<()> {
+        if self.cursor != Some((row, col)) {
+            SetCursorPosition {
+                row: to_param(row + 1),
+                col: to_param(col + 1),
+            }
+            .encode_ansi_into(&mut self.out)?;
+            self.cursor = Some((row, col));
+        }
+        Ok(())
+    }
+
+    fn set_pen(&mut self, attrs: CellAttributes) -> io::Result<()> {
+        if attrs != self.pen {
+            SelectGraphicRendition::new(sgr_transition(&self.pen, &attrs))
+                .encode_ansi_into(&mut self.out)?;
+            self.pen = attrs;
+        }
+        Ok(())
+    }
+}
+
+/// A block move of the rows in `top..=bottom`, by `up` rows upwards or
+/// `-up` rows downwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Scroll {
+    top: usize,
+    bottom: usize,
+    up: isize,
+}
+
+/// Find the block move that lets the most non-blank rows of `new` be
+/// reused from `old`.
+///
+/// The move is only worth it when at least half of the rows it shifts are
+/// reused.
+fn find_scroll(old: &Grid, new: &Grid) -> Option<Scroll> {
+    let hasher = RandomState::new();
+    let hash = |row: &Row| hasher.hash_one(row.cells());
+    let old_hashes: Vec<u64> = old.iter().map(hash).collect();
+    let new_hashes: Vec<u64> = new.iter().map(hash).collect();
+    let top =
+        (0..new_hashes.len()).find(|&i| old_hashes[i] != new_hashes[i])?;
+    let bottom =
+        (0..new_hashes.len()).rfind(|&i| old_hashes[i] != new_hashes[i])?;
+    let reusable = |old_row: usize, new_row: usize| {
+        old_hashes[old_row] == new_hashes[new_row]
+            && !new.row(new_row).is_blank()
+    };
+
+    let mut best = None;
+    let mut best_reused = 0;
+    for shift in 1..=bottom - top {
+        let up = (top..=bottom - shift)
+            .filter(|&i| reusable(i + shift, i))
+            .count();
+        let down = (top + shift..=bottom)
+            .filter(|&i| reusable(i - shift, i))
+            .count();
+        for (reused, up) in
+            [(up, shift.cast_signed()), (down, -shift.cast_signed())]
+        {
+            if reused > best_reused && reused * 2 >= bottom - top + 1 - shift {
+                best_reused = reused;
+                best = Some(Scroll { top, bottom, up });
+            }
+        }
+    }
+    best
+}
+
+/// Where the run of blanks that can be erased at the end of `cells`
+/// starts, and their rendition.
+fn trailing_blanks(cells: &[Cell]) -> (usize, CellAttributes) {
+    let Some(last) = cells.last() else {
+        return (0, CellAttributes::default());
+    };
+    let attrs = *last.attrs();
+    if attrs != attrs.erased() {
+        return (cells.len(), attrs);
+    }
+    let blank = Cell::blank(attrs);
+    let start = cells
+        .iter()
+        .rposition(|cell| *cell != blank)
+        .map_or(0, |i| i + 1);
+    (start, attrs)
+}
+
+/// The `SGR` attributes changing the rendition from `from` to `to`.
+fn sgr_transition(
+    from: &CellAttributes,
+    to: &CellAttributes,
+) -> Vec<SgrAttribute> {
+    let mut changes = Vec::new();
+    if from.intensity != to.intensity {
+        // Bold and faint are independent in some terminals
+        if from.intensity != Intensity::Normal
+            && to.intensity != Intensity::Normal
+        {
+            changes.push(SgrAttribute::Intensity(Intensity::Normal));
+        }
+        changes.push(SgrAttribute::Intensity(to.intensity));
+    }
+    push_changes(from, to, &mut changes);
+
+    let mut reset = vec![SgrAttribute::Reset];
+    if to.intensity != Intensity::Normal {
+        reset.push(SgrAttribute::Intensity(to.intensity));
+    }
+    push_changes(&CellAttributes::default(), to, &mut reset);
+    if reset.len() <= changes.len() {
+        reset
+    } else {
+        changes
+    }
+}
+
+/// Push the attributes other than intensity that differ between `from`
+/// and `to`.
+fn push_changes(
+    from: &CellAttributes,
+    to: &CellAttributes,
+    changes: &mut Vec<SgrAttribute>,
+) {
+    if from.italic != to.italic {
+        changes.push(SgrAttribute::Italic(to.italic));
+    }
+    if from.underline != to.underline {
+        changes.push(SgrAttribute::Underline(to.underline));
+    }
+    if from.blink != to.blink {
+        changes.push(SgrAttribute::Blink(to.blink));
+    }
+    if from.inverse != to.inverse {
+        changes.push(SgrAttribute::Inverse(to.inverse));
+    }
+    if from.conceal != to.conceal {
+        changes.push(SgrAttribute::Conceal(to.conceal));
+    }
+    if from.strike != to.strike {
+        changes.push(SgrAttribute::Strike(to.strike));
+    }
+    if from.overline != to.overline {
+        changes.push(SgrAttribute::Overline(to.overline));
+    }
+    if from.foreground != to.foreground {
+        changes.push(
+            to.foreground.map_or(
+                SgrAttribute::DefaultForeground,
+                SgrAttribute::Foreground,
+            ),
+        );
+    }
+    if from.background != to.background {
+        changes.push(
+            to.background.map_or(
+                SgrAttribute::DefaultBackground,
+                SgrAttribute::Background,
+            ),
+        );
+    }
+    if from.underline_color != to.underline_color {
+        changes.push(to.underline_color.map_or(
+            SgrAttribute::DefaultUnderlineColor,
+            SgrAttribute::UnderlineColor,
+        ));
+    }
+}
+
+fn encode_margins(
+    top: usize,
+    bottom: usize,
+    out: &mut Vec<u8>,
+) -> io::Result<()> {
+    SetTopAndBottomMargins {
+        top: to_param(top + 1),
+        bottom: to_param(bottom + 1),
+    }
+    .encode_ansi_into(out)?;
+    Ok(())
+}
+
+fn to_param(value: impl TryInto<u16>) -> u16 {
+    value.try_into().unwrap_or(u16::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::TerminalOutputParser;
+
+    fn feed(screen: &mut Screen, input: &[u8]) {
+        TerminalOutputParser::new()
+            .feed_with(input, &mut |event| screen.apply(event));
+    }
+
+    /// Render `old` to `old` + `input`, check that replaying the output
+    /// on `old` reproduces the target and return the output.
+    fn render(renderer: Renderer, old: &Screen, input: &[u8]) -> Vec<u8> {
+        let mut new = old.clone();
+        feed(&mut new, input);
+        let mut out = Vec::new();
+        renderer.render(old, &new, &mut out).unwrap();
+        let mut replayed = old.clone();
+        feed(&mut replayed, &out);
+        assert_eq!(replayed.contents(), new.contents(), "{out:?}");
+        for (a, b) in replayed.grid().iter().zip(new.grid()) {
+            assert_eq!(a.cells(), b.cells(), "{out:?}");
+        }
+        assert_eq!(replayed.cursor_position(), new.cursor_position());
+        assert_eq!(replayed.attributes(), new.attributes());
+        assert_eq!(replayed.cursor_visible(), new.cursor_visible());
+        out
+    }
+
+    fn screen(rows: u16, cols: u16, input: &[u8]) -> Screen {
+        let mut screen = Screen::new(rows, cols);
+        feed(&mut screen, input);
+        screen
+    }
+
+    #[test]
+    fn test_unchanged() {
+        let old = screen(3, 10, b"abc\r\ndef");
+        assert!(render(Renderer::new(), &old, b"").is_empty());
+        let renderer = Renderer::new().with_synchronized_update(true);
+        assert!(render(renderer, &old, b"").is_empty());
+    }
+
+    #[test]
+    fn test_changed_cells_only() {
+        let old = screen(3, 20, b"hello world\r\nsecond line");
+        let out = render(Renderer::new(), &old, b"\x1b[1;7HW");
+        assert_eq!(out, b"\x1b[1;7HW");
+        let out = render(Renderer::new(), &old, b"\x1b[1;1HH\x1b[1;11HD");
+        assert_eq!(out, b"\x1b[1;1HH\x1b[1;11HD");
+    }
+
+    #[test]
+    fn test_erase_to_end_of_line() {
+        let old = screen(2, 20, b"hello world");
+        let out = render(Renderer::new(), &old, b"\x1b[1;6H\x1b[K");
+        assert_eq!(out, b"\x1b[1;6H\x1b[K");
+        render(Renderer::new(), &old, b"\x1b[44m\x1b[1;3H\x1b[K\x1b[m");
+        render(Renderer::new(), &old, b"\x1b[1;3H\x1b[44m\x1b[K");
+    }
+
+    #[test]
+    fn test_attributes() {
+        let old = screen(2, 20, b"\x1b[1;31mred\x1b[m plain");
+        let out = render(Renderer::new(), &old, b"\x1b[1;1H\x1b[2;31mred");
+        assert_eq!(out, b"\x1b[1;1H\x1b[2;31mred");
+        render(
+            Renderer::new(),
+            &old,
+            b"\x1b[4:3;58;2;1;2;3;7mx\x1b[27;39my",
+        );
+        // Bold and faint are reset before switching between them
+        let old = screen(1, 5, b"\x1b[1;3;31mx");
+        let out = render(Renderer::new(), &old, b"\x1b[2my");
+        assert_eq!(out, b"\x1b[22;2my");
+    }
+
+    #[test]
+    fn test_wide_characters() {
+        let old = screen(2, 6, "a一b".as_bytes());
+        render(Renderer::new(), &old, b"\x1b[1;3Hx");
+        render(Renderer::new(), &old, "\x1b[1;1H二三四".as_bytes());
+        render(Renderer::new(), &old, "\x1b[1;4H\x1b[K".as_bytes());
+    }
+
+    #[test]
+    fn test_scroll_whole_screen() {
+        let old = screen(4, 10, b"one\r\ntwo\r\nthree\r\nfour");
+        let out = render(Renderer::new(), &old, b"\r\nfive");
+        assert!(out.starts_with(b"\x1b[1S"), "{out:?}");
+        let old = screen(4, 10, b"one\r\ntwo\r\nthree\r\nfour\x1b[H");
+        let out = render(Renderer::new(), &old, b"\x1bMzero");
+        assert!(out.starts_with(b"\x1b[1L"), "{out:?}");
+    }
+
+    #[test]
+    fn test_scroll_region() {
+        // A pane above a status line
+        let old =
+            screen(4, 10, b"one\r\ntwo\r\nthree\r\nstatus\x1b[1;3r\x1b[3;6H");
+        let out = render(Renderer::new(), &old, b"\r\nfour");
+        assert!(out.starts_with(b"\x1b[1;3r\x1b[1S\x1b[0;0r"), "{out:?}");
+        let out = render(Renderer::new(), &old, b"\x1b[1;1H\x1b[Lzero");
+        assert!(out.starts_with(b"\x1b[1;3r\x1b[1L\x1b[0;0r"), "{out:?}");
+        // Rows moving up to the bottom of the screen
+        let old = screen(4, 10, b"one\r\ntwo\r\nthree\r\nfour\x1b[2H");
+        let out = render(Renderer::new(), &old, b"\x1b[M");
+        assert!(out.starts_with(b"\x1b[1M"), "{out:?}");
+    }
+
+    #[test]
+    fn test_cursor_visibility() {
+        let old = screen(2, 5, b"");
+        let out = render(Renderer::new(), &old, b"\x1b[?25l");
+        assert_eq!(out, b"\x1b[?25l");
+    }
+
+    #[test]
+    fn test_synchronized_update() {
+        let old = screen(2, 5, b"");
+        let renderer = Renderer::new().with_synchronized_update(true);
+        let out = render(renderer, &old, b"x");
+        assert_eq!(out, b"\x1b[?2026hx\x1b[?2026l");
+    }
+
+    #[test]
+    fn test_resized_redraws() {
+        let old = screen(2, 5, b"ab");
+        let new = screen(3, 6, b"\x1b[44mcd\x1b[m");
+        let mut out = Vec::new();
+        Renderer::new().render(&old, &new, &mut out).unwrap();
+        let mut replayed = screen(3, 6, b"garbage\x1b[31m");
+        feed(&mut replayed, &out);
+        assert_eq!(replayed.grid(), new.grid());
+        assert_eq!(replayed.cursor_position(), new.cursor_position());
+    }
+
+    #[test]
+    fn test_random_updates() {
+        // xorshift, to keep the test deterministic without dependencies
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move |n: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % n
+        };
+        let pieces: [&[u8]; 14] = [
+            b"text ",
+            "wide一二".as_bytes(),
+            b"\r\n",
+            b"\x1b[K",
+            b"\x1b[2L",
+            b"\x1b[M",
+            b"\x1b[S",
+            b"\x1b[2;5r",
+            b"\x1b[r",
+            b"\x1b[1;33m",
+            b"\x1b[44m",
+            b"\x1b[m",
+            b"\x1b[2P",
+            b"\x1bM",
+        ];
+        let mut old = Screen::new(6, 12);
+        for _ in 0..300 {
+            let mut input = Vec::new();
+            for _ in 0..next(6) {
+                if next(4) == 0 {
+                    let pos = format!("\x1b[{};{}H", next(7) + 1, next(13) + 1);
+                    input.extend_from_slice(pos.as_bytes());
+                }
+                input.extend_from_slice(
+                    pieces[usize::try_from(next(14)).unwrap()],
+                );
+            }
+            let mut new = old.clone();
+            feed(&mut new, &input);
+            // The renderer leaves no margins set
+            feed(&mut new, b"\x1b7\x1b[r\x1b8");
+            let mut out = Vec::new();
+            Renderer::new().render(&old, &new, &mut out).unwrap();
+            let mut replayed = old.clone();
+            feed(&mut replayed, &out);
+            assert_eq!(
+                replayed.grid().iter().map(Row::cells).collect::<Vec<_>>(),
+                new.grid().iter().map(Row::cells).collect::<Vec<_>>(),
+                "{input:?} rendered as {out:?}"
+            );
+            assert_eq!(replayed.cursor_position(), new.cursor_position());
+            old = new;
+        }
+    }
+}
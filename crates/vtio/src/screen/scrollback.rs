@@ -0,0 +1,212 @@
+//! Lines scrolled off the top of the primary screen.
+
+use std::collections::VecDeque;
+use std::mem::{size_of, size_of_val};
+
+use super::grid::Row;
+
+/// Number of rows kept by [`Scrollback::default`].
+pub const DEFAULT_SCROLLBACK_LINES: usize = 10_000;
+
+/// A bounded ring of rows scrolled off the top of the primary screen.
+///
+/// Rows keep their wrapped flag, so a line that was soft-wrapped at the
+/// right margin can be told apart from one broken by a line feed, and
+/// can be rewrapped when the screen is resized.  Rows that end a line are
+/// stored without their trailing blanks.
+///
+/// When a cap is exceeded, the oldest rows are dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scrollback {
+    rows: VecDeque<Row>,
+    max_lines: usize,
+    max_bytes: usize,
+    bytes: usize,
+}
+
+impl Default for Scrollback {
+    fn default() -> Self {
+        Self::new(DEFAULT_SCROLLBACK_LINES)
+    }
+}
+
+impl Scrollback {
+    /// Create a scrollback keeping at most `max_lines` rows.
+    ///
+    /// A cap of 0 disables scrollback.
+    #[must_use]
+    pub fn new(max_lines: usize) -> Self {
+        Self {
+            rows: VecDeque::new(),
+            max_lines,
+            max_bytes: usize::MAX,
+            bytes: 0,
+        }
+    }
+
+    /// Also cap the memory used by the stored cells at about `max_bytes`.
+    #[must_use]
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self.trim();
+        self
+    }
+
+    /// Maximum number of rows kept.
+    #[must_use]
+    pub const fn max_lines(&self) -> usize {
+        self.max_lines
+    }
+
+    /// Maximum number of bytes kept.
+    #[must_use]
+    pub const fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// Number of rows stored.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Whether no rows are stored.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Approximate memory used by the stored rows, in bytes.
+    #[must_use]
+    pub const fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// The row at `index`, where 0 is the oldest row.
+    #[must_use]
+    pub fn row(&self, index: usize) -> Option<&Row> {
+        self.rows.get(index)
+    }
+
+    /// Iterate over the rows, from oldest to newest.
+    #[must_use]
+    pub fn iter(&self) -> std::collections::vec_deque::Iter<'_, Row> {
+        self.rows.iter()
+    }
+
+    /// The stored text, with soft-wrapped rows joined and every other row
+    /// terminated by a newline.
+    #[must_use]
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for row in &self.rows {
+            if row.is_wrapped() {
+                for cell in row.cells() {
+                    cell.push_text(&mut text);
+                }
+            } else {
+                text.push_str(&row.text());
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    /// Remove every row.
+    pub fn clear(&mut self) {
+        self.rows.clear();
+        self.bytes = 0;
+    }
+
+    /// Append a row, dropping the oldest rows if a cap is exceeded.
+    pub(crate) fn push(&mut self, mut row: Row) {
+        if self.max_lines == 0 {
+            return;
+        }
+        if !row.is_wrapped() {
+            row.trim_blanks();
+        }
+        self.bytes += row_bytes(&row);
+        self.rows.push_back(row);
+        self.trim();
+    }
+
+    /// Remove and return every row.
+    pub(crate) fn take(&mut self) -> VecDeque<Row> {
+        self.bytes = 0;
+        std::mem::take(&mut self.rows)
+    }
+
+    fn trim(&mut self) {
+        while self.rows.len() > self.max_lines || self.bytes > self.max_bytes {
+            let Some(row) = self.rows.pop_front() else {
+                break;
+            };
+            self.bytes -= row_bytes(&row);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Scrollback {
+    type Item = &'a Row;
+    type IntoIter = std::collections::vec_deque::Iter<'a, Row>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+fn row_bytes(row: &Row) -> usize {
+    size_of::<Row>() + size_of_val(row.cells())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::screen::{Cell, CellAttributes};
+
+    fn row(text: &str, wrapped: bool) -> Row {
+        let cells = text
+            .chars()
+            .map(|ch| Cell::new(ch, 1, CellAttributes::default()))
+            .collect();
+        Row::from_cells(cells, wrapped)
+    }
+
+    #[test]
+    fn test_line_cap() {
+        let mut scrollback = Scrollback::new(2);
+        for text in ["a", "b", "c"] {
+            scrollback.push(row(text, false));
+        }
+        assert_eq!(scrollback.text(), "b\nc\n");
+        assert_eq!(scrollback.bytes(), 2 * row_bytes(&row("a", false)));
+    }
+
+    #[test]
+    fn test_byte_cap() {
+        let one = row_bytes(&row("abc", false));
+        let mut scrollback = Scrollback::default().with_max_bytes(2 * one);
+        for text in ["abc", "def", "ghi"] {
+            scrollback.push(row(text, false));
+        }
+        assert_eq!(scrollback.len(), 2);
+        assert_eq!(scrollback.row(0).map(Row::text).as_deref(), Some("def"));
+    }
+
+    #[test]
+    fn test_wrapped_rows_keep_blanks() {
+        let mut scrollback = Scrollback::default();
+        scrollback.push(row("ab ", true));
+        scrollback.push(row("cd  ", false));
+        assert_eq!(scrollback.text(), "ab cd\n");
+        assert_eq!(scrollback.row(1).map(|r| r.cells().len()), Some(2));
+    }
+
+    #[test]
+    fn test_disabled() {
+        let mut scrollback = Scrollback::new(0);
+        scrollback.push(row("a", false));
+        assert!(scrollback.is_empty());
+    }
+}
@@ -0,0 +1,652 @@
+//! Tracking of terminal state set by an application.
+//!
+//! A [`TerminalStateTracker`] watches the output of an application, as
+//! parsed by [`TerminalOutputParser`](crate::parser::TerminalOutputParser),
+//! and remembers the state it leaves in the terminal: the modes it set or
+//! reset (mouse tracking, bracketed paste, the alternate screen, ...), the
+//! kitty keyboard enhancement flags stacks of the main and alternate
+//! screens, the window title and the title stack, and the cursor style.
+//!
+//! This is what a multiplexer or session manager needs to keep an
+//! application running while no terminal is attached: mode state requests
+//! (`DECRQM`) can be answered with [`TerminalStateTracker::answer`], and
+//! when a terminal attaches, [`TerminalStateTracker::write_restore`]
+//! brings it into the state the application expects.
+//!
+//! ```ignore
+//! use vtio::state::TerminalStateTracker;
+//!
+//! let mut tracker = TerminalStateTracker::new();
+//! parser.feed_with(output, &mut |event| {
+//!     if let Some(report) = tracker.answer(event) {
+//!         report.encode_ansi_into(&mut pty)?;
+//!     } else {
+//!         tracker.apply(event);
+//!     }
+//! });
+//!
+//! // Later, in a freshly attached terminal
+//! tracker.write_restore(&mut tty)?;
+//! ```
+
+use std::collections::BTreeMap;
+use std::io;
+
+use better_any::TidExt;
+use vt_push_parser::event::{CSI, VTEvent};
+use vtansi::AnsiEncode;
+
+use crate::event::UnrecognizedOutputEvent;
+use crate::event::cursor::{CursorStyle, SetCursorStyle};
+use crate::event::input::ModeReport;
+use crate::event::keyboard::{
+    KeyboardEnhancementFlags, MAX_ENHANCEMENT_STACK_DEPTH,
+    PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    SetKeyboardEnhancementFlags,
+};
+use crate::event::mode::{
+    ModeControl, ModeFunction, TerminalModeRequest, TerminalModeState,
+    default_mode_state,
+};
+use crate::event::terminal::FullReset;
+use crate::event::window::{
+    PopTitle, PushTitle, SetIconName, SetTitle, SetTitleAndIconName,
+    TitleStackTarget,
+};
+use crate::probe::ModeQuery;
+
+/// Maximum depth of the title stack.
+///
+/// When the stack is full, pushing evicts the oldest entry, as xterm does.
+const MAX_TITLE_STACK_DEPTH: usize = 10;
+
+/// DEC private modes that switch to the alternate screen.
+const ALTERNATE_SCREEN_MODES: &[u16] = &[47, 1047, 1049];
+
+/// DEC private modes of which at most one can be set at a time: setting
+/// one resets the others, resetting one resets all of them.
+const EXCLUSIVE_MODES: &[&[u16]] = &[
+    // Mouse tracking
+    &[9, 1000, 1001, 1002, 1003],
+    // Mouse coordinate encoding
+    &[1005, 1006, 1015, 1016],
+    ALTERNATE_SCREEN_MODES,
+];
+
+/// DEC private modes that are not restored by
+/// [`TerminalStateTracker::write_restore`].
+const TRANSIENT_MODES: &[u16] = &[
+    // Synchronized update
+    2026,
+];
+
+/// An entry of the title stack.
+///
+/// A part is `None` if it was not saved by the push, or was never set.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct TitleStackEntry {
+    /// Saved window title.
+    pub title: Option<String>,
+    /// Saved icon name.
+    pub icon_name: Option<String>,
+}
+
+/// Keyboard enhancement flags of one screen, and their stack.
+///
+/// kitty keeps separate flags and stacks for the main and the alternate
+/// screen.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct KeyboardState {
+    flags: KeyboardEnhancementFlags,
+    stack: Vec<KeyboardEnhancementFlags>,
+}
+
+impl KeyboardState {
+    /// Replace (`mode` 1), add (2) or remove (3) flags.
+    fn update(&mut self, flags: KeyboardEnhancementFlags, mode: u16) {
+        match mode {
+            1 => self.flags = flags,
+            2 => self.flags.insert(flags),
+            3 => self.flags.remove(flags),
+            _ => {}
+        }
+    }
+
+    fn push(&mut self, flags: KeyboardEnhancementFlags) {
+        if self.stack.len() == MAX_ENHANCEMENT_STACK_DEPTH {
+            self.stack.remove(0);
+        }
+        self.stack.push(self.flags);
+        self.flags = flags;
+    }
+
+    /// Pop `count` entries.  Popping past the bottom clears the flags.
+    fn pop(&mut self, count: u16) {
+        for _ in 0..count.max(1) {
+            let Some(flags) = self.stack.pop() else {
+                self.flags = KeyboardEnhancementFlags::empty();
+                break;
+            };
+            self.flags = flags;
+        }
+    }
+
+    fn write_restore<W: io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> io::Result<()> {
+        if let Some((&bottom, rest)) = self.stack.split_first() {
+            SetKeyboardEnhancementFlags(bottom).encode_ansi_into(sink)?;
+            for &flags in rest {
+                PushKeyboardEnhancementFlags(flags).encode_ansi_into(sink)?;
+            }
+            PushKeyboardEnhancementFlags(self.flags).encode_ansi_into(sink)?;
+        } else if !self.flags.is_empty() {
+            SetKeyboardEnhancementFlags(self.flags).encode_ansi_into(sink)?;
+        }
+        Ok(())
+    }
+}
+
+/// Terminal state set by an application.
+///
+/// See the [module documentation](self) for an overview.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TerminalStateTracker {
+    modes: BTreeMap<ModeQuery, bool>,
+    /// Keyboard state of the main screen, then of the alternate screen.
+    keyboard: [KeyboardState; 2],
+    title: Option<String>,
+    icon_name: Option<String>,
+    title_stack: Vec<TitleStackEntry>,
+    cursor_style: Option<CursorStyle>,
+}
+
+impl TerminalStateTracker {
+    /// Create a tracker for a freshly reset terminal.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Update the state from an output event.
+    ///
+    /// Events that do not change tracked state are ignored.
+    pub fn apply(&mut self, event: &dyn vtansi::AnsiEvent<'_>) {
+        if event.downcast_ref::<FullReset>().is_some() {
+            *self = Self::default();
+        } else if let Some(set) = event.downcast_ref::<SetTitleAndIconName>() {
+            self.title = Some(set.title.to_string());
+            self.icon_name = Some(set.title.to_string());
+        } else if let Some(set) = event.downcast_ref::<SetTitle>() {
+            self.title = Some(set.title.to_string());
+        } else if let Some(set) = event.downcast_ref::<SetIconName>() {
+            self.icon_name = Some(set.name.to_string());
+        } else if let Some(push) = event.downcast_ref::<PushTitle>() {
+            self.push_title(push.which.unwrap_or(TitleStackTarget::Both));
+        } else if let Some(pop) = event.downcast_ref::<PopTitle>() {
+            self.pop_title(pop.which.unwrap_or(TitleStackTarget::Both));
+        } else if let Some(set) = event.downcast_ref::<SetCursorStyle>() {
+            self.cursor_style = Some(set.style);
+        } else if let Some(SetKeyboardEnhancementFlags(flags)) =
+            event.downcast_ref::<SetKeyboardEnhancementFlags>()
+        {
+            self.keyboard_mut().update(*flags, 1);
+        } else if let Some(PushKeyboardEnhancementFlags(flags)) =
+            event.downcast_ref::<PushKeyboardEnhancementFlags>()
+        {
+            self.keyboard_mut().push(*flags);
+        } else if event.is::<PopKeyboardEnhancementFlags>() {
+            self.keyboard_mut().pop(1);
+        } else if let Some(control) = ModeControl::from_event(event) {
+            let set = match control.function {
+                ModeFunction::Set => true,
+                ModeFunction::Reset => false,
+                ModeFunction::Request | ModeFunction::Report(_) => return,
+            };
+            for mode in control.modes {
+                self.set_mode(control.private, mode, set);
+            }
+        } else if let Some(UnrecognizedOutputEvent(VTEvent::Csi(csi))) =
+            event.downcast_ref::<UnrecognizedOutputEvent>()
+        {
+            self.apply_unrecognized_csi(csi);
+        }
+    }
+
+    /// Apply the forms of the keyboard enhancement and cursor style
+    /// sequences the output parser has no type for.
+    fn apply_unrecognized_csi(&mut self, csi: &CSI<'_>) {
+        let param = |index, default| {
+            csi.params.try_parse::<u16>(index).unwrap_or(default)
+        };
+        match (csi.private, csi.intermediates.as_ref(), csi.final_byte) {
+            (Some(b'='), b"", b'u') => {
+                self.keyboard_mut()
+                    .update(keyboard_flags(param(0, 0)), param(1, 1));
+            }
+            (Some(b'>'), b"", b'u') => {
+                self.keyboard_mut().push(keyboard_flags(param(0, 0)));
+            }
+            (Some(b'<'), b"", b'u') => self.keyboard_mut().pop(param(0, 1)),
+            (None, b" ", b'q') if param(0, 0) == 0 => {
+                self.cursor_style = Some(CursorStyle::Default);
+            }
+            _ => {}
+        }
+    }
+
+    fn set_mode(&mut self, private: bool, mode: u16, set: bool) {
+        if private
+            && let Some(group) =
+                EXCLUSIVE_MODES.iter().find(|group| group.contains(&mode))
+        {
+            for &other in *group {
+                if let Some(state) = self.modes.get_mut(&ModeQuery {
+                    private,
+                    mode: other,
+                }) {
+                    *state = false;
+                }
+            }
+        }
+        self.modes.insert(ModeQuery { private, mode }, set);
+    }
+
+    fn push_title(&mut self, which: TitleStackTarget) {
+        let (title, icon_name) = title_parts(which);
+        if self.title_stack.len() == MAX_TITLE_STACK_DEPTH {
+            self.title_stack.remove(0);
+        }
+        self.title_stack.push(TitleStackEntry {
+            title: self.title.clone().filter(|_| title),
+            icon_name: self.icon_name.clone().filter(|_| icon_name),
+        });
+    }
+
+    fn pop_title(&mut self, which: TitleStackTarget) {
+        let (title, icon_name) = title_parts(which);
+        let Some(entry) = self.title_stack.pop() else {
+            return;
+        };
+        if let Some(saved) = entry.title.filter(|_| title) {
+            self.title = Some(saved);
+        }
+        if let Some(saved) = entry.icon_name.filter(|_| icon_name) {
+            self.icon_name = Some(saved);
+        }
+    }
+
+    /// Return `true` if a mode is set, given its `Request*` type generated
+    /// by [`terminal_mode!`](crate::terminal_mode).
+    ///
+    /// Modes the application never changed are in their default state.
+    #[must_use]
+    pub fn is_mode_set<R: TerminalModeRequest>(&self) -> bool {
+        self.is_mode_number_set(R::PRIVATE, R::MODE)
+    }
+
+    /// Return `true` if a mode is set, given its number.
+    #[must_use]
+    pub fn is_mode_number_set(&self, private: bool, mode: u16) -> bool {
+        self.modes
+            .get(&ModeQuery { private, mode })
+            .copied()
+            .unwrap_or_else(|| is_default_set(private, mode))
+    }
+
+    /// Iterate over the modes the application set, in mode number order.
+    pub fn set_modes(&self) -> impl Iterator<Item = ModeQuery> + '_ {
+        self.modes
+            .iter()
+            .filter_map(|(&query, &set)| set.then_some(query))
+    }
+
+    /// Return `true` if the alternate screen is active.
+    #[must_use]
+    pub fn is_alternate_screen(&self) -> bool {
+        ALTERNATE_SCREEN_MODES
+            .iter()
+            .any(|&mode| self.is_mode_number_set(true, mode))
+    }
+
+    fn keyboard(&self) -> &KeyboardState {
+        &self.keyboard[usize::from(self.is_alternate_screen())]
+    }
+
+    fn keyboard_mut(&mut self) -> &mut KeyboardState {
+        let screen = usize::from(self.is_alternate_screen());
+        &mut self.keyboard[screen]
+    }
+
+    /// The keyboard enhancement flags of the active screen.
+    #[must_use]
+    pub fn keyboard_enhancement_flags(&self) -> KeyboardEnhancementFlags {
+        self.keyboard().flags
+    }
+
+    /// The keyboard enhancement flags stack of the active screen, from
+    /// bottom to top.
+    #[must_use]
+    pub fn keyboard_enhancement_stack(&self) -> &[KeyboardEnhancementFlags] {
+        &self.keyboard().stack
+    }
+
+    /// The window title, if the application set one.
+    #[must_use]
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// The icon name, if the application set one.
+    #[must_use]
+    pub fn icon_name(&self) -> Option<&str> {
+        self.icon_name.as_deref()
+    }
+
+    /// The title stack, from bottom to top.
+    #[must_use]
+    pub fn title_stack(&self) -> &[TitleStackEntry] {
+        &self.title_stack
+    }
+
+    /// The cursor style, if the application set one.
+    #[must_use]
+    pub const fn cursor_style(&self) -> Option<CursorStyle> {
+        self.cursor_style
+    }
+
+    /// Answer a mode state request (`DECRQM`) on the application's behalf.
+    ///
+    /// Return `None` if `event` is not a mode state request.  Modes the
+    /// application changed are reported as set or reset, other modes
+    /// known to the output parser in their default state, and unknown
+    /// modes as not recognized.
+    #[must_use]
+    pub fn answer(
+        &self,
+        event: &dyn vtansi::AnsiEvent<'_>,
+    ) -> Option<ModeReport> {
+        let (private, mode) = ModeControl::request(event)?;
+        let state = match self.modes.get(&ModeQuery { private, mode }) {
+            Some(true) => TerminalModeState::Set,
+            Some(false) => TerminalModeState::Reset,
+            None => default_mode_state(private, mode),
+        };
+        Some(ModeReport {
+            private,
+            mode,
+            state,
+        })
+    }
+
+    /// Write the sequences that bring a freshly reset terminal into the
+    /// tracked state.
+    ///
+    /// The keyboard enhancement flags of the main screen are written before
+    /// switching to the alternate screen, and those of the alternate screen
+    /// after.
+    ///
+    /// # Errors
+    ///
+    /// Return an error if writing fails.
+    pub fn write_restore<W: io::Write + ?Sized>(
+        &self,
+        sink: &mut W,
+    ) -> io::Result<()> {
+        let (alternate, modes): (Vec<_>, Vec<_>) =
+            self.modes.iter().partition(|(query, _)| {
+                query.private && ALTERNATE_SCREEN_MODES.contains(&query.mode)
+            });
+        write_modes(sink, modes)?;
+        self.keyboard[0].write_restore(sink)?;
+        write_modes(sink, alternate)?;
+        if self.is_alternate_screen() {
+            self.keyboard[1].write_restore(sink)?;
+        }
+
+        for entry in &self.title_stack {
+            write_titles(
+                sink,
+                entry.title.as_deref(),
+                entry.icon_name.as_deref(),
+            )?;
+            let which = match (&entry.title, &entry.icon_name) {
+                (Some(_), None) => TitleStackTarget::WindowTitle,
+                (None, Some(_)) => TitleStackTarget::IconName,
+                _ => TitleStackTarget::Both,
+            };
+            PushTitle { which: Some(which) }.encode_ansi_into(sink)?;
+        }
+        write_titles(sink, self.title(), self.icon_name())?;
+
+        if let Some(style) = self.cursor_style {
+            SetCursorStyle { style }.encode_ansi_into(sink)?;
+        }
+        Ok(())
+    }
+}
+
+/// Write the modes that are not in their default state, skipping
+/// transient modes.
+fn write_modes<'a, W: io::Write + ?Sized>(
+    sink: &mut W,
+    modes: impl IntoIterator<Item = (&'a ModeQuery, &'a bool)>,
+) -> io::Result<()> {
+    for (query, &set) in modes {
+        if set == is_default_set(query.private, query.mode)
+            || (query.private && TRANSIENT_MODES.contains(&query.mode))
+        {
+            continue;
+        }
+        let private = if query.private { "?" } else { "" };
+        let final_byte = if set { 'h' } else { 'l' };
+        write!(sink, "\x1b[{private}{}{final_byte}", query.mode)?;
+    }
+    Ok(())
+}
+
+/// Write the window title and icon name, skipping those that are `None`.
+fn write_titles<W: io::Write + ?Sized>(
+    sink: &mut W,
+    title: Option<&str>,
+    icon_name: Option<&str>,
+) -> io::Result<()> {
+    if let Some(title) = title {
+        SetTitle { title }.encode_ansi_into(sink)?;
+    }
+    if let Some(name) = icon_name {
+        SetIconName { name }.encode_ansi_into(sink)?;
+    }
+    Ok(())
+}
+
+fn is_default_set(private: bool, mode: u16) -> bool {
+    default_mode_state(private, mode) == TerminalModeState::Set
+}
+
+fn keyboard_flags(bits: u16) -> KeyboardEnhancementFlags {
+    KeyboardEnhancementFlags::from_bits_truncate(
+        u8::try_from(bits).unwrap_or(u8::MAX),
+    )
+}
+
+/// Which of the window title and icon name a title stack operation
+/// affects.
+fn title_parts(which: TitleStackTarget) -> (bool, bool) {
+    match which {
+        TitleStackTarget::WindowTitle => (true, false),
+        TitleStackTarget::IconName => (false, true),
+        TitleStackTarget::Both | TitleStackTarget::Other(_) => (true, true),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::terminal::{
+        RequestBracketedPasteMode, RequestInsertMode,
+    };
+    use crate::parser::TerminalOutputParser;
+
+    fn track(tracker: &mut TerminalStateTracker, output: &[u8]) {
+        let mut parser = TerminalOutputParser::new();
+        parser.feed_with(output, &mut |event| tracker.apply(event));
+    }
+
+    fn answers(tracker: &TerminalStateTracker, output: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut parser = TerminalOutputParser::new();
+        parser.feed_with(output, &mut |event| {
+            if let Some(report) = tracker.answer(event) {
+                report.encode_ansi_into(&mut out).unwrap();
+            }
+        });
+        out
+    }
+
+    fn restore(tracker: &TerminalStateTracker) -> Vec<u8> {
+        let mut out = Vec::new();
+        tracker.write_restore(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_modes() {
+        let mut tracker = TerminalStateTracker::new();
+        track(&mut tracker, b"\x1b[?1000;1006h\x1b[?2004h\x1b[4h\x1b[?25l");
+        assert!(tracker.is_mode_set::<RequestBracketedPasteMode>());
+        assert!(tracker.is_mode_set::<RequestInsertMode>());
+        assert!(tracker.is_mode_number_set(true, 1000));
+        assert!(!tracker.is_mode_number_set(true, 25));
+        assert!(tracker.is_mode_number_set(true, 7));
+
+        // Mouse tracking modes replace each other
+        track(&mut tracker, b"\x1b[?1003h");
+        assert!(!tracker.is_mode_number_set(true, 1000));
+        let modes: Vec<u16> = tracker.set_modes().map(|q| q.mode).collect();
+        assert_eq!(modes, [4, 1003, 1006, 2004]);
+
+        track(&mut tracker, b"\x1b[?1003l\x1b[?2004l");
+        assert!(!tracker.is_mode_number_set(true, 1003));
+        assert!(!tracker.is_mode_set::<RequestBracketedPasteMode>());
+    }
+
+    #[test]
+    fn test_answer() {
+        let mut tracker = TerminalStateTracker::new();
+        track(&mut tracker, b"\x1b[?2004h\x1b[?1049h\x1b[?9999h");
+        assert_eq!(
+            answers(
+                &tracker,
+                b"\x1b[?2004$p\x1b[4$p\x1b[?25$p\x1b[?1234$p\x1b[?9999$phi"
+            ),
+            b"\x1b[?2004;1$y\x1b[4;2$y\x1b[?25;1$y\x1b[?1234;0$y\
+              \x1b[?9999;1$y"
+        );
+        assert_eq!(
+            answers(&TerminalStateTracker::new(), b"\x1b[?1049$p"),
+            b"\x1b[?1049;2$y"
+        );
+    }
+
+    #[test]
+    fn test_keyboard_enhancement_stack() {
+        let mut tracker = TerminalStateTracker::new();
+        track(&mut tracker, b"\x1b[=1u\x1b[>3u\x1b[>11u");
+        assert_eq!(tracker.keyboard_enhancement_flags().bits(), 11);
+        let stack: Vec<u8> = tracker
+            .keyboard_enhancement_stack()
+            .iter()
+            .map(KeyboardEnhancementFlags::bits)
+            .collect();
+        assert_eq!(stack, [1, 3]);
+        assert_eq!(restore(&tracker), b"\x1b[=1u\x1b[>3u\x1b[>11u");
+
+        track(&mut tracker, b"\x1b[<u");
+        assert_eq!(tracker.keyboard_enhancement_flags().bits(), 3);
+        track(&mut tracker, b"\x1b[=4;2u");
+        assert_eq!(tracker.keyboard_enhancement_flags().bits(), 7);
+        track(&mut tracker, b"\x1b[<5u");
+        assert!(tracker.keyboard_enhancement_flags().is_empty());
+        assert!(tracker.keyboard_enhancement_stack().is_empty());
+
+        for _ in 0..=MAX_ENHANCEMENT_STACK_DEPTH {
+            track(&mut tracker, b"\x1b[>1u");
+        }
+        assert_eq!(
+            tracker.keyboard_enhancement_stack().len(),
+            MAX_ENHANCEMENT_STACK_DEPTH
+        );
+    }
+
+    #[test]
+    fn test_keyboard_enhancement_per_screen() {
+        let mut tracker = TerminalStateTracker::new();
+        track(&mut tracker, b"\x1b[>1u\x1b[?1049h");
+        assert!(tracker.is_alternate_screen());
+        assert!(tracker.keyboard_enhancement_flags().is_empty());
+        assert!(tracker.keyboard_enhancement_stack().is_empty());
+
+        track(&mut tracker, b"\x1b[>3u");
+        assert_eq!(tracker.keyboard_enhancement_flags().bits(), 3);
+        assert_eq!(
+            restore(&tracker),
+            b"\x1b[=0u\x1b[>1u\x1b[?1049h\x1b[=0u\x1b[>3u"
+        );
+
+        // Leaving the alternate screen brings back the main screen flags
+        track(&mut tracker, b"\x1b[<u\x1b[?1049l");
+        assert!(!tracker.is_alternate_screen());
+        assert_eq!(tracker.keyboard_enhancement_flags().bits(), 1);
+        assert_eq!(restore(&tracker), b"\x1b[=0u\x1b[>1u");
+    }
+
+    #[test]
+    fn test_title_stack() {
+        let mut tracker = TerminalStateTracker::new();
+        track(&mut tracker, b"\x1b]0;shell\x07\x1b[22;0t\x1b]2;vim\x07");
+        assert_eq!(tracker.title(), Some("vim"));
+        assert_eq!(tracker.icon_name(), Some("shell"));
+        assert_eq!(
+            tracker.title_stack(),
+            [TitleStackEntry {
+                title: Some("shell".into()),
+                icon_name: Some("shell".into()),
+            }]
+        );
+        assert_eq!(
+            restore(&tracker),
+            b"\x1b]2;shell\x1b\\\x1b]1;shell\x1b\\\x1b[22;0t\
+              \x1b]2;vim\x1b\\\x1b]1;shell\x1b\\"
+        );
+
+        track(&mut tracker, b"\x1b]1;icon\x07\x1b[23;2t");
+        assert_eq!(tracker.title(), Some("shell"));
+        assert_eq!(tracker.icon_name(), Some("icon"));
+        assert!(tracker.title_stack().is_empty());
+    }
+
+    #[test]
+    fn test_restore() {
+        let mut tracker = TerminalStateTracker::new();
+        track(
+            &mut tracker,
+            b"\x1b[?1049h\x1b[?1002h\x1b[?1006h\x1b[?2026h\x1b[?25l\
+              \x1b[?7h\x1b[?2004h\x1b[?2004l\x1b[5 q",
+        );
+        assert!(tracker.is_mode_number_set(true, 1006));
+        assert_eq!(tracker.cursor_style(), Some(CursorStyle::BlinkingBar));
+        assert_eq!(
+            restore(&tracker),
+            b"\x1b[?25l\x1b[?1002h\x1b[?1006h\x1b[?1049h\x1b[5 q"
+        );
+
+        track(&mut tracker, b"\x1b[ q");
+        assert_eq!(tracker.cursor_style(), Some(CursorStyle::Default));
+
+        track(&mut tracker, b"\x1bc");
+        assert_eq!(tracker, TerminalStateTracker::new());
+        assert!(restore(&tracker).is_empty());
+    }
+}
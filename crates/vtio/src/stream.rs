@@ -0,0 +1,205 @@
+//! Asynchronous terminal input.
+//!
+//! [`InputStream`] wraps any [`AsyncRead`] (typically the terminal's input)
+//! and yields parsed [`InputEvent`]s as a [`Stream`].
+//!
+//! A lone `ESC` byte is indistinguishable from the start of an escape
+//! sequence until more input arrives.  When no input arrives within the
+//! escape timeout, the stream calls
+//! [`TerminalInputParser::idle`] so that the pending bytes are reported
+//! (e.g. as an `Esc` key press).
+
+use std::collections::VecDeque;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+use std::time::Duration;
+
+use futures_core::Stream;
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::time::Sleep;
+
+use crate::event::InputEvent;
+use crate::parser::TerminalInputParser;
+
+/// Default time to wait for the rest of an escape sequence.
+pub const DEFAULT_ESCAPE_TIMEOUT: Duration = Duration::from_millis(100);
+
+const READ_BUFFER_SIZE: usize = 4096;
+
+/// A [`Stream`] of terminal input events read from an [`AsyncRead`].
+///
+/// The stream ends when the reader reaches end of file; any pending input
+/// is flushed first.
+pub struct InputStream<R> {
+    reader: R,
+    parser: TerminalInputParser,
+    events: VecDeque<InputEvent>,
+    buffer: Box<[u8]>,
+    escape_timeout: Duration,
+    idle_timer: Option<Pin<Box<Sleep>>>,
+    eof: bool,
+}
+
+impl<R: AsyncRead + Unpin> InputStream<R> {
+    /// Create a stream reading from `reader`, with the
+    /// [default escape timeout](DEFAULT_ESCAPE_TIMEOUT).
+    #[must_use]
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            parser: TerminalInputParser::new(),
+            events: VecDeque::new(),
+            buffer: vec![0; READ_BUFFER_SIZE].into_boxed_slice(),
+            escape_timeout: DEFAULT_ESCAPE_TIMEOUT,
+            idle_timer: None,
+            eof: false,
+        }
+    }
+
+    /// Set the time to wait for the rest of an escape sequence.
+    #[must_use]
+    pub fn with_escape_timeout(mut self, timeout: Duration) -> Self {
+        self.escape_timeout = timeout;
+        self
+    }
+
+    /// Return the escape timeout.
+    #[must_use]
+    pub fn escape_timeout(&self) -> Duration {
+        self.escape_timeout
+    }
+
+    /// Return a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Return a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Consume the stream, returning the underlying reader.
+    ///
+    /// Buffered events and partially received sequences are discarded.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn flush_idle(&mut self) {
+        self.idle_timer = None;
+        self.events.extend(self.parser.idle_owned());
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for InputStream<R> {
+    type Item = io::Result<InputEvent>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(event) = this.events.pop_front() {
+                return Poll::Ready(Some(Ok(event)));
+            }
+            if this.eof {
+                this.flush_idle();
+                return match this.events.pop_front() {
+                    Some(event) => Poll::Ready(Some(Ok(event))),
+                    None => Poll::Ready(None),
+                };
+            }
+
+            let mut buf = ReadBuf::new(&mut this.buffer);
+            match Pin::new(&mut this.reader).poll_read(cx, &mut buf) {
+                Poll::Ready(Ok(())) => {
+                    let data = buf.filled();
+                    if data.is_empty() {
+                        this.eof = true;
+                        continue;
+                    }
+                    this.events.extend(this.parser.feed_owned(data));
+                    // The parser may be holding an incomplete sequence;
+                    // give the terminal a chance to send the rest before
+                    // flushing it.
+                    this.idle_timer =
+                        Some(Box::pin(tokio::time::sleep(this.escape_timeout)));
+                }
+                Poll::Ready(Err(err)) => return Poll::Ready(Some(Err(err))),
+                Poll::Pending => {
+                    let Some(timer) = this.idle_timer.as_mut() else {
+                        return Poll::Pending;
+                    };
+                    ready!(timer.as_mut().poll(cx));
+                    this.flush_idle();
+                    if this.events.is_empty() {
+                        return Poll::Pending;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{KeyCode, KeyEvent, KeyModifiers};
+    use std::future::poll_fn;
+    use tokio::io::AsyncWriteExt;
+
+    async fn next<R: AsyncRead + Unpin>(
+        stream: &mut InputStream<R>,
+    ) -> Option<InputEvent> {
+        poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx))
+            .await
+            .map(Result::unwrap)
+    }
+
+    fn key(code: KeyCode) -> InputEvent {
+        InputEvent::Key(KeyEvent::new(code, KeyModifiers::empty()))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_lone_escape_after_timeout() {
+        let (mut tx, rx) = tokio::io::duplex(64);
+        let mut stream =
+            InputStream::new(rx).with_escape_timeout(Duration::from_millis(50));
+
+        tx.write_all(b"a\x1b").await.unwrap();
+        assert_eq!(next(&mut stream).await, Some(key(KeyCode::Char('a'))));
+        let start = tokio::time::Instant::now();
+        assert_eq!(next(&mut stream).await, Some(key(KeyCode::Esc)));
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_split_sequence_within_timeout() {
+        let (mut tx, rx) = tokio::io::duplex(64);
+        let mut stream = InputStream::new(rx);
+
+        let writer = tokio::spawn(async move {
+            tx.write_all(b"\x1b[").await.unwrap();
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            tx.write_all(b"A").await.unwrap();
+            tx
+        });
+        assert_eq!(next(&mut stream).await, Some(key(KeyCode::Up)));
+        drop(writer.await.unwrap());
+        assert_eq!(next(&mut stream).await, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_eof_flushes_pending_input() {
+        let (mut tx, rx) = tokio::io::duplex(64);
+        let mut stream = InputStream::new(rx);
+
+        tx.write_all(b"\x1b").await.unwrap();
+        drop(tx);
+        assert_eq!(next(&mut stream).await, Some(key(KeyCode::Esc)));
+        assert_eq!(next(&mut stream).await, None);
+    }
+}
@@ -0,0 +1,862 @@
+//! Terminal device management.
+//!
+//! Helpers shared by applications that talk to a terminal directly:
+//!
+//! - [`Tty`]: the terminal device, opened from `/dev/tty` when standard
+//!   input is redirected;
+//! - [`RawModeGuard`]: switch the terminal to raw or cbreak mode and
+//!   restore the original settings on drop, including when a panic aborts
+//!   the process;
+//! - [`window_size`]: query the window size (`TIOCGWINSZ`);
+//! - [`ResizeNotifier`]: get notified of window size changes (`SIGWINCH`);
+//! - [`poll_readable`]: wait for input with a timeout.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
+use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+use std::sync::{Mutex, Once};
+use std::time::Duration;
+
+use crate::event::window::InBandResizeReport;
+
+/// Return `true` if `fd` refers to a terminal.
+pub fn is_tty(fd: impl AsFd) -> bool {
+    // SAFETY: `isatty` only inspects the file descriptor.
+    unsafe { libc::isatty(fd.as_fd().as_raw_fd()) == 1 }
+}
+
+/// An open terminal device.
+#[derive(Debug)]
+pub struct Tty {
+    file: File,
+}
+
+impl Tty {
+    /// Open the controlling terminal (`/dev/tty`).
+    ///
+    /// # Errors
+    ///
+    /// Return an error if the process has no controlling terminal.
+    pub fn open() -> io::Result<Self> {
+        let file =
+            OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+        Ok(Self { file })
+    }
+
+    /// Return standard input if it is a terminal, or open the controlling
+    /// terminal otherwise (e.g. when input is piped in).
+    ///
+    /// # Errors
+    ///
+    /// Return an error if standard input cannot be duplicated, or if it is
+    /// redirected and the process has no controlling terminal.
+    pub fn stdin_or_open() -> io::Result<Self> {
+        let stdin = io::stdin();
+        if is_tty(&stdin) {
+            Self::from_fd(stdin.as_fd().try_clone_to_owned()?)
+        } else {
+            Self::open()
+        }
+    }
+
+    /// Use an already open file descriptor as the terminal.
+    ///
+    /// # Errors
+    ///
+    /// Return an error of kind [`io::ErrorKind::Other`] (`ENOTTY`) if `fd`
+    /// is not a terminal.
+    pub fn from_fd(fd: OwnedFd) -> io::Result<Self> {
+        if !is_tty(&fd) {
+            return Err(io::Error::from_raw_os_error(libc::ENOTTY));
+        }
+        Ok(Self {
+            file: File::from(fd),
+        })
+    }
+
+    /// Switch the terminal to raw mode, see [`RawModeGuard::raw`].
+    ///
+    /// # Errors
+    ///
+    /// Return an error if the terminal settings cannot be changed.
+    pub fn raw_mode(&self) -> io::Result<RawModeGuard> {
+        RawModeGuard::raw(self)
+    }
+
+    /// Return the window size, see [`window_size`].
+    ///
+    /// # Errors
+    ///
+    /// Return an error if the size cannot be queried.
+    pub fn window_size(&self) -> io::Result<WindowSize> {
+        window_size(self)
+    }
+}
+
+impl AsFd for Tty {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.file.as_fd()
+    }
+}
+
+impl AsRawFd for Tty {
+    fn as_raw_fd(&self) -> RawFd {
+        self.file.as_raw_fd()
+    }
+}
+
+impl Read for Tty {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+impl Write for Tty {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Terminal input mode set by [`RawModeGuard`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TtyMode {
+    /// No line buffering, echo, signal generation or input and output
+    /// processing.  Every byte is delivered as is.
+    Raw,
+    /// No line buffering or echo, but signals (e.g. `Ctrl+C`) and output
+    /// processing are kept.
+    Cbreak,
+}
+
+/// Terminal settings saved by active guards, restored by the panic hook.
+static SAVED_SETTINGS: Mutex<Vec<(RawFd, libc::termios)>> =
+    Mutex::new(Vec::new());
+
+static PANIC_HOOK: Once = Once::new();
+
+/// Install a panic hook restoring the saved terminal settings, chained to
+/// the previous hook.
+///
+/// This is only needed when panics abort the process.  When they unwind,
+/// the guards restore the settings as they are dropped, and restoring
+/// them from the hook would break the terminal of a program that catches
+/// the panic, or that panics on a thread not owning the guard.
+fn install_panic_hook() {
+    if !cfg!(panic = "abort") {
+        return;
+    }
+    PANIC_HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            // Restore the terminal before the panic message is printed so
+            // that it is readable.
+            if let Ok(saved) = SAVED_SETTINGS.lock() {
+                for (fd, termios) in saved.iter().rev() {
+                    // SAFETY: the guard owning `fd` keeps it open while
+                    // it is registered.
+                    unsafe { libc::tcsetattr(*fd, libc::TCSANOW, termios) };
+                }
+            }
+            previous(info);
+        }));
+    });
+}
+
+fn get_termios(fd: BorrowedFd<'_>) -> io::Result<libc::termios> {
+    // SAFETY: `termios` is plain data, filled in by `tcgetattr`.
+    unsafe {
+        let mut termios = std::mem::zeroed();
+        if libc::tcgetattr(fd.as_raw_fd(), &raw mut termios) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(termios)
+    }
+}
+
+fn set_termios(fd: BorrowedFd<'_>, termios: &libc::termios) -> io::Result<()> {
+    // SAFETY: `termios` is a valid settings structure.
+    if unsafe { libc::tcsetattr(fd.as_raw_fd(), libc::TCSANOW, termios) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Switch a terminal to raw or cbreak mode for the guard's lifetime.
+///
+/// The original settings are restored when the guard is dropped, which
+/// includes unwinding from a panic.  When panics abort the process, they
+/// are restored by a panic hook instead, before the panic message is
+/// printed.
+#[derive(Debug)]
+pub struct RawModeGuard {
+    fd: OwnedFd,
+    original: libc::termios,
+}
+
+impl RawModeGuard {
+    /// Switch the terminal to [raw mode](TtyMode::Raw).
+    ///
+    /// # Errors
+    ///
+    /// Return an error if `fd` is not a terminal or its settings cannot be
+    /// changed.
+    pub fn raw(fd: impl AsFd) -> io::Result<Self> {
+        Self::new(fd, TtyMode::Raw)
+    }
+
+    /// Switch the terminal to [cbreak mode](TtyMode::Cbreak).
+    ///
+    /// # Errors
+    ///
+    /// Return an error if `fd` is not a terminal or its settings cannot be
+    /// changed.
+    pub fn cbreak(fd: impl AsFd) -> io::Result<Self> {
+        Self::new(fd, TtyMode::Cbreak)
+    }
+
+    /// Switch the terminal to the given mode.
+    ///
+    /// # Errors
+    ///
+    /// Return an error if `fd` is not a terminal or its settings cannot be
+    /// changed.
+    pub fn new(fd: impl AsFd, mode: TtyMode) -> io::Result<Self> {
+        let fd = fd.as_fd().try_clone_to_owned()?;
+        let original = get_termios(fd.as_fd())?;
+        let mut termios = original;
+        match mode {
+            TtyMode::Raw => {
+                termios.c_lflag &= !(libc::ICANON
+                    | libc::ECHO
+                    | libc::ECHONL
+                    | libc::IEXTEN
+                    | libc::ISIG);
+                termios.c_iflag &= !(libc::IXON
+                    | libc::ICRNL
+                    | libc::INLCR
+                    | libc::IGNCR
+                    | libc::BRKINT
+                    | libc::INPCK
+                    | libc::ISTRIP);
+                termios.c_oflag &= !libc::OPOST;
+                termios.c_cflag &= !(libc::CSIZE | libc::PARENB);
+                termios.c_cflag |= libc::CS8;
+            }
+            TtyMode::Cbreak => {
+                termios.c_lflag &= !(libc::ICANON | libc::ECHO);
+            }
+        }
+        termios.c_cc[libc::VMIN] = 1;
+        termios.c_cc[libc::VTIME] = 0;
+
+        install_panic_hook();
+        set_termios(fd.as_fd(), &termios)?;
+        if let Ok(mut saved) = SAVED_SETTINGS.lock() {
+            saved.push((fd.as_raw_fd(), original));
+        }
+        Ok(Self { fd, original })
+    }
+
+    /// Return the settings that will be restored.
+    #[must_use]
+    pub fn original_settings(&self) -> &libc::termios {
+        &self.original
+    }
+}
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        if let Ok(mut saved) = SAVED_SETTINGS.lock() {
+            let fd = self.fd.as_raw_fd();
+            if let Some(pos) = saved.iter().rposition(|(s, _)| *s == fd) {
+                saved.remove(pos);
+            }
+        }
+        let _ = set_termios(self.fd.as_fd(), &self.original);
+    }
+}
+
+/// Terminal window size.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WindowSize {
+    /// Number of rows.
+    pub rows: u16,
+    /// Number of columns.
+    pub cols: u16,
+    /// Text area height in pixels, or 0 if unknown.
+    pub height: u16,
+    /// Text area width in pixels, or 0 if unknown.
+    pub width: u16,
+}
+
+impl From<InBandResizeReport> for WindowSize {
+    fn from(report: InBandResizeReport) -> Self {
+        Self {
+            rows: report.rows,
+            cols: report.cols,
+            height: report.height,
+            width: report.width,
+        }
+    }
+}
+
+/// Query the window size of a terminal (`TIOCGWINSZ`).
+///
+/// # Errors
+///
+/// Return an error if `fd` is not a terminal.
+pub fn window_size(fd: impl AsFd) -> io::Result<WindowSize> {
+    // SAFETY: `winsize` is plain data, filled in by the ioctl.
+    let ws = unsafe {
+        let mut ws: libc::winsize = std::mem::zeroed();
+        if libc::ioctl(fd.as_fd().as_raw_fd(), libc::TIOCGWINSZ, &raw mut ws)
+            != 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+        ws
+    };
+    Ok(WindowSize {
+        rows: ws.ws_row,
+        cols: ws.ws_col,
+        height: ws.ws_ypixel,
+        width: ws.ws_xpixel,
+    })
+}
+
+/// Wait until `fd` has data to read, for at most `timeout`.
+///
+/// Return `true` if data is available, `false` if the timeout elapsed.
+///
+/// # Errors
+///
+/// Return an error if `poll` fails.
+pub fn poll_readable(fd: impl AsFd, timeout: Duration) -> io::Result<bool> {
+    let timeout_ms =
+        libc::c_int::try_from(timeout.as_millis()).unwrap_or(libc::c_int::MAX);
+    let mut pollfd = libc::pollfd {
+        fd: fd.as_fd().as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    // SAFETY: `pollfd` is a single valid entry.
+    match unsafe { libc::poll(&raw mut pollfd, 1, timeout_ms) } {
+        -1 => Err(io::Error::last_os_error()),
+        0 => Ok(false),
+        _ => Ok(true),
+    }
+}
+
+/// Maximum number of simultaneously live [`ResizeNotifier`]s.
+const MAX_RESIZE_NOTIFIERS: usize = 16;
+
+/// Write ends of the notifier pipes, `-1` for unused slots.
+static RESIZE_PIPES: [AtomicI32; MAX_RESIZE_NOTIFIERS] =
+    [const { AtomicI32::new(-1) }; MAX_RESIZE_NOTIFIERS];
+
+/// Number of `SIGWINCH` handlers currently writing to the pipes.
+static RESIZE_HANDLERS_RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+/// The `SIGWINCH` handler replaced by ours, called after notifying the
+/// pipes, and its `sa_flags`.
+static PREVIOUS_SIGWINCH_HANDLER: AtomicUsize = AtomicUsize::new(libc::SIG_DFL);
+static PREVIOUS_SIGWINCH_FLAGS: AtomicI32 = AtomicI32::new(0);
+
+/// Number of live notifiers, and the action to restore once the last one
+/// is dropped.
+static SIGWINCH_ACTION: Mutex<(usize, Option<libc::sigaction>)> =
+    Mutex::new((0, None));
+
+#[cfg(any(target_os = "netbsd", target_os = "openbsd"))]
+use libc::__errno as errno_location;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use libc::__errno_location as errno_location;
+#[cfg(any(target_vendor = "apple", target_os = "freebsd"))]
+use libc::__error as errno_location;
+
+extern "C" fn on_sigwinch(
+    signal: libc::c_int,
+    info: *mut libc::siginfo_t,
+    context: *mut libc::c_void,
+) {
+    // Only async-signal-safe operations are allowed here, and `errno` must
+    // be preserved for the interrupted code.
+    // SAFETY: `errno_location` returns a pointer to this thread's `errno`.
+    let errno = unsafe { *errno_location() };
+    // Keep notifiers from closing a pipe between loading and writing its
+    // descriptor; see `ResizeNotifier::drop`.
+    RESIZE_HANDLERS_RUNNING.fetch_add(1, Ordering::SeqCst);
+    for slot in &RESIZE_PIPES {
+        let fd = slot.load(Ordering::SeqCst);
+        if fd >= 0 {
+            // SAFETY: writing one byte from a local buffer.  A full pipe
+            // already has a notification pending, so errors are ignored.
+            unsafe { libc::write(fd, [0u8].as_ptr().cast(), 1) };
+        }
+    }
+    RESIZE_HANDLERS_RUNNING.fetch_sub(1, Ordering::SeqCst);
+
+    let previous = PREVIOUS_SIGWINCH_HANDLER.load(Ordering::SeqCst);
+    if previous != libc::SIG_DFL && previous != libc::SIG_IGN {
+        if PREVIOUS_SIGWINCH_FLAGS.load(Ordering::SeqCst) & libc::SA_SIGINFO
+            != 0
+        {
+            // SAFETY: the handler was installed with `SA_SIGINFO`, so it
+            // takes the same arguments as this one.
+            let handler: extern "C" fn(
+                libc::c_int,
+                *mut libc::siginfo_t,
+                *mut libc::c_void,
+            ) = unsafe { std::mem::transmute(previous) };
+            handler(signal, info, context);
+        } else {
+            // SAFETY: the handler was installed without `SA_SIGINFO`, so
+            // it only takes the signal number.
+            let handler: extern "C" fn(libc::c_int) =
+                unsafe { std::mem::transmute(previous) };
+            handler(signal);
+        }
+    }
+
+    // SAFETY: as above.
+    unsafe { *errno_location() = errno };
+}
+
+/// Install the `SIGWINCH` handler for a new notifier, saving the action it
+/// replaces.
+fn acquire_sigwinch_handler() -> io::Result<()> {
+    let mut state = SIGWINCH_ACTION
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if state.0 == 0 {
+        // SAFETY: installing a handler that only calls `write` and the
+        // handler it replaces.  `previous` is filled in by `sigaction`.
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = on_sigwinch as libc::sighandler_t;
+            action.sa_flags = libc::SA_RESTART | libc::SA_SIGINFO;
+            libc::sigemptyset(&raw mut action.sa_mask);
+            let mut previous: libc::sigaction = std::mem::zeroed();
+            if libc::sigaction(
+                libc::SIGWINCH,
+                std::ptr::null(),
+                &raw mut previous,
+            ) != 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+            // Publish the previous handler before ours can run.
+            PREVIOUS_SIGWINCH_HANDLER
+                .store(previous.sa_sigaction, Ordering::SeqCst);
+            PREVIOUS_SIGWINCH_FLAGS.store(previous.sa_flags, Ordering::SeqCst);
+            if libc::sigaction(
+                libc::SIGWINCH,
+                &raw const action,
+                std::ptr::null_mut(),
+            ) != 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+            state.1 = Some(previous);
+        }
+    }
+    state.0 += 1;
+    Ok(())
+}
+
+/// Restore the replaced `SIGWINCH` action once the last notifier is gone.
+fn release_sigwinch_handler() {
+    let mut state = SIGWINCH_ACTION
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    state.0 -= 1;
+    if state.0 == 0
+        && let Some(previous) = state.1.take()
+    {
+        // SAFETY: reinstalling the action returned by `sigaction`.
+        unsafe {
+            libc::sigaction(
+                libc::SIGWINCH,
+                &raw const previous,
+                std::ptr::null_mut(),
+            );
+        }
+    }
+}
+
+#[cfg(not(target_vendor = "apple"))]
+fn nonblocking_pipe() -> io::Result<(OwnedFd, OwnedFd)> {
+    use std::os::fd::FromRawFd;
+
+    let mut fds = [0; 2];
+    // SAFETY: `fds` has room for both ends.  The flags are set atomically,
+    // so the descriptors cannot leak into a concurrently spawned process.
+    if unsafe {
+        libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC | libc::O_NONBLOCK)
+    } != 0
+    {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `pipe2` returned two new descriptors that we now own.
+    Ok(unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) })
+}
+
+/// Apple platforms have no `pipe2`, so the flags are set after creating
+/// the pipe.
+#[cfg(target_vendor = "apple")]
+fn nonblocking_pipe() -> io::Result<(OwnedFd, OwnedFd)> {
+    use std::os::fd::FromRawFd;
+
+    let mut fds = [0; 2];
+    // SAFETY: `fds` has room for both ends.
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `pipe` returned two new descriptors that we now own.
+    let (read, write) =
+        unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+    for fd in &fds {
+        // SAFETY: setting flags on descriptors we own.
+        unsafe {
+            let flags = libc::fcntl(*fd, libc::F_GETFL);
+            libc::fcntl(*fd, libc::F_SETFL, flags | libc::O_NONBLOCK);
+            libc::fcntl(*fd, libc::F_SETFD, libc::FD_CLOEXEC);
+        }
+    }
+    Ok((read, write))
+}
+
+/// Notification of terminal window size changes (`SIGWINCH`).
+///
+/// Every notifier receives every signal.  Several signals arriving before
+/// the notifier is checked are coalesced into one notification.  The
+/// notifier's file descriptor becomes readable on a size change, so it can
+/// be watched together with terminal input using `poll` or an event loop.
+///
+/// A `SIGWINCH` handler installed before the first notifier is still
+/// called, and is reinstalled when the last notifier is dropped.
+#[derive(Debug)]
+pub struct ResizeNotifier {
+    read: OwnedFd,
+    write: OwnedFd,
+    slot: usize,
+}
+
+impl ResizeNotifier {
+    /// Install the `SIGWINCH` handler and create a notifier.
+    ///
+    /// # Errors
+    ///
+    /// Return an error if the handler cannot be installed, the pipe cannot
+    /// be created, or too many notifiers are alive.
+    pub fn new() -> io::Result<Self> {
+        acquire_sigwinch_handler()?;
+        Self::with_pipe().inspect_err(|_| release_sigwinch_handler())
+    }
+
+    /// Create the notification pipe and register it with the handler.
+    fn with_pipe() -> io::Result<Self> {
+        let (read, write) = nonblocking_pipe()?;
+        let raw = write.as_raw_fd();
+        let slot = RESIZE_PIPES
+            .iter()
+            .position(|slot| {
+                slot.compare_exchange(
+                    -1,
+                    raw,
+                    Ordering::AcqRel,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+            })
+            .ok_or_else(|| io::Error::other("too many resize notifiers"))?;
+        Ok(Self { read, write, slot })
+    }
+
+    /// Return `true` if the window size changed since the last call,
+    /// without blocking.
+    ///
+    /// # Errors
+    ///
+    /// Return an error if reading the notification pipe fails.
+    pub fn try_recv(&self) -> io::Result<bool> {
+        let mut buf = [0u8; 64];
+        let mut changed = false;
+        loop {
+            // SAFETY: reading into a local buffer.
+            let n = unsafe {
+                libc::read(
+                    self.read.as_raw_fd(),
+                    buf.as_mut_ptr().cast(),
+                    buf.len(),
+                )
+            };
+            match n {
+                -1 => {
+                    let err = io::Error::last_os_error();
+                    return match err.kind() {
+                        io::ErrorKind::WouldBlock => Ok(changed),
+                        io::ErrorKind::Interrupted => continue,
+                        _ => Err(err),
+                    };
+                }
+                0 => return Ok(changed),
+                _ => changed = true,
+            }
+        }
+    }
+
+    /// Block until the window size changes or `timeout` elapses.
+    ///
+    /// Return `true` if the size changed.
+    ///
+    /// # Errors
+    ///
+    /// Return an error if waiting on the notification pipe fails.
+    pub fn wait(&self, timeout: Duration) -> io::Result<bool> {
+        if self.try_recv()? {
+            return Ok(true);
+        }
+        loop {
+            match poll_readable(&self.read, timeout) {
+                Ok(false) => return Ok(false),
+                Ok(true) => return self.try_recv(),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+impl AsFd for ResizeNotifier {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.read.as_fd()
+    }
+}
+
+impl AsRawFd for ResizeNotifier {
+    fn as_raw_fd(&self) -> RawFd {
+        self.read.as_raw_fd()
+    }
+}
+
+impl Drop for ResizeNotifier {
+    fn drop(&mut self) {
+        // Stop the handler from writing before the pipe is closed.
+        let _ = RESIZE_PIPES[self.slot].compare_exchange(
+            self.write.as_raw_fd(),
+            -1,
+            Ordering::SeqCst,
+            Ordering::Relaxed,
+        );
+        release_sigwinch_handler();
+        // A handler running on another thread may have loaded the
+        // descriptor before it was removed.  Closing it now would let the
+        // handler write into whatever file reuses the descriptor, so wait
+        // for running handlers to finish.  A handler interrupting this
+        // thread finishes before the loop resumes.
+        while RESIZE_HANDLERS_RUNNING.load(Ordering::SeqCst) != 0 {
+            std::hint::spin_loop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::fd::FromRawFd;
+
+    /// Serializes the tests installing `SIGWINCH` handlers.
+    static SIGWINCH_TEST: Mutex<()> = Mutex::new(());
+
+    fn lock_sigwinch() -> std::sync::MutexGuard<'static, ()> {
+        SIGWINCH_TEST
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Open a pseudo-terminal pair, returning `(master, slave)`.
+    fn openpty(size: Option<WindowSize>) -> (OwnedFd, OwnedFd) {
+        let ws = size.map(|size| libc::winsize {
+            ws_row: size.rows,
+            ws_col: size.cols,
+            ws_xpixel: size.width,
+            ws_ypixel: size.height,
+        });
+        let mut master = -1;
+        let mut slave = -1;
+        let ret = unsafe {
+            libc::openpty(
+                &raw mut master,
+                &raw mut slave,
+                std::ptr::null_mut(),
+                std::ptr::null(),
+                ws.as_ref().map_or(std::ptr::null(), std::ptr::from_ref),
+            )
+        };
+        assert_eq!(ret, 0, "openpty: {}", io::Error::last_os_error());
+        unsafe { (OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(slave)) }
+    }
+
+    #[test]
+    fn test_tty_from_fd() {
+        let (master, slave) = openpty(None);
+        assert!(is_tty(&slave));
+        let mut tty = Tty::from_fd(slave).unwrap();
+        tty.write_all(b"hi").unwrap();
+        let mut buf = [0u8; 2];
+        File::from(master).read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"hi");
+
+        let (read, _write) = nonblocking_pipe().unwrap();
+        assert!(!is_tty(&read));
+        assert!(Tty::from_fd(read).is_err());
+    }
+
+    #[test]
+    fn test_raw_mode_guard() {
+        let (_master, slave) = openpty(None);
+        let before = get_termios(slave.as_fd()).unwrap();
+        assert_ne!(before.c_lflag & libc::ICANON, 0);
+        {
+            let _guard = RawModeGuard::raw(&slave).unwrap();
+            let raw = get_termios(slave.as_fd()).unwrap();
+            assert_eq!(
+                raw.c_lflag & (libc::ICANON | libc::ECHO | libc::ISIG),
+                0
+            );
+            assert_eq!(raw.c_oflag & libc::OPOST, 0);
+        }
+        let after = get_termios(slave.as_fd()).unwrap();
+        assert_eq!(after.c_lflag, before.c_lflag);
+        assert_eq!(after.c_oflag, before.c_oflag);
+
+        {
+            let _guard = RawModeGuard::cbreak(&slave).unwrap();
+            let cbreak = get_termios(slave.as_fd()).unwrap();
+            assert_eq!(cbreak.c_lflag & (libc::ICANON | libc::ECHO), 0);
+            assert_ne!(cbreak.c_lflag & libc::ISIG, 0);
+        }
+        assert_eq!(get_termios(slave.as_fd()).unwrap().c_lflag, before.c_lflag);
+    }
+
+    #[test]
+    fn test_raw_mode_survives_caught_panic() {
+        let (_master, slave) = openpty(None);
+        let _guard = RawModeGuard::raw(&slave).unwrap();
+        assert!(std::thread::spawn(|| panic!("caught")).join().is_err());
+        let termios = get_termios(slave.as_fd()).unwrap();
+        assert_eq!(termios.c_lflag & libc::ICANON, 0);
+    }
+
+    #[test]
+    fn test_window_size() {
+        let size = WindowSize {
+            rows: 24,
+            cols: 80,
+            height: 384,
+            width: 640,
+        };
+        let (master, slave) = openpty(Some(size));
+        assert_eq!(window_size(&slave).unwrap(), size);
+        assert_eq!(window_size(&master).unwrap(), size);
+        let (read, _write) = nonblocking_pipe().unwrap();
+        assert!(window_size(&read).is_err());
+    }
+
+    #[test]
+    fn test_resize_notifier() {
+        let _lock = lock_sigwinch();
+        let notifier = ResizeNotifier::new().unwrap();
+        let other = ResizeNotifier::new().unwrap();
+        assert!(!notifier.try_recv().unwrap());
+
+        unsafe { libc::raise(libc::SIGWINCH) };
+        unsafe { libc::raise(libc::SIGWINCH) };
+        assert!(notifier.wait(Duration::from_secs(1)).unwrap());
+        assert!(!notifier.try_recv().unwrap());
+        assert!(other.try_recv().unwrap());
+        drop(other);
+
+        assert!(!notifier.wait(Duration::from_millis(10)).unwrap());
+    }
+
+    #[test]
+    fn test_resize_notifier_pipe_flags() {
+        let _lock = lock_sigwinch();
+        let notifier = ResizeNotifier::new().unwrap();
+        for fd in [notifier.read.as_raw_fd(), notifier.write.as_raw_fd()] {
+            let fd_flags = unsafe { libc::fcntl(fd, libc::F_GETFD) };
+            assert_ne!(fd_flags & libc::FD_CLOEXEC, 0);
+            let status_flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+            assert_ne!(status_flags & libc::O_NONBLOCK, 0);
+        }
+    }
+
+    #[test]
+    fn test_resize_notifier_drop_during_signals() {
+        let _lock = lock_sigwinch();
+        let done =
+            std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let raiser = std::thread::spawn({
+            let done = done.clone();
+            move || {
+                while !done.load(Ordering::Relaxed) {
+                    unsafe { libc::raise(libc::SIGWINCH) };
+                }
+            }
+        });
+        for _ in 0..100 {
+            drop(ResizeNotifier::new().unwrap());
+        }
+        done.store(true, Ordering::Relaxed);
+        raiser.join().unwrap();
+        assert_eq!(RESIZE_HANDLERS_RUNNING.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_resize_notifier_chains_previous_handler() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        extern "C" fn previous(_: libc::c_int) {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn current_handler() -> libc::sighandler_t {
+            unsafe {
+                let mut action: libc::sigaction = std::mem::zeroed();
+                libc::sigaction(
+                    libc::SIGWINCH,
+                    std::ptr::null(),
+                    &raw mut action,
+                );
+                action.sa_sigaction
+            }
+        }
+
+        let _lock = lock_sigwinch();
+        let original = unsafe {
+            libc::signal(libc::SIGWINCH, previous as libc::sighandler_t)
+        };
+
+        let notifier = ResizeNotifier::new().unwrap();
+        let other = ResizeNotifier::new().unwrap();
+        unsafe { libc::raise(libc::SIGWINCH) };
+        assert!(notifier.try_recv().unwrap());
+        assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+
+        drop(notifier);
+        assert_ne!(current_handler(), previous as libc::sighandler_t);
+        drop(other);
+        assert_eq!(current_handler(), previous as libc::sighandler_t);
+        unsafe { libc::raise(libc::SIGWINCH) };
+        assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+
+        unsafe { libc::signal(libc::SIGWINCH, original) };
+    }
+}
@@ -1,9 +1,11 @@
 use better_any::TidExt;
-use pretty_assertions::assert_eq;
+use pretty_assertions::{StrComparison, assert_eq};
 use std::fmt::Write;
 use vt_push_parser::ascii::decode_string;
 use vtansi::TerseDisplay;
 use vtio::TerminalInputParser;
+use vtio::event::keyboard::ModifyOtherKeysReport;
+use vtio::event::terminal::{FocusGained, FocusLost};
 use vtio::event::{KeyEvent, KeyboardEnhancementFlagsResponse};
 
 pub struct TestConfig<'a> {
@@ -21,36 +23,59 @@ impl<'a, T: TerseDisplay> std::fmt::Display for TerseFormatter<'a, T> {
     }
 }
 
+/// Modifier key names that differ from the macOS ones used in the
+/// expected output, and their macOS counterparts.
+#[cfg(not(target_os = "macos"))]
+const PLATFORM_MODIFIER_NAMES: &[(&str, &str)] = &[
+    ("Ctrl)", "Control)"),
+    ("Alt)", "Option)"),
+    ("Super)", "Command)"),
+    ("Windows)", "Command)"),
+];
+#[cfg(target_os = "macos")]
+const PLATFORM_MODIFIER_NAMES: &[(&str, &str)] = &[];
+
+fn format_event(result: &mut String, event: &dyn vtansi::AnsiEvent<'_>) {
+    if let Some(key_event) = event.downcast_ref::<KeyEvent>() {
+        // Modifier keys are displayed with platform specific names, so
+        // normalize them to keep the expected output portable.
+        let mut formatted = TerseFormatter(key_event).to_string();
+        for (name, macos_name) in PLATFORM_MODIFIER_NAMES {
+            if let Some(stripped) = formatted.strip_suffix(name) {
+                formatted = format!("{stripped}{macos_name}");
+            }
+        }
+        writeln!(result, "{formatted}").unwrap();
+    } else if let Some(response) =
+        event.downcast_ref::<KeyboardEnhancementFlagsResponse>()
+    {
+        writeln!(result, "{}", TerseFormatter(response)).unwrap();
+    } else if let Some(report) = event.downcast_ref::<ModifyOtherKeysReport>() {
+        writeln!(result, "{}", TerseFormatter(report)).unwrap();
+    } else if let Some(focus) = event.downcast_ref::<FocusGained>() {
+        writeln!(result, "{}", TerseFormatter(focus)).unwrap();
+    } else if let Some(focus) = event.downcast_ref::<FocusLost>() {
+        writeln!(result, "{}", TerseFormatter(focus)).unwrap();
+    }
+}
+
 fn parse(data: &[&[u8]]) -> String {
     let mut parser = TerminalInputParser::new();
     let mut result = String::new();
 
     for chunk in data {
-        parser.feed_with(chunk, &mut |event| {
-            if let Some(key_event) = event.downcast_ref::<KeyEvent>() {
-                writeln!(result, "{}", TerseFormatter(key_event)).unwrap();
-            } else if let Some(response) =
-                event.downcast_ref::<KeyboardEnhancementFlagsResponse>()
-            {
-                writeln!(result, "{}", TerseFormatter(response)).unwrap();
-            }
-        });
+        parser.feed_with(chunk, &mut |event| format_event(&mut result, event));
     }
 
-    parser.idle(&mut |event| {
-        if let Some(key_event) = event.downcast_ref::<KeyEvent>() {
-            writeln!(result, "{}", TerseFormatter(key_event)).unwrap();
-        } else if let Some(response) =
-            event.downcast_ref::<KeyboardEnhancementFlagsResponse>()
-        {
-            writeln!(result, "{}", TerseFormatter(response)).unwrap();
-        }
-    });
+    parser.idle(&mut |event| format_event(&mut result, event));
 
     result
 }
 
-pub fn run_tests<'a>(config: TestConfig<'a>) {
+/// Run the tests of a suite and compare the output with the expected one.
+///
+/// Return `false` if any test failed or the output did not match.
+pub fn run_tests<'a>(config: TestConfig<'a>) -> bool {
     let mut output = String::new();
     let mut failures = 0;
     output.push_str(&format!("# {}\n", config.title));
@@ -72,7 +97,7 @@ pub fn run_tests<'a>(config: TestConfig<'a>) {
         }
 
         let decoded = decode_string(line);
-        println!("  running {:?} ...", test_name);
+        println!("  running {test_name:?} ...");
         let test_name_clone = test_name.clone();
         let line_clone = line.to_string();
         let Ok(test_output) = std::panic::catch_unwind(move || {
@@ -80,7 +105,7 @@ pub fn run_tests<'a>(config: TestConfig<'a>) {
             test(&mut output, &test_name_clone, &line_clone, &decoded);
             output
         }) else {
-            eprintln!("  test {:?} panicked", test_name);
+            eprintln!("  test {test_name:?} panicked");
             failures += 1;
             continue;
         };
@@ -90,8 +115,8 @@ pub fn run_tests<'a>(config: TestConfig<'a>) {
     println!();
 
     if failures > 0 {
-        eprintln!("{} tests failed", failures);
-        std::process::exit(1);
+        eprintln!("{failures} tests failed");
+        return false;
     }
 
     if filter.is_empty() {
@@ -99,10 +124,18 @@ pub fn run_tests<'a>(config: TestConfig<'a>) {
             std::fs::write(config.output_file, output).unwrap();
         } else {
             let expected = std::fs::read_to_string(config.output_file).unwrap();
-            assert_eq!(expected, output);
+            if expected != output {
+                eprintln!(
+                    "output does not match {}:\n{}",
+                    config.output_file,
+                    StrComparison::new(&expected, &output)
+                );
+                return false;
+            }
             println!("all tests passed");
         }
     }
+    true
 }
 
 fn test(output: &mut String, test_name: &str, line: &str, decoded: &[u8]) {
@@ -124,7 +157,7 @@ fn test(output: &mut String, test_name: &str, line: &str, decoded: &[u8]) {
         );
     }
 
-    output.push_str(&format!("## {test_name}\n```\n{}\n```\n\n", line));
+    output.push_str(&format!("## {test_name}\n```\n{line}\n```\n\n"));
     output.push_str("```\n");
     output.push_str(&result);
     output.push_str("```\n");
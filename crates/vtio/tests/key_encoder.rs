@@ -0,0 +1,313 @@
+//! Conformance tests for `KeyEncoder`.
+//!
+//! Every case of the keyboard text suites is decoded with
+//! `TerminalInputParser` and the resulting key events are re-encoded with
+//! a `KeyEncoder` configured like the terminal that produced the input.
+//! The encoder must reproduce the input exactly, unless the input is not
+//! the canonical encoding of its events; those cases are listed with the
+//! bytes the encoder produces instead.  Cases the encoder cannot reproduce
+//! because the parser loses information are listed as known limitations;
+//! they are not counted as passing, and must keep failing until the
+//! limitation is fixed.
+
+use better_any::TidExt;
+use vt_push_parser::ascii::decode_string;
+use vtio::TerminalInputParser;
+use vtio::event::KeyEvent;
+use vtio::event::keyboard::{KeyEncoder, KeyboardEnhancementFlags};
+
+/// Test cases whose input is not the canonical encoding of its events,
+/// with the expected encoder output.
+type Exceptions<'a> = &'a [(&'a str, &'a str)];
+
+/// Names of test cases the encoder cannot reproduce.
+type KnownLimitations<'a> = &'a [&'a str];
+
+/// Decode `input` into key events.
+///
+/// Returns `None` if the parser produced anything other than key events.
+fn decode_keys(input: &[u8]) -> Option<Vec<KeyEvent>> {
+    let mut parser = TerminalInputParser::new();
+    let mut events = Vec::new();
+    let mut only_keys = true;
+    let mut collect = |event: &dyn vtansi::AnsiEvent<'_>| {
+        if let Some(key) = event.downcast_ref::<KeyEvent>() {
+            events.push(key.clone());
+        } else {
+            only_keys = false;
+        }
+    };
+    parser.feed_with(input, &mut collect);
+    parser.idle(&mut collect);
+    (only_keys && !events.is_empty()).then_some(events)
+}
+
+fn check_suite(
+    suite: &str,
+    encoder: &KeyEncoder,
+    exceptions: Exceptions,
+    limitations: KnownLimitations,
+) {
+    let mut test_name = "";
+    let mut failures = Vec::new();
+    let mut known_failures = 0;
+    for line in suite.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix("# ") {
+            test_name = name;
+            continue;
+        }
+
+        // Cases that do not decode to key events only (protocol
+        // responses, sequences the parser does not recognize) exercise
+        // the parser, not the encoder
+        let input = decode_string(line);
+        let Some(events) = decode_keys(&input) else {
+            continue;
+        };
+        let mut output = Vec::new();
+        for event in events {
+            encoder.encode_into(&event, &mut output).unwrap();
+        }
+
+        if limitations.contains(&test_name) {
+            if output == input {
+                failures.push(format!(
+                    "{test_name:?}: listed as a known limitation, but \
+                     round-trips"
+                ));
+            }
+            known_failures += 1;
+            continue;
+        }
+
+        let expected = exceptions
+            .iter()
+            .find(|(name, _)| *name == test_name)
+            .map_or(input, |(_, bytes)| decode_string(bytes));
+        if output != expected {
+            failures.push(format!(
+                "{test_name:?}: expected {:?}, got {:?}",
+                String::from_utf8_lossy(&expected),
+                String::from_utf8_lossy(&output),
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+    assert_eq!(
+        known_failures,
+        limitations.len(),
+        "every known limitation must name a test case"
+    );
+    if known_failures > 0 {
+        println!("{known_failures} known limitations still failing");
+    }
+}
+
+/// Legacy inputs that the legacy encoder writes differently.
+const LEGACY_EXCEPTIONS: Exceptions = &[
+    // Legacy encoding has no way to report Shift with Ctrl+letter
+    ("Ctrl+Shift+A", "<SOH>"),
+];
+
+/// Kitty protocol inputs that are not the canonical encoding of their
+/// events, with the bytes the encoder sends instead.  Each entry states
+/// why the input and the encoder output differ.
+const KITTY_EXCEPTIONS: Exceptions = &[
+    // Key codes are always the unshifted key; an uppercase key code
+    // decodes as Shift plus the shifted key
+    ("Basic CSI u encoding - uppercase A", "<ESC>[97:65;2u"),
+    // Pressing a modifier key sets its own modifier bit
+    ("Left Shift key press", "<ESC>[57441;2u"),
+    // Pressing a modifier key sets its own modifier bit
+    ("Left Control key press", "<ESC>[57442;5u"),
+    // Pressing a modifier key sets its own modifier bit
+    ("Left Alt key press", "<ESC>[57443;3u"),
+    // Pressing a modifier key sets its own modifier bit
+    ("Left Super key press", "<ESC>[57444;9u"),
+    // Pressing a modifier key sets its own modifier bit
+    ("Right Shift key press", "<ESC>[57447;2u"),
+    // Pressing a modifier key sets its own modifier bit
+    ("Right Control key press", "<ESC>[57448;5u"),
+    // The uppercase key code decodes as Shift plus the shifted key, so
+    // the encoder reports the lowercase key with Shift added
+    (
+        "CSI u with base layout key - Cyrillic layout",
+        "<ESC>[1089:1057:99;6u",
+    ),
+    // The default `1:1` (no modifiers, press) is omitted
+    ("Press event explicitly specified", "<ESC>[97u"),
+    // The default `:1` press event type is omitted
+    ("Shift+a press event", "<ESC>[97;2u"),
+    // The default `:1` press event type is omitted
+    ("Keypad Left press with modifiers", "<ESC>[57417;5u"),
+    // Text goes in the third field, not after an empty one
+    (
+        "CSI u with text as codepoints - shift+a produces A (65)",
+        "<ESC>[97;2;65u",
+    ),
+    // Text goes in the third field, not after an empty one
+    (
+        "CSI u with text - option+a produces å (229)",
+        "<ESC>[97;;229u",
+    ),
+    // Text goes in the third field, not after an empty one
+    ("CSI u with multiple text codepoints", "<ESC>[97;;65:66u"),
+    // Text goes in the third field, not after an empty one
+    (
+        "Key with no keycode but text (composition)",
+        "<ESC>[0;;229u",
+    ),
+    // The uppercase key code decodes as Shift plus the shifted key; the
+    // shifted key is reported as an alternate key
+    (
+        "Legacy compatibility - Ctrl+Shift+A before disambiguation",
+        "<ESC>[97:65;6u",
+    ),
+    // F1 uses its legacy `CSI 1 ; mods P` form, not a private-use code
+    ("F1 with shift", "<ESC>[1;2P"),
+    // F5 uses its legacy `CSI 15 ; mods ~` form, not a private-use code
+    ("F5 with ctrl+alt", "<ESC>[15;7~"),
+    // The default `1:1` (no modifiers, press) is omitted
+    ("Escape key press event", "<ESC>[27u"),
+    // The default `1:1` (no modifiers, press) is omitted
+    ("Space bar press event", "<ESC>[32u"),
+    // With DISAMBIGUATE_ESCAPE_CODES, F1 is sent as `CSI P`, not SS3
+    ("Legacy F1 with modifiers (SS3 P)", "<ESC>[P"),
+    // With DISAMBIGUATE_ESCAPE_CODES, F2 is sent as `CSI Q`, not SS3
+    ("Legacy F2 (SS3 Q)", "<ESC>[Q"),
+    // F3 is sent as `CSI 13 ~`; `CSI R` would be a cursor position
+    // report
+    ("Legacy F3 (SS3 R)", "<ESC>[13~"),
+    // With DISAMBIGUATE_ESCAPE_CODES, F4 is sent as `CSI S`, not SS3
+    ("Legacy F4 (SS3 S)", "<ESC>[S"),
+    // The legacy byte decodes as Tab, which REPORT_ALL_KEYS_AS_ESCAPE_CODES
+    // sends as a CSI u sequence
+    ("Escape sequence for Ctrl+I (same as Tab)", "<ESC>[9u"),
+    // The legacy byte decodes as Enter, which
+    // REPORT_ALL_KEYS_AS_ESCAPE_CODES sends as a CSI u sequence
+    ("Escape sequence for Ctrl+M (same as Enter)", "<ESC>[13u"),
+    // The first event's default `1:1` (no modifiers, press) is omitted
+    (
+        "Repeat events for same key",
+        "<ESC>[97u<ESC>[97;1:2u<ESC>[97;1:2u<ESC>[97;1:3u",
+    ),
+    // The uppercase key code decodes as Shift plus the shifted key, so
+    // the encoder reports the lowercase key with Shift added
+    (
+        "Base layout key matching - Cyrillic С maps to Latin c",
+        "<ESC>[1089:1057:99;6u",
+    ),
+    // Key codes are always the unshifted key, so the lowercase key is
+    // reported with the uppercase one as its shifted key
+    (
+        "Base layout key with shift - Cyrillic С (uppercase)",
+        "<ESC>[1089:1057:99;6u",
+    ),
+    // Key codes are always the unshifted key, so the lowercase key is
+    // reported with the uppercase one as its shifted key
+    (
+        "Complex case - alt+ctrl+shift with base layout",
+        "<ESC>[1089:1057:99;8u",
+    ),
+    // The uppercase key code decodes as Shift plus the shifted key, so
+    // the encoder reports the lowercase key with Shift added
+    ("Base layout with no modifiers", "<ESC>[1089:1057:99;2u"),
+    // Text goes in the third field, not after an empty one
+    (
+        "Text as Codepoints - Basic single character",
+        "<ESC>[97;2;65u",
+    ),
+    // Text goes in the third field, not after an empty one
+    (
+        "Text as Codepoints - Shift+a with text \"A\"",
+        "<ESC>[97;2;65u",
+    ),
+    // Keys held with Ctrl or Alt produce no text, so none is reported
+    (
+        "Text as Codepoints - Multiple codepoints (e.g., composed character)",
+        "<ESC>[97;5u",
+    ),
+    // Text goes in the third field, not after an empty one
+    ("Text as Codepoints - Emoji", "<ESC>[97;2;128512u"),
+    // An empty text field is omitted
+    ("Text as Codepoints - Empty text field", "<ESC>[97;2u"),
+    // Keys held with Ctrl or Alt produce no text, so none is reported
+    (
+        "Text as Codepoints - Ctrl+a with text (C0 control)",
+        "<ESC>[97;5u",
+    ),
+    // The uppercase key code decodes as Shift plus the shifted key; text
+    // goes in the third field
+    (
+        "Text as Codepoints - Complex: base layout + text",
+        "<ESC>[1089:1057:99;2;1057u",
+    ),
+    // Text goes in the third field, not after an empty one
+    (
+        "Text as Codepoints - Multiple characters in text",
+        "<ESC>[97;2;65:66:67u",
+    ),
+];
+
+/// Kitty protocol inputs the encoder cannot reproduce.
+///
+/// The parser folds Shift into the shifted key of a `key:shifted` pair
+/// and drops the unshifted key, e.g. `CSI 49:33;2u` decodes as `!`
+/// without modifiers.  The encoder then reports `!` as the key code,
+/// which is not what the terminal sent.
+const KITTY_KNOWN_LIMITATIONS: KnownLimitations = &[
+    "Test number 1 with shift (becomes !)",
+    "Test equals with shift (becomes +)",
+    "Shift+/ (becomes ?)",
+    "Semicolon with shift (colon)",
+    "Comma with shift (less-than)",
+    "Period with shift (greater-than)",
+    "Backtick with shift (tilde)",
+    "Minus with shift (underscore)",
+    "Left bracket with shift (left brace)",
+    "Right bracket with shift (right brace)",
+    "Backslash with shift (pipe)",
+    "Single quote with shift (double quote)",
+    "Base layout for number keys - French keyboard 1 with shift (!)",
+];
+
+#[test]
+fn legacy_keyboard_input() {
+    check_suite(
+        include_str!("keyboard_input.txt"),
+        &KeyEncoder::new(),
+        LEGACY_EXCEPTIONS,
+        &[],
+    );
+}
+
+#[test]
+fn legacy_control_keys() {
+    check_suite(
+        include_str!("control_keys.txt"),
+        &KeyEncoder::new(),
+        &[],
+        &[],
+    );
+}
+
+#[test]
+fn legacy_alt_keys() {
+    check_suite(include_str!("alt_keys.txt"), &KeyEncoder::new(), &[], &[]);
+}
+
+#[test]
+fn kitty_keyboard() {
+    let mut encoder = KeyEncoder::new();
+    encoder.push_keyboard_enhancement_flags(KeyboardEnhancementFlags::all());
+    check_suite(
+        include_str!("kitty_keyboard.txt"),
+        &encoder,
+        KITTY_EXCEPTIONS,
+        KITTY_KNOWN_LIMITATIONS,
+    );
+}
@@ -45,6 +45,24 @@ fn discover_test_suites() -> Vec<TestSuite> {
             output_path: "tests/kitty_keyboard_result.md".to_string(),
             title: "Kitty Keyboard Protocol".to_string(),
         },
+        TestSuite {
+            name: "modify_other_keys".to_string(),
+            input: include_str!("modify_other_keys.txt").to_string(),
+            output_path: "tests/modify_other_keys_result.md".to_string(),
+            title: "xterm modifyOtherKeys".to_string(),
+        },
+        TestSuite {
+            name: "win32_input".to_string(),
+            input: include_str!("win32_input.txt").to_string(),
+            output_path: "tests/win32_input_result.md".to_string(),
+            title: "Win32 Input Mode".to_string(),
+        },
+        TestSuite {
+            name: "focus_events".to_string(),
+            input: include_str!("focus_events.txt").to_string(),
+            output_path: "tests/focus_events_result.md".to_string(),
+            title: "Focus Events".to_string(),
+        },
     ]
 }
 
@@ -63,20 +81,33 @@ pub fn main() {
     };
 
     if suites_to_run.is_empty() {
-        eprintln!("No test suites match filter: {}", filter);
+        eprintln!("No test suites match filter: {filter}");
         std::process::exit(1);
     }
 
+    // Run every suite before reporting, so that one failing suite doesn't
+    // hide failures in the others.
+    let mut failed = Vec::new();
     for suite in suites_to_run {
         println!("\n=== Running test suite: {} ===\n", suite.title);
 
-        common::run_tests(common::TestConfig {
+        let passed = common::run_tests(common::TestConfig {
             input_file: &suite.input,
             output_file: &suite.output_path,
             title: &suite.title,
             filter: "",
         });
 
-        println!("\n=== {} completed ===\n", suite.title);
+        if passed {
+            println!("\n=== {} completed ===\n", suite.title);
+        } else {
+            println!("\n=== {} FAILED ===\n", suite.title);
+            failed.push(suite.name);
+        }
+    }
+
+    if !failed.is_empty() {
+        eprintln!("failed test suites: {}", failed.join(", "));
+        std::process::exit(1);
     }
 }
@@ -0,0 +1,103 @@
+//! vttest-style screen fixtures.
+//!
+//! Each case in `screen.txt` starts with a `# name` line and contains:
+//!
+//! - `size ROWSxCOLS`, the screen size,
+//! - `> input` lines, concatenated and decoded with the `<ESC>` notation
+//!   used by the parser suites,
+//! - `|row|` lines, the expected contents of every row, padded to the
+//!   screen width,
+//! - `@ row,col`, the expected one-based cursor position.
+//!
+//! The input is fed through `TerminalOutputParser` into a `Screen`.
+
+use vt_push_parser::ascii::decode_string;
+use vtio::parser::TerminalOutputParser;
+use vtio::screen::Screen;
+
+#[derive(Default)]
+struct Case {
+    name: String,
+    size: (u16, u16),
+    input: String,
+    rows: Vec<String>,
+    cursor: Option<(u16, u16)>,
+}
+
+fn parse_cases(suite: &str) -> Vec<Case> {
+    let mut cases: Vec<Case> = Vec::new();
+    for line in suite.lines() {
+        if let Some(name) = line.strip_prefix("# ") {
+            cases.push(Case {
+                name: name.to_string(),
+                ..Case::default()
+            });
+            continue;
+        }
+        let Some(case) = cases.last_mut() else {
+            continue;
+        };
+        if let Some(size) = line.strip_prefix("size ") {
+            let (rows, cols) = size.split_once('x').expect("size ROWSxCOLS");
+            case.size = (rows.parse().unwrap(), cols.parse().unwrap());
+        } else if let Some(input) = line.strip_prefix("> ") {
+            case.input.push_str(input);
+        } else if let Some(row) =
+            line.strip_prefix('|').and_then(|l| l.strip_suffix('|'))
+        {
+            case.rows.push(row.to_string());
+        } else if let Some(pos) = line.strip_prefix("@ ") {
+            let (row, col) = pos.split_once(',').expect("@ ROW,COL");
+            case.cursor = Some((row.parse().unwrap(), col.parse().unwrap()));
+        } else {
+            assert!(line.trim().is_empty(), "unexpected line: {line:?}");
+        }
+    }
+    cases
+}
+
+/// Render every row at full width, so trailing blanks are compared too.
+fn render(screen: &Screen) -> Vec<String> {
+    screen
+        .grid()
+        .iter()
+        .map(|row| {
+            let mut text = String::new();
+            for cell in row.cells() {
+                cell.push_text(&mut text);
+            }
+            text
+        })
+        .collect()
+}
+
+#[test]
+fn screen_fixtures() {
+    let mut failures = Vec::new();
+    for case in parse_cases(include_str!("screen.txt")) {
+        let mut screen = Screen::new(case.size.0, case.size.1);
+        let mut parser = TerminalOutputParser::new();
+        parser.feed_with(&decode_string(&case.input), &mut |event| {
+            screen.apply(event);
+        });
+
+        let rows = render(&screen);
+        if rows != case.rows {
+            failures.push(format!(
+                "{:?}: expected\n{}\ngot\n{}",
+                case.name,
+                case.rows.join("\n"),
+                rows.join("\n"),
+            ));
+        }
+        let (row, col) = screen.cursor_position();
+        let cursor = Some((row + 1, col + 1));
+        if case.cursor.is_some() && cursor != case.cursor {
+            failures.push(format!(
+                "{:?}: expected cursor at {:?}, got {cursor:?}",
+                case.name, case.cursor,
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+}